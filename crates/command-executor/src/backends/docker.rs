@@ -0,0 +1,322 @@
+//! Docker containers and compose services launched by
+//! [`LocalLauncher`](super::LocalLauncher)
+//!
+//! Containers run in the foreground with `docker run` and compose services
+//! with `docker compose up`, so the CLI's output and exit status are the
+//! container's. Process control maps to `docker stop` and `docker kill`.
+
+use async_process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::command::Command;
+use crate::error::{Error, Result};
+use crate::target::{ComposeService, DockerContainer};
+
+/// Names containers launched without one
+static CONTAINERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Controls a launched container through the docker CLI
+#[derive(Debug, Clone)]
+pub(crate) struct DockerControl {
+    /// Arguments after `docker` selecting the CLI, e.g. `compose -f ...`
+    cli: Vec<String>,
+    /// Container name or compose service
+    name: String,
+    /// Remove the container when the handle is dropped
+    remove: bool,
+}
+
+impl DockerControl {
+    /// `docker run` for a container, with the command run in it
+    ///
+    /// The command's environment is passed into the container; an empty
+    /// program runs the image's default command. The client runs like the
+    /// command, on a terminal the container gets as its own if it asks for one.
+    pub fn run(container: &DockerContainer, command: &Command) -> (Command, Self) {
+        let name = container.name().map_or_else(
+            || {
+                format!(
+                    "harness-{}-{}",
+                    std::process::id(),
+                    CONTAINERS.fetch_add(1, Ordering::Relaxed)
+                )
+            },
+            str::to_string,
+        );
+
+        let mut cmd = Command::new("docker");
+        cmd.args(["run", "--name", &name]);
+        if container.remove_on_exit() {
+            cmd.arg("--rm");
+        }
+        if command.has_stdin_channel() || command.get_pty().is_some() {
+            cmd.arg("--interactive");
+        }
+        if command.get_pty().is_some() {
+            cmd.arg("--tty");
+        }
+        let mut env: Vec<_> = container
+            .env()
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .chain(command.get_envs().iter().map(|(key, value)| {
+                format!("{}={}", key.to_string_lossy(), value.to_string_lossy())
+            }))
+            .collect();
+        env.sort();
+        for var in env {
+            cmd.args(["--env", &var]);
+        }
+        for (host, path) in container.volumes() {
+            cmd.args(["--volume", &format!("{}:{}", host, path)]);
+        }
+        if let Some(dir) = container.working_dir() {
+            cmd.args(["--workdir", dir]);
+        }
+        cmd.arg(container.image());
+        if !command.get_program().is_empty() {
+            cmd.arg(command.get_program()).args(command.get_args());
+        }
+
+        let control = Self {
+            cli: Vec::new(),
+            name,
+            remove: container.remove_on_exit(),
+        };
+        cmd.run_like(command);
+        (cmd, control)
+    }
+
+    /// `docker compose up` for a single service, with `--no-deps` unless
+    /// the service starts its dependencies
+    ///
+    /// Compose decides what runs; the command only contributes environment
+    /// variables for interpolating the compose file. A detached service is
+    /// started in the background and the command returns once it's up.
+    pub fn compose_up(service: &ComposeService, command: &Command) -> (Command, Self) {
+        let control = Self {
+            cli: service.cli(),
+            name: service.service_name().to_string(),
+            remove: false,
+        };
+
+        let mut cmd = Command::new("docker");
+        cmd.args(&control.cli).arg("up").envs(command.get_envs());
+        if service.is_detached() {
+            cmd.arg("--detach");
+        } else {
+            cmd.args([
+                "--no-log-prefix",
+                "--attach",
+                &control.name,
+                "--exit-code-from",
+                &control.name,
+            ]);
+        }
+        if !service.starts_dependencies() {
+            cmd.arg("--no-deps");
+        }
+        cmd.arg(&control.name).run_like(command);
+        (cmd, control)
+    }
+
+    /// Stop gracefully: the stop signal, then `SIGKILL` after a timeout
+    pub async fn stop(&self) -> Result<()> {
+        self.control("stop", &[])
+            .await
+            .map_err(|e| Error::signal_failed(15, e))
+    }
+
+    /// Send a signal to the container's main process
+    pub async fn signal(&self, signal: i32, name: &str) -> Result<()> {
+        self.control("kill", &[&format!("--signal={}", name)])
+            .await
+            .map_err(|e| Error::signal_failed(signal, e))
+    }
+
+    /// Get rid of the container without waiting, for `Drop`
+    pub fn discard(&self) {
+        let cmd = if self.remove {
+            self.command("rm", &["--force"])
+        } else {
+            self.command("kill", &[])
+        };
+        // async-process reaps the child once it exits
+        let _ = cmd
+            .prepare()
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+    }
+
+    /// `docker <cli> <subcommand> <args> <name>`
+    fn command(&self, subcommand: &str, args: &[&str]) -> Command {
+        let mut cmd = Command::new("docker");
+        cmd.args(&self.cli)
+            .arg(subcommand)
+            .args(args)
+            .arg(&self.name);
+        cmd
+    }
+
+    async fn control(&self, subcommand: &str, args: &[&str]) -> std::result::Result<(), String> {
+        let output = self
+            .command(subcommand, args)
+            .prepare()
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run docker: {}", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .iter()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_run_command() {
+        let container = DockerContainer::new("postgres:15")
+            .with_name("harness-postgres")
+            .with_env("POSTGRES_PASSWORD", "secret")
+            .with_volume("/tmp/pgdata", "/var/lib/postgresql/data")
+            .with_working_dir("/var/lib/postgresql");
+        let command = Command::builder("postgres")
+            .args(["-c", "max_connections=200"])
+            .env("PGDATA", "/var/lib/postgresql/data")
+            .build();
+
+        let (cmd, control) = DockerControl::run(&container, &command);
+        assert_eq!(cmd.get_program(), "docker");
+        assert_eq!(
+            args(&cmd),
+            [
+                "run",
+                "--name",
+                "harness-postgres",
+                "--rm",
+                "--env",
+                "PGDATA=/var/lib/postgresql/data",
+                "--env",
+                "POSTGRES_PASSWORD=secret",
+                "--volume",
+                "/tmp/pgdata:/var/lib/postgresql/data",
+                "--workdir",
+                "/var/lib/postgresql",
+                "postgres:15",
+                "postgres",
+                "-c",
+                "max_connections=200"
+            ]
+        );
+        assert_eq!(
+            args(&control.command("kill", &["--signal=SIGHUP"])),
+            ["kill", "--signal=SIGHUP", "harness-postgres"]
+        );
+    }
+
+    #[test]
+    fn test_run_image_default_command() {
+        let container = DockerContainer::new("ipfs/kubo").with_remove_on_exit(false);
+        let (cmd, control) = DockerControl::run(&container, &Command::new(""));
+
+        let args = args(&cmd);
+        assert_eq!(args.last().unwrap(), "ipfs/kubo");
+        assert!(!args.contains(&"--rm".to_string()));
+        // Unnamed containers get a unique name to control them by
+        assert!(control.name.starts_with("harness-"));
+        let (_, other) = DockerControl::run(&container, &Command::new(""));
+        assert_ne!(control.name, other.name);
+    }
+
+    #[test]
+    fn test_run_like_command() {
+        let container = DockerContainer::new("alpine");
+        let size = crate::PtySize::new(30, 100);
+        let timeout = std::time::Duration::from_secs(5);
+        let command = Command::builder("top").pty(size).timeout(timeout).build();
+
+        let (cmd, _) = DockerControl::run(&container, &command);
+        assert!(args(&cmd).contains(&"--interactive".to_string()));
+        assert!(args(&cmd).contains(&"--tty".to_string()));
+        assert_eq!(cmd.get_pty(), Some(size));
+        assert_eq!(cmd.get_timeout(), Some(timeout));
+
+        let service = ComposeService::new("compose.yaml", "migrate");
+        let (cmd, _) = DockerControl::compose_up(&service, &command);
+        assert_eq!(cmd.get_pty(), Some(size));
+        assert_eq!(cmd.get_timeout(), Some(timeout));
+    }
+
+    #[test]
+    fn test_compose_commands() {
+        let service =
+            ComposeService::new("/srv/stack/compose.yaml", "graph-node").with_project_name("test");
+        let command = Command::builder("ignored").env("TAG", "v0.35").build();
+
+        let (cmd, control) = DockerControl::compose_up(&service, &command);
+        assert_eq!(
+            args(&cmd),
+            [
+                "compose",
+                "--file",
+                "/srv/stack/compose.yaml",
+                "--project-name",
+                "test",
+                "up",
+                "--no-log-prefix",
+                "--attach",
+                "graph-node",
+                "--exit-code-from",
+                "graph-node",
+                "--no-deps",
+                "graph-node"
+            ]
+        );
+        assert_eq!(
+            cmd.get_envs().get(std::ffi::OsStr::new("TAG")),
+            Some(&"v0.35".into())
+        );
+        assert_eq!(
+            args(&control.command("stop", &[])),
+            [
+                "compose",
+                "--file",
+                "/srv/stack/compose.yaml",
+                "--project-name",
+                "test",
+                "stop",
+                "graph-node"
+            ]
+        );
+
+        // Dependencies come up too only when asked for
+        let (cmd, _) =
+            DockerControl::compose_up(&service.clone().with_dependencies(true), &command);
+        assert!(!args(&cmd).contains(&"--no-deps".to_string()));
+
+        assert_eq!(
+            args(&service.command(&["ps", "--quiet"]))[5..],
+            ["ps", "--quiet", "graph-node"]
+        );
+
+        let (cmd, _) = DockerControl::compose_up(&service.with_detach(true), &command);
+        assert_eq!(
+            args(&cmd)[5..],
+            ["up", "--detach", "--no-deps", "graph-node"]
+        );
+    }
+}
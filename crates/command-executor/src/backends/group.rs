@@ -0,0 +1,152 @@
+//! Process groups of managed processes launched by
+//! [`LocalLauncher`](super::LocalLauncher)
+//!
+//! A managed process leads a session of its own, so signals go to its whole
+//! process group. Descendants that moved to another group of the session are
+//! still found through the session, which on Linux is read from `/proc`.
+
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+/// How often the group is checked while waiting for it to empty
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How long killed descendants get to go away
+const KILL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A process group created for a managed process
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ProcessGroup {
+    /// Group ID, the leader's PID
+    pgid: i32,
+    /// Whether the leader also leads a session of its own
+    session: bool,
+}
+
+impl ProcessGroup {
+    /// The group led by `pid`, in a session of its own if `session`
+    pub fn led_by(pid: u32, session: bool) -> Self {
+        Self {
+            pgid: pid as i32,
+            session,
+        }
+    }
+
+    /// Send a signal to every process of the group
+    pub fn signal(&self, signal: Signal) -> Result<()> {
+        signal::killpg(Pid::from_raw(self.pgid), signal).map_err(|e| {
+            Error::signal_failed(signal as i32, format!("process group {}: {}", self.pgid, e))
+        })
+    }
+
+    /// Live processes of the group, and of the session it leads
+    #[cfg(target_os = "linux")]
+    pub fn members(&self) -> Vec<i32> {
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        let ours = std::process::id() as i32;
+        entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<i32>().ok())
+            .filter(|&pid| pid != ours && self.contains(pid))
+            .collect()
+    }
+
+    /// Whether a live process is in the group or its session
+    #[cfg(target_os = "linux")]
+    fn contains(&self, pid: i32) -> bool {
+        let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) else {
+            return false;
+        };
+        // `pid (comm) state ppid pgrp session ...`; comm may contain spaces
+        let fields: Vec<_> = stat
+            .rsplit_once(')')
+            .map_or(Vec::new(), |(_, rest)| rest.split_whitespace().collect());
+        let id = |field: &str| field.parse::<i32>().ok();
+        match fields[..] {
+            [state, _ppid, pgrp, session, ..] => {
+                state != "Z"
+                    && (id(pgrp) == Some(self.pgid)
+                        || (self.session && id(session) == Some(self.pgid)))
+            }
+            _ => false,
+        }
+    }
+
+    /// Live processes of the group
+    #[cfg(not(target_os = "linux"))]
+    pub fn members(&self) -> Vec<i32> {
+        match signal::killpg(Pid::from_raw(self.pgid), None) {
+            Ok(()) => vec![self.pgid],
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Wait until the group is empty, killing whatever is left at `deadline`
+    ///
+    /// Fails if processes of the group survive `SIGKILL`.
+    pub async fn reap(&self, deadline: Instant) -> Result<()> {
+        let mut remaining = self.members();
+        while !remaining.is_empty() && Instant::now() < deadline {
+            async_io::Timer::after(POLL_INTERVAL).await;
+            remaining = self.members();
+        }
+        if remaining.is_empty() {
+            return Ok(());
+        }
+
+        tracing::debug!(
+            "Killing {} remaining processes of group {}",
+            remaining.len(),
+            self.pgid
+        );
+        let _ = self.signal(Signal::SIGKILL);
+        for pid in &remaining {
+            let _ = signal::kill(Pid::from_raw(*pid), Signal::SIGKILL);
+        }
+
+        let deadline = Instant::now() + KILL_TIMEOUT;
+        while Instant::now() < deadline {
+            async_io::Timer::after(POLL_INTERVAL).await;
+            remaining = self.members();
+            if remaining.is_empty() {
+                return Ok(());
+            }
+        }
+        Err(Error::signal_failed(
+            Signal::SIGKILL as i32,
+            format!("processes {:?} of group {} survived", remaining, self.pgid),
+        ))
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_members() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+        let pid = child.id() as i32;
+
+        // Our own group, which we are never counted in
+        let ours = ProcessGroup::led_by(nix::unistd::getpgrp().as_raw() as u32, false);
+        assert!(ours.members().contains(&pid));
+        assert!(!ours.members().contains(&(std::process::id() as i32)));
+        assert!(
+            ProcessGroup::led_by(i32::MAX as u32, true)
+                .members()
+                .is_empty()
+        );
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(!ours.members().contains(&pid));
+    }
+}
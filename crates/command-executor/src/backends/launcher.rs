@@ -2,18 +2,25 @@
 
 use async_process::{Child, Stdio};
 use async_trait::async_trait;
+use chrono::Utc;
 use futures::stream::Stream;
 use futures_lite::io::{AsyncBufReadExt, BufReader, Lines};
 use std::pin::Pin;
 use std::task::{Context, Poll};
-
-use crate::command::Command;
+use std::time::{Duration, Instant};
+
+use super::docker::DockerControl;
+use super::group::ProcessGroup;
+use super::limits::Isolation;
+use super::pty::{self, Pty, PtyOutput};
+use super::systemd::{JournalEntry, SystemdUnit, parse_journal_entry};
+use crate::command::{Command, PtySize};
 use crate::error::{Error, Result};
 use crate::event::{LogFilter, LogSource, NoOpFilter, ProcessEvent, ProcessEventType};
 use crate::launcher::Launcher;
-use crate::process::{ExitStatus, ProcessHandle};
+use crate::process::{ExitStatus, ProcessHandle, terminate_within};
 use crate::stdin::StdinHandle;
-use crate::target::Target;
+use crate::target::{LimitEnforcement, Target};
 
 /// Launcher for executing processes locally
 #[derive(Debug, Clone, Copy)]
@@ -21,12 +28,37 @@ pub struct LocalLauncher;
 
 /// A handle to control a local process (launched by us)
 pub struct LocalProcessHandle {
-    /// The underlying child process
-    child: Child,
+    /// The underlying child process, systemd unit or container
+    process: LocalProcess,
     /// Whether to kill the process on drop
     kill_on_drop: bool,
     /// Handle for stdin (if available)
     stdin: Option<StdinHandle>,
+    /// Resource limits and credentials the process was launched with
+    isolation: Option<Isolation>,
+    /// Process group signals go to: for managed processes, and commands
+    /// launched on a terminal or with a timeout or cancellation token
+    group: Option<ProcessGroup>,
+    /// The pseudo-terminal the process runs on, if any
+    pty: Option<Pty>,
+}
+
+/// What a [`LocalProcessHandle`] controls
+enum LocalProcess {
+    /// A child process
+    Child(Child),
+    /// A systemd unit and the `journalctl` following its output
+    ///
+    /// The unit outlives the handle; only `journalctl` is killed on drop.
+    Unit { unit: SystemdUnit, journal: Child },
+    /// A container and the docker CLI attached to it
+    ///
+    /// The CLI's exit status is the container's. On drop the container is
+    /// removed (or killed, if it is kept), not just the CLI.
+    Container {
+        client: Child,
+        control: DockerControl,
+    },
 }
 
 /// Stream of process events
@@ -34,9 +66,14 @@ pub struct ProcessEventStream {
     _service_name: String,
     stdout: Option<Lines<BufReader<async_process::ChildStdout>>>,
     stderr: Option<Lines<BufReader<async_process::ChildStderr>>>,
+    /// Output of a process running on a pseudo-terminal, unfiltered
+    pty: Option<PtyOutput>,
     filter: Box<dyn LogFilter + Send>,
     started_sent: bool,
     child_id: u32,
+    /// Whether stdout is `journalctl --output=json`, ending once the unit
+    /// went down
+    journal: bool,
 }
 
 #[async_trait]
@@ -51,75 +88,272 @@ impl Launcher for LocalLauncher {
         mut command: Command,
     ) -> Result<(Self::EventStream, Self::Handle)> {
         match target {
-            Target::Command | Target::ManagedProcess(_) => {
-                // Take stdin channel if provided
+            Target::ManagedProcess(managed) => {
                 let stdin_channel = command.take_stdin_channel();
-
-                // Prepare the command for execution
-                let mut async_cmd = command.prepare();
-
-                // Configure stdio for streaming
-                async_cmd.stdout(Stdio::piped());
-                async_cmd.stderr(Stdio::piped());
-
-                // Always configure stdin as piped so we can write to it
-                async_cmd.stdin(Stdio::piped());
-
-                let mut child = async_cmd
-                    .spawn()
-                    .map_err(|e| Error::spawn_failed(format!("Failed to spawn process: {}", e)))?;
-
-                let child_id = child.id();
-
-                let stdout = child.stdout.take().map(|s| BufReader::new(s).lines());
-                let stderr = child.stderr.take().map(|s| BufReader::new(s).lines());
-                let stdin = child.stdin.take();
-
-                // TODO: Get service name from elsewhere (passed from Executor?)
-                let service_name = "local_process".to_string();
-
-                let events = ProcessEventStream {
-                    _service_name: service_name,
-                    stdout,
-                    stderr,
-                    filter: Box::new(NoOpFilter),
-                    started_sent: false,
-                    child_id,
+                let (prepared, isolation) = Isolation::prepare(managed, &command)?;
+                // The isolation hook already starts a session, if any
+                let spawned = match command.get_pty() {
+                    Some(size) => {
+                        spawn_on_pty(prepared, size, false, stdin_channel, "managed_process")?
+                    }
+                    None => spawn_prepared(prepared.into(), stdin_channel, "managed_process")?,
                 };
+                let child = spawned.child;
+                // A group joined rather than created isn't ours to signal
+                let group = match managed.process_group() {
+                    None => Some(ProcessGroup::led_by(child.id(), true)),
+                    Some(0) => Some(ProcessGroup::led_by(child.id(), false)),
+                    Some(_) => None,
+                };
+                let handle = LocalProcessHandle {
+                    process: LocalProcess::Child(child),
+                    kill_on_drop: true,
+                    stdin: spawned.stdin,
+                    isolation: Some(isolation),
+                    group,
+                    pty: spawned.pty,
+                };
+                Ok((spawned.events, handle))
+            }
 
-                let stdin_handle = stdin.map(|s| StdinHandle::new(s, stdin_channel));
-
+            Target::Command => {
+                let spawned = spawn(command, "local_process")?;
                 let handle = LocalProcessHandle {
-                    child,
+                    process: LocalProcess::Child(spawned.child),
                     kill_on_drop: true,
-                    stdin: stdin_handle,
+                    stdin: spawned.stdin,
+                    isolation: None,
+                    group: spawned.group,
+                    pty: spawned.pty,
                 };
+                Ok((spawned.events, handle))
+            }
 
-                Ok((events, handle))
+            Target::SystemdService(service) => {
+                let since = Utc::now();
+                let unit = SystemdUnit::start_service(service, &command).await?;
+                launch_unit(unit, since)
             }
 
-            _ => {
-                // Other target types not implemented yet
-                Err(Error::spawn_failed(
-                    "Target type not yet implemented for LocalLauncher",
-                ))
+            Target::SystemdPortable(portable) => {
+                let since = Utc::now();
+                let unit = SystemdUnit::start_portable(portable).await?;
+                launch_unit(unit, since)
+            }
+
+            Target::DockerContainer(container) => {
+                let (mut docker, control) = DockerControl::run(container, &command);
+                if let Some(channel) = command.take_stdin_channel() {
+                    docker.stdin_channel(channel);
+                }
+                launch_container(docker, control)
             }
+
+            Target::ComposeService(service) => {
+                let (docker, control) = DockerControl::compose_up(service, &command);
+                let (events, mut handle) = launch_container(docker, control)?;
+                // A detached service outlives the `up` that started it
+                handle.kill_on_drop = !service.is_detached();
+                Ok((events, handle))
+            }
+        }
+    }
+}
+
+/// A spawned child process and its stdio
+struct Spawned {
+    events: ProcessEventStream,
+    child: Child,
+    stdin: Option<StdinHandle>,
+    pty: Option<Pty>,
+    /// Process group the child leads, if it was given one
+    group: Option<ProcessGroup>,
+}
+
+/// Spawn a child process with piped stdio, or on a pseudo-terminal in a
+/// session of its own if the command asks for one
+///
+/// A command that may be stopped by a timeout or cancellation gets a process
+/// group of its own, so stopping it stops what it started as well.
+fn spawn(mut command: Command, service_name: &str) -> Result<Spawned> {
+    // Take stdin channel if provided
+    let stdin_channel = command.take_stdin_channel();
+
+    // Prepare the command for execution
+    match command.get_pty() {
+        Some(size) => spawn_on_pty(
+            command.prepare_std(),
+            size,
+            true,
+            stdin_channel,
+            service_name,
+        ),
+        None if command.is_stoppable() => {
+            use std::os::unix::process::CommandExt;
+
+            let mut prepared = command.prepare_std();
+            prepared.process_group(0);
+            let mut spawned = spawn_prepared(prepared.into(), stdin_channel, service_name)?;
+            spawned.group = Some(ProcessGroup::led_by(spawned.child.id(), false));
+            Ok(spawned)
         }
+        None => spawn_prepared(command.prepare(), stdin_channel, service_name),
     }
 }
 
+/// Spawn a prepared command on a new pseudo-terminal
+///
+/// A process in a new session leads it, and it hangs up with the terminal;
+/// it is signalled as a whole.
+fn spawn_on_pty(
+    command: std::process::Command,
+    size: PtySize,
+    new_session: bool,
+    stdin_channel: Option<async_channel::Receiver<String>>,
+    service_name: &str,
+) -> Result<Spawned> {
+    let (child, output, input, pty) = pty::spawn(command, size, new_session)?;
+    let events = ProcessEventStream {
+        _service_name: service_name.to_string(),
+        stdout: None,
+        stderr: None,
+        pty: Some(output),
+        filter: Box::new(NoOpFilter),
+        started_sent: false,
+        child_id: child.id(),
+        journal: false,
+    };
+    let group = new_session.then(|| ProcessGroup::led_by(child.id(), true));
+    Ok(Spawned {
+        events,
+        child,
+        stdin: Some(StdinHandle::pty(input, stdin_channel)),
+        pty: Some(pty),
+        group,
+    })
+}
+
+/// Spawn a prepared command with piped stdio
+fn spawn_prepared(
+    mut async_cmd: async_process::Command,
+    stdin_channel: Option<async_channel::Receiver<String>>,
+    service_name: &str,
+) -> Result<Spawned> {
+    // Configure stdio for streaming
+    async_cmd.stdout(Stdio::piped());
+    async_cmd.stderr(Stdio::piped());
+
+    // Always configure stdin as piped so we can write to it
+    async_cmd.stdin(Stdio::piped());
+
+    let mut child = async_cmd
+        .spawn()
+        .map_err(|e| Error::spawn_failed(format!("Failed to spawn process: {}", e)))?;
+
+    let child_id = child.id();
+
+    let stdout = child.stdout.take().map(|s| BufReader::new(s).lines());
+    let stderr = child.stderr.take().map(|s| BufReader::new(s).lines());
+    let stdin = child.stdin.take();
+
+    // TODO: Get service name from elsewhere (passed from Executor?)
+    let events = ProcessEventStream {
+        _service_name: service_name.to_string(),
+        stdout,
+        stderr,
+        pty: None,
+        filter: Box::new(NoOpFilter),
+        started_sent: false,
+        child_id,
+        journal: false,
+    };
+
+    let stdin_handle = stdin.map(|s| StdinHandle::new(s, stdin_channel));
+
+    Ok(Spawned {
+        events,
+        child,
+        stdin: stdin_handle,
+        pty: None,
+        group: None,
+    })
+}
+
+/// Run a container through the docker CLI in the foreground
+fn launch_container(
+    docker: Command,
+    control: DockerControl,
+) -> Result<(ProcessEventStream, LocalProcessHandle)> {
+    let spawned = spawn(docker, "docker_container")?;
+    let handle = LocalProcessHandle {
+        process: LocalProcess::Container {
+            client: spawned.child,
+            control,
+        },
+        kill_on_drop: true,
+        stdin: spawned.stdin,
+        isolation: None,
+        group: spawned.group,
+        pty: spawned.pty,
+    };
+    Ok((spawned.events, handle))
+}
+
+/// Follow a started unit's journal
+fn launch_unit(
+    unit: SystemdUnit,
+    since: chrono::DateTime<Utc>,
+) -> Result<(ProcessEventStream, LocalProcessHandle)> {
+    let mut journal = unit.follow_journal(since)?;
+    let stdout = journal.stdout.take().map(|s| BufReader::new(s).lines());
+
+    let events = ProcessEventStream {
+        _service_name: "systemd_unit".to_string(),
+        stdout,
+        stderr: None,
+        pty: None,
+        filter: Box::new(NoOpFilter),
+        // A unit that already finished has no main process to report
+        started_sent: unit.main_pid().is_none(),
+        child_id: unit.main_pid().unwrap_or_default(),
+        journal: true,
+    };
+
+    let handle = LocalProcessHandle {
+        process: LocalProcess::Unit { unit, journal },
+        kill_on_drop: true,
+        stdin: None,
+        isolation: None,
+        group: None,
+        pty: None,
+    };
+
+    Ok((events, handle))
+}
+
 #[async_trait]
 impl ProcessHandle for LocalProcessHandle {
     fn pid(&self) -> Option<u32> {
-        Some(self.child.id())
+        match &self.process {
+            LocalProcess::Child(child) | LocalProcess::Container { client: child, .. } => {
+                Some(child.id())
+            }
+            LocalProcess::Unit { unit, .. } => unit.main_pid(),
+        }
     }
 
     async fn wait(&mut self) -> Result<ExitStatus> {
-        let status = self
-            .child
+        let child = match &mut self.process {
+            LocalProcess::Child(child) | LocalProcess::Container { client: child, .. } => child,
+            LocalProcess::Unit { unit, .. } => return unit.wait().await,
+        };
+        let status = child
             .status()
             .await
             .map_err(|e| Error::spawn_failed(format!("Failed to wait for process: {}", e)))?;
+        if let Some(isolation) = &mut self.isolation {
+            isolation.release();
+        }
 
         Ok(ExitStatus {
             code: status.code(),
@@ -132,19 +366,28 @@ impl ProcessHandle for LocalProcessHandle {
     }
 
     async fn terminate(&mut self) -> Result<()> {
+        let child = match &mut self.process {
+            LocalProcess::Child(child) => child,
+            LocalProcess::Unit { unit, .. } => return unit.stop().await,
+            LocalProcess::Container { control, .. } => return control.stop().await,
+        };
+
         #[cfg(unix)]
         {
             use nix::sys::signal::{self, Signal};
             use nix::unistd::Pid;
 
-            let pid = Pid::from_raw(self.child.id() as i32);
+            if let Some(group) = &self.group {
+                return group.signal(Signal::SIGTERM);
+            }
+            let pid = Pid::from_raw(child.id() as i32);
             signal::kill(pid, Signal::SIGTERM)
                 .map_err(|e| Error::signal_failed(15, e.to_string()))?;
         }
 
         #[cfg(not(unix))]
         {
-            self.child
+            child
                 .kill()
                 .map_err(|e| Error::signal_failed(-1, e.to_string()))?;
         }
@@ -153,19 +396,28 @@ impl ProcessHandle for LocalProcessHandle {
     }
 
     async fn kill(&mut self) -> Result<()> {
+        let child = match &mut self.process {
+            LocalProcess::Child(child) => child,
+            LocalProcess::Unit { unit, .. } => return unit.kill(9, "SIGKILL").await,
+            LocalProcess::Container { control, .. } => return control.signal(9, "SIGKILL").await,
+        };
+
         #[cfg(unix)]
         {
             use nix::sys::signal::{self, Signal};
             use nix::unistd::Pid;
 
-            let pid = Pid::from_raw(self.child.id() as i32);
+            if let Some(group) = &self.group {
+                return group.signal(Signal::SIGKILL);
+            }
+            let pid = Pid::from_raw(child.id() as i32);
             signal::kill(pid, Signal::SIGKILL)
                 .map_err(|e| Error::signal_failed(9, e.to_string()))?;
         }
 
         #[cfg(not(unix))]
         {
-            self.child
+            child
                 .kill()
                 .map_err(|e| Error::signal_failed(-1, e.to_string()))?;
         }
@@ -174,12 +426,21 @@ impl ProcessHandle for LocalProcessHandle {
     }
 
     async fn interrupt(&mut self) -> Result<()> {
+        let child = match &mut self.process {
+            LocalProcess::Child(child) => child,
+            LocalProcess::Unit { unit, .. } => return unit.kill(2, "SIGINT").await,
+            LocalProcess::Container { control, .. } => return control.signal(2, "SIGINT").await,
+        };
+
         #[cfg(unix)]
         {
             use nix::sys::signal::{self, Signal};
             use nix::unistd::Pid;
 
-            let pid = Pid::from_raw(self.child.id() as i32);
+            if let Some(group) = &self.group {
+                return group.signal(Signal::SIGINT);
+            }
+            let pid = Pid::from_raw(child.id() as i32);
             signal::kill(pid, Signal::SIGINT)
                 .map_err(|e| Error::signal_failed(2, e.to_string()))?;
         }
@@ -193,13 +454,36 @@ impl ProcessHandle for LocalProcessHandle {
         Ok(())
     }
 
+    /// For managed processes the whole group is stopped: the grace period
+    /// covers descendants as well, and whatever is left of the group (and the
+    /// session it leads) after it is killed. Fails if processes survive that.
+    async fn shutdown(&mut self, grace: Duration) -> Result<ExitStatus> {
+        let Some(group) = self.group else {
+            return terminate_within(self, grace).await;
+        };
+        let deadline = Instant::now() + grace;
+        let status = match terminate_within(self, grace).await {
+            // Nothing left to signal: the process already exited
+            Err(_) if group.members().is_empty() => self.wait().await?,
+            result => result?,
+        };
+        group.reap(deadline).await?;
+        Ok(status)
+    }
+
     async fn reload(&mut self) -> Result<()> {
+        let child = match &mut self.process {
+            LocalProcess::Child(child) => child,
+            LocalProcess::Unit { unit, .. } => return unit.reload().await,
+            LocalProcess::Container { control, .. } => return control.signal(1, "SIGHUP").await,
+        };
+
         #[cfg(unix)]
         {
             use nix::sys::signal::{self, Signal};
             use nix::unistd::Pid;
 
-            let pid = Pid::from_raw(self.child.id() as i32);
+            let pid = Pid::from_raw(child.id() as i32);
             signal::kill(pid, Signal::SIGHUP)
                 .map_err(|e| Error::signal_failed(1, e.to_string()))?;
         }
@@ -215,6 +499,24 @@ impl ProcessHandle for LocalProcessHandle {
 }
 
 impl LocalProcessHandle {
+    /// How the process's memory and CPU limits are enforced, if it has any
+    pub fn limit_enforcement(&self) -> Option<&LimitEnforcement> {
+        self.isolation.as_ref().and_then(Isolation::enforcement)
+    }
+
+    /// Resize the pseudo-terminal the process runs on
+    ///
+    /// Fails if the process wasn't launched on one (see [`Command::pty`]).
+    pub fn resize_pty(&mut self, size: PtySize) -> Result<()> {
+        match &self.pty {
+            Some(pty) => pty.resize(size),
+            None => Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "process has no pseudo-terminal",
+            ))),
+        }
+    }
+
     /// Get a mutable reference to the stdin handle
     pub fn stdin_mut(&mut self) -> Option<&mut StdinHandle> {
         self.stdin.as_mut()
@@ -238,11 +540,30 @@ impl LocalProcessHandle {
 
 impl Drop for LocalProcessHandle {
     fn drop(&mut self) {
-        if self.kill_on_drop {
-            // Try to kill the process if it's still running
-            // We use kill() instead of terminate() to ensure it dies
-            // This is synchronous kill, not the async method
-            let _ = self.child.kill();
+        match &mut self.process {
+            LocalProcess::Child(child) if self.kill_on_drop => {
+                // Try to kill the process if it's still running
+                // We use kill() instead of terminate() to ensure it dies
+                // This is synchronous kill, not the async method
+                let _ = child.kill();
+                #[cfg(unix)]
+                if let Some(group) = &self.group {
+                    let _ = group.signal(nix::sys::signal::Signal::SIGKILL);
+                }
+            }
+            LocalProcess::Child(_) => {}
+            LocalProcess::Unit { journal, .. } => {
+                let _ = journal.kill();
+            }
+            LocalProcess::Container { client, control } if self.kill_on_drop => {
+                let _ = client.kill();
+                #[cfg(unix)]
+                if let Some(group) = &self.group {
+                    let _ = group.signal(nix::sys::signal::Signal::SIGKILL);
+                }
+                control.discard();
+            }
+            LocalProcess::Container { .. } => {}
         }
     }
 }
@@ -258,9 +579,39 @@ impl Stream for ProcessEventStream {
             return Poll::Ready(Some(event));
         }
 
-        // Try to read from stdout
-        if let Some(stdout) = &mut self.stdout {
+        // Terminal output goes out as read
+        if let Some(output) = &mut self.pty {
+            match output.poll_chunk(cx) {
+                Poll::Ready(Some(chunk)) => {
+                    let event = ProcessEvent::new_with_data(ProcessEventType::Output, chunk);
+                    return Poll::Ready(Some(event));
+                }
+                Poll::Ready(None) => self.pty = None,
+                Poll::Pending => {}
+            }
+        }
+
+        // Try to read from stdout, skipping lines that produce no event
+        while let Some(stdout) = &mut self.stdout {
             match Pin::new(stdout).poll_next(cx) {
+                Poll::Ready(Some(Ok(line))) if self.journal => match parse_journal_entry(&line) {
+                    JournalEntry::Output(mut event) => {
+                        let source = match event.event_type {
+                            ProcessEventType::Stderr => LogSource::Stderr,
+                            _ => LogSource::Stdout,
+                        };
+                        let data = event.data.take().unwrap_or_default();
+                        if let Some(filtered) = self.filter.filter(&data, source) {
+                            event.data = Some(filtered.to_string());
+                            return Poll::Ready(Some(event));
+                        }
+                    }
+                    JournalEntry::UnitDown => {
+                        self.stdout = None;
+                        return Poll::Ready(None);
+                    }
+                    JournalEntry::Skip => {}
+                },
                 Poll::Ready(Some(Ok(line))) => {
                     // Apply filter
                     if let Some(filtered) = self.filter.filter(&line, LogSource::Stdout) {
@@ -270,8 +621,7 @@ impl Stream for ProcessEventStream {
                         );
                         return Poll::Ready(Some(event));
                     }
-                    // Line was filtered out, try again
-                    return self.poll_next(cx);
+                    // Line was filtered out, read the next one
                 }
                 Poll::Ready(Some(Err(_))) => {
                     // Error reading stdout, remove it
@@ -281,12 +631,12 @@ impl Stream for ProcessEventStream {
                     // Stdout closed
                     self.stdout = None;
                 }
-                Poll::Pending => {}
+                Poll::Pending => break,
             }
         }
 
         // Try to read from stderr
-        if let Some(stderr) = &mut self.stderr {
+        while let Some(stderr) = &mut self.stderr {
             match Pin::new(stderr).poll_next(cx) {
                 Poll::Ready(Some(Ok(line))) => {
                     // Apply filter
@@ -297,8 +647,7 @@ impl Stream for ProcessEventStream {
                         );
                         return Poll::Ready(Some(event));
                     }
-                    // Line was filtered out, try again
-                    return self.poll_next(cx);
+                    // Line was filtered out, read the next one
                 }
                 Poll::Ready(Some(Err(_))) => {
                     // Error reading stderr, remove it
@@ -308,12 +657,12 @@ impl Stream for ProcessEventStream {
                     // Stderr closed
                     self.stderr = None;
                 }
-                Poll::Pending => {}
+                Poll::Pending => break,
             }
         }
 
-        // If both streams are closed, the stream is exhausted
-        if self.stdout.is_none() && self.stderr.is_none() {
+        // If all streams are closed, the stream is exhausted
+        if self.stdout.is_none() && self.stderr.is_none() && self.pty.is_none() {
             return Poll::Ready(None);
         }
 
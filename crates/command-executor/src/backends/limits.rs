@@ -0,0 +1,368 @@
+//! Process groups, resource limits and user switching for managed processes
+//! launched by [`LocalLauncher`](super::LocalLauncher)
+//!
+//! Memory and CPU limits need a cgroup: a transient `systemd-run --scope`
+//! when systemd is running, otherwise a cgroup v2 group of our own. Without
+//! either, memory falls back to `RLIMIT_AS` and the CPU quota is not
+//! enforced. The session or process group, open file limit, nice level and
+//! user are set in the child between `fork` and `exec`.
+
+use nix::sys::resource::{Resource, setrlimit};
+use nix::unistd::{Gid, Pid, Uid, User};
+use std::ffi::CString;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::command::Command;
+use crate::error::{Error, Result};
+use crate::target::{LimitEnforcement, ManagedProcess, ResourceLimits};
+
+/// Where the cgroup v2 hierarchy is mounted
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Names the cgroups of processes launched by this process
+static CGROUPS: AtomicUsize = AtomicUsize::new(0);
+
+/// The user a process is switched to
+#[derive(Debug, Clone)]
+struct Credentials {
+    name: String,
+    uid: Uid,
+    gid: Gid,
+    groups: Vec<Gid>,
+}
+
+impl Credentials {
+    /// Look up a user by name or numeric uid
+    fn resolve(user: &str) -> Result<Self> {
+        let found = match user.parse::<u32>() {
+            Ok(uid) => User::from_uid(Uid::from_raw(uid)),
+            Err(_) => User::from_name(user),
+        }
+        .map_err(|e| Error::spawn_failed(format!("Failed to look up user '{}': {}", user, e)))?
+        .ok_or_else(|| Error::spawn_failed(format!("Unknown user '{}'", user)))?;
+
+        let name = CString::new(found.name.as_bytes())
+            .map_err(|_| Error::spawn_failed(format!("Invalid user name '{}'", found.name)))?;
+        let groups = nix::unistd::getgrouplist(&name, found.gid).map_err(|e| {
+            Error::spawn_failed(format!("Failed to look up groups of '{}': {}", user, e))
+        })?;
+
+        Ok(Self {
+            name: found.name,
+            uid: found.uid,
+            gid: found.gid,
+            groups,
+        })
+    }
+}
+
+/// Process group, limits and credentials applied to one launched process
+///
+/// Owns the cgroup created for the process, if any, and removes it once the
+/// process is gone.
+#[derive(Debug)]
+pub(crate) struct Isolation {
+    enforcement: Option<LimitEnforcement>,
+    /// The cgroup created for the process, until it is removed
+    cgroup: Option<PathBuf>,
+}
+
+impl Isolation {
+    /// Prepare the command for a managed process with its process group,
+    /// limits and user
+    pub fn prepare(
+        managed: &ManagedProcess,
+        command: &Command,
+    ) -> Result<(std::process::Command, Self)> {
+        let process_group = managed.process_group();
+        let limits = managed.limits();
+        let credentials = managed.user().map(Credentials::resolve).transpose()?;
+
+        let mut cgroup_procs = None;
+        let enforcement = if !limits.needs_cgroup() {
+            None
+        } else if systemd_available() {
+            Some(LimitEnforcement::SystemdScope)
+        } else {
+            match create_cgroup(limits) {
+                Ok((dir, procs)) => {
+                    cgroup_procs = Some(procs);
+                    Some(LimitEnforcement::Cgroup(dir))
+                }
+                Err(e) => {
+                    debug!("No cgroup for resource limits, using rlimits: {}", e);
+                    if limits.cpu_quota.is_some() {
+                        warn!("CPU quota needs systemd or cgroup v2 and is not enforced");
+                    }
+                    Some(LimitEnforcement::Rlimit)
+                }
+            }
+        };
+
+        let mut prepared = match &enforcement {
+            // The scope switches the user itself; the rest is inherited
+            // through `systemd-run`
+            Some(LimitEnforcement::SystemdScope) => command
+                .wrapped("systemd-run", scope_args(limits, credentials.as_ref()))
+                .prepare_std(),
+            _ => command.prepare_std(),
+        };
+        let credentials =
+            credentials.filter(|_| enforcement != Some(LimitEnforcement::SystemdScope));
+        let memory_rlimit = limits
+            .memory_max
+            .filter(|_| enforcement == Some(LimitEnforcement::Rlimit));
+        let nofile = limits.nofile;
+        let nice = limits.nice;
+
+        #[allow(unsafe_code)]
+        // SAFETY: the hook only makes system calls on values prepared before
+        // the fork; it doesn't allocate or take locks
+        unsafe {
+            use std::os::unix::process::CommandExt;
+            prepared.pre_exec(move || {
+                // A session of its own makes the process a group leader
+                // without a controlling terminal
+                match process_group {
+                    None => {
+                        nix::unistd::setsid()?;
+                    }
+                    Some(pgid) => nix::unistd::setpgid(Pid::from_raw(0), Pid::from_raw(pgid))?,
+                }
+                // Then join the cgroup, so everything below is accounted
+                if let Some(procs) = &cgroup_procs {
+                    let mut procs: &File = procs;
+                    procs.write_all(b"0")?;
+                }
+                if let Some(nofile) = nofile {
+                    setrlimit(Resource::RLIMIT_NOFILE, nofile, nofile)?;
+                }
+                if let Some(bytes) = memory_rlimit {
+                    setrlimit(Resource::RLIMIT_AS, bytes, bytes)?;
+                }
+                if let Some(nice) = nice
+                    && nix::libc::setpriority(nix::libc::PRIO_PROCESS, 0, nice) != 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+                // Groups before the gid, and both before giving up root
+                if let Some(credentials) = &credentials {
+                    nix::unistd::setgroups(&credentials.groups)?;
+                    nix::unistd::setgid(credentials.gid)?;
+                    nix::unistd::setuid(credentials.uid)?;
+                }
+                Ok(())
+            });
+        }
+
+        let cgroup = match &enforcement {
+            Some(LimitEnforcement::Cgroup(dir)) => Some(dir.clone()),
+            _ => None,
+        };
+        Ok((
+            prepared,
+            Self {
+                enforcement,
+                cgroup,
+            },
+        ))
+    }
+
+    /// How memory and CPU limits are enforced, if any are set
+    pub fn enforcement(&self) -> Option<&LimitEnforcement> {
+        self.enforcement.as_ref()
+    }
+
+    /// Remove the process's cgroup, which only succeeds once it exited
+    pub fn release(&mut self) {
+        if let Some(dir) = &self.cgroup
+            && std::fs::remove_dir(dir).is_ok()
+        {
+            self.cgroup = None;
+        }
+    }
+}
+
+impl Drop for Isolation {
+    fn drop(&mut self) {
+        let Some(dir) = self.cgroup.take() else {
+            return;
+        };
+        if std::fs::remove_dir(&dir).is_ok() {
+            return;
+        }
+        // The process was only just killed; give it a moment to exit
+        std::thread::spawn(move || {
+            for _ in 0..50 {
+                std::thread::sleep(Duration::from_millis(20));
+                if std::fs::remove_dir(&dir).is_ok() {
+                    return;
+                }
+            }
+            warn!("Failed to remove cgroup {}", dir.display());
+        });
+    }
+}
+
+/// Whether systemd is running and `systemd-run` is available
+fn systemd_available() -> bool {
+    Path::new("/run/systemd/system").exists()
+        && std::env::var_os("PATH").is_some_and(|path| {
+            std::env::split_paths(&path).any(|dir| dir.join("systemd-run").is_file())
+        })
+}
+
+/// `systemd-run` arguments running a command in a scope with the limits
+fn scope_args(limits: &ResourceLimits, credentials: Option<&Credentials>) -> Vec<String> {
+    let mut args = vec![
+        "--scope".to_string(),
+        "--quiet".to_string(),
+        "--collect".to_string(),
+    ];
+    if !nix::unistd::geteuid().is_root() {
+        args.push("--user".to_string());
+    }
+    if let Some(credentials) = credentials {
+        args.push(format!("--uid={}", credentials.name));
+        args.push(format!("--gid={}", credentials.gid));
+    }
+    if let Some(bytes) = limits.memory_max {
+        args.push(format!("--property=MemoryMax={}", bytes));
+    }
+    if let Some(quota) = limits.cpu_quota {
+        args.push(format!("--property=CPUQuota={}%", quota));
+    }
+    args.push("--".to_string());
+    args
+}
+
+/// Create a cgroup v2 group with the limits, returning it and its open
+/// `cgroup.procs` for the child to join
+fn create_cgroup(limits: &ResourceLimits) -> std::io::Result<(PathBuf, File)> {
+    let root = Path::new(CGROUP_ROOT);
+    if !root.join("cgroup.controllers").exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "no cgroup v2 hierarchy",
+        ));
+    }
+
+    // Controllers have to be enabled on every level above the group
+    let parent = root.join("harness");
+    std::fs::create_dir_all(&parent)?;
+    for dir in [root, parent.as_path()] {
+        std::fs::write(dir.join("cgroup.subtree_control"), "+memory +cpu")?;
+    }
+
+    let dir = parent.join(format!(
+        "{}-{}",
+        std::process::id(),
+        CGROUPS.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir(&dir)?;
+    let configure = || -> std::io::Result<File> {
+        if let Some(bytes) = limits.memory_max {
+            std::fs::write(dir.join("memory.max"), bytes.to_string())?;
+        }
+        if let Some(quota) = limits.cpu_quota {
+            std::fs::write(dir.join("cpu.max"), cpu_max(quota))?;
+        }
+        File::options().write(true).open(dir.join("cgroup.procs"))
+    };
+    match configure() {
+        Ok(procs) => Ok((dir, procs)),
+        Err(e) => {
+            let _ = std::fs::remove_dir(&dir);
+            Err(e)
+        }
+    }
+}
+
+/// `cpu.max` for a quota in percent of one CPU, per 100ms period
+fn cpu_max(quota: u32) -> String {
+    format!("{} 100000", u64::from(quota) * 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root() -> Credentials {
+        Credentials::resolve("root").unwrap()
+    }
+
+    #[test]
+    fn test_resolve_user() {
+        let by_name = root();
+        assert_eq!((by_name.uid.as_raw(), by_name.gid.as_raw()), (0, 0));
+        assert!(by_name.groups.contains(&Gid::from_raw(0)));
+        assert_eq!(Credentials::resolve("0").unwrap().name, "root");
+        assert!(Credentials::resolve("no-such-user-here").is_err());
+    }
+
+    #[test]
+    fn test_scope_args() {
+        let limits = ResourceLimits {
+            memory_max: Some(512 << 20),
+            cpu_quota: Some(150),
+            ..Default::default()
+        };
+        let args = scope_args(&limits, Some(&root())).join(" ");
+        let user = if nix::unistd::geteuid().is_root() {
+            ""
+        } else {
+            " --user"
+        };
+        assert_eq!(
+            args,
+            format!(
+                "--scope --quiet --collect{} --uid=root --gid=0 \
+                 --property=MemoryMax=536870912 --property=CPUQuota=150% --",
+                user
+            )
+        );
+
+        let command = Command::builder("anvil")
+            .arg("--port")
+            .env("RUST_LOG", "info")
+            .current_dir("/tmp")
+            .build();
+        let wrapped = command.wrapped("systemd-run", ["--scope", "--"]);
+        assert_eq!(wrapped.get_program(), "systemd-run");
+        assert_eq!(wrapped.get_args(), ["--scope", "--", "anvil", "--port"]);
+        assert_eq!(wrapped.get_envs(), command.get_envs());
+        assert_eq!(wrapped.get_current_dir(), Some(Path::new("/tmp")));
+    }
+
+    #[test]
+    fn test_limits_display() {
+        let limits = ResourceLimits {
+            memory_max: Some(512 << 20),
+            cpu_quota: Some(150),
+            nofile: Some(4096),
+            nice: Some(10),
+        };
+        assert_eq!(
+            limits.to_string(),
+            "memory 512M, cpu 150%, nofile 4096, nice 10"
+        );
+        assert_eq!(ResourceLimits::default().to_string(), "none");
+        assert!(ResourceLimits::default().is_empty());
+        let odd = ResourceLimits {
+            memory_max: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(odd.to_string(), "memory 1000");
+    }
+
+    #[test]
+    fn test_cpu_max() {
+        assert_eq!(cpu_max(50), "50000 100000");
+        assert_eq!(cpu_max(200), "200000 100000");
+    }
+}
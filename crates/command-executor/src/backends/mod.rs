@@ -1,10 +1,17 @@
 //! Backend implementations for different execution contexts
 //!
-//! This module provides the local execution backend.
-//! For SSH and Docker support, use the layered execution system instead.
+//! This module provides the local execution backend, which also launches
+//! systemd units and Docker containers on the local host.
+//! For SSH, and for running commands in existing containers, use the layered
+//! execution system instead.
 
 pub mod attacher;
+mod docker;
+mod group;
 pub mod launcher;
+mod limits;
+mod pty;
+mod systemd;
 
 pub use attacher::{LocalAttachedHandle, LocalAttacher};
 pub use launcher::{LocalLauncher, LocalProcessHandle};
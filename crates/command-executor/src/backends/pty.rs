@@ -0,0 +1,202 @@
+//! Pseudo-terminals for processes launched by [`LocalLauncher`](super::LocalLauncher)
+//!
+//! The child gets the terminal's slave side as stdin, stdout and stderr, and
+//! as its controlling terminal when it leads a session. We keep the master
+//! side: reads return everything the child wrote, writes are its input, and
+//! resizing it sends the child `SIGWINCH`.
+
+use async_io::Async;
+use async_process::Child;
+use futures::io::AsyncRead;
+use nix::fcntl::{FcntlArg, FdFlag, fcntl};
+use nix::libc;
+use nix::pty::{Winsize, openpty};
+use std::fs::File;
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::pin::Pin;
+use std::process::Stdio;
+use std::task::{Context, Poll};
+
+use crate::command::PtySize;
+use crate::error::{Error, Result};
+
+/// Bytes read from the terminal at a time
+const CHUNK_SIZE: usize = 4096;
+
+/// The master side of a child's pseudo-terminal
+pub(crate) struct Pty {
+    master: OwnedFd,
+}
+
+impl Pty {
+    /// Resize the terminal, which sends its foreground process group
+    /// `SIGWINCH`
+    pub(crate) fn resize(&self, size: PtySize) -> Result<()> {
+        let winsize = winsize(size);
+        #[allow(unsafe_code)]
+        // SAFETY: `TIOCSWINSZ` reads a `winsize` through the pointer, which
+        // outlives the call, from a descriptor we own
+        let result = unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &winsize) };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+/// Spawn a command on a new pseudo-terminal of the given size
+///
+/// With `new_session` the child starts a session of its own first;
+/// otherwise it only gets a controlling terminal if the command already
+/// makes it a session leader. Returns the child, the terminal's output, a
+/// writer for its input and the terminal itself.
+pub(crate) fn spawn(
+    mut command: std::process::Command,
+    size: PtySize,
+    new_session: bool,
+) -> Result<(Child, PtyOutput, Async<File>, Pty)> {
+    let pty = openpty(Some(&winsize(size)), None)
+        .map_err(|e| Error::spawn_failed(format!("Failed to open pseudo-terminal: {}", e)))?;
+    // Neither side may leak into other children; the child's stdio is
+    // duplicated from the slave
+    for fd in [&pty.master, &pty.slave] {
+        fcntl(fd.as_raw_fd(), FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+    }
+
+    #[allow(unsafe_code)]
+    // SAFETY: the hook only makes system calls; it doesn't allocate or take
+    // locks
+    unsafe {
+        use std::os::unix::process::CommandExt;
+        command.pre_exec(move || {
+            if new_session {
+                nix::unistd::setsid()?;
+            }
+            // Only a session leader can acquire a controlling terminal
+            let pid = nix::unistd::getpid();
+            if nix::unistd::getsid(None)? == pid && libc::ioctl(0, libc::TIOCSCTTY, 0) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    // Set on the async command, which would otherwise inherit ours
+    let mut command = async_process::Command::from(command);
+    command.stdin(Stdio::from(pty.slave.try_clone()?));
+    command.stdout(Stdio::from(pty.slave.try_clone()?));
+    command.stderr(Stdio::from(pty.slave));
+    let child = command
+        .spawn()
+        .map_err(|e| Error::spawn_failed(format!("Failed to spawn process: {}", e)))?;
+    // Drop our copies of the slave, so reads see the end of output once the
+    // child and its descendants are done with the terminal
+    drop(command);
+
+    let input = Async::new(File::from(pty.master.try_clone()?))?;
+    let output = PtyOutput {
+        reader: Async::new(File::from(pty.master.try_clone()?))?,
+        pending: Vec::new(),
+        done: false,
+    };
+    Ok((child, output, input, Pty { master: pty.master }))
+}
+
+/// Output read from a pseudo-terminal, as text
+pub(crate) struct PtyOutput {
+    reader: Async<File>,
+    /// Bytes of a character split across reads
+    pending: Vec<u8>,
+    done: bool,
+}
+
+impl PtyOutput {
+    /// Poll for the next chunk of output, None once the terminal is closed
+    ///
+    /// Chunks hold whole characters; invalid UTF-8 is replaced.
+    pub(crate) fn poll_chunk(&mut self, cx: &mut Context<'_>) -> Poll<Option<String>> {
+        let mut buf = [0u8; CHUNK_SIZE];
+        loop {
+            if self.done {
+                if self.pending.is_empty() {
+                    return Poll::Ready(None);
+                }
+                let rest = String::from_utf8_lossy(&self.pending).into_owned();
+                self.pending.clear();
+                return Poll::Ready(Some(rest));
+            }
+            match Pin::new(&mut self.reader).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(0)) => self.done = true,
+                Poll::Ready(Ok(n)) => {
+                    self.pending.extend_from_slice(&buf[..n]);
+                    let text = take_utf8(&mut self.pending);
+                    if !text.is_empty() {
+                        return Poll::Ready(Some(text));
+                    }
+                }
+                Poll::Ready(Err(e)) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                // Linux fails reads with EIO once the slave side is closed
+                Poll::Ready(Err(_)) => self.done = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Take the text decoded from `bytes`, leaving an incomplete trailing
+/// character for the next read
+fn take_utf8(bytes: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                text.push_str(valid);
+                bytes.clear();
+                return text;
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                text.push_str(&String::from_utf8_lossy(&bytes[..valid]));
+                match e.error_len() {
+                    None => {
+                        bytes.drain(..valid);
+                        return text;
+                    }
+                    Some(invalid) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        bytes.drain(..valid + invalid);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn winsize(size: PtySize) -> Winsize {
+    Winsize {
+        ws_row: size.rows,
+        ws_col: size.cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_utf8() {
+        // "é" split across reads
+        let mut bytes = b"caf\xc3".to_vec();
+        assert_eq!(take_utf8(&mut bytes), "caf");
+        assert_eq!(bytes, b"\xc3");
+        bytes.extend_from_slice(b"\xa9!");
+        assert_eq!(take_utf8(&mut bytes), "é!");
+        assert!(bytes.is_empty());
+
+        let mut bytes = b"a\xffb".to_vec();
+        assert_eq!(take_utf8(&mut bytes), "a\u{fffd}b");
+        assert!(bytes.is_empty());
+    }
+}
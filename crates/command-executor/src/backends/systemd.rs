@@ -0,0 +1,455 @@
+//! systemd units launched by [`LocalLauncher`](super::LocalLauncher)
+//!
+//! Transient units are created with `systemd-run`, installed units are started
+//! with `systemctl` and portable images are attached with `portablectl` first.
+//! Output is read back from the journal (`journalctl -f -o json`), and process
+//! control maps to `systemctl stop`, `systemctl kill` and `systemctl reload`.
+
+use async_process::{Child, Stdio};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::command::Command;
+use crate::error::{Error, Result};
+use crate::event::{ProcessEvent, ProcessEventType};
+use crate::process::ExitStatus;
+use crate::target::{SystemdPortable, SystemdService};
+
+/// How often [`SystemdUnit::wait`] checks the unit's state
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Journal message IDs the service manager logs when a unit goes down
+/// (stopped, failed to start, failed, deactivated successfully)
+const UNIT_DOWN_MESSAGES: [&str; 4] = [
+    "9d1aaa27d60140bd96365438aad20286",
+    "be02cf6855d2428ba40df7e9d022f03d",
+    "d9b373ed55a64feb8242e02dbe79a49c",
+    "7ad2d189f7e94e70a38c781354912448",
+];
+
+/// Unit properties read to tell whether and how a unit finished
+const STATE_PROPERTIES: &str = "ActiveState,Result,ExecMainCode,ExecMainStatus";
+
+/// A unit started by the launcher
+#[derive(Debug, Clone)]
+pub(crate) struct SystemdUnit {
+    unit: String,
+    /// Managed by the user's service manager rather than the system's
+    user: bool,
+    /// Created with `systemd-run`, so it has no reload command and is
+    /// unloaded once it finished
+    transient: bool,
+    main_pid: Option<u32>,
+}
+
+/// A journal entry of a launched unit
+#[derive(Debug, Clone)]
+pub(crate) enum JournalEntry {
+    /// Something the unit logged
+    Output(ProcessEvent),
+    /// The service manager reported the unit went down
+    UnitDown,
+    /// Anything else the service manager logged about the unit
+    Skip,
+}
+
+impl SystemdUnit {
+    /// Start a systemd service target, running `command` in a transient unit
+    /// unless the target names an installed one
+    pub async fn start_service(service: &SystemdService, command: &Command) -> Result<Self> {
+        let unit = Self {
+            unit: service.unit_name().to_string(),
+            user: service.user_manager(),
+            transient: service.is_transient(),
+            main_pid: None,
+        };
+
+        if unit.transient {
+            // A failed unit of a previous run would block the name
+            let _ = output(unit.systemctl(["reset-failed"])).await;
+            output(systemd_run(service, command))
+                .await
+                .map_err(|e| Error::spawn_failed(format!("systemd-run failed: {}", e)))?;
+        } else {
+            output(unit.systemctl(["start"])).await.map_err(|e| {
+                Error::spawn_failed(format!("Failed to start {}: {}", unit.unit, e))
+            })?;
+        }
+        unit.with_main_pid().await
+    }
+
+    /// Attach a portable image unless it already is, then start its unit
+    pub async fn start_portable(portable: &SystemdPortable) -> Result<Self> {
+        let unit = Self {
+            unit: portable.unit_name().to_string(),
+            user: false,
+            transient: false,
+            main_pid: None,
+        };
+
+        let mut attached = Command::new("portablectl");
+        attached.args(["is-attached", portable.image_name()]);
+        if output(attached).await.is_err() {
+            let mut attach = Command::new("portablectl");
+            attach.args(["attach", portable.image_name()]);
+            output(attach).await.map_err(|e| {
+                Error::spawn_failed(format!("Failed to attach {}: {}", portable.image_name(), e))
+            })?;
+        }
+
+        output(unit.systemctl(["start"]))
+            .await
+            .map_err(|e| Error::spawn_failed(format!("Failed to start {}: {}", unit.unit, e)))?;
+        unit.with_main_pid().await
+    }
+
+    async fn with_main_pid(mut self) -> Result<Self> {
+        let pid = output(self.systemctl(["show", "--property=MainPID", "--value"]))
+            .await
+            .map_err(Error::spawn_failed)?;
+        self.main_pid = pid.trim().parse().ok().filter(|pid| *pid != 0);
+        Ok(self)
+    }
+
+    /// PID of the unit's main process when it was started
+    pub fn main_pid(&self) -> Option<u32> {
+        self.main_pid
+    }
+
+    /// Follow the unit's journal from `since` on
+    pub fn follow_journal(&self, since: DateTime<Utc>) -> Result<Child> {
+        let mut journal = self.journal_command(since).prepare();
+        journal.stdout(Stdio::piped());
+        journal.stderr(Stdio::null());
+        journal
+            .spawn()
+            .map_err(|e| Error::spawn_failed(format!("Failed to start journalctl: {}", e)))
+    }
+
+    /// Wait for the unit to go down and return how its main process exited
+    pub async fn wait(&self) -> Result<ExitStatus> {
+        loop {
+            let properties =
+                output(self.systemctl(["show", &format!("--property={}", STATE_PROPERTIES)]))
+                    .await
+                    .map_err(|e| {
+                        Error::spawn_failed(format!("Failed to query {}: {}", self.unit, e))
+                    })?;
+            if let Some(status) = exit_status(&parse_properties(&properties)) {
+                if self.transient {
+                    // Unload a failed transient unit so its name can be reused
+                    let _ = output(self.systemctl(["reset-failed"])).await;
+                }
+                return Ok(status);
+            }
+            async_io::Timer::after(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Stop the unit the way its unit file says to
+    pub async fn stop(&self) -> Result<()> {
+        output(self.systemctl(["stop"]))
+            .await
+            .map(|_| ())
+            .map_err(|e| Error::signal_failed(15, e))
+    }
+
+    /// Send a signal to the unit's processes
+    pub async fn kill(&self, signal: i32, name: &str) -> Result<()> {
+        output(self.systemctl(["kill", &format!("--signal={}", name)]))
+            .await
+            .map(|_| ())
+            .map_err(|e| Error::signal_failed(signal, e))
+    }
+
+    /// Reload the unit, or send `SIGHUP` to a transient one, which has no
+    /// reload command
+    pub async fn reload(&self) -> Result<()> {
+        if self.transient {
+            return self.kill(1, "SIGHUP").await;
+        }
+        output(self.systemctl(["reload"]))
+            .await
+            .map(|_| ())
+            .map_err(|e| Error::signal_failed(1, e))
+    }
+
+    /// `systemctl <args> <unit>` for the unit's service manager
+    fn systemctl<const N: usize>(&self, args: [&str; N]) -> Command {
+        let mut cmd = Command::new("systemctl");
+        if self.user {
+            cmd.arg("--user");
+        }
+        cmd.args(args).arg(&self.unit);
+        cmd
+    }
+
+    fn journal_command(&self, since: DateTime<Utc>) -> Command {
+        let mut cmd = Command::new("journalctl");
+        if self.user {
+            cmd.arg("--user");
+        }
+        cmd.args(["--follow", "--output=json", "--no-pager"])
+            .arg(format!(
+                "--since=@{}.{:06}",
+                since.timestamp(),
+                since.timestamp_subsec_micros()
+            ))
+            .arg(format!("--unit={}", self.unit));
+        cmd
+    }
+}
+
+/// `systemd-run` invocation creating a transient service for `command`
+fn systemd_run(service: &SystemdService, command: &Command) -> Command {
+    let mut cmd = Command::new("systemd-run");
+    if service.user_manager() {
+        cmd.arg("--user");
+    }
+    cmd.arg(format!("--unit={}", service.unit_name()))
+        .args(["--quiet", "--property=Type=exec"]);
+    if let Some(dir) = command.get_current_dir() {
+        cmd.arg(format!("--working-directory={}", dir.display()));
+    }
+    let mut env: Vec<_> = command.get_envs().iter().collect();
+    env.sort();
+    for (key, value) in env {
+        cmd.arg(format!(
+            "--setenv={}={}",
+            key.to_string_lossy(),
+            value.to_string_lossy()
+        ));
+    }
+    cmd.arg("--")
+        .arg(command.get_program())
+        .args(command.get_args());
+    cmd
+}
+
+/// Run a control command, returning its stdout or, if it failed, its stderr
+async fn output(command: Command) -> std::result::Result<String, String> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let output = command
+        .prepare()
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// `Key=Value` lines as printed by `systemctl show`
+fn parse_properties(output: &str) -> HashMap<&str, &str> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .collect()
+}
+
+/// Exit status of a unit that went down, `None` while it's still up
+fn exit_status(properties: &HashMap<&str, &str>) -> Option<ExitStatus> {
+    match properties.get("ActiveState").copied() {
+        Some("inactive" | "failed") => {}
+        _ => return None,
+    }
+
+    let status = properties
+        .get("ExecMainStatus")
+        .and_then(|status| status.parse().ok());
+    let succeeded = matches!(properties.get("Result").copied(), Some("success") | None);
+    // `ExecMainCode` is a `CLD_*` code: 1 exited, 2 killed, 3 dumped core
+    let (code, signal) = match properties.get("ExecMainCode").copied() {
+        Some("1") => (status, None),
+        Some("2" | "3") => (None, status),
+        // Never ran, or collected after it finished
+        _ => (Some(if succeeded { 0 } else { 1 }), None),
+    };
+    Some(ExitStatus {
+        code,
+        #[cfg(unix)]
+        signal,
+    })
+}
+
+/// Interpret a line of `journalctl --output=json`
+pub(crate) fn parse_journal_entry(line: &str) -> JournalEntry {
+    let Ok(entry) = serde_json::from_str::<Value>(line) else {
+        return JournalEntry::Skip;
+    };
+
+    if entry["MESSAGE_ID"]
+        .as_str()
+        .is_some_and(|id| UNIT_DOWN_MESSAGES.contains(&id))
+    {
+        return JournalEntry::UnitDown;
+    }
+    // The service manager's messages about the unit name it in `UNIT` or
+    // `USER_UNIT`; the unit's own output doesn't
+    if !entry["UNIT"].is_null() || !entry["USER_UNIT"].is_null() {
+        return JournalEntry::Skip;
+    }
+
+    let message = match &entry["MESSAGE"] {
+        Value::String(message) => message.clone(),
+        // Messages that aren't valid UTF-8 come as byte arrays
+        Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|b| b.as_u64().map(|b| b as u8))
+                .collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+        _ => return JournalEntry::Skip,
+    };
+    // Error priority or above (`err`, `crit`, `alert`, `emerg`)
+    let event_type = match entry["PRIORITY"]
+        .as_str()
+        .and_then(|p| p.parse::<u8>().ok())
+    {
+        Some(priority) if priority <= 3 => ProcessEventType::Stderr,
+        _ => ProcessEventType::Stdout,
+    };
+
+    let mut event = ProcessEvent::new_with_data(event_type, message);
+    if let Some(timestamp) = entry["__REALTIME_TIMESTAMP"]
+        .as_str()
+        .and_then(|micros| micros.parse().ok())
+        .and_then(DateTime::from_timestamp_micros)
+    {
+        event.timestamp = timestamp;
+    }
+    JournalEntry::Output(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .iter()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_systemd_run_command() {
+        let command = Command::builder("graph-node")
+            .args(["--config", "config.toml"])
+            .env("GRAPH_LOG", "debug")
+            .current_dir("/srv/graph")
+            .build();
+
+        let cmd = systemd_run(&SystemdService::new("graph-node.service"), &command);
+        assert_eq!(cmd.get_program(), "systemd-run");
+        assert_eq!(
+            args(&cmd),
+            [
+                "--user",
+                "--unit=graph-node.service",
+                "--quiet",
+                "--property=Type=exec",
+                "--working-directory=/srv/graph",
+                "--setenv=GRAPH_LOG=debug",
+                "--",
+                "graph-node",
+                "--config",
+                "config.toml"
+            ]
+        );
+
+        let system = SystemdService::new("graph-node.service").with_user_manager(false);
+        assert!(!args(&systemd_run(&system, &command)).contains(&"--user".to_string()));
+    }
+
+    #[test]
+    fn test_control_commands() {
+        let unit = SystemdUnit {
+            unit: "anvil.service".to_string(),
+            user: false,
+            transient: false,
+            main_pid: None,
+        };
+        assert_eq!(
+            args(&unit.systemctl(["kill", "--signal=SIGINT"])),
+            ["kill", "--signal=SIGINT", "anvil.service"]
+        );
+
+        let since = DateTime::from_timestamp_micros(1_700_000_000_000_042).unwrap();
+        assert_eq!(
+            args(&unit.journal_command(since)),
+            [
+                "--follow",
+                "--output=json",
+                "--no-pager",
+                "--since=@1700000000.000042",
+                "--unit=anvil.service"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exit_status() {
+        let status = |output: &str| exit_status(&parse_properties(output));
+
+        assert!(status("ActiveState=active\nResult=success\n").is_none());
+        assert!(status("ActiveState=deactivating\n").is_none());
+
+        let exited =
+            status("ActiveState=failed\nResult=exit-code\nExecMainCode=1\nExecMainStatus=3\n")
+                .unwrap();
+        assert_eq!(exited.code, Some(3));
+
+        let killed =
+            status("ActiveState=failed\nResult=signal\nExecMainCode=2\nExecMainStatus=9\n")
+                .unwrap();
+        assert_eq!((killed.code, killed.signal), (None, Some(9)));
+
+        // Transient units are gone once they finished successfully
+        let collected =
+            status("ActiveState=inactive\nResult=success\nExecMainCode=0\nExecMainStatus=0\n")
+                .unwrap();
+        assert!(collected.success());
+    }
+
+    #[test]
+    fn test_parse_journal_entry() {
+        let JournalEntry::Output(event) = parse_journal_entry(
+            r#"{"MESSAGE":"Listening on 8545","PRIORITY":"6","_SYSTEMD_UNIT":"anvil.service","__REALTIME_TIMESTAMP":"1700000000000000"}"#,
+        ) else {
+            panic!("expected output");
+        };
+        assert_eq!(event.event_type, ProcessEventType::Stdout);
+        assert_eq!(event.data.as_deref(), Some("Listening on 8545"));
+        assert_eq!(event.timestamp.timestamp(), 1_700_000_000);
+
+        let JournalEntry::Output(event) =
+            parse_journal_entry(r#"{"MESSAGE":[104,105,255],"PRIORITY":"3"}"#)
+        else {
+            panic!("expected output");
+        };
+        assert_eq!(event.event_type, ProcessEventType::Stderr);
+        assert_eq!(event.data.as_deref(), Some("hi\u{fffd}"));
+
+        assert!(matches!(
+            parse_journal_entry(
+                r#"{"MESSAGE":"anvil.service: Deactivated successfully.","MESSAGE_ID":"7ad2d189f7e94e70a38c781354912448","UNIT":"anvil.service"}"#
+            ),
+            JournalEntry::UnitDown
+        ));
+        assert!(matches!(
+            parse_journal_entry(r#"{"MESSAGE":"Started anvil.service.","UNIT":"anvil.service"}"#),
+            JournalEntry::Skip
+        ));
+        assert!(matches!(
+            parse_journal_entry("not json"),
+            JournalEntry::Skip
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+//! Cancellation of running commands
+
+use async_channel::{Receiver, Sender};
+
+/// A token to cancel commands with
+///
+/// Clones share the token: cancelling any of them cancels all commands
+/// given one of them with [`Command::cancel_on`](crate::Command::cancel_on).
+/// It is runtime-agnostic and cancelling is idempotent.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    /// Closed on cancellation; nothing is ever sent
+    sender: Sender<()>,
+    receiver: Receiver<()>,
+}
+
+impl CancellationToken {
+    /// Create a token that isn't cancelled
+    pub fn new() -> Self {
+        let (sender, receiver) = async_channel::bounded(1);
+        Self { sender, receiver }
+    }
+
+    /// Cancel the commands using this token
+    pub fn cancel(&self) {
+        self.sender.close();
+    }
+
+    /// Whether the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    /// Wait until the token is cancelled
+    pub async fn cancelled(&self) {
+        // Only fails, once the channel is closed
+        let _ = self.receiver.recv().await;
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        let waiter = std::thread::spawn(move || futures_lite::future::block_on(clone.cancelled()));
+        token.cancel();
+        token.cancel();
+        waiter.join().unwrap();
+        assert!(token.is_cancelled());
+    }
+}
@@ -5,6 +5,9 @@ use async_process::Command as AsyncCommand;
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cancel::CancellationToken;
 
 /// A command to be executed
 ///
@@ -24,6 +27,34 @@ pub struct Command {
     env_clear: bool,
     /// Channel to receive stdin input line by line
     stdin_channel: Option<Receiver<String>>,
+    /// Run on a pseudo-terminal of this size instead of pipes
+    pty: Option<PtySize>,
+    /// How long the command may run when executed to completion
+    timeout: Option<Duration>,
+    /// Token stopping the command when cancelled
+    cancellation: Option<CancellationToken>,
+}
+
+/// Size of a pseudo-terminal, in character cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtySize {
+    /// Number of rows
+    pub rows: u16,
+    /// Number of columns
+    pub cols: u16,
+}
+
+impl PtySize {
+    /// A terminal of `rows` by `cols` cells
+    pub fn new(rows: u16, cols: u16) -> Self {
+        Self { rows, cols }
+    }
+}
+
+impl Default for PtySize {
+    fn default() -> Self {
+        Self::new(24, 80)
+    }
 }
 
 impl Command {
@@ -36,6 +67,9 @@ impl Command {
             current_dir: None,
             env_clear: false,
             stdin_channel: None,
+            pty: None,
+            timeout: None,
+            cancellation: None,
         }
     }
 
@@ -99,6 +133,42 @@ impl Command {
         self
     }
 
+    /// Run the command on a pseudo-terminal instead of pipes
+    ///
+    /// stdin, stdout and stderr all refer to the terminal, which becomes the
+    /// process's controlling terminal. Output arrives as a single stream of
+    /// [`ProcessEventType::Output`](crate::event::ProcessEventType::Output)
+    /// chunks, and input written through the `StdinHandle` is read as typed.
+    /// [`LocalLauncher`](crate::backends::LocalLauncher) honors it for
+    /// `Target::Command` and `Target::ManagedProcess`, and runs the `docker`
+    /// client of container targets on it; `SshLayer` and `DockerLayer` carry
+    /// it over to the `ssh` or `docker` client.
+    pub fn pty(&mut self, size: PtySize) -> &mut Self {
+        self.pty = Some(size);
+        self
+    }
+
+    /// Stop the command if it runs longer than `timeout`
+    ///
+    /// [`Launcher::execute`](crate::Launcher::execute) then terminates it
+    /// (and kills it if it doesn't exit), and fails with
+    /// [`Error::Timeout`](crate::Error::Timeout) holding the output so far.
+    /// Processes launched with a timeout or a cancellation token run in a
+    /// process group of their own, which is stopped as a whole.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stop the command once `token` is cancelled
+    ///
+    /// Like a timeout, but [`Launcher::execute`](crate::Launcher::execute)
+    /// fails with [`Error::Cancelled`](crate::Error::Cancelled).
+    pub fn cancel_on(&mut self, token: CancellationToken) -> &mut Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     /// Get the program name
     pub fn get_program(&self) -> &OsStr {
         &self.program
@@ -114,11 +184,36 @@ impl Command {
         &self.env
     }
 
+    /// Whether the command starts from an empty environment
+    pub fn get_env_clear(&self) -> bool {
+        self.env_clear
+    }
+
     /// Get the current directory
     pub fn get_current_dir(&self) -> Option<&std::path::Path> {
         self.current_dir.as_deref()
     }
 
+    /// Get the pseudo-terminal size, if the command runs on one
+    pub fn get_pty(&self) -> Option<PtySize> {
+        self.pty
+    }
+
+    /// Get the timeout, if any
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Get the cancellation token, if any
+    pub fn get_cancellation(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+
+    /// Whether the command may be stopped by a timeout or cancellation
+    pub(crate) fn is_stoppable(&self) -> bool {
+        self.timeout.is_some() || self.cancellation.is_some()
+    }
+
     /// Check if this command has a stdin channel configured
     pub fn has_stdin_channel(&self) -> bool {
         self.stdin_channel.is_some()
@@ -131,7 +226,13 @@ impl Command {
 
     /// Prepare this command for execution by converting to an `async_process::Command`
     pub fn prepare(&self) -> AsyncCommand {
-        let mut cmd = AsyncCommand::new(&self.program);
+        self.prepare_std().into()
+    }
+
+    /// Prepare this command as a `std::process::Command`, for configuration
+    /// `async_process` doesn't expose
+    pub(crate) fn prepare_std(&self) -> std::process::Command {
+        let mut cmd = std::process::Command::new(&self.program);
 
         // Add arguments
         cmd.args(&self.args);
@@ -151,6 +252,37 @@ impl Command {
 
         cmd
     }
+
+    /// This command run through a wrapper, e.g. `systemd-run ... -- <command>`
+    ///
+    /// The wrapper gets the command's environment and working directory, but
+    /// not its stdin channel. It runs like the command otherwise (see
+    /// [`Command::run_like`]).
+    pub(crate) fn wrapped<I, S>(&self, wrapper: impl AsRef<OsStr>, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut cmd = Command::new(wrapper);
+        cmd.args(args).arg(&self.program).args(&self.args);
+        cmd.env = self.env.clone();
+        cmd.env_clear = self.env_clear;
+        cmd.current_dir = self.current_dir.clone();
+        cmd.run_like(self);
+        cmd
+    }
+
+    /// Run like `other`: on its pseudo-terminal, with its timeout and
+    /// cancellation token, for commands such as `ssh` that run it
+    ///
+    /// Program, arguments, environment and working directory are left alone,
+    /// so execution layers outside this crate can rebuild those as they need.
+    pub fn run_like(&mut self, other: &Command) -> &mut Self {
+        self.pty = other.pty;
+        self.timeout = other.timeout;
+        self.cancellation = other.cancellation.clone();
+        self
+    }
 }
 
 /// Builder pattern helper
@@ -197,6 +329,24 @@ impl CommandBuilder {
         self
     }
 
+    /// Run on a pseudo-terminal of the given size
+    pub fn pty(mut self, size: PtySize) -> Self {
+        self.0.pty(size);
+        self
+    }
+
+    /// Stop the command if it runs longer than `timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.0.timeout(timeout);
+        self
+    }
+
+    /// Stop the command once `token` is cancelled
+    pub fn cancel_on(mut self, token: CancellationToken) -> Self {
+        self.0.cancel_on(token);
+        self
+    }
+
     /// Build the command
     pub fn build(self) -> Command {
         self.0
@@ -266,4 +416,34 @@ mod tests {
         assert_eq!(cmd1.get_args(), cmd2.get_args());
         assert_eq!(cmd1.get_envs(), cmd2.get_envs());
     }
+
+    #[test]
+    fn test_command_pty() {
+        let cmd = Command::builder("sh").pty(PtySize::new(40, 120)).build();
+        assert_eq!(cmd.get_pty(), Some(PtySize::new(40, 120)));
+        assert_eq!(Command::new("sh").get_pty(), None);
+
+        // Wrappers run on the same terminal
+        let wrapped = cmd.wrapped("env", ["-i"]);
+        assert_eq!(wrapped.get_pty(), Some(PtySize::new(40, 120)));
+    }
+
+    #[test]
+    fn test_command_timeout_and_cancellation() {
+        let token = CancellationToken::new();
+        let cmd = Command::builder("sleep")
+            .arg("10")
+            .timeout(Duration::from_secs(2))
+            .cancel_on(token.clone())
+            .build();
+        assert_eq!(cmd.get_timeout(), Some(Duration::from_secs(2)));
+        assert!(cmd.is_stoppable());
+        assert!(!Command::new("sleep").is_stoppable());
+
+        // Wrappers are stopped the same way
+        let wrapped = cmd.wrapped("nice", ["-n", "5"]);
+        assert_eq!(wrapped.get_timeout(), Some(Duration::from_secs(2)));
+        token.cancel();
+        assert!(wrapped.get_cancellation().unwrap().is_cancelled());
+    }
 }
@@ -28,6 +28,22 @@ pub enum Error {
         reason: String,
     },
 
+    /// Command ran longer than its timeout and was stopped
+    #[error("command timed out after {timeout:?}")]
+    Timeout {
+        /// The timeout that expired
+        timeout: std::time::Duration,
+        /// Output captured before the command was stopped
+        output: String,
+    },
+
+    /// Command was stopped by its cancellation token
+    #[error("command cancelled")]
+    Cancelled {
+        /// Output captured before the command was stopped
+        output: String,
+    },
+
     /// Command not found
     #[error("command not found: {command}")]
     CommandNotFound {
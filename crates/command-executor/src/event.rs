@@ -53,6 +53,12 @@ pub enum ProcessEventType {
     Stdout,
     /// Log line from stderr  
     Stderr,
+    /// Raw output from a pseudo-terminal
+    ///
+    /// stdout and stderr merged as the terminal shows them, in chunks as
+    /// they are read rather than lines, including line endings and escape
+    /// sequences.
+    Output,
 }
 
 /// Filter for process log output
@@ -1,11 +1,14 @@
 //! Launcher trait for executing commands in different contexts
 
 use crate::command::Command;
-use crate::error::Result;
-use crate::event::ProcessEvent;
+use crate::error::{Error, Result};
+use crate::event::{ProcessEvent, ProcessEventType};
 use crate::process::{ExitResult, ProcessHandle};
 use async_trait::async_trait;
 use futures::stream::Stream;
+use futures_lite::future::{or, pending};
+use std::time::Duration;
+use tracing::warn;
 
 /// A launcher that can execute commands in a specific context
 #[async_trait]
@@ -27,21 +30,113 @@ pub trait Launcher: Send + Sync + 'static {
     ) -> Result<(Self::EventStream, Self::Handle)>;
 
     /// Execute a command and wait for it to complete, capturing output
+    ///
+    /// A command with a timeout or cancellation token is stopped with
+    /// [`ProcessHandle::shutdown`] once it expires or is cancelled, failing
+    /// with [`Error::Timeout`] or [`Error::Cancelled`] and the output so far.
     async fn execute(&self, target: &Self::Target, command: Command) -> Result<ExitResult> {
         use futures::StreamExt;
 
+        let timeout = command.get_timeout();
+        let cancellation = command.get_cancellation().cloned();
+        if cancellation
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            return Err(Error::Cancelled {
+                output: String::new(),
+            });
+        }
+
         let (mut events, mut handle) = self.launch(target, command).await?;
         let mut output = String::new();
 
-        // Collect all output from the event stream
-        while let Some(event) = events.next().await {
-            if let Some(data) = &event.data {
-                output.push_str(data);
-                output.push('\n');
+        // Collect all output and wait for the exit, unless stopped first;
+        // the process may close its output long before it exits
+        let finished = or(
+            async {
+                while let Some(event) = events.next().await {
+                    capture(&mut output, &event);
+                }
+                Ok(handle.wait().await)
+            },
+            async {
+                let expired = async {
+                    match timeout {
+                        Some(timeout) => {
+                            async_io::Timer::after(timeout).await;
+                        }
+                        None => pending::<()>().await,
+                    }
+                    Stop::Timeout
+                };
+                let cancelled = async {
+                    match &cancellation {
+                        Some(token) => token.cancelled().await,
+                        None => pending::<()>().await,
+                    }
+                    Stop::Cancelled
+                };
+                Err(or(expired, cancelled).await)
+            },
+        )
+        .await;
+
+        let stop = match finished {
+            Ok(status) => {
+                return Ok(ExitResult {
+                    status: status?,
+                    output,
+                });
             }
+            Err(stop) => stop,
+        };
+
+        if let Err(e) = handle.shutdown(STOP_GRACE).await {
+            warn!("Failed to stop command: {}", e);
         }
+        // Pick up what was written before the process went down
+        or(
+            async {
+                while let Some(event) = events.next().await {
+                    capture(&mut output, &event);
+                }
+            },
+            async {
+                async_io::Timer::after(OUTPUT_DRAIN).await;
+            },
+        )
+        .await;
+
+        Err(match stop {
+            Stop::Timeout => Error::Timeout {
+                timeout: timeout.unwrap_or_default(),
+                output,
+            },
+            Stop::Cancelled => Error::Cancelled { output },
+        })
+    }
+}
+
+/// How long a timed out or cancelled command gets to exit after `SIGTERM`
+const STOP_GRACE: Duration = Duration::from_secs(2);
+
+/// How long output of a stopped command is still read
+const OUTPUT_DRAIN: Duration = Duration::from_millis(200);
 
-        let status = handle.wait().await?;
-        Ok(ExitResult { status, output })
+/// Why a command was stopped before it finished
+enum Stop {
+    Timeout,
+    Cancelled,
+}
+
+/// Append an event's output to the captured output
+fn capture(output: &mut String, event: &ProcessEvent) {
+    if let Some(data) = &event.data {
+        output.push_str(data);
+        // Terminal output keeps its own line endings
+        if event.event_type != ProcessEventType::Output {
+            output.push('\n');
+        }
     }
 }
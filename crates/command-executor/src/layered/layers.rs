@@ -176,6 +176,10 @@ impl ExecutionLayer for SshLayer {
 
         ssh_cmd.arg(remote_command);
 
+        // ssh talks to the remote terminal through the local one, and
+        // stopping it ends the remote command
+        ssh_cmd.run_like(&command);
+
         Ok(ssh_cmd)
     }
 
@@ -281,6 +285,10 @@ impl ExecutionLayer for DockerLayer {
         let command_string = command_to_shell_string(&command)?;
         docker_cmd.arg(command_string);
 
+        // The docker CLI relays the container's terminal through the local
+        // one, and stopping it ends the command in the container
+        docker_cmd.run_like(&command);
+
         Ok(docker_cmd)
     }
 
@@ -432,6 +440,26 @@ mod tests {
         assert!(result_string.contains("'ps aux'"));
     }
 
+    #[test]
+    fn test_layers_run_like_command() {
+        let context = ExecutionContext::new();
+        let size = crate::PtySize::new(30, 100);
+        let timeout = std::time::Duration::from_secs(5);
+        let cmd = Command::builder("top").pty(size).timeout(timeout).build();
+
+        let ssh = SshLayer::new("example.com").with_tty(true);
+        let result = ssh.wrap_command(cmd.clone(), &context).unwrap();
+        assert_eq!(result.get_program(), "ssh");
+        assert_eq!(result.get_pty(), Some(size));
+        assert_eq!(result.get_timeout(), Some(timeout));
+
+        let docker = DockerLayer::new("my-container").with_tty(true);
+        let result = docker.wrap_command(cmd, &context).unwrap();
+        assert_eq!(result.get_program(), "docker");
+        assert_eq!(result.get_pty(), Some(size));
+        assert_eq!(result.get_timeout(), Some(timeout));
+    }
+
     #[test]
     fn test_local_layer() {
         let layer = LocalLayer::new();
@@ -7,6 +7,7 @@
 
 pub mod attacher;
 pub mod backends;
+pub mod cancel;
 pub mod command;
 pub mod error;
 pub mod event;
@@ -21,7 +22,8 @@ pub mod target;
 mod stdin_test;
 
 pub use attacher::{AttachConfig, AttachedHandle, Attacher, ServiceStatus};
-pub use command::Command;
+pub use cancel::CancellationToken;
+pub use command::{Command, PtySize};
 pub use error::{Error, Result};
 pub use event::{LogFilter, LogSource, NoOpFilter, ProcessEvent, ProcessEventType};
 pub use executor::Executor;
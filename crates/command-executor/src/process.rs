@@ -2,6 +2,7 @@
 
 use crate::error::Result;
 use async_trait::async_trait;
+use std::time::Duration;
 
 /// A handle to control a running process
 #[async_trait]
@@ -26,6 +27,32 @@ pub trait ProcessHandle: Send + Sync {
     /// Note: Not all processes handle SIGHUP. This is typically used
     /// by daemons to reload their configuration.
     async fn reload(&mut self) -> Result<()>;
+
+    /// Stop the process: `terminate`, then `kill` if it hasn't exited
+    /// within `grace`
+    async fn shutdown(&mut self, grace: Duration) -> Result<ExitStatus> {
+        terminate_within(self, grace).await
+    }
+}
+
+/// `terminate`, wait up to `grace` for the process to exit, then `kill`
+pub(crate) async fn terminate_within<H: ProcessHandle + ?Sized>(
+    handle: &mut H,
+    grace: Duration,
+) -> Result<ExitStatus> {
+    handle.terminate().await?;
+    let exited = futures_lite::future::or(async { Some(handle.wait().await) }, async {
+        async_io::Timer::after(grace).await;
+        None
+    })
+    .await;
+    match exited {
+        Some(status) => status,
+        None => {
+            handle.kill().await?;
+            handle.wait().await
+        }
+    }
 }
 
 /// Process exit status
@@ -5,21 +5,71 @@
 
 use crate::error::Result;
 use async_channel::Receiver;
-use futures::io::AsyncWriteExt;
+use async_io::Async;
+use futures::io::{AsyncWrite, AsyncWriteExt};
+use std::fs::File;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 /// Handle for writing to a process's stdin
 pub struct StdinHandle {
     /// The actual stdin writer
-    stdin: Option<async_process::ChildStdin>,
+    stdin: Option<StdinWriter>,
     /// Optional channel to receive input from
     channel: Option<Receiver<String>>,
 }
 
+/// Where input for the process goes
+enum StdinWriter {
+    /// The process's stdin pipe
+    Pipe(async_process::ChildStdin),
+    /// The master side of the process's pseudo-terminal
+    Pty(Async<File>),
+}
+
+impl AsyncWrite for StdinWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            StdinWriter::Pipe(pipe) => Pin::new(pipe).poll_write(cx, buf),
+            StdinWriter::Pty(master) => Pin::new(master).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            StdinWriter::Pipe(pipe) => Pin::new(pipe).poll_flush(cx),
+            StdinWriter::Pty(master) => Pin::new(master).poll_flush(cx),
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            StdinWriter::Pipe(pipe) => Pin::new(pipe).poll_close(cx),
+            StdinWriter::Pty(master) => Pin::new(master).poll_close(cx),
+        }
+    }
+}
+
 impl StdinHandle {
     /// Create a new stdin handle
     pub fn new(stdin: async_process::ChildStdin, channel: Option<Receiver<String>>) -> Self {
         Self {
-            stdin: Some(stdin),
+            stdin: Some(StdinWriter::Pipe(stdin)),
+            channel,
+        }
+    }
+
+    /// Create a handle writing to a pseudo-terminal's master side
+    ///
+    /// Input is read by the process as if typed: the terminal echoes it and,
+    /// in canonical mode, delivers it line by line.
+    pub(crate) fn pty(master: Async<File>, channel: Option<Receiver<String>>) -> Self {
+        Self {
+            stdin: Some(StdinWriter::Pty(master)),
             channel,
         }
     }
@@ -33,8 +83,21 @@ impl StdinHandle {
     }
 
     /// Take the stdin writer, leaving None in its place
+    ///
+    /// Returns None for a pseudo-terminal, which keeps its writer.
     pub fn take_stdin(&mut self) -> Option<async_process::ChildStdin> {
-        self.stdin.take()
+        match self.stdin.take() {
+            Some(StdinWriter::Pipe(stdin)) => Some(stdin),
+            other => {
+                self.stdin = other;
+                None
+            }
+        }
+    }
+
+    /// Whether input goes to a pseudo-terminal rather than a pipe
+    pub fn is_pty(&self) -> bool {
+        matches!(self.stdin, Some(StdinWriter::Pty(_)))
     }
 
     /// Write a line to stdin (adds newline)
@@ -78,6 +141,9 @@ impl StdinHandle {
     }
 
     /// Close stdin by dropping the writer
+    ///
+    /// A pseudo-terminal stays open until the process exits; to signal end
+    /// of input, write the terminal's EOF character (`\x04`) instead.
     pub fn close(&mut self) {
         self.stdin.take();
     }
@@ -6,6 +6,7 @@
 
 use crate::command::Command;
 use crate::error::Result;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -29,12 +30,20 @@ pub enum Target {
 // Individual target type structs
 
 /// Execute as a managed process (we track PID and lifecycle)
+///
+/// The process is started in a session of its own, so it and everything it
+/// starts form a process group that is signalled as a whole.
 #[derive(Debug, Clone)]
 pub struct ManagedProcess {
-    /// Optional process group ID for managing child processes
+    /// Process group to join instead of starting a new session (0 for a new
+    /// group in the current session)
     process_group: Option<i32>,
     /// Whether to restart on failure
     restart_on_failure: bool,
+    /// Resource limits applied to the process
+    limits: ResourceLimits,
+    /// User (name or uid) to run the process as
+    user: Option<String>,
 }
 
 impl ManagedProcess {
@@ -43,6 +52,8 @@ impl ManagedProcess {
         Self {
             process_group: None,
             restart_on_failure: false,
+            limits: ResourceLimits::default(),
+            user: None,
         }
     }
 
@@ -52,6 +63,9 @@ impl ManagedProcess {
     }
 
     /// Set the process group ID
+    ///
+    /// The process joins the group instead of starting a session of its own;
+    /// 0 starts a new group in the current session.
     pub fn with_process_group(mut self, pgid: i32) -> Self {
         self.process_group = Some(pgid);
         self
@@ -62,6 +76,33 @@ impl ManagedProcess {
         self.restart_on_failure = true;
         self
     }
+
+    /// Bound the process's resources
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Run the process as another user (name or numeric uid)
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Get the process group to join, if set
+    pub fn process_group(&self) -> Option<i32> {
+        self.process_group
+    }
+
+    /// Get the resource limits
+    pub fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    /// Get the user the process runs as
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
 }
 
 impl Default for ManagedProcess {
@@ -74,6 +115,8 @@ impl Default for ManagedProcess {
 pub struct ManagedProcessBuilder {
     process_group: Option<i32>,
     restart_on_failure: bool,
+    limits: ResourceLimits,
+    user: Option<String>,
 }
 
 impl ManagedProcessBuilder {
@@ -82,6 +125,8 @@ impl ManagedProcessBuilder {
         Self {
             process_group: None,
             restart_on_failure: false,
+            limits: ResourceLimits::default(),
+            user: None,
         }
     }
 
@@ -97,37 +142,185 @@ impl ManagedProcessBuilder {
         self
     }
 
+    /// Set resource limits
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the user to run as
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
     /// Build the ManagedProcess
     pub fn build(self) -> ManagedProcess {
         ManagedProcess {
             process_group: self.process_group,
             restart_on_failure: self.restart_on_failure,
+            limits: self.limits,
+            user: self.user,
         }
     }
 }
 
-/// Execute via systemd (systemctl commands)
+/// Resource limits for a managed process
+///
+/// Memory and CPU are bounded with a cgroup (a transient systemd scope when
+/// systemd is running, a cgroup v2 group otherwise) and fall back to
+/// `RLIMIT_AS` when neither is available. The open file limit and nice level
+/// are set in the process itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// Maximum memory in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_max: Option<u64>,
+    /// CPU quota in percent of one CPU (200 is two full CPUs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_quota: Option<u32>,
+    /// Maximum number of open files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nofile: Option<u64>,
+    /// Nice level, from -20 (highest priority) to 19
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+}
+
+impl ResourceLimits {
+    /// Whether no limit is set
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Whether a limit needs a cgroup to be enforced
+    pub fn needs_cgroup(&self) -> bool {
+        self.memory_max.is_some() || self.cpu_quota.is_some()
+    }
+}
+
+impl std::fmt::Display for ResourceLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut limits = Vec::new();
+        if let Some(bytes) = self.memory_max {
+            limits.push(format!("memory {}", format_bytes(bytes)));
+        }
+        if let Some(quota) = self.cpu_quota {
+            limits.push(format!("cpu {}%", quota));
+        }
+        if let Some(nofile) = self.nofile {
+            limits.push(format!("nofile {}", nofile));
+        }
+        if let Some(nice) = self.nice {
+            limits.push(format!("nice {}", nice));
+        }
+        if limits.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", limits.join(", "))
+        }
+    }
+}
+
+/// Format a byte count with the largest binary unit that divides it
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [(&str, u64); 4] = [
+        ("T", 1 << 40),
+        ("G", 1 << 30),
+        ("M", 1 << 20),
+        ("K", 1 << 10),
+    ];
+    UNITS
+        .iter()
+        .find(|(_, size)| bytes >= *size && bytes.is_multiple_of(*size))
+        .map_or_else(
+            || bytes.to_string(),
+            |(unit, size)| format!("{}{}", bytes / size, unit),
+        )
+}
+
+/// How a process's memory and CPU limits are enforced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitEnforcement {
+    /// A transient systemd scope (`systemd-run --scope`)
+    SystemdScope,
+    /// A cgroup v2 group created for the process
+    Cgroup(PathBuf),
+    /// Resource limits of the process only; CPU quotas are not enforced
+    Rlimit,
+}
+
+impl std::fmt::Display for LimitEnforcement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitEnforcement::SystemdScope => write!(f, "systemd scope"),
+            LimitEnforcement::Cgroup(path) => write!(f, "cgroup {}", path.display()),
+            LimitEnforcement::Rlimit => write!(f, "rlimit"),
+        }
+    }
+}
+
+/// Execute via systemd
+///
+/// By default the command runs in a transient unit created with
+/// `systemd-run`; [`SystemdService::installed`] starts a unit that already
+/// exists instead, ignoring the command.
 #[derive(Debug, Clone)]
 pub struct SystemdService {
     /// The systemd unit name
     unit_name: String,
+    /// Whether to create a transient unit for the command
+    transient: bool,
+    /// Whether the unit belongs to the user's service manager (`--user`)
+    user_manager: bool,
 }
 
 impl SystemdService {
-    /// Create a new systemd service target
+    /// Create a systemd service target running the command in a transient
+    /// unit of the user's service manager
     pub fn new(unit_name: impl Into<String>) -> Self {
         Self {
             unit_name: unit_name.into(),
+            transient: true,
+            user_manager: true,
         }
     }
 
+    /// Create a systemd service target for an installed unit, started with
+    /// `systemctl start`
+    pub fn installed(unit_name: impl Into<String>) -> Self {
+        Self {
+            transient: false,
+            ..Self::new(unit_name)
+        }
+    }
+
+    /// Use the user's service manager (the default) or the system's
+    pub fn with_user_manager(mut self, user: bool) -> Self {
+        self.user_manager = user;
+        self
+    }
+
     /// Get the unit name
     pub fn unit_name(&self) -> &str {
         &self.unit_name
     }
+
+    /// Whether the command runs in a transient unit
+    pub fn is_transient(&self) -> bool {
+        self.transient
+    }
+
+    /// Whether the unit belongs to the user's service manager
+    pub fn user_manager(&self) -> bool {
+        self.user_manager
+    }
 }
 
-/// Execute via systemd-portable (portablectl commands)
+/// Execute via systemd-portable
+///
+/// The image is attached with `portablectl` unless it already is, and the
+/// unit is started in the system service manager; the command is ignored.
 #[derive(Debug, Clone)]
 pub struct SystemdPortable {
     /// The portable service image name
@@ -371,6 +564,10 @@ pub struct ComposeService {
     service_name: String,
     /// Optional project name
     project_name: Option<String>,
+    /// Whether `up` starts the services this one depends on
+    dependencies: bool,
+    /// Whether `up` returns once the service is started
+    detach: bool,
 }
 
 impl ComposeService {
@@ -380,6 +577,8 @@ impl ComposeService {
             compose_file: compose_file.into(),
             service_name: service_name.into(),
             project_name: None,
+            dependencies: false,
+            detach: false,
         }
     }
 
@@ -389,6 +588,25 @@ impl ComposeService {
         self
     }
 
+    /// Also start the services this one depends on
+    ///
+    /// Off by default: the service is brought up with `--exit-code-from`,
+    /// which stops everything once any container exits, so a one-shot
+    /// dependency such as a migration job would take the service down too.
+    pub fn with_dependencies(mut self, dependencies: bool) -> Self {
+        self.dependencies = dependencies;
+        self
+    }
+
+    /// Return once the service is started instead of staying attached to it
+    ///
+    /// The launched process is then `docker compose up --detach`, and the
+    /// service keeps running when its handle is dropped.
+    pub fn with_detach(mut self, detach: bool) -> Self {
+        self.detach = detach;
+        self
+    }
+
     /// Get the compose file path
     pub fn compose_file(&self) -> &PathBuf {
         &self.compose_file
@@ -403,6 +621,39 @@ impl ComposeService {
     pub fn project_name(&self) -> Option<&str> {
         self.project_name.as_deref()
     }
+
+    /// Check if the services this one depends on are started with it
+    pub fn starts_dependencies(&self) -> bool {
+        self.dependencies
+    }
+
+    /// Check if `up` returns once the service is started
+    pub fn is_detached(&self) -> bool {
+        self.detach
+    }
+
+    /// `docker compose --file <file> [--project-name <project>] <args> <service>`
+    ///
+    /// For running other compose subcommands, e.g. `ps` or `logs`, on the
+    /// service.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new("docker");
+        cmd.args(self.cli()).args(args).arg(&self.service_name);
+        cmd
+    }
+
+    /// Arguments after `docker` selecting this service's project
+    pub(crate) fn cli(&self) -> Vec<String> {
+        let mut cli = vec![
+            "compose".to_string(),
+            "--file".to_string(),
+            self.compose_file.display().to_string(),
+        ];
+        if let Some(project) = &self.project_name {
+            cli.extend(["--project-name".to_string(), project.clone()]);
+        }
+        cli
+    }
 }
 
 /// A service that can be observed but not controlled
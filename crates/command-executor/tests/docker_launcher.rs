@@ -0,0 +1,178 @@
+//! LocalLauncher with Docker targets, against a stand-in `docker` script
+//! on `PATH`
+//!
+//! Everything runs in one test since it changes the process's `PATH`.
+
+#![cfg(unix)]
+#![allow(unsafe_code)]
+
+use command_executor::{
+    Command, ProcessEventType,
+    backends::LocalLauncher,
+    launcher::Launcher,
+    process::ProcessHandle,
+    target::{ComposeService, DockerContainer, Target},
+};
+use futures::StreamExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::Duration;
+
+/// The stand-in logs every call to `$DIR/calls`; `run` and `compose up`
+/// stay in the foreground until `stop` marks the container stopped, unless
+/// detached
+const DOCKER: &str = r#"echo "docker $*" >> "$DIR/calls"
+case "$*" in
+  *" up --detach "*) echo "Container started" >&2 ;;
+  *" up "*|run\ *)
+    echo running > "$DIR/state"
+    echo "hello from the container"
+    echo "warming up" >&2
+    while [ "$(cat "$DIR/state")" = running ]; do sleep 0.05; done
+    exit 143 ;;
+  *" stop "*|stop\ *) echo stopped > "$DIR/state" ;;
+  *"kill --signal=SIGHUP"*) ;;
+  *) echo "unknown container" >&2; exit 1 ;;
+esac"#;
+
+fn install_docker(dir: &Path) {
+    let path = dir.join("docker");
+    std::fs::write(
+        &path,
+        format!("#!/bin/sh\nDIR='{}'\n{}\n", dir.display(), DOCKER),
+    )
+    .unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = std::env::var("PATH").unwrap_or_default();
+    // SAFETY: this is the only test in the binary
+    unsafe { std::env::set_var("PATH", format!("{}:{}", dir.display(), path)) };
+}
+
+fn calls(dir: &Path) -> Vec<String> {
+    std::fs::read_to_string(dir.join("calls"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Wait for the stand-in to have been called `count` times; the handle
+/// discards containers in the background on drop
+async fn wait_for_calls(dir: &Path, count: usize) -> Vec<String> {
+    for _ in 0..100 {
+        let calls = calls(dir);
+        if calls.len() >= count {
+            return calls;
+        }
+        smol::Timer::after(Duration::from_millis(20)).await;
+    }
+    calls(dir)
+}
+
+#[smol_potat::test]
+async fn test_docker_targets() {
+    let dir = tempfile::tempdir().unwrap();
+    install_docker(dir.path());
+
+    // A container running the command, in the foreground
+    let target = Target::DockerContainer(
+        DockerContainer::new("ghcr.io/foundry-rs/foundry")
+            .with_name("anvil")
+            .with_env("FOUNDRY_PROFILE", "ci")
+            .with_volume("/tmp/chain", "/data"),
+    );
+    let command = Command::builder("anvil").args(["--port", "8545"]).build();
+    let (events, mut handle) = LocalLauncher.launch(&target, command).await.unwrap();
+    assert!(handle.pid().is_some());
+
+    let mut output: Vec<_> = events
+        .take(3)
+        .map(|event| (event.event_type, event.data))
+        .collect()
+        .await;
+    assert!(matches!(output[0].0, ProcessEventType::Started { .. }));
+    output.remove(0);
+    output.sort_by_key(|(_, data)| data.clone());
+    assert_eq!(
+        output,
+        vec![
+            (
+                ProcessEventType::Stdout,
+                Some("hello from the container".to_string())
+            ),
+            (ProcessEventType::Stderr, Some("warming up".to_string())),
+        ]
+    );
+
+    handle.reload().await.unwrap();
+    handle.terminate().await.unwrap();
+    let status = handle.wait().await.unwrap();
+    assert_eq!(status.code, Some(143));
+    // The stand-in doesn't know `interrupt`'s signal
+    assert!(handle.interrupt().await.is_err());
+    drop(handle);
+
+    assert_eq!(
+        wait_for_calls(dir.path(), 5).await,
+        vec![
+            "docker run --name anvil --rm --env FOUNDRY_PROFILE=ci \
+             --volume /tmp/chain:/data ghcr.io/foundry-rs/foundry anvil --port 8545",
+            "docker kill --signal=SIGHUP anvil",
+            "docker stop anvil",
+            "docker kill --signal=SIGINT anvil",
+            // Removed containers are cleaned up on drop
+            "docker rm --force anvil",
+        ]
+    );
+    std::fs::remove_file(dir.path().join("calls")).unwrap();
+
+    // A compose service brought up on its own
+    let target = Target::ComposeService(
+        ComposeService::new("/srv/stack/compose.yaml", "postgres").with_project_name("harness"),
+    );
+    let (mut events, mut handle) = LocalLauncher
+        .launch(&target, Command::new("ignored"))
+        .await
+        .unwrap();
+    // Only stop it once it's up, or the stand-in misses the stop
+    while let Some(event) = events.next().await {
+        if event.event_type == ProcessEventType::Stdout {
+            break;
+        }
+    }
+    handle.terminate().await.unwrap();
+    assert_eq!(handle.wait().await.unwrap().code, Some(143));
+    drop(handle);
+
+    let compose = "docker compose --file /srv/stack/compose.yaml --project-name harness";
+    assert_eq!(
+        wait_for_calls(dir.path(), 3).await,
+        vec![
+            format!(
+                "{} up --no-log-prefix --attach postgres --exit-code-from postgres --no-deps postgres",
+                compose
+            ),
+            format!("{} stop postgres", compose),
+            format!("{} kill postgres", compose),
+        ]
+    );
+    std::fs::remove_file(dir.path().join("calls")).unwrap();
+
+    // A detached service is left running once `up` returns
+    let target = Target::ComposeService(
+        ComposeService::new("/srv/stack/compose.yaml", "postgres")
+            .with_project_name("harness")
+            .with_detach(true),
+    );
+    let result = LocalLauncher
+        .execute(&target, Command::new("ignored"))
+        .await
+        .unwrap();
+    assert!(result.success());
+    smol::Timer::after(Duration::from_millis(100)).await;
+    assert_eq!(
+        calls(dir.path()),
+        vec![format!("{} up --detach --no-deps postgres", compose)]
+    );
+}
@@ -5,7 +5,7 @@ use command_executor::{
     backends::LocalLauncher,
     launcher::Launcher,
     process::ProcessHandle,
-    target::{ManagedProcess, Target},
+    target::{ManagedProcess, ResourceLimits, Target},
 };
 use futures::StreamExt;
 
@@ -137,6 +137,65 @@ async fn test_local_launcher_managed_process() {
     assert!(output.contains("managed process"));
 }
 
+/// Run `sh -c <script>` as a managed process and collect its output
+async fn run_managed(process: ManagedProcess, script: &str) -> (Option<i32>, Vec<String>) {
+    let command = Command::builder("sh").args(["-c", script]).build();
+    let (events, mut handle) = LocalLauncher
+        .launch(&Target::ManagedProcess(process), command)
+        .await
+        .unwrap();
+    let output = events
+        .filter_map(|event| async { event.data })
+        .collect()
+        .await;
+    (handle.wait().await.unwrap().code, output)
+}
+
+#[smol_potat::test]
+async fn test_local_launcher_managed_process_limits() {
+    let limits = ResourceLimits {
+        nofile: Some(256),
+        nice: Some(7),
+        ..Default::default()
+    };
+    let process = ManagedProcess::builder().limits(limits).build();
+
+    let (code, output) = run_managed(process, "ulimit -n; cut -d' ' -f19 /proc/self/stat").await;
+    assert_eq!(code, Some(0));
+    assert_eq!(output, ["256", "7"]);
+
+    // Memory is bounded by a scope, a cgroup or the address space
+    let limits = ResourceLimits {
+        memory_max: Some(64 << 20),
+        ..Default::default()
+    };
+    let process = ManagedProcess::new().with_limits(limits);
+    let command = Command::builder("true").build();
+    let (_, handle) = LocalLauncher
+        .launch(&Target::ManagedProcess(process), command)
+        .await
+        .unwrap();
+    assert!(handle.limit_enforcement().is_some());
+}
+
+#[smol_potat::test]
+async fn test_local_launcher_managed_process_user() {
+    if !nix::unistd::geteuid().is_root() {
+        return;
+    }
+
+    let process = ManagedProcess::new().with_user("nobody");
+    let (code, output) = run_managed(process, "id -u; id -g").await;
+    assert_eq!(code, Some(0));
+    assert_eq!(output, ["65534", "65534"]);
+
+    let process = ManagedProcess::new().with_user("no-such-user-here");
+    let result = LocalLauncher
+        .launch(&Target::ManagedProcess(process), Command::new("true"))
+        .await;
+    assert!(result.is_err());
+}
+
 #[smol_potat::test]
 async fn test_local_launcher_drop_kills_process() {
     let launcher = LocalLauncher;
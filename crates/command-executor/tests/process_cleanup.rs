@@ -139,3 +139,109 @@ fn test_cleanup_with_multiple_processes() {
         }
     });
 }
+
+/// Whether a process exists and isn't a zombie (orphans may not be reaped
+/// promptly in containers)
+#[cfg(target_os = "linux")]
+fn is_running(pid: i32) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid)).is_ok_and(|stat| {
+        !stat
+            .rsplit_once(')')
+            .unwrap()
+            .1
+            .trim_start()
+            .starts_with('Z')
+    })
+}
+
+/// Processes in the group led by `pgid`
+#[cfg(target_os = "linux")]
+fn group_members(pgid: u32) -> Vec<i32> {
+    std::fs::read_dir("/proc")
+        .unwrap()
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<i32>().ok())
+        .filter(|pid| {
+            std::fs::read_to_string(format!("/proc/{}/stat", pid)).is_ok_and(|stat| {
+                let fields: Vec<_> = stat
+                    .rsplit_once(')')
+                    .unwrap()
+                    .1
+                    .split_whitespace()
+                    .collect();
+                fields[2] == pgid.to_string()
+            })
+        })
+        .collect()
+}
+
+/// Launch `sh -c <script>` as a managed process and wait for it to print
+/// `ready`
+#[cfg(target_os = "linux")]
+async fn launch_group(script: &str) -> (command_executor::backends::LocalProcessHandle, Vec<i32>) {
+    use command_executor::target::ManagedProcess;
+    use futures::StreamExt;
+
+    let executor = Executor::local("test-group-cleanup");
+    let target = Target::ManagedProcess(ManagedProcess::new());
+    let command = Command::builder("sh").args(["-c", script]).build();
+    let (mut events, handle) = executor.launch(&target, command).await.unwrap();
+    while let Some(event) = events.next().await {
+        if event.data.as_deref() == Some("ready") {
+            break;
+        }
+    }
+    let pid = handle.pid().unwrap();
+    let members = group_members(pid);
+    assert!(
+        members.len() >= 3,
+        "expected sh and two sleeps: {:?}",
+        members
+    );
+    (handle, members)
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_managed_process_shutdown_stops_group() {
+    futures::executor::block_on(async {
+        let (mut handle, members) = launch_group("sleep 60 & sleep 60 & echo ready; wait").await;
+
+        let status = handle.shutdown(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(status.signal, Some(15));
+        for pid in members {
+            assert!(!is_running(pid), "process {} of the group survived", pid);
+        }
+    });
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_managed_process_shutdown_kills_after_grace() {
+    futures::executor::block_on(async {
+        // The shell and its children ignore SIGTERM
+        let (mut handle, members) =
+            launch_group("trap '' TERM; sleep 60 & sleep 60 & echo ready; wait").await;
+
+        let started = std::time::Instant::now();
+        let status = handle.shutdown(Duration::from_millis(300)).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert_eq!(status.signal, Some(9));
+        for pid in members {
+            assert!(!is_running(pid), "process {} of the group survived", pid);
+        }
+    });
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_managed_process_drop_kills_group() {
+    futures::executor::block_on(async {
+        let (handle, members) = launch_group("sleep 60 & sleep 60 & echo ready; wait").await;
+        drop(handle);
+
+        smol::Timer::after(Duration::from_millis(200)).await;
+        for pid in members {
+            assert!(!is_running(pid), "process {} of the group survived", pid);
+        }
+    });
+}
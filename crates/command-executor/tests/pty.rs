@@ -0,0 +1,134 @@
+//! Tests for processes launched on a pseudo-terminal
+
+use command_executor::{
+    Command, Launcher, ManagedProcess, ProcessEventType, ProcessHandle, PtySize, Target,
+    backends::LocalLauncher,
+};
+use futures::StreamExt;
+use std::time::Duration;
+
+/// Collect terminal output until it contains `needle`
+async fn read_until(
+    events: &mut (impl futures::Stream<Item = command_executor::ProcessEvent> + Unpin),
+    needle: &str,
+) -> String {
+    let mut output = String::new();
+    let read = async {
+        while let Some(event) = events.next().await {
+            if event.event_type == ProcessEventType::Output {
+                output.push_str(event.data.as_deref().unwrap_or_default());
+                if output.contains(needle) {
+                    break;
+                }
+            }
+        }
+    };
+    let timeout = async {
+        smol::Timer::after(Duration::from_secs(10)).await;
+    };
+    futures::future::select(Box::pin(read), Box::pin(timeout)).await;
+    output
+}
+
+#[smol_potat::test]
+async fn test_pty_merges_output() {
+    let command = Command::builder("sh")
+        .arg("-c")
+        .arg("test -t 0 && test -t 1 && test -t 2 && echo out; echo err >&2")
+        .pty(PtySize::default())
+        .build();
+
+    let result = LocalLauncher
+        .execute(&Target::Command, command)
+        .await
+        .unwrap();
+
+    assert!(result.status.success());
+    // Both streams arrive through the terminal, with its line endings
+    assert_eq!(result.output, "out\r\nerr\r\n");
+}
+
+#[smol_potat::test]
+async fn test_pty_controlling_terminal() {
+    // /dev/tty only opens for a process with a controlling terminal
+    let command = Command::builder("sh")
+        .arg("-c")
+        .arg("echo ok > /dev/tty")
+        .pty(PtySize::default())
+        .build();
+    let result = LocalLauncher
+        .execute(&Target::Command, command)
+        .await
+        .unwrap();
+    assert!(result.status.success());
+    assert_eq!(result.output, "ok\r\n");
+
+    let command = Command::builder("sh")
+        .arg("-c")
+        .arg("echo ok > /dev/tty")
+        .pty(PtySize::default())
+        .build();
+    let target = Target::ManagedProcess(ManagedProcess::new());
+    let result = LocalLauncher.execute(&target, command).await.unwrap();
+    assert!(result.status.success());
+    assert_eq!(result.output, "ok\r\n");
+}
+
+#[smol_potat::test]
+async fn test_pty_input() {
+    let command = Command::builder("sh")
+        .arg("-c")
+        .arg("read line; echo \"got $line\"")
+        .pty(PtySize::default())
+        .build();
+    let (mut events, mut handle) = LocalLauncher
+        .launch(&Target::Command, command)
+        .await
+        .unwrap();
+
+    let stdin = handle.stdin_mut().expect("terminal input");
+    assert!(stdin.is_pty());
+    stdin.write(b"hello\r").await.unwrap();
+
+    // The terminal echoes the input before the reply
+    let output = read_until(&mut events, "got hello").await;
+    assert!(output.contains("hello\r\n"), "output: {:?}", output);
+    assert!(output.contains("got hello"), "output: {:?}", output);
+
+    let status = handle.wait().await.unwrap();
+    assert!(status.success());
+}
+
+#[smol_potat::test]
+async fn test_pty_resize() {
+    let command = Command::builder("sh")
+        .arg("-c")
+        .arg("stty size; read line; stty size")
+        .pty(PtySize::new(24, 80))
+        .build();
+    let (mut events, mut handle) = LocalLauncher
+        .launch(&Target::Command, command)
+        .await
+        .unwrap();
+
+    let output = read_until(&mut events, "24 80").await;
+    assert!(output.contains("24 80"), "output: {:?}", output);
+
+    handle.resize_pty(PtySize::new(50, 132)).unwrap();
+    handle.stdin_mut().unwrap().write(b"\r").await.unwrap();
+    let output = read_until(&mut events, "50 132").await;
+    assert!(output.contains("50 132"), "output: {:?}", output);
+
+    handle.wait().await.unwrap();
+}
+
+#[smol_potat::test]
+async fn test_resize_without_pty() {
+    let (_events, mut handle) = LocalLauncher
+        .launch(&Target::Command, Command::new("true"))
+        .await
+        .unwrap();
+    assert!(handle.resize_pty(PtySize::default()).is_err());
+    assert!(!handle.stdin_mut().unwrap().is_pty());
+    handle.wait().await.unwrap();
+}
@@ -1,12 +1,13 @@
-//! Integration tests for systemd-portable that run in a real systemd container
+//! Integration tests for systemd targets that run in a real systemd container
 //!
 //! These tests require the systemd container to be running.
 //! Run them with: ./tests/systemd-container/run-systemd-tests.sh
 
 #![cfg(all(test, unix, feature = "integration-tests"))]
 
+use command_executor::backends::LocalLauncher;
 use command_executor::{Command, Executor, ProcessEventType, ProcessHandle};
-use command_executor::{SystemdPortable, Target};
+use command_executor::{SystemdPortable, SystemdService, Target};
 use futures::StreamExt;
 use std::time::Duration;
 
@@ -29,10 +30,7 @@ fn test_real_portablectl_attach_detach() {
 
     futures::executor::block_on(async {
         let executor = Executor::local("systemd-integration");
-        let target = Target::SystemdPortable(SystemdPortable::new(
-            "/opt/portable-services/echo-service",
-            "echo-service.service",
-        ));
+        let target = Target::Command;
 
         // First, ensure the service is not attached
         let detach_cmd = Command::builder("portablectl")
@@ -96,6 +94,21 @@ fn test_real_portablectl_attach_detach() {
     });
 }
 
+/// Run a command to completion, returning its exit code and stdout
+async fn run(executor: &Executor<LocalLauncher>, args: &[&str]) -> (Option<i32>, String) {
+    let command = Command::builder(args[0]).args(&args[1..]).build();
+    let (mut events, mut handle) = executor.launch(&Target::Command, command).await.unwrap();
+
+    let mut output = String::new();
+    while let Some(event) = events.next().await {
+        if let (ProcessEventType::Stdout, Some(data)) = (event.event_type, event.data) {
+            output.push_str(&data);
+            output.push('\n');
+        }
+    }
+    (handle.wait().await.unwrap().code, output)
+}
+
 #[test]
 fn test_portable_service_lifecycle() {
     if !is_in_systemd_container() {
@@ -105,81 +118,39 @@ fn test_portable_service_lifecycle() {
 
     futures::executor::block_on(async {
         let executor = Executor::local("systemd-lifecycle");
+        run(&executor, &["portablectl", "detach", "counter-service"]).await;
+
+        // Launching attaches the image and starts the unit
         let target = Target::SystemdPortable(SystemdPortable::new(
             "/opt/portable-services/counter-service",
             "counter-service.service",
         ));
+        let (_events, mut handle) = executor
+            .launch(&target, Command::new("ignored"))
+            .await
+            .unwrap();
+        assert!(handle.pid().is_some(), "Unit should have a main process");
 
-        // Clean slate
-        let detach_cmd = Command::builder("portablectl")
-            .arg("detach")
-            .arg("counter-service")
-            .build();
-        let _ = executor.launch(&target, detach_cmd).await;
-
-        // Attach the service
-        let attach_cmd = Command::builder("portablectl")
-            .arg("attach")
-            .arg("--copy=copy")
-            .arg("/opt/portable-services/counter-service")
-            .build();
-
-        let (_events, mut handle) = executor.launch(&target, attach_cmd).await.unwrap();
-        handle.wait().await.unwrap();
-
-        // Start the service
-        let start_cmd = Command::builder("systemctl")
-            .arg("start")
-            .arg("counter-service.service")
-            .build();
-
-        let (_events, mut handle) = executor.launch(&target, start_cmd).await.unwrap();
-        handle.wait().await.unwrap();
-
-        // Give it time to run
         smol::Timer::after(Duration::from_secs(3)).await;
-
-        // Check service status
-        let status_cmd = Command::builder("systemctl")
-            .arg("is-active")
-            .arg("counter-service.service")
-            .build();
-
-        let (mut events, mut handle) = executor.launch(&target, status_cmd).await.unwrap();
-
-        let mut status_output = String::new();
-        while let Some(event) = events.next().await {
-            if let ProcessEventType::Stdout = event.event_type {
-                if let Some(data) = event.data {
-                    status_output.push_str(&data);
-                }
-            }
-        }
-
-        let exit_status = handle.wait().await.unwrap();
-        assert_eq!(exit_status.code, Some(0), "Service should be active");
-        assert!(
-            status_output.contains("active"),
-            "Service status should be active"
-        );
-
-        // Stop the service
-        let stop_cmd = Command::builder("systemctl")
-            .arg("stop")
-            .arg("counter-service.service")
-            .build();
-
-        let (_events, mut handle) = executor.launch(&target, stop_cmd).await.unwrap();
-        handle.wait().await.unwrap();
-
-        // Detach the service
-        let detach_cmd = Command::builder("portablectl")
-            .arg("detach")
-            .arg("counter-service")
-            .build();
-
-        let (_events, mut handle) = executor.launch(&target, detach_cmd).await.unwrap();
+        let (code, status) = run(
+            &executor,
+            &["systemctl", "is-active", "counter-service.service"],
+        )
+        .await;
+        assert_eq!(code, Some(0), "Service should be active");
+        assert!(status.contains("active"), "Service status should be active");
+
+        // Terminating stops the unit
+        handle.terminate().await.unwrap();
         handle.wait().await.unwrap();
+        let (code, _) = run(
+            &executor,
+            &["systemctl", "is-active", "counter-service.service"],
+        )
+        .await;
+        assert_ne!(code, Some(0), "Service should be stopped");
+
+        run(&executor, &["portablectl", "detach", "counter-service"]).await;
     });
 }
 
@@ -192,74 +163,62 @@ fn test_portable_service_logs() {
 
     futures::executor::block_on(async {
         let executor = Executor::local("systemd-logs");
+        run(&executor, &["portablectl", "detach", "echo-service"]).await;
+
         let target = Target::SystemdPortable(SystemdPortable::new(
             "/opt/portable-services/echo-service",
             "echo-service.service",
         ));
+        let (mut events, mut handle) = executor
+            .launch(&target, Command::new("ignored"))
+            .await
+            .unwrap();
 
-        // Clean slate
-        let detach_cmd = Command::builder("portablectl")
-            .arg("detach")
-            .arg("echo-service")
-            .build();
-        let _ = executor.launch(&target, detach_cmd).await;
-
-        // Attach and start the service
-        let attach_cmd = Command::builder("portablectl")
-            .arg("attach")
-            .arg("--copy=copy")
-            .arg("--now") // Start immediately
-            .arg("/opt/portable-services/echo-service")
-            .build();
-
-        let (_events, mut handle) = executor.launch(&target, attach_cmd).await.unwrap();
-        handle.wait().await.unwrap();
-
-        // Give it time to generate some logs
-        smol::Timer::after(Duration::from_secs(2)).await;
-
-        // Read logs with journalctl
-        let logs_cmd = Command::builder("journalctl")
-            .arg("-u")
-            .arg("echo-service.service")
-            .arg("--no-pager")
-            .arg("-n")
-            .arg("10")
-            .build();
-
-        let (mut events, mut handle) = executor.launch(&target, logs_cmd).await.unwrap();
-
-        let mut log_output = String::new();
+        // Output comes from the unit's journal
+        let mut found = false;
         while let Some(event) = events.next().await {
-            if let ProcessEventType::Stdout = event.event_type {
-                if let Some(data) = event.data {
-                    log_output.push_str(&data);
-                    log_output.push('\n');
+            if let (ProcessEventType::Stdout, Some(data)) = (event.event_type, event.data) {
+                if data.contains("Echo service") {
+                    found = true;
+                    break;
                 }
             }
         }
+        assert!(found, "Logs should contain service output");
 
+        // The event stream ends once the unit is stopped
+        handle.terminate().await.unwrap();
+        while events.next().await.is_some() {}
         handle.wait().await.unwrap();
 
-        // Verify logs contain expected output
-        assert!(
-            log_output.contains("Echo service"),
-            "Logs should contain service output"
-        );
+        run(&executor, &["portablectl", "detach", "echo-service"]).await;
+    });
+}
 
-        // Stop and detach
-        let stop_cmd = Command::builder("systemctl")
-            .arg("stop")
-            .arg("echo-service.service")
-            .build();
-        let (_events, mut handle) = executor.launch(&target, stop_cmd).await.unwrap();
-        handle.wait().await.unwrap();
+#[test]
+fn test_transient_service() {
+    if !is_in_systemd_container() {
+        eprintln!("Skipping test - not in systemd container");
+        return;
+    }
 
-        let detach_cmd = Command::builder("portablectl")
-            .arg("detach")
-            .arg("echo-service")
+    futures::executor::block_on(async {
+        let executor = Executor::local("systemd-transient");
+        let target = Target::SystemdService(
+            SystemdService::new("harness-transient.service").with_user_manager(false),
+        );
+        let command = Command::builder("sh")
+            .args(["-c", "echo transient says hi; exit 3"])
             .build();
-        let (_events, mut handle) = executor.launch(&target, detach_cmd).await.unwrap();
-        handle.wait().await.unwrap();
+
+        let (mut events, mut handle) = executor.launch(&target, command).await.unwrap();
+        let mut output = String::new();
+        while let Some(event) = events.next().await {
+            if let Some(data) = event.data {
+                output.push_str(&data);
+            }
+        }
+        assert!(output.contains("transient says hi"));
+        assert_eq!(handle.wait().await.unwrap().code, Some(3));
     });
 }
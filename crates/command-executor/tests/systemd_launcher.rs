@@ -0,0 +1,169 @@
+//! LocalLauncher with systemd targets, against stand-in `systemd-run`,
+//! `systemctl`, `journalctl` and `portablectl` scripts on `PATH`
+//!
+//! Everything runs in one test since it changes the process's `PATH`.
+
+#![cfg(unix)]
+#![allow(unsafe_code)]
+
+use command_executor::{
+    Command, ProcessEventType,
+    backends::LocalLauncher,
+    launcher::Launcher,
+    process::ProcessHandle,
+    target::{SystemdPortable, SystemdService, Target},
+};
+use futures::StreamExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// The stand-ins keep the unit's state in `$DIR/state` and log every call
+/// to `$DIR/calls`. `journalctl` opens with a burst of service-manager
+/// messages, which the event stream skips.
+const SCRIPTS: [(&str, &str); 4] = [
+    (
+        "systemd-run",
+        r#"echo "systemd-run $*" >> "$DIR/calls"
+echo active > "$DIR/state""#,
+    ),
+    (
+        "systemctl",
+        r#"echo "systemctl $*" >> "$DIR/calls"
+case "$*" in
+  *MainPID*) echo 4242 ;;
+  *show*)
+    if [ "$(cat "$DIR/state")" = active ]; then
+      echo ActiveState=active
+    else
+      printf 'ActiveState=failed\nResult=signal\nExecMainCode=2\nExecMainStatus=15\n'
+    fi ;;
+  *start*) echo active > "$DIR/state" ;;
+  *stop*) echo stopped > "$DIR/state" ;;
+esac"#,
+    ),
+    (
+        "journalctl",
+        r#"i=0
+while [ $i -lt 20000 ]; do
+  echo '{"MESSAGE":"Started unit.","UNIT":"unit.service"}'
+  i=$((i + 1))
+done
+echo '{"MESSAGE":"hello from the unit","PRIORITY":"6"}'
+echo '{"MESSAGE":"something broke","PRIORITY":"3"}'
+while [ "$(cat "$DIR/state")" = active ]; do sleep 0.05; done
+echo '{"MESSAGE":"Stopped unit.","MESSAGE_ID":"9d1aaa27d60140bd96365438aad20286","UNIT":"unit.service"}'
+exec sleep 60"#,
+    ),
+    (
+        "portablectl",
+        r#"echo "portablectl $*" >> "$DIR/calls"
+[ "$1" != is-attached ]"#,
+    ),
+];
+
+fn install_scripts(dir: &Path) {
+    for (name, body) in SCRIPTS {
+        let path = dir.join(name);
+        std::fs::write(
+            &path,
+            format!("#!/bin/sh\nDIR='{}'\n{}\n", dir.display(), body),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    std::fs::write(dir.join("state"), "inactive").unwrap();
+
+    let path = std::env::var("PATH").unwrap_or_default();
+    // SAFETY: this is the only test in the binary
+    unsafe { std::env::set_var("PATH", format!("{}:{}", dir.display(), path)) };
+}
+
+fn calls(dir: &Path) -> Vec<String> {
+    std::fs::read_to_string(dir.join("calls"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[smol_potat::test]
+async fn test_systemd_targets() {
+    let dir = tempfile::tempdir().unwrap();
+    install_scripts(dir.path());
+
+    // A transient unit running the command
+    let command = Command::builder("anvil")
+        .args(["--port", "8545"])
+        .env("RUST_LOG", "info")
+        .build();
+    let target = Target::SystemdService(SystemdService::new("unit.service"));
+    let (mut events, mut handle) = LocalLauncher.launch(&target, command).await.unwrap();
+    assert_eq!(handle.pid(), Some(4242));
+
+    let mut seen = Vec::new();
+    for _ in 0..3 {
+        let event = events.next().await.unwrap();
+        seen.push((event.event_type, event.data));
+    }
+    assert_eq!(
+        seen,
+        vec![
+            (ProcessEventType::Started { pid: 4242 }, None),
+            (
+                ProcessEventType::Stdout,
+                Some("hello from the unit".to_string())
+            ),
+            (
+                ProcessEventType::Stderr,
+                Some("something broke".to_string())
+            ),
+        ]
+    );
+
+    handle.terminate().await.unwrap();
+    // The stream ends once the journal reports the unit stopped
+    assert!(events.next().await.is_none());
+    let status = handle.wait().await.unwrap();
+    assert_eq!((status.code, status.signal), (None, Some(15)));
+    handle.reload().await.unwrap();
+    drop(handle);
+
+    assert_eq!(
+        calls(dir.path()),
+        vec![
+            "systemctl --user reset-failed unit.service",
+            "systemd-run --user --unit=unit.service --quiet --property=Type=exec \
+             --setenv=RUST_LOG=info -- anvil --port 8545",
+            "systemctl --user show --property=MainPID --value unit.service",
+            "systemctl --user stop unit.service",
+            "systemctl --user show --property=ActiveState,Result,ExecMainCode,ExecMainStatus unit.service",
+            "systemctl --user reset-failed unit.service",
+            // Transient units have no reload command
+            "systemctl --user kill --signal=SIGHUP unit.service",
+        ]
+    );
+    std::fs::remove_file(dir.path().join("calls")).unwrap();
+
+    // Portable images are attached before their unit starts
+    let target = Target::SystemdPortable(SystemdPortable::new(
+        "/opt/portable-services/echo-service",
+        "unit.service",
+    ));
+    let (_events, mut handle) = LocalLauncher
+        .launch(&target, Command::new("ignored"))
+        .await
+        .unwrap();
+    handle.interrupt().await.unwrap();
+    handle.reload().await.unwrap();
+    assert_eq!(
+        calls(dir.path()),
+        vec![
+            "portablectl is-attached /opt/portable-services/echo-service",
+            "portablectl attach /opt/portable-services/echo-service",
+            "systemctl start unit.service",
+            "systemctl show --property=MainPID --value unit.service",
+            "systemctl kill --signal=SIGINT unit.service",
+            "systemctl reload unit.service",
+        ]
+    );
+}
@@ -0,0 +1,137 @@
+//! Tests for command timeouts and cancellation
+
+use command_executor::{
+    CancellationToken, Command, Error, Launcher, Target, backends::LocalLauncher,
+};
+use std::time::{Duration, Instant};
+
+/// Whether a process is still running (zombies don't count)
+fn is_running(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid)).is_ok_and(|stat| {
+        !stat
+            .rsplit_once(')')
+            .is_some_and(|(_, rest)| rest.trim_start().starts_with('Z'))
+    })
+}
+
+#[smol_potat::test]
+async fn test_timeout_captures_partial_output() {
+    let command = Command::builder("sh")
+        .arg("-c")
+        .arg("echo started; sleep 30")
+        .timeout(Duration::from_millis(500))
+        .build();
+
+    let start = Instant::now();
+    let result = LocalLauncher.execute(&Target::Command, command).await;
+
+    match result {
+        Err(Error::Timeout { timeout, output }) => {
+            assert_eq!(timeout, Duration::from_millis(500));
+            assert_eq!(output, "started\n");
+        }
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[smol_potat::test]
+async fn test_timeout_after_output_closed() {
+    // Nothing more is read once the streams are closed, only the exit is
+    // waited for
+    let command = Command::builder("sh")
+        .arg("-c")
+        .arg("echo hi; exec >&- 2>&-; sleep 6")
+        .timeout(Duration::from_millis(500))
+        .build();
+
+    let start = Instant::now();
+    match LocalLauncher.execute(&Target::Command, command).await {
+        Err(Error::Timeout { output, .. }) => assert_eq!(output, "hi\n"),
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[smol_potat::test]
+async fn test_timeout_stops_process_group() {
+    // The background sleep ignores SIGTERM like its shell, so the group has
+    // to be killed
+    let command = Command::builder("sh")
+        .arg("-c")
+        .arg("trap '' TERM; sleep 30 & echo $!; wait")
+        .timeout(Duration::from_millis(300))
+        .build();
+
+    let start = Instant::now();
+    let Err(Error::Timeout { output, .. }) = LocalLauncher.execute(&Target::Command, command).await
+    else {
+        panic!("expected a timeout");
+    };
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let pid: u32 = output.trim().parse().expect("background pid");
+    // Killed descendants are reaped by init
+    for _ in 0..50 {
+        if !is_running(pid) {
+            return;
+        }
+        smol::Timer::after(Duration::from_millis(20)).await;
+    }
+    panic!("background process {} survived the timeout", pid);
+}
+
+#[smol_potat::test]
+async fn test_finishes_within_timeout() {
+    let command = Command::builder("echo")
+        .arg("done")
+        .timeout(Duration::from_secs(10))
+        .build();
+
+    let result = LocalLauncher
+        .execute(&Target::Command, command)
+        .await
+        .unwrap();
+    assert!(result.success());
+    assert_eq!(result.output, "done\n");
+}
+
+#[smol_potat::test]
+async fn test_cancellation() {
+    let token = CancellationToken::new();
+    let command = Command::builder("sh")
+        .arg("-c")
+        .arg("echo waiting; sleep 30")
+        .cancel_on(token.clone())
+        .build();
+
+    smol::spawn(async move {
+        smol::Timer::after(Duration::from_millis(300)).await;
+        token.cancel();
+    })
+    .detach();
+
+    let start = Instant::now();
+    match LocalLauncher.execute(&Target::Command, command).await {
+        Err(Error::Cancelled { output }) => assert_eq!(output, "waiting\n"),
+        other => panic!("expected cancellation, got {:?}", other),
+    }
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[smol_potat::test]
+async fn test_cancelled_before_launch() {
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("ran");
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let command = Command::builder("touch")
+        .arg(&marker)
+        .cancel_on(token)
+        .build();
+
+    let result = LocalLauncher.execute(&Target::Command, command).await;
+    assert!(matches!(result, Err(Error::Cancelled { .. })));
+    assert!(!marker.exists());
+}
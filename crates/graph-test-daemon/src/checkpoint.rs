@@ -0,0 +1,230 @@
+//! Coordinated checkpoints of the Graph test stack
+//!
+//! A checkpoint captures the chain (anvil `evm_snapshot`), every managed
+//! database (copied into a template database) and the IPFS pin set, so a
+//! test suite can reset to e.g. "contracts deployed, subgraph synced" without
+//! restarting anything.
+//!
+//! Restoring a checkpoint discards every checkpoint taken after it: anvil
+//! drops later snapshots when it reverts, so they could no longer be restored
+//! consistently.
+
+use chrono::Utc;
+use harness_core::prelude::*;
+use schemars::JsonSchema;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{info, warn};
+
+use crate::services::{AnvilService, IpfsService, PostgresService};
+
+/// State captured by a checkpoint, keyed by service instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Checkpoint {
+    /// Checkpoint ID, e.g. `checkpoint-1`
+    pub id: String,
+    /// Optional label, e.g. `subgraph-synced`
+    pub label: Option<String>,
+    /// RFC 3339 creation time
+    pub created_at: String,
+    /// Anvil snapshot ID per instance
+    pub anvil: BTreeMap<String, String>,
+    /// Template database per Postgres instance
+    pub postgres: BTreeMap<String, String>,
+    /// Recursively pinned CIDs per IPFS instance
+    pub ipfs: BTreeMap<String, Vec<String>>,
+}
+
+/// Parameters of the `checkpoint` action
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CheckpointParams {
+    /// Label to remember the checkpoint by
+    pub label: Option<String>,
+}
+
+/// Parameters of the `restore-checkpoint` and `delete-checkpoint` actions
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CheckpointRef {
+    /// Checkpoint ID or label
+    pub id: String,
+}
+
+/// Takes and restores checkpoints across the stack's services
+#[derive(Default)]
+pub struct Checkpoints {
+    anvil: Vec<(String, AnvilService)>,
+    postgres: Vec<(String, PostgresService)>,
+    ipfs: Vec<(String, IpfsService)>,
+    /// Checkpoints in creation order
+    checkpoints: Mutex<Vec<Checkpoint>>,
+    next_id: AtomicU64,
+}
+
+impl Checkpoints {
+    /// Create a tracker with no services
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include an anvil instance in checkpoints
+    pub fn add_anvil(&mut self, instance: impl Into<String>, anvil: AnvilService) {
+        self.anvil.push((instance.into(), anvil));
+    }
+
+    /// Include a Postgres instance in checkpoints
+    pub fn add_postgres(&mut self, instance: impl Into<String>, postgres: PostgresService) {
+        self.postgres.push((instance.into(), postgres));
+    }
+
+    /// Include an IPFS instance in checkpoints
+    pub fn add_ipfs(&mut self, instance: impl Into<String>, ipfs: IpfsService) {
+        self.ipfs.push((instance.into(), ipfs));
+    }
+
+    /// Checkpoints that can be restored, oldest first
+    pub fn list(&self) -> Vec<Checkpoint> {
+        self.checkpoints.lock().unwrap().clone()
+    }
+
+    /// Find a checkpoint by ID or label
+    pub fn get(&self, id: &str) -> Option<Checkpoint> {
+        let checkpoints = self.checkpoints.lock().unwrap();
+        checkpoints
+            .iter()
+            .find(|c| c.id == id)
+            .or_else(|| {
+                checkpoints
+                    .iter()
+                    .rev()
+                    .find(|c| c.label.as_deref() == Some(id))
+            })
+            .cloned()
+    }
+
+    /// Capture the current state of every service
+    pub async fn create(&self, label: Option<String>) -> Result<Checkpoint> {
+        let number = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut checkpoint = Checkpoint {
+            id: format!("checkpoint-{}", number),
+            label,
+            created_at: Utc::now().to_rfc3339(),
+            anvil: BTreeMap::new(),
+            postgres: BTreeMap::new(),
+            ipfs: BTreeMap::new(),
+        };
+        info!("Creating {}", checkpoint.id);
+
+        for (instance, anvil) in &self.anvil {
+            checkpoint
+                .anvil
+                .insert(instance.clone(), anvil.snapshot().await?);
+        }
+
+        for (instance, postgres) in &self.postgres {
+            let template = format!("{}__checkpoint_{}", postgres.db_name(), number);
+            if let Err(e) = postgres.create_template(&template).await {
+                self.discard(&checkpoint).await;
+                return Err(e);
+            }
+            checkpoint.postgres.insert(instance.clone(), template);
+        }
+
+        for (instance, ipfs) in &self.ipfs {
+            match ipfs.pins().await {
+                Ok(pins) => {
+                    checkpoint.ipfs.insert(instance.clone(), pins);
+                }
+                Err(e) => {
+                    self.discard(&checkpoint).await;
+                    return Err(e);
+                }
+            }
+        }
+
+        self.checkpoints.lock().unwrap().push(checkpoint.clone());
+        Ok(checkpoint)
+    }
+
+    /// Reset every service to a checkpoint
+    ///
+    /// The checkpoint stays available, so it can be restored again by the
+    /// next test.
+    pub async fn restore(&self, id: &str) -> Result<Checkpoint> {
+        let mut checkpoint = self
+            .get(id)
+            .ok_or_else(|| Error::action(format!("Checkpoint '{}' not found", id)))?;
+        info!("Restoring {}", checkpoint.id);
+
+        for (instance, anvil) in &self.anvil {
+            let Some(snapshot) = checkpoint.anvil.get(instance) else {
+                continue;
+            };
+            anvil.revert(snapshot).await?;
+            // Reverting consumes the snapshot; take a fresh one of the same state
+            let snapshot = anvil.snapshot().await?;
+            checkpoint.anvil.insert(instance.clone(), snapshot);
+        }
+
+        for (instance, postgres) in &self.postgres {
+            if let Some(template) = checkpoint.postgres.get(instance) {
+                postgres.restore_template(template).await?;
+            }
+        }
+
+        for (instance, ipfs) in &self.ipfs {
+            let Some(pinned) = checkpoint.ipfs.get(instance) else {
+                continue;
+            };
+            let current = ipfs.pins().await?;
+            for cid in current.iter().filter(|cid| !pinned.contains(cid)) {
+                ipfs.unpin(cid).await?;
+            }
+            for cid in pinned.iter().filter(|cid| !current.contains(cid)) {
+                ipfs.pin(cid).await?;
+            }
+        }
+
+        let later = {
+            let mut checkpoints = self.checkpoints.lock().unwrap();
+            match checkpoints.iter().position(|c| c.id == checkpoint.id) {
+                Some(position) => {
+                    checkpoints[position] = checkpoint.clone();
+                    checkpoints.split_off(position + 1)
+                }
+                // Deleted while restoring
+                None => Vec::new(),
+            }
+        };
+        for stale in &later {
+            info!("Discarding {} taken after {}", stale.id, checkpoint.id);
+            self.discard(stale).await;
+        }
+
+        Ok(checkpoint)
+    }
+
+    /// Forget a checkpoint and drop its template databases
+    pub async fn delete(&self, id: &str) -> Result<Checkpoint> {
+        let checkpoint = self
+            .get(id)
+            .ok_or_else(|| Error::action(format!("Checkpoint '{}' not found", id)))?;
+        self.checkpoints
+            .lock()
+            .unwrap()
+            .retain(|c| c.id != checkpoint.id);
+        self.discard(&checkpoint).await;
+        Ok(checkpoint)
+    }
+
+    /// Drop the template databases of a checkpoint, logging failures
+    async fn discard(&self, checkpoint: &Checkpoint) {
+        for (instance, postgres) in &self.postgres {
+            if let Some(template) = checkpoint.postgres.get(instance)
+                && let Err(e) = postgres.drop_database(template).await
+            {
+                warn!("Failed to drop checkpoint database '{}': {}", template, e);
+            }
+        }
+    }
+}
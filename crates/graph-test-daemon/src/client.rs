@@ -0,0 +1,877 @@
+//! Typed daemon client
+//!
+//! Generated from the daemon's published schema by `cargo xtask codegen`.
+//! Do not edit by hand.
+
+#![allow(missing_docs, clippy::all)]
+
+use harness_core::{Result, TlsClientConfig};
+use harness_core::client::{Client, TestClient, TypedEventStream};
+use harness_core::service::ServiceState;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+/// Typed client for the daemon
+pub struct GraphTestClient {
+    inner: TestClient,
+}
+
+impl GraphTestClient {
+    /// Connect to the daemon
+    pub async fn connect(endpoint: SocketAddr) -> Result<Self> {
+        Ok(Self::new(TestClient::connect(endpoint).await?))
+    }
+
+    /// Connect to a daemon serving its endpoint over TLS
+    pub async fn connect_tls(endpoint: SocketAddr, tls_config: TlsClientConfig, server_name: &str) -> Result<Self> {
+        Ok(Self::new(TestClient::connect_tls(endpoint, tls_config, server_name).await?))
+    }
+
+    /// Wrap a connected client
+    pub fn new(inner: TestClient) -> Self {
+        Self { inner }
+    }
+
+    /// Untyped client underneath
+    pub fn inner(&self) -> &TestClient {
+        &self.inner
+    }
+
+    /// Set up a complete Graph Protocol test stack
+    pub async fn setup_test_stack(&self, params: serde_json::Value) -> Result<serde_json::Value> {
+        let result = self.inner.action("setup-test-stack", serde_json::to_value(params)?).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Check health of all Graph Protocol services
+    pub async fn health_check_stack(&self, params: serde_json::Value) -> Result<serde_json::Value> {
+        let result = self.inner.action("health-check-stack", serde_json::to_value(params)?).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Snapshot anvil, Postgres and IPFS state for a later restore
+    pub async fn checkpoint(&self, params: CheckpointParams) -> Result<Checkpoint> {
+        let result = self.inner.action("checkpoint", serde_json::to_value(params)?).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Reset anvil, Postgres and IPFS to a checkpoint
+    pub async fn restore_checkpoint(&self, params: CheckpointRef) -> Result<Checkpoint> {
+        let result = self.inner.action("restore-checkpoint", serde_json::to_value(params)?).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// List checkpoints, oldest first
+    pub async fn list_checkpoints(&self, params: serde_json::Value) -> Result<ArrayOfCheckpoint> {
+        let result = self.inner.action("list-checkpoints", serde_json::to_value(params)?).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Forget a checkpoint and drop its template databases
+    pub async fn delete_checkpoint(&self, params: CheckpointRef) -> Result<Checkpoint> {
+        let result = self.inner.action("delete-checkpoint", serde_json::to_value(params)?).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// `anvil`: Anvil local Ethereum blockchain for testing
+    pub fn anvil(&self) -> AnvilService<'_> {
+        AnvilService { client: &self.inner, instance: "anvil" }
+    }
+
+    /// `graph-node`: Graph Node service for subgraph deployment and querying
+    pub fn graph_node(&self) -> GraphNodeService<'_> {
+        GraphNodeService { client: &self.inner, instance: "graph-node" }
+    }
+
+    /// `ipfs`: IPFS distributed storage service
+    pub fn ipfs(&self) -> IpfsService<'_> {
+        IpfsService { client: &self.inner, instance: "ipfs" }
+    }
+
+    /// `postgres`: PostgreSQL database service
+    pub fn postgres(&self) -> PostgresService<'_> {
+        PostgresService { client: &self.inner, instance: "postgres" }
+    }
+
+    /// `deploy-graph-contracts`: Deploy Graph Protocol smart contracts
+    pub fn deploy_graph_contracts(&self) -> GraphContractsTask<'_> {
+        GraphContractsTask { client: &self.inner, instance: "deploy-graph-contracts" }
+    }
+
+    /// `deploy-tap-contracts`: Deploy TAP (Timeline Aggregation Protocol) contracts
+    pub fn deploy_tap_contracts(&self) -> TapContractsTask<'_> {
+        TapContractsTask { client: &self.inner, instance: "deploy-tap-contracts" }
+    }
+}
+
+/// Handle for `anvil` service instances
+#[derive(Clone, Copy)]
+pub struct AnvilService<'a> {
+    client: &'a TestClient,
+    instance: &'static str,
+}
+
+impl AnvilService<'_> {
+    /// Instance name
+    pub fn instance(&self) -> &'static str {
+        self.instance
+    }
+
+    /// Current state of the instance
+    pub async fn state(&self) -> Result<ServiceState> {
+        self.client.service_state(self.instance).await
+    }
+
+    /// Anvil local Ethereum blockchain for testing
+    pub async fn dispatch(&self, input: AnvilAction) -> Result<TypedEventStream<AnvilEvent>> {
+        Ok(self.client.dispatch(self.instance, "default", serde_json::to_value(input)?).await?.typed())
+    }
+
+    /// Mine a number of blocks
+    pub async fn mine_blocks(&self, count: u64, interval_secs: Option<u64>) -> Result<TypedEventStream<AnvilEvent>> {
+        self.dispatch(AnvilAction::MineBlocks { count, interval_secs }).await
+    }
+
+    /// Set account balance
+    pub async fn set_balance(&self, address: String, balance: String) -> Result<TypedEventStream<AnvilEvent>> {
+        self.dispatch(AnvilAction::SetBalance { address, balance }).await
+    }
+
+    /// Reset the chain to a fork of another network
+    pub async fn fork(&self, url: String, block_number: Option<u64>) -> Result<TypedEventStream<AnvilEvent>> {
+        self.dispatch(AnvilAction::Fork { url, block_number }).await
+    }
+
+    /// Snapshot the chain state
+    pub async fn snapshot(&self) -> Result<TypedEventStream<AnvilEvent>> {
+        self.dispatch(AnvilAction::Snapshot).await
+    }
+
+    /// Revert the chain to a snapshot
+    pub async fn revert(&self, snapshot_id: String) -> Result<TypedEventStream<AnvilEvent>> {
+        self.dispatch(AnvilAction::Revert { snapshot_id }).await
+    }
+
+    /// Advance the timestamp of the next block
+    pub async fn increase_time(&self, seconds: u64) -> Result<TypedEventStream<AnvilEvent>> {
+        self.dispatch(AnvilAction::IncreaseTime { seconds }).await
+    }
+
+    /// Send transactions from an account without its key
+    pub async fn impersonate(&self, address: String) -> Result<TypedEventStream<AnvilEvent>> {
+        self.dispatch(AnvilAction::Impersonate { address }).await
+    }
+
+    /// Stop impersonating an account
+    pub async fn stop_impersonating(&self, address: String) -> Result<TypedEventStream<AnvilEvent>> {
+        self.dispatch(AnvilAction::StopImpersonating { address }).await
+    }
+
+    /// Query an account balance
+    pub async fn get_balance(&self, address: String) -> Result<TypedEventStream<AnvilEvent>> {
+        self.dispatch(AnvilAction::GetBalance { address }).await
+    }
+}
+
+/// Handle for `graph-node` service instances
+#[derive(Clone, Copy)]
+pub struct GraphNodeService<'a> {
+    client: &'a TestClient,
+    instance: &'static str,
+}
+
+impl GraphNodeService<'_> {
+    /// Instance name
+    pub fn instance(&self) -> &'static str {
+        self.instance
+    }
+
+    /// Current state of the instance
+    pub async fn state(&self) -> Result<ServiceState> {
+        self.client.service_state(self.instance).await
+    }
+
+    /// Graph Node service for subgraph deployment and querying
+    pub async fn dispatch(&self, input: GraphNodeAction) -> Result<TypedEventStream<GraphNodeEvent>> {
+        Ok(self.client.dispatch(self.instance, "default", serde_json::to_value(input)?).await?.typed())
+    }
+
+    /// Create a subgraph name if needed, deploy a manifest to it and follow
+    /// indexing until it is synced
+    pub async fn deploy_subgraph(&self, name: String, ipfs_hash: String, version_label: Option<String>) -> Result<TypedEventStream<GraphNodeEvent>> {
+        self.dispatch(GraphNodeAction::DeploySubgraph { name, ipfs_hash, version_label }).await
+    }
+
+    /// Wait until a deployment has indexed a block, or reached the chain head
+    /// when no block is given
+    pub async fn wait_for_sync(&self, deployment: String, block: Option<u64>, timeout: Option<u64>) -> Result<TypedEventStream<GraphNodeEvent>> {
+        self.dispatch(GraphNodeAction::WaitForSync { deployment, block, timeout }).await
+    }
+
+    /// Query a deployed subgraph
+    pub async fn query_subgraph(&self, subgraph_name: String, query: String) -> Result<TypedEventStream<GraphNodeEvent>> {
+        self.dispatch(GraphNodeAction::QuerySubgraph { subgraph_name, query }).await
+    }
+
+    /// Remove a subgraph name
+    pub async fn remove_subgraph(&self, name: String) -> Result<TypedEventStream<GraphNodeEvent>> {
+        self.dispatch(GraphNodeAction::RemoveSubgraph { name }).await
+    }
+}
+
+/// Handle for `ipfs` service instances
+#[derive(Clone, Copy)]
+pub struct IpfsService<'a> {
+    client: &'a TestClient,
+    instance: &'static str,
+}
+
+impl IpfsService<'_> {
+    /// Instance name
+    pub fn instance(&self) -> &'static str {
+        self.instance
+    }
+
+    /// Current state of the instance
+    pub async fn state(&self) -> Result<ServiceState> {
+        self.client.service_state(self.instance).await
+    }
+
+    /// IPFS distributed storage service
+    pub async fn dispatch(&self, input: IpfsAction) -> Result<TypedEventStream<IpfsEvent>> {
+        Ok(self.client.dispatch(self.instance, "default", serde_json::to_value(input)?).await?.typed())
+    }
+
+    /// Add content to IPFS
+    pub async fn add_content(&self, content: String, pin: Option<bool>) -> Result<TypedEventStream<IpfsEvent>> {
+        self.dispatch(IpfsAction::AddContent { content, pin }).await
+    }
+
+    /// Add a local directory, e.g. a subgraph's build output
+    ///
+    /// A `subgraph.yaml` at its root is added once more with its file
+    /// references pointing into the directory, ready for `DeploySubgraph`.
+    pub async fn add_directory(&self, path: String, pin: Option<bool>) -> Result<TypedEventStream<IpfsEvent>> {
+        self.dispatch(IpfsAction::AddDirectory { path, pin }).await
+    }
+
+    /// Pin a hash
+    pub async fn pin(&self, hash: String) -> Result<TypedEventStream<IpfsEvent>> {
+        self.dispatch(IpfsAction::Pin { hash }).await
+    }
+
+    /// Unpin a hash
+    pub async fn unpin(&self, hash: String) -> Result<TypedEventStream<IpfsEvent>> {
+        self.dispatch(IpfsAction::Unpin { hash }).await
+    }
+
+    /// Get content by hash or IPFS path
+    pub async fn cat(&self, hash: String) -> Result<TypedEventStream<IpfsEvent>> {
+        self.dispatch(IpfsAction::Cat { hash }).await
+    }
+}
+
+/// Handle for `postgres` service instances
+#[derive(Clone, Copy)]
+pub struct PostgresService<'a> {
+    client: &'a TestClient,
+    instance: &'static str,
+}
+
+impl PostgresService<'_> {
+    /// Instance name
+    pub fn instance(&self) -> &'static str {
+        self.instance
+    }
+
+    /// Current state of the instance
+    pub async fn state(&self) -> Result<ServiceState> {
+        self.client.service_state(self.instance).await
+    }
+
+    /// PostgreSQL database service
+    pub async fn dispatch(&self, input: PostgresAction) -> Result<TypedEventStream<PostgresEvent>> {
+        Ok(self.client.dispatch(self.instance, "default", serde_json::to_value(input)?).await?.typed())
+    }
+
+    /// Create a new database from `template0`, as graph-node requires
+    pub async fn create_database(&self, name: String, extensions: Option<Vec<String>>, locale: Option<String>) -> Result<TypedEventStream<PostgresEvent>> {
+        self.dispatch(PostgresAction::CreateDatabase { name, extensions, locale }).await
+    }
+
+    /// Run a single SQL statement
+    pub async fn execute_query(&self, query: String, database: Option<String>) -> Result<TypedEventStream<PostgresEvent>> {
+        self.dispatch(PostgresAction::ExecuteQuery { query, database }).await
+    }
+
+    /// Dump a database with `pg_dump` to a local SQL file
+    pub async fn backup(&self, backup_path: String, database: Option<String>) -> Result<TypedEventStream<PostgresEvent>> {
+        self.dispatch(PostgresAction::Backup { backup_path, database }).await
+    }
+
+    /// Load a backup into an existing database, replacing the objects in it
+    pub async fn restore(&self, backup_path: String, database: Option<String>) -> Result<TypedEventStream<PostgresEvent>> {
+        self.dispatch(PostgresAction::Restore { backup_path, database }).await
+    }
+
+    /// Drop a database, disconnecting its sessions
+    pub async fn drop_database(&self, name: String) -> Result<TypedEventStream<PostgresEvent>> {
+        self.dispatch(PostgresAction::DropDatabase { name }).await
+    }
+}
+
+/// Handle for `graph-contracts` task instances
+#[derive(Clone, Copy)]
+pub struct GraphContractsTask<'a> {
+    client: &'a TestClient,
+    instance: &'static str,
+}
+
+impl GraphContractsTask<'_> {
+    /// Instance name
+    pub fn instance(&self) -> &'static str {
+        self.instance
+    }
+
+    /// Deploy Graph Protocol smart contracts
+    pub async fn execute(&self, input: GraphContractsAction) -> Result<TypedEventStream<GraphContractsEvent>> {
+        Ok(self.client.execute_task(self.instance, serde_json::to_value(input)?).await?.typed())
+    }
+
+    /// Deploy all Graph Protocol contracts
+    pub async fn deploy_all(&self) -> Result<TypedEventStream<GraphContractsEvent>> {
+        self.execute(GraphContractsAction::DeployAll).await
+    }
+
+    /// Deploy a specific contract
+    pub async fn deploy_contract(&self, name: String) -> Result<TypedEventStream<GraphContractsEvent>> {
+        self.execute(GraphContractsAction::DeployContract { name }).await
+    }
+
+    /// Verify deployment addresses
+    pub async fn verify_deployment(&self) -> Result<TypedEventStream<GraphContractsEvent>> {
+        self.execute(GraphContractsAction::VerifyDeployment).await
+    }
+}
+
+/// Handle for `tap-contracts` task instances
+#[derive(Clone, Copy)]
+pub struct TapContractsTask<'a> {
+    client: &'a TestClient,
+    instance: &'static str,
+}
+
+impl TapContractsTask<'_> {
+    /// Instance name
+    pub fn instance(&self) -> &'static str {
+        self.instance
+    }
+
+    /// Deploy TAP (Timeline Aggregation Protocol) contracts
+    pub async fn execute(&self, input: TapContractsAction) -> Result<TypedEventStream<TapContractsEvent>> {
+        Ok(self.client.execute_task(self.instance, serde_json::to_value(input)?).await?.typed())
+    }
+
+    /// Deploy all TAP contracts
+    pub async fn deploy_all(&self) -> Result<TypedEventStream<TapContractsEvent>> {
+        self.execute(TapContractsAction::DeployAll).await
+    }
+
+    /// Verify deployment
+    pub async fn verify_deployment(&self) -> Result<TypedEventStream<TapContractsEvent>> {
+        self.execute(TapContractsAction::VerifyDeployment).await
+    }
+}
+
+/// Parameters of the `checkpoint` action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointParams {
+    /// Label to remember the checkpoint by
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// State captured by a checkpoint, keyed by service instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Checkpoint ID, e.g. `checkpoint-1`
+    pub id: String,
+    /// RFC 3339 creation time
+    pub created_at: String,
+    /// Anvil snapshot ID per instance
+    pub anvil: std::collections::HashMap<String, String>,
+    /// Template database per Postgres instance
+    pub postgres: std::collections::HashMap<String, String>,
+    /// Recursively pinned CIDs per IPFS instance
+    pub ipfs: std::collections::HashMap<String, Vec<String>>,
+    /// Optional label, e.g. `subgraph-synced`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Parameters of the `restore-checkpoint` and `delete-checkpoint` actions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointRef {
+    /// Checkpoint ID or label
+    pub id: String,
+}
+
+pub type ArrayOfCheckpoint = Vec<Checkpoint>;
+
+/// Actions for Anvil blockchain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AnvilAction {
+    /// Mine a number of blocks
+    MineBlocks {
+        /// Number of blocks to mine
+        count: u64,
+        /// Timestamp interval between the mined blocks
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        interval_secs: Option<u64>,
+    },
+    /// Set account balance
+    SetBalance {
+        /// Account address
+        address: String,
+        /// Balance in wei, decimal or 0x-prefixed hex
+        balance: String,
+    },
+    /// Reset the chain to a fork of another network
+    Fork {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        block_number: Option<u64>,
+    },
+    /// Snapshot the chain state
+    Snapshot,
+    /// Revert the chain to a snapshot
+    Revert {
+        /// Snapshot ID returned by `Snapshot`
+        snapshot_id: String,
+    },
+    /// Advance the timestamp of the next block
+    IncreaseTime {
+        /// Seconds to advance by
+        seconds: u64,
+    },
+    /// Send transactions from an account without its key
+    Impersonate {
+        /// Account address
+        address: String,
+    },
+    /// Stop impersonating an account
+    StopImpersonating {
+        /// Account address
+        address: String,
+    },
+    /// Query an account balance
+    GetBalance {
+        /// Account address
+        address: String,
+    },
+}
+
+/// Events from Anvil blockchain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum AnvilEvent {
+    /// Block mined
+    BlockMined {
+        block_number: u64,
+        block_hash: String,
+        /// Block timestamp in seconds since the Unix epoch
+        timestamp: u64,
+    },
+    /// Balance updated
+    BalanceUpdated {
+        address: String,
+        /// New balance in wei
+        new_balance: String,
+    },
+    /// Fork created
+    ForkCreated {
+        fork_url: String,
+        forked_at_block: u64,
+    },
+    /// Snapshot taken
+    SnapshotTaken {
+        /// ID to revert to the snapshot with
+        snapshot_id: String,
+        /// Block the snapshot was taken at
+        block_number: u64,
+    },
+    /// Chain reverted to a snapshot
+    Reverted {
+        /// Snapshot reverted to
+        snapshot_id: String,
+        /// Block after reverting
+        block_number: u64,
+    },
+    /// Chain time advanced
+    TimeIncreased {
+        /// Seconds advanced by
+        seconds: u64,
+        /// Total offset from wall-clock time in seconds
+        total_offset: u64,
+    },
+    /// Account is being impersonated
+    ImpersonationStarted {
+        /// Account address
+        address: String,
+    },
+    /// Account is no longer impersonated
+    ImpersonationStopped {
+        /// Account address
+        address: String,
+    },
+    /// Account balance
+    Balance {
+        /// Account address
+        address: String,
+        /// Balance in wei
+        balance: String,
+    },
+    /// Error occurred
+    Error {
+        message: String,
+    },
+}
+
+/// Actions that can be performed on a Graph Node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum GraphNodeAction {
+    /// Create a subgraph name if needed, deploy a manifest to it and follow
+    /// indexing until it is synced
+    DeploySubgraph {
+        name: String,
+        /// IPFS hash of the subgraph manifest
+        ipfs_hash: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version_label: Option<String>,
+    },
+    /// Wait until a deployment has indexed a block, or reached the chain head
+    /// when no block is given
+    WaitForSync {
+        /// Deployment ID (manifest IPFS hash)
+        deployment: String,
+        /// Block to wait for
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        block: Option<u64>,
+        /// Seconds to wait, defaulting to the service's sync timeout
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout: Option<u64>,
+    },
+    /// Query a deployed subgraph
+    QuerySubgraph {
+        subgraph_name: String,
+        query: String,
+    },
+    /// Remove a subgraph name
+    RemoveSubgraph {
+        /// Subgraph name
+        name: String,
+    },
+}
+
+/// Events emitted by Graph Node actions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum GraphNodeEvent {
+    /// Deployment started
+    DeploymentStarted {
+        deployment_id: String,
+        timestamp: String,
+    },
+    /// Deployment progress
+    DeploymentProgress {
+        deployment_id: String,
+        /// Deployment health reported by graph-node
+        status: String,
+        /// Progress from the start block to the chain head
+        percent: u8,
+    },
+    /// Deployment synced to the chain head
+    DeploymentCompleted {
+        deployment_id: String,
+        endpoints: Vec<String>,
+    },
+    /// Deployment reached the block a `WaitForSync` waited for
+    Synced {
+        /// Deployment ID (manifest IPFS hash)
+        deployment_id: String,
+        /// Latest indexed block
+        block_number: u64,
+    },
+    /// Query result
+    QueryResult {
+        /// Full GraphQL response
+        data: serde_json::Value,
+    },
+    /// Subgraph name removed
+    SubgraphRemoved {
+        /// Subgraph name
+        name: String,
+    },
+    /// Error occurred
+    Error {
+        message: String,
+    },
+}
+
+/// Actions for IPFS
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IpfsAction {
+    /// Add content to IPFS
+    AddContent {
+        /// Content to add, as text
+        content: String,
+        /// Pin the content, `true` by default
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pin: Option<bool>,
+    },
+    /// Add a local directory, e.g. a subgraph's build output
+    ///
+    /// A `subgraph.yaml` at its root is added once more with its file
+    /// references pointing into the directory, ready for `DeploySubgraph`.
+    AddDirectory {
+        /// Local directory
+        path: String,
+        /// Pin the directory, `true` by default
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pin: Option<bool>,
+    },
+    /// Pin a hash
+    Pin {
+        hash: String,
+    },
+    /// Unpin a hash
+    Unpin {
+        hash: String,
+    },
+    /// Get content by hash or IPFS path
+    Cat {
+        hash: String,
+    },
+}
+
+/// A file or directory added to IPFS
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IpfsEntry {
+    /// Path relative to what was added, the root directory's name for the
+    /// root itself
+    pub name: String,
+    /// CID
+    pub hash: String,
+    /// Cumulative size as reported by IPFS
+    pub size: u64,
+}
+
+/// Events from IPFS
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum IpfsEvent {
+    /// Content added
+    ContentAdded {
+        hash: String,
+        size: u64,
+    },
+    /// Directory added
+    DirectoryAdded {
+        /// CID of the directory
+        hash: String,
+        /// Every file and subdirectory, the directory itself last
+        entries: Vec<IpfsEntry>,
+        /// CID of the rewritten subgraph manifest, if the directory has one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        manifest: Option<String>,
+    },
+    /// Hash pinned
+    Pinned {
+        hash: String,
+    },
+    /// Hash unpinned
+    Unpinned {
+        hash: String,
+    },
+    /// Content retrieved, as (lossy) UTF-8
+    ContentRetrieved {
+        hash: String,
+        content: String,
+    },
+    /// Error occurred
+    Error {
+        message: String,
+    },
+}
+
+/// Actions for PostgreSQL
+///
+/// Actions without a `database` run against the service's own database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum PostgresAction {
+    /// Create a new database from `template0`, as graph-node requires
+    CreateDatabase {
+        /// Database name
+        name: String,
+        /// Extensions to create, graph-node's (`pg_trgm`, `btree_gist`,
+        /// `postgres_fdw`) by default
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        extensions: Option<Vec<String>>,
+        /// Collation and character classification, `C` by default
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        locale: Option<String>,
+    },
+    /// Run a single SQL statement
+    ExecuteQuery {
+        /// SQL statement
+        query: String,
+        /// Database to run it in
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        database: Option<String>,
+    },
+    /// Dump a database with `pg_dump` to a local SQL file
+    Backup {
+        /// File to write the dump to
+        backup_path: String,
+        /// Database to dump
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        database: Option<String>,
+    },
+    /// Load a backup into an existing database, replacing the objects in it
+    Restore {
+        /// File written by `Backup`
+        backup_path: String,
+        /// Database to load it into
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        database: Option<String>,
+    },
+    /// Drop a database, disconnecting its sessions
+    DropDatabase {
+        /// Database name
+        name: String,
+    },
+}
+
+/// Events from PostgreSQL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum PostgresEvent {
+    /// Database created
+    DatabaseCreated {
+        name: String,
+    },
+    /// Query executed
+    QueryExecuted {
+        /// Rows returned or affected
+        rows_affected: u64,
+        /// Returned rows as objects of column name to text value
+        rows: Vec<serde_json::Value>,
+    },
+    /// Backup completed
+    BackupCompleted {
+        path: String,
+        size_bytes: u64,
+    },
+    /// Backup loaded
+    Restored {
+        /// Database the backup was loaded into
+        database: String,
+        /// Backup file
+        path: String,
+    },
+    /// Database dropped
+    DatabaseDropped {
+        /// Database name
+        name: String,
+    },
+    /// Error occurred
+    Error {
+        message: String,
+    },
+}
+
+/// Actions for Graph contracts deployment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum GraphContractsAction {
+    /// Deploy all Graph Protocol contracts
+    DeployAll,
+    /// Deploy a specific contract
+    DeployContract {
+        name: String,
+    },
+    /// Verify deployment addresses
+    VerifyDeployment,
+}
+
+/// Events from Graph contracts deployment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum GraphContractsEvent {
+    /// Deployment process started
+    DeploymentStarted {
+        total_contracts: usize,
+    },
+    /// Compiling a contract
+    ContractCompiling {
+        name: String,
+    },
+    /// Contract deployed successfully
+    ContractDeployed {
+        name: String,
+        address: String,
+    },
+    /// Deployment progress update
+    DeploymentProgress {
+        completed: usize,
+        total: usize,
+    },
+    /// All contracts deployed
+    DeploymentCompleted {
+        addresses: std::collections::HashMap<String, String>,
+    },
+    /// Verification result
+    VerificationResult {
+        success: bool,
+        message: String,
+    },
+    /// Error occurred
+    Error {
+        message: String,
+    },
+}
+
+/// Actions for TAP contracts deployment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum TapContractsAction {
+    /// Deploy all TAP contracts
+    DeployAll,
+    /// Verify deployment
+    VerifyDeployment,
+}
+
+/// Events from TAP contracts deployment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum TapContractsEvent {
+    /// Deployment started
+    DeploymentStarted,
+    /// Contract deployed
+    ContractDeployed {
+        name: String,
+        address: String,
+    },
+    /// Deployment completed
+    DeploymentCompleted {
+        addresses: std::collections::HashMap<String, String>,
+    },
+    /// Error occurred
+    Error {
+        message: String,
+    },
+}
@@ -3,16 +3,20 @@
 //! This module implements the GraphTestDaemon which extends BaseDaemon with
 //! Graph Protocol specific services that can perform actions.
 
+use async_runtime_compat::smol::SmolSpawner;
 use async_trait::async_trait;
+use harness_core::action::ActionInfo;
+use harness_core::daemon::DaemonBuilder;
 use harness_core::prelude::*;
+use harness_core::protocol::DaemonSchema;
 use harness_core::{Registry, ServiceManager};
-use serde::{Deserialize, Serialize};
-use service_orchestration::{HealthCheck, ServiceInstanceConfig, ServiceTarget, StackConfig};
-use std::collections::HashMap;
+use service_orchestration::{ServiceTarget, StackConfig};
 use std::net::SocketAddr;
 use std::path::Path;
+use std::sync::Arc;
 use tracing::info;
 
+use crate::checkpoint::{Checkpoint, CheckpointParams, CheckpointRef, Checkpoints};
 use crate::service_registry::ServiceRegistry;
 use crate::services::{AnvilService, GraphNodeService, IpfsService, PostgresService};
 
@@ -40,18 +44,38 @@ impl GraphTestDaemon {
 
     /// Create a new Graph Test Daemon from a stack configuration
     pub async fn from_stack_config(endpoint: SocketAddr, config: GraphStackConfig) -> Result<Self> {
+        let base = Self::builder(endpoint, &config)?.build().await?;
+
+        Ok(Self { base })
+    }
+
+    /// Schema the daemon publishes for a stack configuration
+    ///
+    /// Used to generate the typed [`client`](crate::client) without starting
+    /// a daemon.
+    pub fn schema(config: &GraphStackConfig) -> Result<DaemonSchema> {
+        let endpoint = SocketAddr::from(([127, 0, 0, 1], 0));
+        Ok(Self::builder(endpoint, config)?.schema())
+    }
+
+    /// Builder with the Graph-specific services, tasks and actions registered
+    fn builder(endpoint: SocketAddr, config: &GraphStackConfig) -> Result<DaemonBuilder> {
         // Convert config to Value for validation
-        let config_value = serde_json::to_value(&config)
+        let config_value = serde_json::to_value(config)
             .map_err(|e| Error::daemon(format!("Failed to convert config: {}", e)))?;
 
         // Build the base daemon with Graph-specific services
         let mut builder = BaseDaemon::builder()
             .with_endpoint(endpoint)
+            .with_spawner(SmolSpawner)
             .with_config(config_value);
 
         // Create service registry for dynamic service creation
         let service_registry = ServiceRegistry::new();
 
+        // Services whose state is captured by checkpoints
+        let mut checkpoints = Checkpoints::new();
+
         // Get mutable access to the service stack for registration
         {
             let stack = builder.service_stack_mut();
@@ -102,6 +126,7 @@ impl GraphTestDaemon {
 
                         let anvil = AnvilService::new(chain_id, port);
                         stack.register(instance_name.clone(), anvil)?;
+                        checkpoints.add_anvil(instance_name, AnvilService::new(chain_id, port));
                         info!(
                             "Registered Anvil service with chain_id: {}, port: {}",
                             chain_id, port
@@ -123,8 +148,28 @@ impl GraphTestDaemon {
                             _ => 5432,
                         };
 
-                        let postgres = PostgresService::new(db_name.clone(), port);
-                        stack.register(instance_name.clone(), postgres)?;
+                        // Docker-hosted databases are reached through the orchestrator's container
+                        let container = match &service_config.orchestration.target {
+                            ServiceTarget::Docker { .. } => Some(format!(
+                                "orchestrator-{}",
+                                service_config.orchestration.name
+                            )),
+                            _ => None,
+                        };
+                        let postgres = || {
+                            let mut postgres = PostgresService::new(db_name.clone(), port)
+                                .with_user(env.get("POSTGRES_USER").map_or("postgres", |u| u));
+                            if let Some(password) = env.get("POSTGRES_PASSWORD") {
+                                postgres = postgres.with_password(password);
+                            }
+                            if let Some(container) = &container {
+                                postgres = postgres.with_container(container);
+                            }
+                            postgres
+                        };
+
+                        stack.register(instance_name.clone(), postgres())?;
+                        checkpoints.add_postgres(instance_name, postgres());
                         info!(
                             "Registered PostgreSQL service with db_name: {}, port: {}",
                             db_name, port
@@ -143,6 +188,8 @@ impl GraphTestDaemon {
 
                         let ipfs = IpfsService::new(api_port, gateway_port);
                         stack.register(instance_name.clone(), ipfs)?;
+                        checkpoints
+                            .add_ipfs(instance_name, IpfsService::new(api_port, gateway_port));
                         info!(
                             "Registered IPFS service with api_port: {}, gateway_port: {}",
                             api_port, gateway_port
@@ -210,12 +257,88 @@ impl GraphTestDaemon {
                 },
             )?;
 
-        let base = builder.build().await?;
-
-        Ok(Self { base })
+        register_checkpoint_actions(builder, Arc::new(checkpoints))
     }
 }
 
+/// Register the `checkpoint`, `restore-checkpoint`, `list-checkpoints` and
+/// `delete-checkpoint` actions
+fn register_checkpoint_actions(
+    builder: DaemonBuilder,
+    checkpoints: Arc<Checkpoints>,
+) -> Result<DaemonBuilder> {
+    let create = checkpoints.clone();
+    let restore = checkpoints.clone();
+    let list = checkpoints.clone();
+    let delete = checkpoints;
+
+    builder
+        .register_action_with_info(
+            ActionInfo::new(
+                "checkpoint",
+                "Snapshot anvil, Postgres and IPFS state for a later restore",
+            )
+            .with_params_schema(schema::<CheckpointParams>())
+            .with_returns_schema(schema::<Checkpoint>()),
+            move |params| {
+                let checkpoints = create.clone();
+                async move {
+                    let params: CheckpointParams = if params.is_null() {
+                        CheckpointParams::default()
+                    } else {
+                        serde_json::from_value(params)?
+                    };
+                    let checkpoint = checkpoints.create(params.label).await?;
+                    Ok(serde_json::to_value(checkpoint)?)
+                }
+            },
+        )?
+        .register_action_with_info(
+            ActionInfo::new(
+                "restore-checkpoint",
+                "Reset anvil, Postgres and IPFS to a checkpoint",
+            )
+            .with_params_schema(schema::<CheckpointRef>())
+            .with_returns_schema(schema::<Checkpoint>()),
+            move |params| {
+                let checkpoints = restore.clone();
+                async move {
+                    let params: CheckpointRef = serde_json::from_value(params)?;
+                    let checkpoint = checkpoints.restore(&params.id).await?;
+                    Ok(serde_json::to_value(checkpoint)?)
+                }
+            },
+        )?
+        .register_action_with_info(
+            ActionInfo::new("list-checkpoints", "List checkpoints, oldest first")
+                .with_returns_schema(schema::<Vec<Checkpoint>>()),
+            move |_params| {
+                let checkpoints = list.clone();
+                async move { Ok(serde_json::to_value(checkpoints.list())?) }
+            },
+        )?
+        .register_action_with_info(
+            ActionInfo::new(
+                "delete-checkpoint",
+                "Forget a checkpoint and drop its template databases",
+            )
+            .with_params_schema(schema::<CheckpointRef>())
+            .with_returns_schema(schema::<Checkpoint>()),
+            move |params| {
+                let checkpoints = delete.clone();
+                async move {
+                    let params: CheckpointRef = serde_json::from_value(params)?;
+                    let checkpoint = checkpoints.delete(&params.id).await?;
+                    Ok(serde_json::to_value(checkpoint)?)
+                }
+            },
+        )
+}
+
+fn schema<T: schemars::JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap_or_default()
+}
+
 #[async_trait]
 impl Daemon for GraphTestDaemon {
     async fn start(&self) -> Result<()> {
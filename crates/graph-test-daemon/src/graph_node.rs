@@ -0,0 +1,333 @@
+//! Graph Node API client
+//!
+//! Used by [`GraphNodeService`](crate::services::GraphNodeService) to manage
+//! subgraphs through graph-node's admin JSON-RPC API (`subgraph_create`,
+//! `subgraph_deploy`, `subgraph_remove`), follow indexing through the index
+//! node status API and run GraphQL queries.
+
+use crate::http;
+use crate::rpc::{JsonRpcClient, parse_quantity};
+use harness_core::prelude::*;
+
+/// Status API query for a single deployment
+const STATUS_QUERY: &str = "query($deployment: String!) { \
+    indexingStatuses(subgraphs: [$deployment]) { \
+        subgraph synced health fatalError { message } nonFatalErrors { message } \
+        chains { network chainHeadBlock { number } earliestBlock { number } latestBlock { number } } \
+    } }";
+
+/// Endpoints graph-node reports for a deployed subgraph
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubgraphEndpoints {
+    /// GraphiQL playground URL
+    pub playground: Option<String>,
+    /// GraphQL HTTP endpoint
+    pub queries: Option<String>,
+    /// GraphQL WebSocket endpoint
+    pub subscriptions: Option<String>,
+}
+
+/// Indexing status of a deployment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexingStatus {
+    /// Deployment ID
+    pub subgraph: String,
+    /// Whether the deployment has caught up with the chain head
+    pub synced: bool,
+    /// `healthy`, `unhealthy` or `failed`
+    pub health: String,
+    /// Error that stopped indexing, if any
+    pub fatal_error: Option<String>,
+    /// Errors indexing continued past
+    pub non_fatal_errors: Vec<String>,
+    /// Per-chain progress
+    pub chains: Vec<ChainIndexingStatus>,
+}
+
+/// Indexing progress on one chain
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChainIndexingStatus {
+    /// Network name, e.g. `mainnet`
+    pub network: String,
+    /// Latest block graph-node has seen on the chain
+    pub chain_head_block: Option<u64>,
+    /// Start block of the deployment
+    pub earliest_block: Option<u64>,
+    /// Latest block the deployment has processed
+    pub latest_block: Option<u64>,
+}
+
+impl IndexingStatus {
+    /// Whether indexing stopped with a fatal error
+    pub fn is_failed(&self) -> bool {
+        self.health == "failed"
+    }
+
+    /// Whether indexing ran into any error
+    pub fn is_healthy(&self) -> bool {
+        self.health == "healthy"
+    }
+
+    /// Latest processed block on the first chain
+    pub fn latest_block(&self) -> Option<u64> {
+        self.chains.first()?.latest_block
+    }
+
+    /// Whether the deployment has indexed `block`, or reached the chain head
+    /// without one
+    pub fn has_reached(&self, block: Option<u64>) -> bool {
+        match block {
+            Some(block) => self.latest_block().is_some_and(|latest| latest >= block),
+            None => self.synced,
+        }
+    }
+
+    /// Progress from the start block to `block`, or to the chain head without
+    /// one, in percent
+    pub fn percent(&self, block: Option<u64>) -> u8 {
+        if self.has_reached(block) {
+            return 100;
+        }
+        let Some(chain) = self.chains.first() else {
+            return 0;
+        };
+        let (Some(head), Some(latest)) = (block.or(chain.chain_head_block), chain.latest_block)
+        else {
+            return 0;
+        };
+
+        let start = chain.earliest_block.unwrap_or(0).min(latest);
+        if head <= start {
+            return if latest >= head { 100 } else { 0 };
+        }
+        ((latest.saturating_sub(start)) * 100 / (head - start)).min(100) as u8
+    }
+
+    /// Health, last indexed block and errors, for failure messages
+    pub fn diagnostic(&self) -> String {
+        let mut diagnostic = format!(
+            "health: {}, last indexed block: {}",
+            self.health,
+            self.latest_block()
+                .map_or_else(|| "none".to_string(), |block| block.to_string())
+        );
+        if let Some(error) = &self.fatal_error {
+            diagnostic.push_str(&format!(", fatal error: {}", error));
+        }
+        if !self.non_fatal_errors.is_empty() {
+            diagnostic.push_str(&format!(", errors: {}", self.non_fatal_errors.join("; ")));
+        }
+        diagnostic
+    }
+}
+
+/// Client for a graph-node's query, admin and index node status endpoints
+#[derive(Debug)]
+pub struct GraphNodeClient {
+    query_url: String,
+    admin: JsonRpcClient,
+    status_url: String,
+}
+
+impl GraphNodeClient {
+    /// Create a client for graph-node on `host` with the given query, admin
+    /// and index node status ports
+    pub fn new(host: &str, query_port: u16, admin_port: u16, status_port: u16) -> Self {
+        Self {
+            query_url: format!("http://{}:{}", host, query_port),
+            admin: JsonRpcClient::new(format!("http://{}:{}", host, admin_port)),
+            status_url: format!("http://{}:{}/graphql", host, status_port),
+        }
+    }
+
+    /// GraphQL HTTP endpoint of a subgraph
+    pub fn subgraph_url(&self, name: &str) -> String {
+        format!("{}/subgraphs/name/{}", self.query_url, name)
+    }
+
+    /// Register a subgraph name; succeeds if the name already exists
+    pub async fn create_subgraph(&self, name: &str) -> Result<()> {
+        match self
+            .admin
+            .call::<Value>("subgraph_create", json!({ "name": name }))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) if e.to_string().to_lowercase().contains("already exists") => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deploy a subgraph manifest from IPFS under a name
+    pub async fn deploy_subgraph(
+        &self,
+        name: &str,
+        ipfs_hash: &str,
+        version_label: Option<&str>,
+    ) -> Result<SubgraphEndpoints> {
+        let mut params = json!({ "name": name, "ipfs_hash": ipfs_hash });
+        if let Some(version_label) = version_label {
+            params["version_label"] = json!(version_label);
+        }
+        self.admin.call("subgraph_deploy", params).await
+    }
+
+    /// Remove a subgraph name and unassign its deployments
+    pub async fn remove_subgraph(&self, name: &str) -> Result<()> {
+        self.admin
+            .call::<Value>("subgraph_remove", json!({ "name": name }))
+            .await?;
+        Ok(())
+    }
+
+    /// Indexing status of a deployment, `None` until graph-node picks it up
+    pub async fn indexing_status(&self, deployment: &str) -> Result<Option<IndexingStatus>> {
+        let body = graphql(
+            &self.status_url,
+            STATUS_QUERY,
+            json!({ "deployment": deployment }),
+        )
+        .await?;
+
+        let Some(status) = body["data"]["indexingStatuses"]
+            .as_array()
+            .and_then(|statuses| statuses.first())
+        else {
+            return Ok(None);
+        };
+
+        let block = |value: &Value| -> Result<Option<u64>> {
+            match &value["number"] {
+                Value::Null => Ok(None),
+                number => Ok(Some(parse_quantity(number)? as u64)),
+            }
+        };
+        let chains = status["chains"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|chain| {
+                Ok(ChainIndexingStatus {
+                    network: chain["network"].as_str().unwrap_or_default().to_string(),
+                    chain_head_block: block(&chain["chainHeadBlock"])?,
+                    earliest_block: block(&chain["earliestBlock"])?,
+                    latest_block: block(&chain["latestBlock"])?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Some(IndexingStatus {
+            subgraph: status["subgraph"]
+                .as_str()
+                .unwrap_or(deployment)
+                .to_string(),
+            synced: status["synced"].as_bool().unwrap_or(false),
+            health: status["health"].as_str().unwrap_or("unknown").to_string(),
+            fatal_error: status["fatalError"]["message"].as_str().map(str::to_string),
+            non_fatal_errors: status["nonFatalErrors"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|e| e["message"].as_str().map(str::to_string))
+                .collect(),
+            chains,
+        }))
+    }
+
+    /// Run a GraphQL query against a subgraph, returning the whole response
+    pub async fn query(&self, name: &str, query: &str) -> Result<Value> {
+        graphql(&self.subgraph_url(name), query, Value::Null).await
+    }
+}
+
+/// POST a GraphQL request, failing on HTTP or GraphQL errors
+async fn graphql(url: &str, query: &str, variables: Value) -> Result<Value> {
+    let mut request = json!({ "query": query });
+    if !variables.is_null() {
+        request["variables"] = variables;
+    }
+
+    let response = http::post_json(url, &request).await?;
+    let body: Value = response.json().map_err(|_| {
+        Error::client(format!(
+            "{} returned HTTP {}: {}",
+            url,
+            response.status,
+            response.text()
+        ))
+    })?;
+
+    if let Some(errors) = body["errors"].as_array().filter(|e| !e.is_empty()) {
+        let messages: Vec<_> = errors
+            .iter()
+            .map(|e| {
+                e["message"]
+                    .as_str()
+                    .map_or_else(|| e.to_string(), str::to_string)
+            })
+            .collect();
+        return Err(Error::client(format!(
+            "GraphQL query failed: {}",
+            messages.join("; ")
+        )));
+    }
+    if !response.is_success() {
+        return Err(Error::client(format!(
+            "{} returned HTTP {}: {}",
+            url,
+            response.status,
+            response.text()
+        )));
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(synced: bool, earliest: u64, latest: Option<u64>, head: u64) -> IndexingStatus {
+        IndexingStatus {
+            subgraph: "QmDeployment".to_string(),
+            synced,
+            health: "healthy".to_string(),
+            fatal_error: None,
+            non_fatal_errors: Vec::new(),
+            chains: vec![ChainIndexingStatus {
+                network: "mainnet".to_string(),
+                chain_head_block: Some(head),
+                earliest_block: Some(earliest),
+                latest_block: latest,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(status(false, 0, None, 100).percent(None), 0);
+        assert_eq!(status(false, 0, Some(25), 100).percent(None), 25);
+        assert_eq!(status(false, 50, Some(75), 150).percent(None), 25);
+        assert_eq!(status(false, 0, Some(120), 100).percent(None), 100);
+        assert_eq!(status(false, 10, Some(10), 10).percent(None), 100);
+        assert_eq!(status(true, 0, Some(90), 100).percent(None), 100);
+
+        // Towards a target block instead of the chain head
+        assert_eq!(status(false, 0, Some(25), 100).percent(Some(50)), 50);
+        assert_eq!(status(false, 0, Some(60), 100).percent(Some(50)), 100);
+    }
+
+    #[test]
+    fn test_has_reached_and_diagnostic() {
+        let mut status = status(false, 0, Some(40), 100);
+        assert!(status.has_reached(Some(40)));
+        assert!(!status.has_reached(Some(41)));
+        assert!(!status.has_reached(None));
+
+        status.health = "failed".to_string();
+        status.fatal_error = Some("Mapping aborted".to_string());
+        assert_eq!(
+            status.diagnostic(),
+            "health: failed, last indexed block: 40, fatal error: Mapping aborted"
+        );
+    }
+}
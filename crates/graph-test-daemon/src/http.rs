@@ -0,0 +1,280 @@
+//! Minimal HTTP/1.1 client for talking to local stack services
+//!
+//! Anvil, IPFS and Graph Node all expose plain HTTP APIs on localhost. This
+//! client runs on any executor (it only needs `smol::net`), which keeps the
+//! daemon free of a tokio dependency. Each request uses its own connection.
+
+use harness_core::prelude::*;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use smol::net::TcpStream;
+
+/// HTTP response with the body fully read
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Whether the status is 2xx
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Body as (lossy) UTF-8 text
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Body parsed as JSON
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+/// Send a request and read the whole response
+pub(crate) async fn request(
+    method: &str,
+    url: &str,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<Response> {
+    let (authority, path) = split_url(url)?;
+
+    let mut stream = TcpStream::connect(authority.as_str())
+        .await
+        .map_err(|e| Error::client(format!("Failed to connect to {}: {}", url, e)))?;
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        method,
+        path,
+        authority,
+        body.len()
+    );
+    if let Some(content_type) = content_type {
+        head.push_str(&format!("Content-Type: {}\r\n", content_type));
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.flush().await?;
+
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw).await?;
+    parse_response(&raw).map_err(|e| Error::client(format!("{} {}: {}", method, url, e)))
+}
+
+/// POST a JSON body
+pub(crate) async fn post_json(url: &str, body: &Value) -> Result<Response> {
+    request(
+        "POST",
+        url,
+        Some("application/json"),
+        &serde_json::to_vec(body)?,
+    )
+    .await
+}
+
+/// `multipart/form-data` body builder
+pub(crate) struct Multipart {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Multipart {
+    pub fn new() -> Self {
+        Self {
+            boundary: format!("harness-{}", uuid::Uuid::new_v4().simple()),
+            body: Vec::new(),
+        }
+    }
+
+    /// Append a `file` part; `filename` is percent-encoded
+    pub fn file(&mut self, filename: &str, content_type: &str, data: &[u8]) -> &mut Self {
+        self.body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+                 Content-Type: {}\r\n\r\n",
+                self.boundary,
+                percent_encode(filename),
+                content_type
+            )
+            .as_bytes(),
+        );
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    /// Content type header value and the finished body
+    pub fn finish(mut self) -> (String, Vec<u8>) {
+        self.body
+            .extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        (
+            format!("multipart/form-data; boundary={}", self.boundary),
+            self.body,
+        )
+    }
+}
+
+/// Percent-encode everything but unreserved characters
+pub(crate) fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Split `http://host:port/path` into `host:port` and `/path`
+fn split_url(url: &str) -> Result<(String, String)> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        Error::client(format!(
+            "Unsupported URL '{}': only http:// is supported",
+            url
+        ))
+    })?;
+
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return Err(Error::client(format!("Missing host in URL '{}'", url)));
+    }
+
+    let authority = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+    Ok((authority, path.to_string()))
+}
+
+fn parse_response(raw: &[u8]) -> std::result::Result<Response, String> {
+    let end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or("incomplete response headers")?;
+    let head = std::str::from_utf8(&raw[..end]).map_err(|_| "invalid response headers")?;
+    let body = &raw[end + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or("invalid status line")?;
+
+    let mut chunked = false;
+    let mut length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            length = value.parse::<usize>().ok();
+        }
+    }
+
+    let body = if chunked {
+        decode_chunked(body)?
+    } else {
+        match length {
+            Some(length) => body.get(..length).ok_or("truncated body")?.to_vec(),
+            None => body.to_vec(),
+        }
+    };
+
+    Ok(Response { status, body })
+}
+
+fn decode_chunked(mut data: &[u8]) -> std::result::Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = data
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or("truncated chunk")?;
+        let size = std::str::from_utf8(&data[..line_end])
+            .ok()
+            .and_then(|line| line.split(';').next())
+            .and_then(|size| usize::from_str_radix(size.trim(), 16).ok())
+            .ok_or("invalid chunk size")?;
+        data = &data[line_end + 2..];
+
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(data.get(..size).ok_or("truncated chunk")?);
+        data = data.get(size + 2..).ok_or("truncated chunk")?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_url() {
+        assert_eq!(
+            split_url("http://127.0.0.1:8545").unwrap(),
+            ("127.0.0.1:8545".to_string(), "/".to_string())
+        );
+        assert_eq!(
+            split_url("http://localhost/api/v0/add?pin=true").unwrap(),
+            (
+                "localhost:80".to_string(),
+                "/api/v0/add?pin=true".to_string()
+            )
+        );
+        assert!(split_url("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+        let response = parse_response(raw).unwrap();
+        assert!(response.is_success());
+        assert_eq!(response.text(), "{}");
+
+        let raw = b"HTTP/1.1 500 Internal Server Error\r\nTransfer-Encoding: chunked\r\n\r\n\
+            4\r\nWiki\r\n5;ext=1\r\npedia\r\n0\r\n\r\n";
+        let response = parse_response(raw).unwrap();
+        assert_eq!(response.status, 500);
+        assert_eq!(response.text(), "Wikipedia");
+
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+    }
+
+    #[test]
+    fn test_multipart() {
+        let mut multipart = Multipart::new();
+        multipart.file(
+            "build/schema graphql",
+            "application/octet-stream",
+            b"type X",
+        );
+        let boundary = multipart.boundary.clone();
+        let (content_type, body) = multipart.finish();
+
+        assert_eq!(
+            content_type,
+            format!("multipart/form-data; boundary={}", boundary)
+        );
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.starts_with(&format!("--{}\r\n", boundary)));
+        assert!(body.contains("filename=\"build%2Fschema%20graphql\""));
+        assert!(body.contains("\r\n\r\ntype X\r\n"));
+        assert!(body.ends_with(&format!("--{}--\r\n", boundary)));
+    }
+}
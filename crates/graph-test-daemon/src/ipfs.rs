@@ -0,0 +1,345 @@
+//! Kubo RPC API client
+//!
+//! Used by [`IpfsService`](crate::services::IpfsService) to add files and
+//! directories, read content back and manage pins through a Kubo node's
+//! `/api/v0` endpoints.
+
+use crate::http::{self, Multipart, percent_encode};
+use harness_core::prelude::*;
+use schemars::JsonSchema;
+use std::path::{Component, Path, PathBuf};
+
+/// Manifest name graph-cli writes into a subgraph's build directory
+pub const SUBGRAPH_MANIFEST: &str = "subgraph.yaml";
+
+/// A file or directory added to IPFS
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct IpfsEntry {
+    /// Path relative to what was added, the root directory's name for the
+    /// root itself
+    pub name: String,
+    /// CID
+    pub hash: String,
+    /// Cumulative size as reported by IPFS
+    pub size: u64,
+}
+
+/// Result of adding a directory
+#[derive(Debug, Clone)]
+pub struct AddedDirectory {
+    /// The directory itself
+    pub root: IpfsEntry,
+    /// Every file and subdirectory, root last
+    pub entries: Vec<IpfsEntry>,
+}
+
+/// Client for a Kubo node's RPC API
+#[derive(Debug, Clone)]
+pub struct IpfsClient {
+    api_url: String,
+}
+
+impl IpfsClient {
+    /// Create a client for the RPC API at `api_url`, e.g.
+    /// `http://127.0.0.1:5001/api/v0`
+    pub fn new(api_url: impl Into<String>) -> Self {
+        Self {
+            api_url: api_url.into(),
+        }
+    }
+
+    /// Add a single file
+    pub async fn add(&self, name: &str, data: &[u8], pin: bool) -> Result<IpfsEntry> {
+        let mut multipart = Multipart::new();
+        multipart.file(name, "application/octet-stream", data);
+
+        self.add_multipart(multipart, pin)
+            .await?
+            .pop()
+            .ok_or_else(|| Error::action("IPFS add returned no entries"))
+    }
+
+    /// Add a directory with everything in it
+    pub async fn add_directory(&self, path: &Path, pin: bool) -> Result<AddedDirectory> {
+        let root = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| Error::action(format!("Not a directory: {}", path.display())))?;
+
+        let dir = path.to_path_buf();
+        let files = smol::unblock(move || read_tree(&dir))
+            .await
+            .map_err(|e| Error::action(format!("Failed to read {}: {}", path.display(), e)))?;
+
+        let mut multipart = Multipart::new();
+        multipart.file(&root, "application/x-directory", &[]);
+        for (relative, data) in &files {
+            let name = format!("{}/{}", root, relative);
+            match data {
+                Some(data) => multipart.file(&name, "application/octet-stream", data),
+                None => multipart.file(&name, "application/x-directory", &[]),
+            };
+        }
+
+        let entries = self.add_multipart(multipart, pin).await?;
+        let root = entries
+            .iter()
+            .find(|entry| entry.name == root)
+            .cloned()
+            .ok_or_else(|| Error::action(format!("IPFS add did not return {}", root)))?;
+        Ok(AddedDirectory { root, entries })
+    }
+
+    /// Content of a file, by CID or IPFS path
+    pub async fn cat(&self, path: &str) -> Result<Vec<u8>> {
+        let response = self
+            .call(&format!("cat?arg={}", percent_encode(path)), None)
+            .await?;
+        Ok(response.body)
+    }
+
+    /// CIDs pinned recursively
+    pub async fn pins(&self) -> Result<Vec<String>> {
+        let body: Value = self.call("pin/ls?type=recursive", None).await?.json()?;
+        let mut pins: Vec<String> = body["Keys"]
+            .as_object()
+            .map(|keys| keys.keys().cloned().collect())
+            .unwrap_or_default();
+        pins.sort();
+        Ok(pins)
+    }
+
+    /// Pin a CID recursively
+    pub async fn pin(&self, cid: &str) -> Result<()> {
+        self.call(&format!("pin/add?arg={}", percent_encode(cid)), None)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove a recursive pin
+    pub async fn unpin(&self, cid: &str) -> Result<()> {
+        self.call(&format!("pin/rm?arg={}", percent_encode(cid)), None)
+            .await?;
+        Ok(())
+    }
+
+    /// POST to `add`, which answers with one JSON object per added entry
+    async fn add_multipart(&self, multipart: Multipart, pin: bool) -> Result<Vec<IpfsEntry>> {
+        let response = self
+            .call(&format!("add?pin={}", pin), Some(multipart))
+            .await?;
+
+        response
+            .text()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let entry: Value = serde_json::from_str(line)?;
+                let size = match &entry["Size"] {
+                    Value::String(size) => size.parse().ok(),
+                    size => size.as_u64(),
+                };
+                match (entry["Name"].as_str(), entry["Hash"].as_str()) {
+                    (Some(name), Some(hash)) => Ok(IpfsEntry {
+                        name: name.to_string(),
+                        hash: hash.to_string(),
+                        size: size.unwrap_or(0),
+                    }),
+                    _ => Err(Error::action(format!(
+                        "Unexpected IPFS add output: {}",
+                        line
+                    ))),
+                }
+            })
+            .collect()
+    }
+
+    /// Call an RPC endpoint, e.g. `pin/ls?type=recursive`
+    async fn call(&self, endpoint: &str, multipart: Option<Multipart>) -> Result<http::Response> {
+        let url = format!("{}/{}", self.api_url, endpoint);
+        let response = match multipart {
+            Some(multipart) => {
+                let (content_type, body) = multipart.finish();
+                http::request("POST", &url, Some(&content_type), &body).await?
+            }
+            None => http::request("POST", &url, None, &[]).await?,
+        };
+
+        if !response.is_success() {
+            let message = response
+                .json::<Value>()
+                .ok()
+                .and_then(|body| body["Message"].as_str().map(str::to_string))
+                .unwrap_or_else(|| response.text());
+            let name = endpoint.split('?').next().unwrap_or(endpoint);
+            return Err(Error::action(format!("IPFS {} failed: {}", name, message)));
+        }
+        Ok(response)
+    }
+}
+
+/// Files (with their content) and directories (without) under `dir`, as
+/// `/`-separated relative paths with every directory before its contents
+fn read_tree(dir: &Path) -> std::io::Result<Vec<(String, Option<Vec<u8>>)>> {
+    let mut tree = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        let mut children: Vec<_> = std::fs::read_dir(dir.join(&relative))?
+            .map(|entry| entry.map(|entry| relative.join(entry.file_name())))
+            .collect::<std::io::Result<_>>()?;
+        children.sort();
+
+        for child in children {
+            let name = child
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            // Symlinks are added as what they point to
+            if std::fs::metadata(dir.join(&child))?.is_dir() {
+                tree.push((name, None));
+                pending.push(child);
+            } else {
+                tree.push((name, Some(std::fs::read(dir.join(&child))?)));
+            }
+        }
+    }
+
+    // Sorting by path keeps every directory before its contents
+    tree.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(tree)
+}
+
+/// Point a subgraph manifest's relative `file:` references into the
+/// directory `dir_cid` it was added with, the way `graph deploy` uploads
+/// them
+///
+/// `base` is the manifest's directory relative to the added directory,
+/// empty for a manifest at its root.
+pub fn rewrite_manifest(manifest: &str, dir_cid: &str, base: &str) -> Result<String> {
+    let mut manifest: serde_yaml::Value = serde_yaml::from_str(manifest)
+        .map_err(|e| Error::action(format!("Invalid subgraph manifest: {}", e)))?;
+    rewrite_links(&mut manifest, dir_cid, Path::new(base))?;
+    serde_yaml::to_string(&manifest)
+        .map_err(|e| Error::action(format!("Failed to write subgraph manifest: {}", e)))
+}
+
+fn rewrite_links(value: &mut serde_yaml::Value, dir_cid: &str, base: &Path) -> Result<()> {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            for (key, value) in mapping.iter_mut() {
+                if key.as_str() == Some("file")
+                    && let Some(file) = value.as_str()
+                    && !file.starts_with("/ipfs/")
+                {
+                    let path = resolve(base, file)?;
+                    let mut link = serde_yaml::Mapping::new();
+                    link.insert("/".into(), format!("/ipfs/{}/{}", dir_cid, path).into());
+                    *value = serde_yaml::Value::Mapping(link);
+                } else {
+                    rewrite_links(value, dir_cid, base)?;
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(values) => {
+            for value in values {
+                rewrite_links(value, dir_cid, base)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// `file` relative to `base`, without leaving the added directory
+fn resolve(base: &Path, file: &str) -> Result<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for component in base.join(file).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            Component::ParentDir if parts.pop().is_some() => {}
+            _ => {
+                return Err(Error::action(format!(
+                    "Manifest references {} outside the added directory",
+                    file
+                )));
+            }
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_manifest() {
+        let manifest = "\
+specVersion: 0.0.5
+schema:
+  file: schema.graphql
+dataSources:
+  - kind: ethereum
+    mapping:
+      file: ./Contract/Contract.wasm
+      abis:
+        - name: Contract
+          file: Contract/abis/../abis/Contract.json
+templates: []
+";
+        let rewritten: serde_yaml::Value =
+            serde_yaml::from_str(&rewrite_manifest(manifest, "QmDir", "").unwrap()).unwrap();
+
+        assert_eq!(
+            rewritten["schema"]["file"]["/"],
+            "/ipfs/QmDir/schema.graphql"
+        );
+        let mapping = &rewritten["dataSources"][0]["mapping"];
+        assert_eq!(mapping["file"]["/"], "/ipfs/QmDir/Contract/Contract.wasm");
+        assert_eq!(
+            mapping["abis"][0]["file"]["/"],
+            "/ipfs/QmDir/Contract/abis/Contract.json"
+        );
+        assert_eq!(rewritten["specVersion"], "0.0.5");
+    }
+
+    #[test]
+    fn test_rewrite_manifest_keeps_ipfs_links_and_stays_inside() {
+        let manifest = "schema:\n  file: /ipfs/QmSchema\n";
+        assert_eq!(
+            rewrite_manifest(manifest, "QmDir", "").unwrap(),
+            "schema:\n  file: /ipfs/QmSchema\n"
+        );
+
+        let manifest = "schema:\n  file: ../schema.graphql\n";
+        assert!(rewrite_manifest(manifest, "QmDir", "").is_err());
+        assert_eq!(
+            rewrite_manifest(manifest, "QmDir", "build").unwrap(),
+            "schema:\n  file:\n    /: /ipfs/QmDir/schema.graphql\n"
+        );
+    }
+
+    #[test]
+    fn test_read_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("Contract/abis")).unwrap();
+        std::fs::write(dir.path().join("subgraph.yaml"), "specVersion").unwrap();
+        std::fs::write(dir.path().join("Contract/abis/Contract.json"), "[]").unwrap();
+
+        let tree = read_tree(dir.path()).unwrap();
+        assert_eq!(
+            tree,
+            vec![
+                ("Contract".to_string(), None),
+                ("Contract/abis".to_string(), None),
+                (
+                    "Contract/abis/Contract.json".to_string(),
+                    Some(b"[]".to_vec())
+                ),
+                ("subgraph.yaml".to_string(), Some(b"specVersion".to_vec())),
+            ]
+        );
+    }
+}
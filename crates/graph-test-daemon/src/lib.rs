@@ -6,13 +6,22 @@
 
 #![warn(missing_docs)]
 
+pub mod checkpoint;
+#[rustfmt::skip]
+pub mod client;
 pub mod daemon;
+pub mod graph_node;
+mod http;
+pub mod ipfs;
+mod psql;
+pub mod rpc;
 pub mod service_registry;
 pub mod services;
 pub mod services_test;
 pub mod tasks;
 
 // Export the main types
+pub use checkpoint::{Checkpoint, Checkpoints};
 pub use daemon::GraphTestDaemon;
 pub use services::{
     AnvilAction, AnvilEvent, AnvilService, GraphNodeAction, GraphNodeEvent, GraphNodeService,
@@ -0,0 +1,384 @@
+//! `psql` and `pg_dump` invocations in a PostgreSQL service's execution context
+//!
+//! Docker-hosted databases are reached with `docker exec` into the
+//! orchestrator's container; anything else connects over TCP on localhost.
+
+use command_executor::backends::LocalLauncher;
+use command_executor::{Command, DockerLayer, LayeredExecutor, ProcessEventType, ProcessHandle};
+use futures::StreamExt;
+use harness_core::prelude::*;
+
+/// Marks the line `ROW_COUNT` is echoed on after a query
+const ROW_COUNT_MARKER: &str = "__harness_row_count__";
+
+/// Stands in for SQL `NULL` in query output
+const NULL_MARKER: &str = "__harness_null__";
+
+/// Connection settings for running `psql`
+#[derive(Debug, Clone)]
+pub(crate) struct Psql {
+    pub user: String,
+    pub password: Option<String>,
+    pub port: u16,
+    /// Container to `docker exec` into
+    pub container: Option<String>,
+}
+
+/// Output of a successful `psql` run
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PsqlOutput {
+    pub stdout: Vec<String>,
+}
+
+/// Rows returned by a query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct QueryResult {
+    /// Rows returned or affected, as reported by `psql`
+    pub rows_affected: u64,
+    /// Returned rows as JSON objects of text values
+    pub rows: Vec<Value>,
+}
+
+impl Psql {
+    /// Run SQL commands against a database
+    ///
+    /// Each command is passed as its own `-c`, so statements that can't run
+    /// in a transaction block (e.g. `CREATE DATABASE`) may follow each other.
+    pub async fn run(&self, database: &str, commands: &[String]) -> Result<PsqlOutput> {
+        self.exec(self.command(database, commands), None).await
+    }
+
+    /// Run a single statement and collect what it returned
+    pub async fn query(&self, database: &str, query: &str) -> Result<QueryResult> {
+        let commands = [
+            "\\pset format csv".to_string(),
+            "\\pset tuples_only off".to_string(),
+            format!("\\pset null {}", NULL_MARKER),
+            query.to_string(),
+            format!("\\echo {} :ROW_COUNT", ROW_COUNT_MARKER),
+        ];
+        let output = self.run(database, &commands).await?;
+        parse_query_output(&output.stdout).map_err(Error::action)
+    }
+
+    /// Dump a database as plain SQL that drops and recreates its objects
+    pub async fn dump(&self, database: &str) -> Result<String> {
+        let mut cmd = self.connect("pg_dump", database);
+        cmd.args(["--clean", "--if-exists", "--no-owner"]);
+
+        let output = self.exec(cmd, None).await?;
+        let mut sql = output.stdout.join("\n");
+        sql.push('\n');
+        Ok(sql)
+    }
+
+    /// Run an SQL script in a single transaction, fed through stdin
+    pub async fn restore(&self, database: &str, sql: &[u8]) -> Result<()> {
+        let mut cmd = self.connect("psql", database);
+        cmd.args(["-X", "-q", "-v", "ON_ERROR_STOP=1", "-1", "-f", "-"]);
+
+        self.exec(cmd, Some(sql)).await?;
+        Ok(())
+    }
+
+    async fn exec(&self, cmd: Command, stdin: Option<&[u8]>) -> Result<PsqlOutput> {
+        let program = cmd.get_program().to_string_lossy().to_string();
+        let executor = self.executor(stdin.is_some());
+        let (mut events, mut handle) = executor
+            .execute(cmd, &command_executor::Target::Command)
+            .await
+            .map_err(|e| Error::action(format!("Failed to run {}: {}", program, e)))?;
+
+        // Feed stdin while reading output, so neither side can fill a pipe
+        let stdin = handle.take_stdin().map(|mut writer| async move {
+            if let Some(input) = stdin {
+                writer.write(input).await?;
+            }
+            // Dropping the writer closes stdin
+            Ok::<_, command_executor::Error>(())
+        });
+        let write = async {
+            match stdin {
+                Some(write) => write.await,
+                None => Ok(()),
+            }
+        };
+        let read = async {
+            let mut output = PsqlOutput::default();
+            let mut stderr = Vec::new();
+            while let Some(event) = events.next().await {
+                match (&event.event_type, event.data) {
+                    (ProcessEventType::Stdout, Some(line)) => output.stdout.push(line),
+                    (ProcessEventType::Stderr, Some(line)) => stderr.push(line),
+                    _ => {}
+                }
+            }
+            (output, stderr)
+        };
+        let (written, (output, stderr)) = futures::join!(write, read);
+
+        let status = handle
+            .wait()
+            .await
+            .map_err(|e| Error::action(format!("Failed to wait for {}: {}", program, e)))?;
+        if !status.success() {
+            return Err(Error::action(format!(
+                "{} exited with {:?}: {}",
+                program,
+                status.code,
+                stderr.join("\n")
+            )));
+        }
+        written.map_err(|e| Error::action(format!("Failed to write to {}: {}", program, e)))?;
+
+        Ok(output)
+    }
+
+    fn executor(&self, interactive: bool) -> LayeredExecutor<LocalLauncher> {
+        let executor = LayeredExecutor::new(LocalLauncher);
+        match self.docker_layer() {
+            Some(layer) => executor.with_layer(layer.with_interactive(interactive)),
+            None => executor,
+        }
+    }
+
+    fn docker_layer(&self) -> Option<DockerLayer> {
+        let mut layer = DockerLayer::new(self.container.as_ref()?);
+        if let Some(password) = &self.password {
+            layer = layer.with_env("PGPASSWORD", password);
+        }
+        Some(layer)
+    }
+
+    fn command(&self, database: &str, commands: &[String]) -> Command {
+        let mut cmd = self.connect("psql", database);
+        cmd.args(["-X", "-q", "-A", "-t", "-v", "ON_ERROR_STOP=1"]);
+        for command in commands {
+            cmd.args(["-c", command]);
+        }
+        cmd
+    }
+
+    /// A `program` invocation with the connection options for a database
+    fn connect(&self, program: &str, database: &str) -> Command {
+        let mut cmd = Command::new(program);
+        // Never prompt for a password; there is nobody to answer
+        cmd.args(["-w", "-U", &self.user, "-d", database]);
+
+        if self.container.is_none() {
+            cmd.args(["-h", "127.0.0.1", "-p", &self.port.to_string()]);
+            if let Some(password) = &self.password {
+                cmd.env("PGPASSWORD", password);
+            }
+        }
+        cmd
+    }
+}
+
+/// Quote an SQL identifier
+pub(crate) fn ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quote an SQL string literal
+pub(crate) fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Split `psql` CSV output followed by the echoed row count into rows
+fn parse_query_output(lines: &[String]) -> std::result::Result<QueryResult, String> {
+    let marker = lines
+        .iter()
+        .rposition(|line| line.starts_with(ROW_COUNT_MARKER))
+        .ok_or("psql did not report a row count")?;
+    let rows_affected = lines[marker][ROW_COUNT_MARKER.len()..]
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid row count: {}", lines[marker]))?;
+
+    let mut records = parse_csv(&lines[..marker].join("\n"))?.into_iter();
+    let Some(header) = records.next() else {
+        return Ok(QueryResult {
+            rows_affected,
+            rows: Vec::new(),
+        });
+    };
+
+    let rows = records
+        .map(|record| {
+            let row: serde_json::Map<String, Value> = header
+                .iter()
+                .zip(record)
+                .map(|(column, value)| {
+                    let value = if value == NULL_MARKER {
+                        Value::Null
+                    } else {
+                        Value::String(value)
+                    };
+                    (column.clone(), value)
+                })
+                .collect();
+            Value::Object(row)
+        })
+        .collect();
+
+    Ok(QueryResult {
+        rows_affected,
+        rows,
+    })
+}
+
+/// Parse RFC 4180 CSV as written by `psql`
+fn parse_csv(text: &str) -> std::result::Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    if text.is_empty() {
+        return Ok(records);
+    }
+
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unterminated quoted field in psql output".to_string());
+    }
+    record.push(field);
+    records.push(record);
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use command_executor::ExecutionLayer;
+    use command_executor::layered::ExecutionContext;
+
+    fn psql(container: Option<&str>) -> Psql {
+        Psql {
+            user: "graph-node".to_string(),
+            password: Some("secret".to_string()),
+            port: 5433,
+            container: container.map(str::to_string),
+        }
+    }
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .iter()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_local_command() {
+        let cmd = psql(None).command("graph-node", &["SELECT 1".to_string()]);
+        let args = args(&cmd);
+
+        assert_eq!(cmd.get_program(), "psql");
+        assert!(args.windows(2).any(|w| w == ["-d", "graph-node"]));
+        assert!(args.windows(2).any(|w| w == ["-p", "5433"]));
+        assert!(args.ends_with(&["-c".to_string(), "SELECT 1".to_string()]));
+        assert_eq!(
+            cmd.get_envs().get(std::ffi::OsStr::new("PGPASSWORD")),
+            Some(&"secret".into())
+        );
+    }
+
+    #[test]
+    fn test_docker_command() {
+        let psql = psql(Some("orchestrator-postgres"));
+        let cmd = psql
+            .docker_layer()
+            .unwrap()
+            .wrap_command(
+                psql.command("graph-node", &["SELECT 1".to_string()]),
+                &ExecutionContext::default(),
+            )
+            .unwrap();
+        let args = args(&cmd);
+
+        assert_eq!(cmd.get_program(), "docker");
+        assert!(args.contains(&"orchestrator-postgres".to_string()));
+        assert!(args.contains(&"PGPASSWORD=secret".to_string()));
+        assert!(!args.contains(&"-i".to_string()));
+        let script = args.last().unwrap();
+        assert!(script.starts_with("psql "));
+        assert!(!script.contains("-h"));
+        assert!(script.ends_with("-c 'SELECT 1'"));
+    }
+
+    #[test]
+    fn test_pg_dump_connects_like_psql() {
+        let cmd = psql(None).connect("pg_dump", "graph-node");
+        assert_eq!(cmd.get_program(), "pg_dump");
+        assert_eq!(
+            args(&cmd),
+            [
+                "-w",
+                "-U",
+                "graph-node",
+                "-d",
+                "graph-node",
+                "-h",
+                "127.0.0.1",
+                "-p",
+                "5433"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_query_output() {
+        let lines: Vec<String> = [
+            "id,name,note",
+            "1,alice,__harness_null__",
+            "2,\"bob, \"\"the builder\"\"\",\"multi",
+            "line\"",
+            "__harness_row_count__ 2",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+
+        let result = parse_query_output(&lines).unwrap();
+        assert_eq!(result.rows_affected, 2);
+        assert_eq!(
+            result.rows,
+            vec![
+                json!({ "id": "1", "name": "alice", "note": null }),
+                json!({ "id": "2", "name": "bob, \"the builder\"", "note": "multi\nline" }),
+            ]
+        );
+
+        // Statements that return no rows only report a count
+        let result = parse_query_output(&["__harness_row_count__ 3".to_string()]).unwrap();
+        assert_eq!(result.rows_affected, 3);
+        assert!(result.rows.is_empty());
+
+        assert!(parse_query_output(&["1".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(ident("graph-node"), "\"graph-node\"");
+        assert_eq!(ident("a\"b"), "\"a\"\"b\"");
+        assert_eq!(literal("it's"), "'it''s'");
+    }
+}
@@ -0,0 +1,114 @@
+//! Ethereum JSON-RPC client
+//!
+//! Used by [`AnvilService`](crate::services::AnvilService) to drive a local
+//! anvil node through its `eth_*`, `evm_*` and `anvil_*` methods.
+
+use crate::http;
+use harness_core::prelude::*;
+use serde::de::DeserializeOwned;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// JSON-RPC 2.0 client over HTTP
+#[derive(Debug)]
+pub struct JsonRpcClient {
+    url: String,
+    next_id: AtomicU64,
+}
+
+impl JsonRpcClient {
+    /// Create a client for an `http://` endpoint
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Endpoint URL
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Call a method and deserialize its result
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+
+        let response = http::post_json(&self.url, &request).await?;
+        let body: Value = response.json().map_err(|_| {
+            Error::client(format!(
+                "{} returned HTTP {}: {}",
+                method,
+                response.status,
+                response.text()
+            ))
+        })?;
+
+        if let Some(error) = body.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            return Err(Error::client(format!("{} failed: {}", method, message)));
+        }
+
+        let result = body.get("result").cloned().unwrap_or(Value::Null);
+        serde_json::from_value(result)
+            .map_err(|e| Error::client(format!("{} returned an unexpected result: {}", method, e)))
+    }
+}
+
+/// Encode a number as a JSON-RPC quantity (`0x`-prefixed hex)
+pub(crate) fn quantity(value: u128) -> String {
+    format!("0x{:x}", value)
+}
+
+/// Decode a JSON-RPC quantity, accepting hex strings, decimal strings and numbers
+pub(crate) fn parse_quantity(value: &Value) -> Result<u128> {
+    let parsed = match value {
+        Value::Number(n) => n.as_u64().map(u128::from),
+        Value::String(s) => parse_amount(s).ok(),
+        _ => None,
+    };
+    parsed.ok_or_else(|| Error::client(format!("Invalid quantity: {}", value)))
+}
+
+/// Parse a user-supplied amount given in decimal or `0x` hex
+pub(crate) fn parse_amount(amount: &str) -> Result<u128> {
+    let amount = amount.trim();
+    let parsed = match amount
+        .strip_prefix("0x")
+        .or_else(|| amount.strip_prefix("0X"))
+    {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => amount.parse::<u128>(),
+    };
+    parsed.map_err(|_| Error::validation(format!("Invalid amount '{}'", amount)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantities() {
+        assert_eq!(quantity(0), "0x0");
+        assert_eq!(quantity(255), "0xff");
+        assert_eq!(parse_quantity(&json!("0xff")).unwrap(), 255);
+        assert_eq!(parse_quantity(&json!(12)).unwrap(), 12);
+        assert!(parse_quantity(&json!(null)).is_err());
+
+        assert_eq!(
+            parse_amount("1000000000000000000").unwrap(),
+            1_000_000_000_000_000_000
+        );
+        assert_eq!(parse_amount("0xDE0B6B3A7640000").unwrap(), 10u128.pow(18));
+        assert!(parse_amount("ten").is_err());
+    }
+}
@@ -3,23 +3,261 @@
 //! This module defines services for Graph Protocol components that implement
 //! the harness-core Service trait with strongly typed actions and events.
 
-use async_channel::Receiver;
+use async_channel::{Receiver, Sender};
 use async_trait::async_trait;
 use harness_core::prelude::*;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::info;
 
+use crate::graph_node::{GraphNodeClient, IndexingStatus};
+use crate::ipfs::{IpfsClient, IpfsEntry, SUBGRAPH_MANIFEST, rewrite_manifest};
+use crate::psql::{Psql, ident, literal};
+use crate::rpc::{JsonRpcClient, parse_amount, parse_quantity, quantity};
+
 /// Graph Node service that can deploy and manage subgraphs
-#[derive(Default)]
+///
+/// Subgraphs are managed through graph-node's admin JSON-RPC API, deployment
+/// progress is read from the index node status API and queries go to the
+/// GraphQL endpoint.
 pub struct GraphNodeService {
     endpoint: String,
+    query_port: u16,
+    admin_port: u16,
+    status_port: u16,
+    poll_interval: Duration,
+    sync_timeout: Duration,
 }
 
 impl GraphNodeService {
     pub fn new(endpoint: String) -> Self {
-        Self { endpoint }
+        Self {
+            endpoint,
+            query_port: 8000,
+            admin_port: 8020,
+            status_port: 8030,
+            poll_interval: Duration::from_secs(1),
+            sync_timeout: Duration::from_secs(600),
+        }
+    }
+
+    /// Use non-default ports for the GraphQL, admin and index node status APIs
+    pub fn with_ports(mut self, query: u16, admin: u16, status: u16) -> Self {
+        self.query_port = query;
+        self.admin_port = admin;
+        self.status_port = status;
+        self
+    }
+
+    /// How often to poll the status API while a deployment syncs
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// How long a deployment may take to sync before reporting an error
+    pub fn with_sync_timeout(mut self, timeout: Duration) -> Self {
+        self.sync_timeout = timeout;
+        self
+    }
+
+    /// Client for the node's APIs
+    pub fn client(&self) -> GraphNodeClient {
+        GraphNodeClient::new(
+            &self.endpoint,
+            self.query_port,
+            self.admin_port,
+            self.status_port,
+        )
+    }
+
+    async fn run(&self, action: GraphNodeAction, tx: &Sender<GraphNodeEvent>) -> Result<()> {
+        let client = self.client();
+
+        match action {
+            GraphNodeAction::DeploySubgraph {
+                name,
+                ipfs_hash,
+                version_label,
+            } => {
+                info!(
+                    "Deploying subgraph '{}' with IPFS hash '{}'",
+                    name, ipfs_hash
+                );
+
+                client.create_subgraph(&name).await?;
+                let deployed = client
+                    .deploy_subgraph(&name, &ipfs_hash, version_label.as_deref())
+                    .await?;
+
+                let _ = tx
+                    .send(GraphNodeEvent::DeploymentStarted {
+                        deployment_id: ipfs_hash.clone(),
+                        timestamp: chrono::Utc::now().to_rfc3339(),
+                    })
+                    .await;
+
+                let endpoints = deployed
+                    .queries
+                    .or_else(|| Some(client.subgraph_url(&name)))
+                    .into_iter()
+                    .chain(deployed.subscriptions)
+                    .collect();
+
+                // Follow indexing in the background so progress streams to the caller
+                let tx = tx.clone();
+                let poll_interval = self.poll_interval;
+                let sync_timeout = self.sync_timeout;
+                smol::spawn(async move {
+                    let result =
+                        follow_sync(&client, &ipfs_hash, None, poll_interval, sync_timeout, &tx)
+                            .await;
+                    let event = match result {
+                        Ok(_) => GraphNodeEvent::DeploymentCompleted {
+                            deployment_id: ipfs_hash,
+                            endpoints,
+                        },
+                        Err(e) => GraphNodeEvent::Error {
+                            message: e.to_string(),
+                        },
+                    };
+                    let _ = tx.send(event).await;
+                })
+                .detach();
+            }
+
+            GraphNodeAction::WaitForSync {
+                deployment,
+                block,
+                timeout,
+            } => {
+                info!(
+                    "Waiting for {} to reach {}",
+                    deployment,
+                    block.map_or_else(|| "the chain head".to_string(), |b| format!("block {}", b))
+                );
+
+                let tx = tx.clone();
+                let poll_interval = self.poll_interval;
+                let timeout = timeout.map_or(self.sync_timeout, Duration::from_secs);
+                smol::spawn(async move {
+                    let result =
+                        follow_sync(&client, &deployment, block, poll_interval, timeout, &tx).await;
+                    let event = match result {
+                        Ok(status) => GraphNodeEvent::Synced {
+                            deployment_id: deployment,
+                            block_number: status.latest_block().unwrap_or_default(),
+                        },
+                        Err(e) => GraphNodeEvent::Error {
+                            message: e.to_string(),
+                        },
+                    };
+                    let _ = tx.send(event).await;
+                })
+                .detach();
+            }
+
+            GraphNodeAction::QuerySubgraph {
+                subgraph_name,
+                query,
+            } => {
+                info!(
+                    "Querying subgraph '{}' with query: {}",
+                    subgraph_name, query
+                );
+
+                let data = client.query(&subgraph_name, &query).await?;
+                let _ = tx.send(GraphNodeEvent::QueryResult { data }).await;
+            }
+
+            GraphNodeAction::RemoveSubgraph { name } => {
+                info!("Removing subgraph '{}'", name);
+
+                client.remove_subgraph(&name).await?;
+                let _ = tx.send(GraphNodeEvent::SubgraphRemoved { name }).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Report indexing progress until a deployment has indexed `block`, or
+/// reached the chain head without one
+///
+/// Fails with the deployment's health, last indexed block and errors as soon
+/// as it is no longer healthy.
+async fn follow_sync(
+    client: &GraphNodeClient,
+    deployment: &str,
+    block: Option<u64>,
+    poll_interval: Duration,
+    timeout: Duration,
+    tx: &Sender<GraphNodeEvent>,
+) -> Result<IndexingStatus> {
+    let deadline = Instant::now() + timeout;
+    let mut reported = None;
+    let mut last = None;
+
+    loop {
+        if let Some(status) = client.indexing_status(deployment).await? {
+            if !status.is_healthy() {
+                return Err(Error::action(format!(
+                    "Deployment {} is {} ({})",
+                    deployment,
+                    if status.is_failed() {
+                        "failed"
+                    } else {
+                        "unhealthy"
+                    },
+                    status.diagnostic()
+                )));
+            }
+
+            let percent = status.percent(block);
+            if reported != Some(percent) {
+                reported = Some(percent);
+                let _ = tx
+                    .send(GraphNodeEvent::DeploymentProgress {
+                        deployment_id: deployment.to_string(),
+                        status: status.health.clone(),
+                        percent,
+                    })
+                    .await;
+            }
+            if status.has_reached(block) {
+                return Ok(status);
+            }
+            last = Some(status);
+        }
+
+        if Instant::now() >= deadline {
+            let target =
+                block.map_or_else(|| "the chain head".to_string(), |b| format!("block {}", b));
+            return Err(Error::action(match last {
+                Some(status) => format!(
+                    "Deployment {} did not reach {} within {}s ({})",
+                    deployment,
+                    target,
+                    timeout.as_secs(),
+                    status.diagnostic()
+                ),
+                None => format!(
+                    "Deployment {} did not reach {} within {}s: graph-node reports no indexing status for it",
+                    deployment,
+                    target,
+                    timeout.as_secs()
+                ),
+            }));
+        }
+        smol::Timer::after(poll_interval).await;
+    }
+}
+
+impl Default for GraphNodeService {
+    fn default() -> Self {
+        Self::new("localhost".to_string())
     }
 }
 
@@ -27,19 +265,34 @@ impl GraphNodeService {
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum GraphNodeAction {
-    /// Deploy a new subgraph
+    /// Create a subgraph name if needed, deploy a manifest to it and follow
+    /// indexing until it is synced
     DeploySubgraph {
         name: String,
+        /// IPFS hash of the subgraph manifest
         ipfs_hash: String,
         version_label: Option<String>,
     },
+    /// Wait until a deployment has indexed a block, or reached the chain head
+    /// when no block is given
+    WaitForSync {
+        /// Deployment ID (manifest IPFS hash)
+        deployment: String,
+        /// Block to wait for
+        block: Option<u64>,
+        /// Seconds to wait, defaulting to the service's sync timeout
+        timeout: Option<u64>,
+    },
     /// Query a deployed subgraph
     QuerySubgraph {
         subgraph_name: String,
         query: String,
     },
-    /// Remove a subgraph deployment
-    RemoveSubgraph { deployment_id: String },
+    /// Remove a subgraph name
+    RemoveSubgraph {
+        /// Subgraph name
+        name: String,
+    },
 }
 
 /// Events emitted by Graph Node actions
@@ -54,16 +307,33 @@ pub enum GraphNodeEvent {
     /// Deployment progress
     DeploymentProgress {
         deployment_id: String,
+        /// Deployment health reported by graph-node
         status: String,
+        /// Progress from the start block to the chain head
         percent: u8,
     },
-    /// Deployment completed
+    /// Deployment synced to the chain head
     DeploymentCompleted {
         deployment_id: String,
         endpoints: Vec<String>,
     },
+    /// Deployment reached the block a `WaitForSync` waited for
+    Synced {
+        /// Deployment ID (manifest IPFS hash)
+        deployment_id: String,
+        /// Latest indexed block
+        block_number: u64,
+    },
     /// Query result
-    QueryResult { data: serde_json::Value },
+    QueryResult {
+        /// Full GraphQL response
+        data: serde_json::Value,
+    },
+    /// Subgraph name removed
+    SubgraphRemoved {
+        /// Subgraph name
+        name: String,
+    },
     /// Error occurred
     Error { message: String },
 }
@@ -88,82 +358,12 @@ impl Service for GraphNodeService {
     async fn dispatch_action(&self, action: Self::Action) -> Result<Receiver<Self::Event>> {
         let (tx, rx) = async_channel::unbounded();
 
-        match action {
-            GraphNodeAction::DeploySubgraph {
-                name,
-                ipfs_hash,
-                version_label,
-            } => {
-                info!(
-                    "Deploying subgraph '{}' with IPFS hash '{}'",
-                    name, ipfs_hash
-                );
-
-                // Send deployment started event
-                let _ = tx
-                    .send(GraphNodeEvent::DeploymentStarted {
-                        deployment_id: format!("Qm{}", &ipfs_hash[2..]),
-                        timestamp: chrono::Utc::now().to_rfc3339(),
-                    })
-                    .await;
-
-                // Simulate deployment progress
-                for i in 0..=100 {
-                    if i % 20 == 0 {
-                        let _ = tx
-                            .send(GraphNodeEvent::DeploymentProgress {
-                                deployment_id: format!("Qm{}", &ipfs_hash[2..]),
-                                status: "Syncing blockchain data".to_string(),
-                                percent: i,
-                            })
-                            .await;
-                        smol::Timer::after(Duration::from_millis(100)).await;
-                    }
-                }
-
-                // Send completion
-                let _ = tx
-                    .send(GraphNodeEvent::DeploymentCompleted {
-                        deployment_id: format!("Qm{}", &ipfs_hash[2..]),
-                        endpoints: vec![
-                            format!("http://{}:8000/subgraphs/name/{}", self.endpoint, name),
-                            format!("ws://{}:8001/subgraphs/name/{}", self.endpoint, name),
-                        ],
-                    })
-                    .await;
-            }
-
-            GraphNodeAction::QuerySubgraph {
-                subgraph_name,
-                query,
-            } => {
-                info!(
-                    "Querying subgraph '{}' with query: {}",
-                    subgraph_name, query
-                );
-
-                // Simulate query execution
-                let _ = tx
-                    .send(GraphNodeEvent::QueryResult {
-                        data: json!({
-                            "data": {
-                                "example": "result"
-                            }
-                        }),
-                    })
-                    .await;
-            }
-
-            GraphNodeAction::RemoveSubgraph { deployment_id } => {
-                info!("Removing subgraph deployment '{}'", deployment_id);
-
-                let _ = tx
-                    .send(GraphNodeEvent::DeploymentCompleted {
-                        deployment_id: deployment_id.clone(),
-                        endpoints: vec![],
-                    })
-                    .await;
-            }
+        if let Err(e) = self.run(action, &tx).await {
+            let _ = tx
+                .send(GraphNodeEvent::Error {
+                    message: e.to_string(),
+                })
+                .await;
         }
 
         Ok(rx)
@@ -213,23 +413,213 @@ impl ServiceSetup for GraphNodeService {
 }
 
 /// Anvil blockchain service for testing
+///
+/// Actions are carried out over anvil's JSON-RPC API.
 pub struct AnvilService {
     chain_id: u64,
     port: u16,
+    rpc: JsonRpcClient,
 }
 
 impl AnvilService {
     pub fn new(chain_id: u64, port: u16) -> Self {
-        Self { chain_id, port }
+        Self {
+            chain_id,
+            port,
+            rpc: JsonRpcClient::new(format!("http://127.0.0.1:{}", port)),
+        }
+    }
+
+    /// Chain ID the node is expected to run with
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// JSON-RPC port
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// JSON-RPC client for the node
+    pub fn rpc(&self) -> &JsonRpcClient {
+        &self.rpc
+    }
+
+    async fn block_number(&self) -> Result<u64> {
+        let number: Value = self.rpc.call("eth_blockNumber", json!([])).await?;
+        Ok(parse_quantity(&number)? as u64)
+    }
+
+    async fn balance(&self, address: &str) -> Result<String> {
+        let balance: Value = self
+            .rpc
+            .call("eth_getBalance", json!([address, "latest"]))
+            .await?;
+        Ok(parse_quantity(&balance)?.to_string())
+    }
+
+    /// Snapshot the chain state, returning the snapshot ID
+    pub(crate) async fn snapshot(&self) -> Result<String> {
+        let snapshot: Value = self.rpc.call("evm_snapshot", json!([])).await?;
+        Ok(snapshot.as_str().unwrap_or_default().to_string())
+    }
+
+    /// Revert the chain to a snapshot, which consumes it
+    pub(crate) async fn revert(&self, snapshot_id: &str) -> Result<()> {
+        let reverted: bool = self.rpc.call("evm_revert", json!([snapshot_id])).await?;
+        if !reverted {
+            return Err(Error::action(format!(
+                "Snapshot {} does not exist",
+                snapshot_id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn run(&self, action: AnvilAction, tx: &Sender<AnvilEvent>) -> Result<()> {
+        match action {
+            AnvilAction::MineBlocks {
+                count,
+                interval_secs,
+            } => {
+                info!("Mining {} blocks", count);
+
+                let first = self.block_number().await? + 1;
+                let mut params = vec![json!(quantity(count.into()))];
+                if let Some(interval) = interval_secs {
+                    params.push(json!(quantity(interval.into())));
+                }
+                let _: Value = self.rpc.call("anvil_mine", Value::Array(params)).await?;
+
+                for number in first..first + count {
+                    let block: Value = self
+                        .rpc
+                        .call(
+                            "eth_getBlockByNumber",
+                            json!([quantity(number.into()), false]),
+                        )
+                        .await?;
+                    let _ = tx
+                        .send(AnvilEvent::BlockMined {
+                            block_number: number,
+                            block_hash: block["hash"].as_str().unwrap_or_default().to_string(),
+                            timestamp: parse_quantity(&block["timestamp"])? as u64,
+                        })
+                        .await;
+                }
+            }
+
+            AnvilAction::SetBalance { address, balance } => {
+                info!("Setting balance for {} to {}", address, balance);
+
+                let wei = parse_amount(&balance)?;
+                let _: Value = self
+                    .rpc
+                    .call("anvil_setBalance", json!([address, quantity(wei)]))
+                    .await?;
+
+                let _ = tx
+                    .send(AnvilEvent::BalanceUpdated {
+                        new_balance: self.balance(&address).await?,
+                        address,
+                    })
+                    .await;
+            }
+
+            AnvilAction::Fork { url, block_number } => {
+                info!("Creating fork from {} at block {:?}", url, block_number);
+
+                let mut forking = json!({ "jsonRpcUrl": url });
+                if let Some(block_number) = block_number {
+                    forking["blockNumber"] = json!(block_number);
+                }
+                let _: Value = self
+                    .rpc
+                    .call("anvil_reset", json!([{ "forking": forking }]))
+                    .await?;
+
+                let _ = tx
+                    .send(AnvilEvent::ForkCreated {
+                        fork_url: url,
+                        forked_at_block: self.block_number().await?,
+                    })
+                    .await;
+            }
+
+            AnvilAction::Snapshot => {
+                let snapshot_id = self.snapshot().await?;
+                info!("Took chain snapshot {}", snapshot_id);
+
+                let _ = tx
+                    .send(AnvilEvent::SnapshotTaken {
+                        snapshot_id,
+                        block_number: self.block_number().await?,
+                    })
+                    .await;
+            }
+
+            AnvilAction::Revert { snapshot_id } => {
+                info!("Reverting chain to snapshot {}", snapshot_id);
+
+                self.revert(&snapshot_id).await?;
+
+                let _ = tx
+                    .send(AnvilEvent::Reverted {
+                        snapshot_id,
+                        block_number: self.block_number().await?,
+                    })
+                    .await;
+            }
+
+            AnvilAction::IncreaseTime { seconds } => {
+                info!("Advancing chain time by {}s", seconds);
+
+                let offset: Value = self.rpc.call("evm_increaseTime", json!([seconds])).await?;
+                let _ = tx
+                    .send(AnvilEvent::TimeIncreased {
+                        seconds,
+                        total_offset: parse_quantity(&offset)? as u64,
+                    })
+                    .await;
+            }
+
+            AnvilAction::Impersonate { address } => {
+                info!("Impersonating {}", address);
+
+                let _: Value = self
+                    .rpc
+                    .call("anvil_impersonateAccount", json!([address]))
+                    .await?;
+                let _ = tx.send(AnvilEvent::ImpersonationStarted { address }).await;
+            }
+
+            AnvilAction::StopImpersonating { address } => {
+                info!("Stopping impersonation of {}", address);
+
+                let _: Value = self
+                    .rpc
+                    .call("anvil_stopImpersonatingAccount", json!([address]))
+                    .await?;
+                let _ = tx.send(AnvilEvent::ImpersonationStopped { address }).await;
+            }
+
+            AnvilAction::GetBalance { address } => {
+                let _ = tx
+                    .send(AnvilEvent::Balance {
+                        balance: self.balance(&address).await?,
+                        address,
+                    })
+                    .await;
+            }
+        }
+
+        Ok(())
     }
 }
 
 impl Default for AnvilService {
     fn default() -> Self {
-        Self {
-            chain_id: 31337,
-            port: 8545,
-        }
+        Self::new(31337, 8545)
     }
 }
 
@@ -239,16 +629,50 @@ impl Default for AnvilService {
 pub enum AnvilAction {
     /// Mine a number of blocks
     MineBlocks {
+        /// Number of blocks to mine
         count: u64,
+        /// Timestamp interval between the mined blocks
         interval_secs: Option<u64>,
     },
     /// Set account balance
-    SetBalance { address: String, balance: String },
-    /// Create a fork
+    SetBalance {
+        /// Account address
+        address: String,
+        /// Balance in wei, decimal or 0x-prefixed hex
+        balance: String,
+    },
+    /// Reset the chain to a fork of another network
     Fork {
         url: String,
         block_number: Option<u64>,
     },
+    /// Snapshot the chain state
+    Snapshot,
+    /// Revert the chain to a snapshot
+    Revert {
+        /// Snapshot ID returned by `Snapshot`
+        snapshot_id: String,
+    },
+    /// Advance the timestamp of the next block
+    IncreaseTime {
+        /// Seconds to advance by
+        seconds: u64,
+    },
+    /// Send transactions from an account without its key
+    Impersonate {
+        /// Account address
+        address: String,
+    },
+    /// Stop impersonating an account
+    StopImpersonating {
+        /// Account address
+        address: String,
+    },
+    /// Query an account balance
+    GetBalance {
+        /// Account address
+        address: String,
+    },
 }
 
 /// Events from Anvil blockchain
@@ -259,10 +683,13 @@ pub enum AnvilEvent {
     BlockMined {
         block_number: u64,
         block_hash: String,
+        /// Block timestamp in seconds since the Unix epoch
+        timestamp: u64,
     },
     /// Balance updated
     BalanceUpdated {
         address: String,
+        /// New balance in wei
         new_balance: String,
     },
     /// Fork created
@@ -270,6 +697,44 @@ pub enum AnvilEvent {
         fork_url: String,
         forked_at_block: u64,
     },
+    /// Snapshot taken
+    SnapshotTaken {
+        /// ID to revert to the snapshot with
+        snapshot_id: String,
+        /// Block the snapshot was taken at
+        block_number: u64,
+    },
+    /// Chain reverted to a snapshot
+    Reverted {
+        /// Snapshot reverted to
+        snapshot_id: String,
+        /// Block after reverting
+        block_number: u64,
+    },
+    /// Chain time advanced
+    TimeIncreased {
+        /// Seconds advanced by
+        seconds: u64,
+        /// Total offset from wall-clock time in seconds
+        total_offset: u64,
+    },
+    /// Account is being impersonated
+    ImpersonationStarted {
+        /// Account address
+        address: String,
+    },
+    /// Account is no longer impersonated
+    ImpersonationStopped {
+        /// Account address
+        address: String,
+    },
+    /// Account balance
+    Balance {
+        /// Account address
+        address: String,
+        /// Balance in wei
+        balance: String,
+    },
     /// Error occurred
     Error { message: String },
 }
@@ -294,49 +759,12 @@ impl Service for AnvilService {
     async fn dispatch_action(&self, action: Self::Action) -> Result<Receiver<Self::Event>> {
         let (tx, rx) = async_channel::unbounded();
 
-        match action {
-            AnvilAction::MineBlocks {
-                count,
-                interval_secs,
-            } => {
-                info!("Mining {} blocks", count);
-
-                let interval = interval_secs.unwrap_or(0);
-                for i in 0..count {
-                    let _ = tx
-                        .send(AnvilEvent::BlockMined {
-                            block_number: 1000 + i,
-                            block_hash: format!("0x{:064x}", i),
-                        })
-                        .await;
-
-                    if interval > 0 {
-                        smol::Timer::after(Duration::from_secs(interval)).await;
-                    }
-                }
-            }
-
-            AnvilAction::SetBalance { address, balance } => {
-                info!("Setting balance for {} to {}", address, balance);
-
-                let _ = tx
-                    .send(AnvilEvent::BalanceUpdated {
-                        address: address.clone(),
-                        new_balance: balance,
-                    })
-                    .await;
-            }
-
-            AnvilAction::Fork { url, block_number } => {
-                info!("Creating fork from {} at block {:?}", url, block_number);
-
-                let _ = tx
-                    .send(AnvilEvent::ForkCreated {
-                        fork_url: url,
-                        forked_at_block: block_number.unwrap_or(0),
-                    })
-                    .await;
-            }
+        if let Err(e) = self.run(action, &tx).await {
+            let _ = tx
+                .send(AnvilEvent::Error {
+                    message: e.to_string(),
+                })
+                .await;
         }
 
         Ok(rx)
@@ -346,34 +774,160 @@ impl Service for AnvilService {
 /// PostgreSQL database service
 pub struct PostgresService {
     db_name: String,
-    port: u16,
+    psql: Psql,
 }
 
 impl PostgresService {
     pub fn new(db_name: String, port: u16) -> Self {
-        Self { db_name, port }
+        Self {
+            db_name,
+            psql: Psql {
+                user: "postgres".to_string(),
+                password: None,
+                port,
+                container: None,
+            },
+        }
+    }
+
+    /// Connect as this user
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.psql.user = user.into();
+        self
+    }
+
+    /// Authenticate with this password
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.psql.password = Some(password.into());
+        self
+    }
+
+    /// Run `psql` inside this Docker container instead of connecting over TCP
+    pub fn with_container(mut self, container: impl Into<String>) -> Self {
+        self.psql.container = Some(container.into());
+        self
+    }
+
+    /// Database the service manages
+    pub fn db_name(&self) -> &str {
+        &self.db_name
+    }
+
+    /// Copy the database into a template database
+    ///
+    /// Other sessions on the database are terminated first, since Postgres
+    /// can only copy a database nobody is connected to.
+    pub(crate) async fn create_template(&self, template: &str) -> Result<()> {
+        info!("Copying database '{}' to '{}'", self.db_name, template);
+        self.psql
+            .run(
+                "postgres",
+                &[
+                    terminate_sessions(&self.db_name),
+                    format!(
+                        "CREATE DATABASE {} TEMPLATE {}",
+                        ident(template),
+                        ident(&self.db_name)
+                    ),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Replace the database with a copy of a template database
+    pub(crate) async fn restore_template(&self, template: &str) -> Result<()> {
+        info!("Restoring database '{}' from '{}'", self.db_name, template);
+        self.psql
+            .run(
+                "postgres",
+                &[
+                    format!(
+                        "DROP DATABASE IF EXISTS {} WITH (FORCE)",
+                        ident(&self.db_name)
+                    ),
+                    format!(
+                        "CREATE DATABASE {} TEMPLATE {}",
+                        ident(&self.db_name),
+                        ident(template)
+                    ),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Drop a database if it exists
+    pub(crate) async fn drop_database(&self, name: &str) -> Result<()> {
+        self.psql
+            .run(
+                "postgres",
+                &[format!(
+                    "DROP DATABASE IF EXISTS {} WITH (FORCE)",
+                    ident(name)
+                )],
+            )
+            .await?;
+        Ok(())
     }
 }
 
+fn terminate_sessions(database: &str) -> String {
+    format!(
+        "SELECT pg_terminate_backend(pid) FROM pg_stat_activity \
+         WHERE datname = {} AND pid <> pg_backend_pid()",
+        literal(database)
+    )
+}
+
 impl Default for PostgresService {
     fn default() -> Self {
-        Self {
-            db_name: "graph-node".to_string(),
-            port: 5432,
-        }
+        Self::new("graph-node".to_string(), 5432)
     }
 }
 
 /// Actions for PostgreSQL
+///
+/// Actions without a `database` run against the service's own database.
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum PostgresAction {
-    /// Create a new database
-    CreateDatabase { name: String },
-    /// Run a SQL query
-    ExecuteQuery { query: String },
-    /// Backup the database
-    Backup { backup_path: String },
+    /// Create a new database from `template0`, as graph-node requires
+    CreateDatabase {
+        /// Database name
+        name: String,
+        /// Collation and character classification, `C` by default
+        locale: Option<String>,
+        /// Extensions to create, graph-node's (`pg_trgm`, `btree_gist`,
+        /// `postgres_fdw`) by default
+        extensions: Option<Vec<String>>,
+    },
+    /// Run a single SQL statement
+    ExecuteQuery {
+        /// SQL statement
+        query: String,
+        /// Database to run it in
+        database: Option<String>,
+    },
+    /// Dump a database with `pg_dump` to a local SQL file
+    Backup {
+        /// File to write the dump to
+        backup_path: String,
+        /// Database to dump
+        database: Option<String>,
+    },
+    /// Load a backup into an existing database, replacing the objects in it
+    Restore {
+        /// File written by `Backup`
+        backup_path: String,
+        /// Database to load it into
+        database: Option<String>,
+    },
+    /// Drop a database, disconnecting its sessions
+    DropDatabase {
+        /// Database name
+        name: String,
+    },
 }
 
 /// Events from PostgreSQL
@@ -383,62 +937,166 @@ pub enum PostgresEvent {
     /// Database created
     DatabaseCreated { name: String },
     /// Query executed
-    QueryExecuted { rows_affected: u64 },
+    QueryExecuted {
+        /// Rows returned or affected
+        rows_affected: u64,
+        /// Returned rows as objects of column name to text value
+        rows: Vec<serde_json::Value>,
+    },
     /// Backup completed
     BackupCompleted { path: String, size_bytes: u64 },
+    /// Backup loaded
+    Restored {
+        /// Database the backup was loaded into
+        database: String,
+        /// Backup file
+        path: String,
+    },
+    /// Database dropped
+    DatabaseDropped {
+        /// Database name
+        name: String,
+    },
     /// Error occurred
     Error { message: String },
 }
 
-#[async_trait]
-impl Service for PostgresService {
-    type Action = PostgresAction;
-    type Event = PostgresEvent;
-
-    fn service_type() -> &'static str {
-        "postgres"
-    }
+/// Extensions graph-node needs in its database
+const GRAPH_NODE_EXTENSIONS: [&str; 3] = ["pg_trgm", "btree_gist", "postgres_fdw"];
 
-    fn name(&self) -> &str {
-        "postgres"
-    }
+impl PostgresService {
+    async fn run(&self, action: PostgresAction, tx: &Sender<PostgresEvent>) -> Result<()> {
+        match action {
+            PostgresAction::CreateDatabase {
+                name,
+                locale,
+                extensions,
+            } => {
+                let locale = locale.unwrap_or_else(|| "C".to_string());
+                let extensions = extensions.unwrap_or_else(|| {
+                    GRAPH_NODE_EXTENSIONS
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect()
+                });
+                info!(
+                    "Creating database '{}' with locale '{}' and extensions {:?}",
+                    name, locale, extensions
+                );
 
-    fn description(&self) -> &str {
-        "PostgreSQL database service"
-    }
+                self.psql
+                    .run(
+                        "postgres",
+                        &[format!(
+                            "CREATE DATABASE {} TEMPLATE template0 ENCODING 'UTF8' LC_COLLATE {} LC_CTYPE {}",
+                            ident(&name),
+                            literal(&locale),
+                            literal(&locale)
+                        )],
+                    )
+                    .await?;
+                if !extensions.is_empty() {
+                    let commands: Vec<_> = extensions
+                        .iter()
+                        .map(|e| format!("CREATE EXTENSION IF NOT EXISTS {}", ident(e)))
+                        .collect();
+                    self.psql.run(&name, &commands).await?;
+                }
 
-    async fn dispatch_action(&self, action: Self::Action) -> Result<Receiver<Self::Event>> {
-        let (tx, rx) = async_channel::unbounded();
+                let _ = tx.send(PostgresEvent::DatabaseCreated { name }).await;
+            }
 
-        match action {
-            PostgresAction::CreateDatabase { name } => {
-                info!("Creating database '{}'", name);
+            PostgresAction::ExecuteQuery { query, database } => {
+                let database = database.unwrap_or_else(|| self.db_name.clone());
+                info!("Executing query on '{}': {}", database, query);
 
+                let result = self.psql.query(&database, &query).await?;
                 let _ = tx
-                    .send(PostgresEvent::DatabaseCreated { name: name.clone() })
+                    .send(PostgresEvent::QueryExecuted {
+                        rows_affected: result.rows_affected,
+                        rows: result.rows,
+                    })
                     .await;
             }
 
-            PostgresAction::ExecuteQuery { query } => {
-                info!("Executing query: {}", query);
+            PostgresAction::Backup {
+                backup_path,
+                database,
+            } => {
+                let database = database.unwrap_or_else(|| self.db_name.clone());
+                info!("Backing up '{}' to {}", database, backup_path);
+
+                let sql = self.psql.dump(&database).await?;
+                smol::fs::write(&backup_path, &sql).await.map_err(|e| {
+                    Error::action(format!("Failed to write backup {}: {}", backup_path, e))
+                })?;
 
                 let _ = tx
-                    .send(PostgresEvent::QueryExecuted {
-                        rows_affected: 42, // Simulated
+                    .send(PostgresEvent::BackupCompleted {
+                        path: backup_path,
+                        size_bytes: sql.len() as u64,
                     })
                     .await;
             }
 
-            PostgresAction::Backup { backup_path } => {
-                info!("Creating backup at {}", backup_path);
+            PostgresAction::Restore {
+                backup_path,
+                database,
+            } => {
+                let database = database.unwrap_or_else(|| self.db_name.clone());
+                info!("Restoring '{}' from {}", database, backup_path);
+
+                let sql = smol::fs::read(&backup_path).await.map_err(|e| {
+                    Error::action(format!("Failed to read backup {}: {}", backup_path, e))
+                })?;
+                self.psql.restore(&database, &sql).await?;
 
                 let _ = tx
-                    .send(PostgresEvent::BackupCompleted {
+                    .send(PostgresEvent::Restored {
+                        database,
                         path: backup_path,
-                        size_bytes: 1024 * 1024, // 1MB simulated
                     })
                     .await;
             }
+
+            PostgresAction::DropDatabase { name } => {
+                info!("Dropping database '{}'", name);
+
+                self.drop_database(&name).await?;
+                let _ = tx.send(PostgresEvent::DatabaseDropped { name }).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Service for PostgresService {
+    type Action = PostgresAction;
+    type Event = PostgresEvent;
+
+    fn service_type() -> &'static str {
+        "postgres"
+    }
+
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    fn description(&self) -> &str {
+        "PostgreSQL database service"
+    }
+
+    async fn dispatch_action(&self, action: Self::Action) -> Result<Receiver<Self::Event>> {
+        let (tx, rx) = async_channel::unbounded();
+
+        if let Err(e) = self.run(action, &tx).await {
+            let _ = tx
+                .send(PostgresEvent::Error {
+                    message: e.to_string(),
+                })
+                .await;
         }
 
         Ok(rx)
@@ -458,14 +1116,39 @@ impl IpfsService {
             gateway_port,
         }
     }
+
+    /// Base URL of the Kubo RPC API
+    pub fn api_url(&self) -> String {
+        format!("http://127.0.0.1:{}/api/v0", self.api_port)
+    }
+
+    /// Gateway port
+    pub fn gateway_port(&self) -> u16 {
+        self.gateway_port
+    }
+
+    /// Client for the Kubo RPC API
+    pub fn client(&self) -> IpfsClient {
+        IpfsClient::new(self.api_url())
+    }
+
+    /// CIDs pinned recursively
+    pub(crate) async fn pins(&self) -> Result<Vec<String>> {
+        self.client().pins().await
+    }
+
+    pub(crate) async fn pin(&self, cid: &str) -> Result<()> {
+        self.client().pin(cid).await
+    }
+
+    pub(crate) async fn unpin(&self, cid: &str) -> Result<()> {
+        self.client().unpin(cid).await
+    }
 }
 
 impl Default for IpfsService {
     fn default() -> Self {
-        Self {
-            api_port: 5001,
-            gateway_port: 8080,
-        }
+        Self::new(5001, 8080)
     }
 }
 
@@ -474,12 +1157,27 @@ impl Default for IpfsService {
 #[serde(tag = "type")]
 pub enum IpfsAction {
     /// Add content to IPFS
-    AddContent { content: String },
+    AddContent {
+        /// Content to add, as text
+        content: String,
+        /// Pin the content, `true` by default
+        pin: Option<bool>,
+    },
+    /// Add a local directory, e.g. a subgraph's build output
+    ///
+    /// A `subgraph.yaml` at its root is added once more with its file
+    /// references pointing into the directory, ready for `DeploySubgraph`.
+    AddDirectory {
+        /// Local directory
+        path: String,
+        /// Pin the directory, `true` by default
+        pin: Option<bool>,
+    },
     /// Pin a hash
     Pin { hash: String },
     /// Unpin a hash
     Unpin { hash: String },
-    /// Get content by hash
+    /// Get content by hash or IPFS path
     Cat { hash: String },
 }
 
@@ -489,47 +1187,75 @@ pub enum IpfsAction {
 pub enum IpfsEvent {
     /// Content added
     ContentAdded { hash: String, size: u64 },
+    /// Directory added
+    DirectoryAdded {
+        /// CID of the directory
+        hash: String,
+        /// Every file and subdirectory, the directory itself last
+        entries: Vec<IpfsEntry>,
+        /// CID of the rewritten subgraph manifest, if the directory has one
+        manifest: Option<String>,
+    },
     /// Hash pinned
     Pinned { hash: String },
     /// Hash unpinned
     Unpinned { hash: String },
-    /// Content retrieved
+    /// Content retrieved, as (lossy) UTF-8
     ContentRetrieved { hash: String, content: String },
     /// Error occurred
     Error { message: String },
 }
 
-#[async_trait]
-impl Service for IpfsService {
-    type Action = IpfsAction;
-    type Event = IpfsEvent;
-
-    fn service_type() -> &'static str {
-        "ipfs"
-    }
-
-    fn name(&self) -> &str {
-        "ipfs"
-    }
-
-    fn description(&self) -> &str {
-        "IPFS distributed storage service"
-    }
-
-    async fn dispatch_action(&self, action: Self::Action) -> Result<Receiver<Self::Event>> {
-        let (tx, rx) = async_channel::unbounded();
-
+impl IpfsService {
+    async fn run(&self, action: IpfsAction, tx: &Sender<IpfsEvent>) -> Result<()> {
+        let client = self.client();
         match action {
-            IpfsAction::AddContent { content } => {
-                info!("Adding content to IPFS");
-
-                // Simulate IPFS hash generation
-                let hash = format!("Qm{:x}", content.len());
+            IpfsAction::AddContent { content, pin } => {
+                info!("Adding {} bytes to IPFS", content.len());
 
+                let entry = client
+                    .add("content", content.as_bytes(), pin.unwrap_or(true))
+                    .await?;
                 let _ = tx
                     .send(IpfsEvent::ContentAdded {
-                        hash,
-                        size: content.len() as u64,
+                        hash: entry.hash,
+                        size: entry.size,
+                    })
+                    .await;
+            }
+
+            IpfsAction::AddDirectory { path, pin } => {
+                info!("Adding directory {} to IPFS", path);
+                let pin = pin.unwrap_or(true);
+
+                let dir = std::path::Path::new(&path);
+                let added = client.add_directory(dir, pin).await?;
+
+                let manifest_path = dir.join(SUBGRAPH_MANIFEST);
+                let manifest = if manifest_path.is_file() {
+                    let manifest = smol::fs::read_to_string(&manifest_path)
+                        .await
+                        .map_err(|e| {
+                            Error::action(format!(
+                                "Failed to read {}: {}",
+                                manifest_path.display(),
+                                e
+                            ))
+                        })?;
+                    let manifest = rewrite_manifest(&manifest, &added.root.hash, "")?;
+                    let entry = client
+                        .add(SUBGRAPH_MANIFEST, manifest.as_bytes(), pin)
+                        .await?;
+                    Some(entry.hash)
+                } else {
+                    None
+                };
+
+                let _ = tx
+                    .send(IpfsEvent::DirectoryAdded {
+                        hash: added.root.hash,
+                        entries: added.entries,
+                        manifest,
                     })
                     .await;
             }
@@ -537,27 +1263,62 @@ impl Service for IpfsService {
             IpfsAction::Pin { hash } => {
                 info!("Pinning hash: {}", hash);
 
-                let _ = tx.send(IpfsEvent::Pinned { hash: hash.clone() }).await;
+                client.pin(&hash).await?;
+                let _ = tx.send(IpfsEvent::Pinned { hash }).await;
             }
 
             IpfsAction::Unpin { hash } => {
                 info!("Unpinning hash: {}", hash);
 
-                let _ = tx.send(IpfsEvent::Unpinned { hash: hash.clone() }).await;
+                client.unpin(&hash).await?;
+                let _ = tx.send(IpfsEvent::Unpinned { hash }).await;
             }
 
             IpfsAction::Cat { hash } => {
                 info!("Retrieving content for hash: {}", hash);
 
+                let content = client.cat(&hash).await?;
                 let _ = tx
                     .send(IpfsEvent::ContentRetrieved {
-                        hash: hash.clone(),
-                        content: "Example content".to_string(),
+                        hash,
+                        content: String::from_utf8_lossy(&content).into_owned(),
                     })
                     .await;
             }
         }
 
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Service for IpfsService {
+    type Action = IpfsAction;
+    type Event = IpfsEvent;
+
+    fn service_type() -> &'static str {
+        "ipfs"
+    }
+
+    fn name(&self) -> &str {
+        "ipfs"
+    }
+
+    fn description(&self) -> &str {
+        "IPFS distributed storage service"
+    }
+
+    async fn dispatch_action(&self, action: Self::Action) -> Result<Receiver<Self::Event>> {
+        let (tx, rx) = async_channel::unbounded();
+
+        if let Err(e) = self.run(action, &tx).await {
+            let _ = tx
+                .send(IpfsEvent::Error {
+                    message: e.to_string(),
+                })
+                .await;
+        }
+
         Ok(rx)
     }
 }
@@ -9,7 +9,8 @@ mod tests {
 
     #[smol_potat::test]
     async fn test_graph_node_service() {
-        let service = GraphNodeService::new("localhost".to_string());
+        // Nothing listens on port 1, so the action reports an error event
+        let service = GraphNodeService::new("127.0.0.1".to_string()).with_ports(1, 1, 1);
 
         // Test service metadata
         assert_eq!(service.name(), "graph-node");
@@ -24,36 +25,23 @@ mod tests {
 
         let events = service.dispatch_action(action).await.unwrap();
 
-        // Collect events
-        let mut event_count = 0;
-        while let Ok(event) = events.recv().await {
-            event_count += 1;
-            match event {
-                GraphNodeEvent::DeploymentStarted { deployment_id, .. } => {
-                    assert!(deployment_id.starts_with("Qm"));
-                }
-                GraphNodeEvent::DeploymentProgress { percent, .. } => {
-                    assert!(percent <= 100);
-                }
-                GraphNodeEvent::DeploymentCompleted { endpoints, .. } => {
-                    assert!(!endpoints.is_empty());
-                }
-                _ => {}
-            }
-        }
-
-        assert!(event_count > 0);
+        let event = events.recv().await.unwrap();
+        assert!(
+            matches!(event, GraphNodeEvent::Error { message } if message.contains("subgraph_create") || message.contains("connect"))
+        );
+        assert!(events.recv().await.is_err());
     }
 
     #[smol_potat::test]
     async fn test_anvil_service() {
-        let service = AnvilService::new(31337, 8545);
+        // Nothing listens on port 1, so the action reports an error event
+        let service = AnvilService::new(31337, 1);
 
         // Test service metadata
         assert_eq!(service.name(), "anvil");
         assert!(service.description().contains("Anvil"));
+        assert_eq!(service.rpc().url(), "http://127.0.0.1:1");
 
-        // Test mine blocks action
         let action = AnvilAction::MineBlocks {
             count: 3,
             interval_secs: None,
@@ -61,15 +49,11 @@ mod tests {
 
         let events = service.dispatch_action(action).await.unwrap();
 
-        // Should receive 3 block mined events
-        let mut block_count = 0;
-        while let Ok(event) = events.recv().await {
-            if let AnvilEvent::BlockMined { .. } = event {
-                block_count += 1;
-            }
-        }
-
-        assert_eq!(block_count, 3);
+        let event = events.recv().await.unwrap();
+        assert!(
+            matches!(event, AnvilEvent::Error { message } if message.contains("eth_blockNumber") || message.contains("connect"))
+        );
+        assert!(events.recv().await.is_err());
     }
 
     #[test]
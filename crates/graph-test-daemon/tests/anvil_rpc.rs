@@ -0,0 +1,263 @@
+//! AnvilService against an in-process JSON-RPC stand-in, and against a real
+//! anvil binary when one is installed
+
+mod common;
+
+use futures::StreamExt;
+use graph_test_daemon::{AnvilAction, AnvilEvent, AnvilService};
+use harness_core::prelude::{Result, Service};
+use serde_json::{Value, json};
+
+const ACCOUNT: &str = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+
+async fn run(service: &AnvilService, action: AnvilAction) -> Vec<AnvilEvent> {
+    let events = service.dispatch_action(action).await.unwrap();
+    events.collect().await
+}
+
+#[smol_potat::test]
+async fn test_mine_blocks_from_receipts() {
+    let (port, chain) = common::anvil::start().await;
+    chain.lock().unwrap().block = 10;
+    let anvil = AnvilService::new(31337, port);
+
+    let events = run(
+        &anvil,
+        AnvilAction::MineBlocks {
+            count: 3,
+            interval_secs: Some(12),
+        },
+    )
+    .await;
+
+    let mined: Vec<u64> = events
+        .iter()
+        .map(|e| match e {
+            AnvilEvent::BlockMined {
+                block_number,
+                block_hash,
+                timestamp,
+            } => {
+                assert_eq!(block_hash, &format!("0x{:064x}", block_number));
+                assert_eq!(*timestamp, 1_700_000_000 + block_number * 12);
+                *block_number
+            }
+            other => panic!("unexpected event {:?}", other),
+        })
+        .collect();
+    assert_eq!(mined, vec![11, 12, 13]);
+    assert!(
+        chain
+            .lock()
+            .unwrap()
+            .calls
+            .contains(&"anvil_mine".to_string())
+    );
+}
+
+#[smol_potat::test]
+async fn test_balances() {
+    let (port, _chain) = common::anvil::start().await;
+    let anvil = AnvilService::new(31337, port);
+
+    let events = run(
+        &anvil,
+        AnvilAction::SetBalance {
+            address: ACCOUNT.to_string(),
+            balance: "0xde0b6b3a7640000".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [AnvilEvent::BalanceUpdated { new_balance, .. }] if new_balance == "1000000000000000000"
+    ));
+
+    let events = run(
+        &anvil,
+        AnvilAction::GetBalance {
+            address: ACCOUNT.to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [AnvilEvent::Balance { balance, .. }] if balance == "1000000000000000000"
+    ));
+
+    let events = run(
+        &anvil,
+        AnvilAction::SetBalance {
+            address: ACCOUNT.to_string(),
+            balance: "lots".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(&events[..], [AnvilEvent::Error { message }] if message.contains("lots")));
+}
+
+#[smol_potat::test]
+async fn test_snapshot_and_revert() {
+    let (port, chain) = common::anvil::start().await;
+    let anvil = AnvilService::new(31337, port);
+
+    let events = run(&anvil, AnvilAction::Snapshot).await;
+    let AnvilEvent::SnapshotTaken {
+        snapshot_id,
+        block_number: 0,
+    } = &events[0]
+    else {
+        panic!("unexpected events {:?}", events);
+    };
+
+    run(
+        &anvil,
+        AnvilAction::MineBlocks {
+            count: 5,
+            interval_secs: None,
+        },
+    )
+    .await;
+    assert_eq!(chain.lock().unwrap().block, 5);
+
+    let events = run(
+        &anvil,
+        AnvilAction::Revert {
+            snapshot_id: snapshot_id.clone(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [AnvilEvent::Reverted {
+            block_number: 0,
+            ..
+        }]
+    ));
+
+    // Snapshots are consumed by a revert
+    let events = run(
+        &anvil,
+        AnvilAction::Revert {
+            snapshot_id: snapshot_id.clone(),
+        },
+    )
+    .await;
+    assert!(
+        matches!(&events[..], [AnvilEvent::Error { message }] if message.contains("does not exist"))
+    );
+}
+
+#[smol_potat::test]
+async fn test_fork_time_and_impersonation() {
+    let (port, chain) = common::anvil::start().await;
+    let anvil = AnvilService::new(31337, port);
+
+    let events = run(
+        &anvil,
+        AnvilAction::Fork {
+            url: "http://mainnet.example".to_string(),
+            block_number: Some(19_000_000),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [AnvilEvent::ForkCreated {
+            forked_at_block: 19_000_000,
+            ..
+        }]
+    ));
+
+    run(&anvil, AnvilAction::IncreaseTime { seconds: 60 }).await;
+    let events = run(&anvil, AnvilAction::IncreaseTime { seconds: 30 }).await;
+    assert!(matches!(
+        &events[..],
+        [AnvilEvent::TimeIncreased {
+            seconds: 30,
+            total_offset: 90
+        }]
+    ));
+
+    let address = ACCOUNT.to_string();
+    let events = run(
+        &anvil,
+        AnvilAction::Impersonate {
+            address: address.clone(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [AnvilEvent::ImpersonationStarted { .. }]
+    ));
+    assert_eq!(chain.lock().unwrap().impersonating, vec![address.clone()]);
+
+    run(&anvil, AnvilAction::StopImpersonating { address }).await;
+    assert!(chain.lock().unwrap().impersonating.is_empty());
+}
+
+#[smol_potat::test]
+async fn test_real_anvil_when_installed() {
+    let Ok(binary) = which_anvil() else {
+        eprintln!("anvil not installed; skipping");
+        return;
+    };
+
+    let port = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let _anvil_process = Reaped(
+        std::process::Command::new(binary)
+            .args(["--port", &port.to_string(), "--silent"])
+            .spawn()
+            .unwrap(),
+    );
+
+    let anvil = AnvilService::new(31337, port);
+    let mut ready = false;
+    for _ in 0..50 {
+        let probe: Result<Value> = anvil.rpc().call("eth_blockNumber", json!([])).await;
+        if probe.is_ok() {
+            ready = true;
+            break;
+        }
+        smol::Timer::after(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(ready, "anvil did not start");
+
+    let events = run(
+        &anvil,
+        AnvilAction::MineBlocks {
+            count: 2,
+            interval_secs: None,
+        },
+    )
+    .await;
+
+    assert_eq!(events.len(), 2, "{:?}", events);
+    assert!(matches!(
+        &events[1],
+        AnvilEvent::BlockMined { block_number: 2, block_hash, .. } if block_hash.len() == 66
+    ));
+}
+
+/// Kills and reaps a child process when dropped, even if the test fails
+struct Reaped(std::process::Child);
+
+impl Drop for Reaped {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn which_anvil() -> std::io::Result<std::path::PathBuf> {
+    std::env::var_os("PATH")
+        .into_iter()
+        .flat_map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .map(|dir| dir.join("anvil"))
+        .find(|path| path.is_file())
+        .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+}
@@ -0,0 +1,89 @@
+//! Stack checkpoints against anvil and IPFS stand-ins
+
+mod common;
+
+use graph_test_daemon::daemon::GraphStackConfig;
+use graph_test_daemon::{AnvilService, Checkpoints, GraphTestDaemon, IpfsService};
+
+#[smol_potat::test]
+async fn test_restore_checkpoint() {
+    let (anvil_port, chain) = common::anvil::start().await;
+    let (ipfs_port, kubo) = common::ipfs::start().await;
+    kubo.lock().unwrap().pins.insert("QmSubgraph".to_string());
+
+    let mut checkpoints = Checkpoints::new();
+    checkpoints.add_anvil("anvil", AnvilService::new(31337, anvil_port));
+    checkpoints.add_ipfs("ipfs", IpfsService::new(ipfs_port, 8080));
+
+    let deployed = checkpoints
+        .create(Some("deployed".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(deployed.id, "checkpoint-1");
+    assert_eq!(deployed.ipfs["ipfs"], vec!["QmSubgraph".to_string()]);
+
+    // A test mines blocks and changes pins
+    for _ in 0..2 {
+        chain.lock().unwrap().block += 5;
+        {
+            let pins = &mut kubo.lock().unwrap().pins;
+            pins.remove("QmSubgraph");
+            pins.insert("QmScratch".to_string());
+        }
+
+        let restored = checkpoints.restore("deployed").await.unwrap();
+        assert_eq!(restored.id, deployed.id);
+        assert_eq!(chain.lock().unwrap().block, 0);
+        assert_eq!(
+            kubo.lock().unwrap().pins.iter().collect::<Vec<_>>(),
+            vec!["QmSubgraph"]
+        );
+    }
+
+    // Restoring an older checkpoint discards newer ones
+    chain.lock().unwrap().block = 7;
+    let later = checkpoints.create(None).await.unwrap();
+    assert_eq!(checkpoints.list().len(), 2);
+    checkpoints.restore(&deployed.id).await.unwrap();
+    assert_eq!(chain.lock().unwrap().block, 0);
+    assert_eq!(checkpoints.list().len(), 1);
+    assert!(checkpoints.restore(&later.id).await.is_err());
+
+    checkpoints.delete("deployed").await.unwrap();
+    assert!(checkpoints.list().is_empty());
+    assert!(checkpoints.restore("deployed").await.is_err());
+}
+
+#[smol_potat::test]
+async fn test_failed_checkpoint_is_not_recorded() {
+    let (anvil_port, _chain) = common::anvil::start().await;
+
+    let mut checkpoints = Checkpoints::new();
+    checkpoints.add_anvil("anvil", AnvilService::new(31337, anvil_port));
+    // Nothing listens on port 1
+    checkpoints.add_ipfs("ipfs", IpfsService::new(1, 8080));
+
+    assert!(checkpoints.create(None).await.is_err());
+    assert!(checkpoints.list().is_empty());
+}
+
+#[test]
+fn test_daemon_publishes_checkpoint_actions() {
+    let config: GraphStackConfig =
+        serde_yaml::from_str(include_str!("../configs/graph-stack.yaml")).unwrap();
+    let schema = GraphTestDaemon::schema(&config).unwrap();
+
+    for name in [
+        "checkpoint",
+        "restore-checkpoint",
+        "list-checkpoints",
+        "delete-checkpoint",
+    ] {
+        let action = schema.actions.iter().find(|a| a.name == name).unwrap();
+        assert!(
+            action.returns_schema.is_some(),
+            "{} has no return schema",
+            name
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! The checked-in typed client matches the daemon's schema
+
+use graph_test_daemon::client as typed;
+use graph_test_daemon::daemon::GraphStackConfig;
+use graph_test_daemon::{AnvilAction, AnvilEvent, GraphContractsAction, GraphTestDaemon};
+use harness_core::codegen;
+
+fn schema() -> harness_core::protocol::DaemonSchema {
+    let config: GraphStackConfig =
+        serde_yaml::from_str(include_str!("../configs/graph-stack.yaml")).unwrap();
+    GraphTestDaemon::schema(&config).unwrap()
+}
+
+#[test]
+fn test_generated_client_is_up_to_date() {
+    let generated = codegen::generate(&schema(), "GraphTestClient");
+
+    assert!(
+        generated == include_str!("../src/client.rs"),
+        "src/client.rs is out of date; run `cargo xtask codegen`"
+    );
+}
+
+#[test]
+fn test_typed_actions_match_service_types() {
+    let action = typed::AnvilAction::MineBlocks {
+        count: 3,
+        interval_secs: None,
+    };
+    let value = serde_json::to_value(&action).unwrap();
+    assert!(matches!(
+        serde_json::from_value(value).unwrap(),
+        AnvilAction::MineBlocks {
+            count: 3,
+            interval_secs: None
+        }
+    ));
+
+    let value = serde_json::to_value(typed::GraphContractsAction::DeployAll).unwrap();
+    assert!(matches!(
+        serde_json::from_value(value).unwrap(),
+        GraphContractsAction::DeployAll
+    ));
+}
+
+#[test]
+fn test_typed_events_match_service_types() {
+    let event = AnvilEvent::BlockMined {
+        block_number: 7,
+        block_hash: "0xabc".to_string(),
+        timestamp: 1_700_000_000,
+    };
+    let typed: typed::AnvilEvent =
+        serde_json::from_value(serde_json::to_value(&event).unwrap()).unwrap();
+
+    assert_eq!(
+        typed,
+        typed::AnvilEvent::BlockMined {
+            block_number: 7,
+            block_hash: "0xabc".to_string(),
+            timestamp: 1_700_000_000,
+        }
+    );
+}
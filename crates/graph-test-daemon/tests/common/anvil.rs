@@ -0,0 +1,122 @@
+//! Anvil JSON-RPC stand-in
+
+use super::serve;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Chain state of the stand-in node
+#[derive(Default)]
+pub struct Chain {
+    pub block: u64,
+    pub time_offset: u64,
+    pub balances: HashMap<String, u128>,
+    pub snapshots: Vec<(u64, HashMap<String, u128>)>,
+    pub impersonating: Vec<String>,
+    pub calls: Vec<String>,
+}
+
+impl Chain {
+    fn handle(&mut self, method: &str, params: &[Value]) -> std::result::Result<Value, String> {
+        self.calls.push(method.to_string());
+        let hex = |n: u128| json!(format!("0x{:x}", n));
+        let parse = |v: &Value| {
+            u128::from_str_radix(v.as_str().unwrap().trim_start_matches("0x"), 16).unwrap()
+        };
+
+        Ok(match method {
+            "eth_blockNumber" => hex(self.block.into()),
+            "eth_getBlockByNumber" => {
+                let number = parse(&params[0]);
+                if number > self.block.into() {
+                    Value::Null
+                } else {
+                    json!({
+                        "number": hex(number),
+                        "hash": format!("0x{:064x}", number),
+                        "timestamp": hex(1_700_000_000 + number * 12),
+                    })
+                }
+            }
+            "eth_getBalance" => {
+                let address = params[0].as_str().unwrap().to_lowercase();
+                hex(self.balances.get(&address).copied().unwrap_or_default())
+            }
+            "anvil_mine" => {
+                self.block += parse(&params[0]) as u64;
+                Value::Null
+            }
+            "anvil_setBalance" => {
+                let address = params[0].as_str().unwrap().to_lowercase();
+                self.balances.insert(address, parse(&params[1]));
+                Value::Null
+            }
+            "anvil_reset" => {
+                let forking = &params[0]["forking"];
+                if forking["jsonRpcUrl"].as_str().is_none() {
+                    return Err("missing fork url".to_string());
+                }
+                self.block = forking["blockNumber"].as_u64().unwrap_or(100);
+                Value::Bool(true)
+            }
+            "evm_snapshot" => {
+                self.snapshots.push((self.block, self.balances.clone()));
+                hex(self.snapshots.len() as u128 - 1)
+            }
+            "evm_revert" => {
+                let id = parse(&params[0]) as usize;
+                if id >= self.snapshots.len() {
+                    return Ok(Value::Bool(false));
+                }
+                let (block, balances) = self.snapshots[id].clone();
+                self.snapshots.truncate(id);
+                self.block = block;
+                self.balances = balances;
+                Value::Bool(true)
+            }
+            "evm_increaseTime" => {
+                self.time_offset += params[0].as_u64().unwrap();
+                hex(self.time_offset.into())
+            }
+            "anvil_impersonateAccount" => {
+                self.impersonating
+                    .push(params[0].as_str().unwrap().to_string());
+                Value::Null
+            }
+            "anvil_stopImpersonatingAccount" => {
+                let address = params[0].as_str().unwrap();
+                self.impersonating.retain(|a| a != address);
+                Value::Null
+            }
+            other => return Err(format!("Method not found: {}", other)),
+        })
+    }
+}
+
+/// Start an anvil stand-in, returning its port and chain state
+pub async fn start() -> (u16, Arc<Mutex<Chain>>) {
+    let chain = Arc::new(Mutex::new(Chain::default()));
+    let state = chain.clone();
+
+    let port = serve(move |request| {
+        let request: Value = serde_json::from_slice(&request.body).unwrap();
+        let params = request["params"].as_array().cloned().unwrap_or_default();
+        let outcome = state
+            .lock()
+            .unwrap()
+            .handle(request["method"].as_str().unwrap(), &params);
+
+        let response = match outcome {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+            Err(message) => json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32601, "message": message },
+            }),
+        };
+        (200, response)
+    })
+    .await;
+
+    (port, chain)
+}
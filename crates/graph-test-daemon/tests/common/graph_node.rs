@@ -0,0 +1,157 @@
+//! Graph Node admin, status and query API stand-in
+//!
+//! All three APIs are served on one port: JSON-RPC on `/`, the index node
+//! status API on `/graphql` and subgraph queries on `/subgraphs/name/{name}`.
+
+use super::{Request, serve};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// A deployment being indexed
+#[derive(Debug, Clone, Default)]
+pub struct Deployment {
+    pub start_block: u64,
+    pub latest_block: Option<u64>,
+    pub fatal_error: Option<String>,
+    pub non_fatal_errors: Vec<String>,
+}
+
+/// State of the stand-in node
+#[derive(Debug, Default)]
+pub struct Node {
+    pub chain_head: u64,
+    /// Blocks a deployment advances per status request
+    pub blocks_per_poll: u64,
+    /// Subgraph names and their current deployment
+    pub subgraphs: BTreeMap<String, Option<String>>,
+    pub deployments: BTreeMap<String, Deployment>,
+    pub calls: Vec<String>,
+}
+
+impl Node {
+    fn handle(&mut self, request: Request) -> (u16, Value) {
+        let body: Value = serde_json::from_slice(&request.body).unwrap_or_default();
+        match request.route() {
+            "/" => self.admin(&body),
+            "/graphql" => (200, self.status(&body)),
+            route => match route.strip_prefix("/subgraphs/name/") {
+                Some(name) => self.query(name),
+                None => (404, json!({ "message": "not found" })),
+            },
+        }
+    }
+
+    fn admin(&mut self, body: &Value) -> (u16, Value) {
+        let method = body["method"].as_str().unwrap_or_default().to_string();
+        let params = &body["params"];
+        let name = params["name"].as_str().unwrap_or_default().to_string();
+        self.calls.push(method.clone());
+
+        let result = match method.as_str() {
+            "subgraph_create" if self.subgraphs.contains_key(&name) => {
+                Err(format!("subgraph name already exists: {}", name))
+            }
+            "subgraph_create" => {
+                self.subgraphs.insert(name, None);
+                Ok(Value::Null)
+            }
+            "subgraph_deploy" => match self.subgraphs.get_mut(&name) {
+                Some(current) => {
+                    let hash = params["ipfs_hash"].as_str().unwrap().to_string();
+                    *current = Some(hash.clone());
+                    self.deployments.entry(hash).or_default();
+                    Ok(json!({
+                        "playground": format!("http://localhost:8000/subgraphs/name/{}/graphql", name),
+                        "queries": format!("http://localhost:8000/subgraphs/name/{}", name),
+                        "subscriptions": format!("ws://localhost:8001/subgraphs/name/{}", name),
+                    }))
+                }
+                None => Err(format!("subgraph name not found: {}", name)),
+            },
+            "subgraph_remove" => match self.subgraphs.remove(&name) {
+                Some(_) => Ok(Value::Null),
+                None => Err(format!("subgraph name not found: {}", name)),
+            },
+            _ => Err(format!("method not found: {}", method)),
+        };
+
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": body["id"], "result": result }),
+            Err(message) => json!({
+                "jsonrpc": "2.0",
+                "id": body["id"],
+                "error": { "code": -32603, "message": message },
+            }),
+        };
+        (200, response)
+    }
+
+    fn status(&mut self, body: &Value) -> Value {
+        let Some(hash) = body["variables"]["deployment"].as_str() else {
+            return json!({ "errors": [{ "message": "missing deployment" }] });
+        };
+        let head = self.chain_head;
+        let step = self.blocks_per_poll;
+        let Some(deployment) = self.deployments.get_mut(hash) else {
+            return json!({ "data": { "indexingStatuses": [] } });
+        };
+
+        let latest = match deployment.latest_block {
+            Some(latest) => (latest + step).min(head),
+            None => deployment.start_block,
+        };
+        deployment.latest_block = Some(latest);
+        let failed = deployment.fatal_error.as_ref();
+        let health = match (failed, deployment.non_fatal_errors.is_empty()) {
+            (Some(_), _) => "failed",
+            (None, false) => "unhealthy",
+            (None, true) => "healthy",
+        };
+        let non_fatal_errors: Vec<Value> = deployment
+            .non_fatal_errors
+            .iter()
+            .map(|message| json!({ "message": message }))
+            .collect();
+
+        json!({
+            "data": {
+                "indexingStatuses": [{
+                    "subgraph": hash,
+                    "synced": failed.is_none() && latest >= head,
+                    "health": health,
+                    "fatalError": failed.map(|message| json!({ "message": message })),
+                    "nonFatalErrors": non_fatal_errors,
+                    "chains": [{
+                        "network": "mainnet",
+                        "chainHeadBlock": { "number": head.to_string() },
+                        "earliestBlock": { "number": deployment.start_block.to_string() },
+                        "latestBlock": { "number": latest.to_string() },
+                    }],
+                }],
+            },
+        })
+    }
+
+    fn query(&self, name: &str) -> (u16, Value) {
+        match self.subgraphs.get(name) {
+            Some(Some(hash)) => (200, json!({ "data": { "_meta": { "deployment": hash } } })),
+            _ => (
+                404,
+                json!({ "errors": [{ "message": format!("Subgraph `{}` not found", name) }] }),
+            ),
+        }
+    }
+}
+
+/// Start a graph-node stand-in, returning its port and state
+pub async fn start() -> (u16, Arc<Mutex<Node>>) {
+    let node = Arc::new(Mutex::new(Node {
+        chain_head: 100,
+        blocks_per_poll: 25,
+        ..Default::default()
+    }));
+    let state = node.clone();
+    let port = serve(move |request| state.lock().unwrap().handle(request)).await;
+    (port, node)
+}
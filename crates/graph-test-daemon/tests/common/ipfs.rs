@@ -0,0 +1,214 @@
+//! Kubo RPC API stand-in
+//!
+//! CIDs are fake but deterministic: `Qm` followed by a hash of the content,
+//! or of the entries for directories.
+
+use super::{Request, serve_raw};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+/// Stored object
+#[derive(Debug, Clone)]
+pub enum Object {
+    File(Vec<u8>),
+    /// Entry names and their CIDs
+    Directory(BTreeMap<String, String>),
+}
+
+/// State of the stand-in node
+#[derive(Debug, Default)]
+pub struct Kubo {
+    /// Recursively pinned CIDs
+    pub pins: BTreeSet<String>,
+    pub objects: BTreeMap<String, Object>,
+}
+
+/// Start a Kubo stand-in, returning its API port and state
+pub async fn start() -> (u16, Arc<Mutex<Kubo>>) {
+    let kubo = Arc::new(Mutex::new(Kubo::default()));
+    let state = kubo.clone();
+    let port = serve_raw(move |request| state.lock().unwrap().handle(request)).await;
+    (port, kubo)
+}
+
+impl Kubo {
+    /// Content of a file by CID or `cid/path`
+    pub fn cat(&self, path: &str) -> Option<Vec<u8>> {
+        let path = path.trim_start_matches("/ipfs/");
+        let mut parts = path.split('/');
+        let mut cid = parts.next()?.to_string();
+        for part in parts {
+            match self.objects.get(&cid)? {
+                Object::Directory(entries) => cid = entries.get(part)?.clone(),
+                Object::File(_) => return None,
+            }
+        }
+        match self.objects.get(&cid)? {
+            Object::File(data) => Some(data.clone()),
+            Object::Directory(_) => None,
+        }
+    }
+
+    fn handle(&mut self, request: Request) -> (u16, Vec<u8>) {
+        let arg = request.query("arg").map(|arg| decode(&arg));
+        match (request.route(), arg) {
+            ("/api/v0/add", _) => self.add(&request),
+            ("/api/v0/cat", Some(path)) => match self.cat(&path) {
+                Some(data) => (200, data),
+                None => error(format!("{}: no link named in path", path)),
+            },
+            ("/api/v0/pin/ls", _) => {
+                let keys: serde_json::Map<String, Value> = self
+                    .pins
+                    .iter()
+                    .map(|cid| (cid.clone(), json!({ "Type": "recursive" })))
+                    .collect();
+                (200, json!({ "Keys": keys }).to_string().into_bytes())
+            }
+            ("/api/v0/pin/add", Some(cid)) => {
+                self.pins.insert(cid.clone());
+                (200, json!({ "Pins": [cid] }).to_string().into_bytes())
+            }
+            ("/api/v0/pin/rm", Some(cid)) => {
+                if self.pins.remove(&cid) {
+                    (200, json!({ "Pins": [cid] }).to_string().into_bytes())
+                } else {
+                    error("not pinned or pinned indirectly".to_string())
+                }
+            }
+            (route, _) => (
+                404,
+                json!({ "Message": format!("unknown endpoint {}", route) })
+                    .to_string()
+                    .into_bytes(),
+            ),
+        }
+    }
+
+    /// Store multipart parts, answering with one JSON line per entry,
+    /// parents last
+    fn add(&mut self, request: &Request) -> (u16, Vec<u8>) {
+        let parts = parse_multipart(&request.body);
+        let pin = request.query("pin").as_deref() != Some("false");
+
+        let mut files = BTreeMap::new();
+        let mut dirs = BTreeSet::new();
+        for (name, content_type, data) in parts {
+            if content_type == "application/x-directory" {
+                dirs.insert(name);
+            } else {
+                files.insert(name, data);
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut cids: BTreeMap<String, String> = BTreeMap::new();
+        for (name, data) in &files {
+            let cid = fake_cid(data);
+            lines.push(json!({ "Name": name, "Hash": cid, "Size": data.len().to_string() }));
+            self.objects.insert(cid.clone(), Object::File(data.clone()));
+            cids.insert(name.clone(), cid);
+        }
+        // Deepest directories first, so their CIDs exist for their parents
+        let mut dirs: Vec<_> = dirs.into_iter().collect();
+        dirs.sort_by_key(|dir| std::cmp::Reverse(dir.matches('/').count()));
+        for dir in dirs {
+            let prefix = format!("{}/", dir);
+            let entries: BTreeMap<String, String> = cids
+                .iter()
+                .filter_map(|(name, cid)| {
+                    let child = name.strip_prefix(&prefix)?;
+                    (!child.contains('/')).then(|| (child.to_string(), cid.clone()))
+                })
+                .collect();
+            let cid = fake_cid(format!("{:?}", entries).as_bytes());
+            lines.push(json!({ "Name": dir, "Hash": cid, "Size": "0" }));
+            self.objects.insert(cid.clone(), Object::Directory(entries));
+            cids.insert(dir, cid);
+        }
+
+        if pin && let Some(last) = lines.last() {
+            self.pins.insert(last["Hash"].as_str().unwrap().to_string());
+        }
+        let body: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        (200, body.into_bytes())
+    }
+}
+
+fn error(message: String) -> (u16, Vec<u8>) {
+    (
+        500,
+        json!({ "Message": message, "Code": 0 })
+            .to_string()
+            .into_bytes(),
+    )
+}
+
+fn fake_cid(data: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    format!("Qm{:016x}", hasher.finish())
+}
+
+/// Parts of a `multipart/form-data` body as (filename, content type, data)
+fn parse_multipart(body: &[u8]) -> Vec<(String, String, Vec<u8>)> {
+    let Some(end) = body.windows(2).position(|w| w == b"\r\n") else {
+        return Vec::new();
+    };
+    let delimiter = [b"\r\n", &body[..end]].concat();
+
+    let mut parts = Vec::new();
+    let mut rest = &body[end..];
+    while let Some(start) = rest.windows(4).position(|w| w == b"\r\n\r\n") {
+        let head = String::from_utf8_lossy(&rest[..start]).to_string();
+        let data = &rest[start + 4..];
+        let Some(len) = data
+            .windows(delimiter.len())
+            .position(|w| w == delimiter.as_slice())
+        else {
+            break;
+        };
+
+        let header = |name: &str| {
+            head.lines().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case(name)
+                    .then(|| value.trim().to_string())
+            })
+        };
+        let filename = header("content-disposition")
+            .and_then(|value| {
+                let (_, filename) = value.split_once("filename=\"")?;
+                Some(decode(filename.trim_end_matches('"')))
+            })
+            .unwrap_or_default();
+        let content_type = header("content-type").unwrap_or_default();
+        parts.push((filename, content_type, data[..len].to_vec()));
+
+        rest = &data[len + delimiter.len()..];
+    }
+    parts
+}
+
+/// Percent-decode a query value or filename
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,114 @@
+//! In-process stand-ins for the HTTP APIs of stack services
+
+#![allow(dead_code)]
+
+pub mod anvil;
+pub mod graph_node;
+pub mod ipfs;
+
+use serde_json::Value;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use smol::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+
+/// Request received by a stand-in server
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// Path including the query string
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of a query parameter
+    pub fn query(&self, name: &str) -> Option<String> {
+        let (_, query) = self.path.split_once('?')?;
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    }
+
+    /// Path without the query string
+    pub fn route(&self) -> &str {
+        self.path.split('?').next().unwrap_or_default()
+    }
+}
+
+/// Serve HTTP on an ephemeral localhost port, answering every request with
+/// the handler's status and JSON body
+pub async fn serve<F>(handler: F) -> u16
+where
+    F: Fn(Request) -> (u16, Value) + Send + Sync + 'static,
+{
+    serve_raw(move |request| {
+        let (status, body) = handler(request);
+        (status, serde_json::to_vec(&body).unwrap())
+    })
+    .await
+}
+
+/// Like [`serve`], for handlers that write the response body themselves
+pub async fn serve_raw<F>(handler: F) -> u16
+where
+    F: Fn(Request) -> (u16, Vec<u8>) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handler = Arc::new(handler);
+
+    smol::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            smol::spawn(async move { respond(stream, &*handler).await }).detach();
+        }
+    })
+    .detach();
+
+    port
+}
+
+async fn respond<F>(mut stream: TcpStream, handler: &F)
+where
+    F: Fn(Request) -> (u16, Vec<u8>),
+{
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 4096];
+    let (head, body) = loop {
+        let n = stream.read(&mut buf).await.unwrap();
+        raw.extend_from_slice(&buf[..n]);
+        let Some(end) = raw.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let head = String::from_utf8_lossy(&raw[..end]).to_string();
+        let length: usize = head
+            .lines()
+            .find_map(|l| {
+                let (name, value) = l.split_once(':')?;
+                name.eq_ignore_ascii_case("content-length")
+                    .then(|| value.trim().parse().unwrap())
+            })
+            .unwrap_or(0);
+        if raw.len() >= end + 4 + length {
+            break (head, raw[end + 4..end + 4 + length].to_vec());
+        }
+    };
+
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let request = Request {
+        method: request_line.next().unwrap_or_default().to_string(),
+        path: request_line.next().unwrap_or_default().to_string(),
+        body,
+    };
+
+    let (status, body) = handler(request);
+    let head = format!(
+        "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+        status,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(&body).await.unwrap();
+}
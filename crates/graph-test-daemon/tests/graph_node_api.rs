@@ -0,0 +1,233 @@
+//! GraphNodeService against an in-process stand-in for graph-node's admin,
+//! index node status and query APIs
+
+mod common;
+
+use futures::StreamExt;
+use graph_test_daemon::{GraphNodeAction, GraphNodeEvent, GraphNodeService};
+use harness_core::prelude::Service;
+use std::time::Duration;
+
+const MANIFEST: &str = "QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco";
+
+fn graph_node(port: u16) -> GraphNodeService {
+    GraphNodeService::new("127.0.0.1".to_string())
+        .with_ports(port, port, port)
+        .with_poll_interval(Duration::from_millis(10))
+}
+
+async fn run(service: &GraphNodeService, action: GraphNodeAction) -> Vec<GraphNodeEvent> {
+    let events = service.dispatch_action(action).await.unwrap();
+    events.collect().await
+}
+
+fn deploy(name: &str) -> GraphNodeAction {
+    GraphNodeAction::DeploySubgraph {
+        name: name.to_string(),
+        ipfs_hash: MANIFEST.to_string(),
+        version_label: Some("v0.0.1".to_string()),
+    }
+}
+
+#[smol_potat::test]
+async fn test_deploy_reports_sync_progress() {
+    let (port, node) = common::graph_node::start().await;
+    let service = graph_node(port);
+
+    let events = run(&service, deploy("test/subgraph")).await;
+
+    assert!(matches!(
+        &events[0],
+        GraphNodeEvent::DeploymentStarted { deployment_id, .. } if deployment_id == MANIFEST
+    ));
+    let progress: Vec<u8> = events
+        .iter()
+        .filter_map(|e| match e {
+            GraphNodeEvent::DeploymentProgress { percent, .. } => Some(*percent),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(progress, vec![0, 25, 50, 75, 100]);
+    assert!(matches!(
+        events.last().unwrap(),
+        GraphNodeEvent::DeploymentCompleted { deployment_id, endpoints }
+            if deployment_id == MANIFEST
+                && endpoints == &[
+                    "http://localhost:8000/subgraphs/name/test/subgraph".to_string(),
+                    "ws://localhost:8001/subgraphs/name/test/subgraph".to_string(),
+                ]
+    ));
+    assert_eq!(
+        node.lock().unwrap().calls,
+        vec!["subgraph_create", "subgraph_deploy"]
+    );
+
+    // Redeploying to an existing name is fine
+    let events = run(&service, deploy("test/subgraph")).await;
+    assert!(matches!(
+        events.last().unwrap(),
+        GraphNodeEvent::DeploymentCompleted { .. }
+    ));
+}
+
+#[smol_potat::test]
+async fn test_failed_deployment() {
+    let (port, node) = common::graph_node::start().await;
+    node.lock().unwrap().deployments.insert(
+        MANIFEST.to_string(),
+        common::graph_node::Deployment {
+            fatal_error: Some("Mapping aborted".to_string()),
+            ..Default::default()
+        },
+    );
+
+    let events = run(&graph_node(port), deploy("broken")).await;
+    assert!(matches!(
+        events.last().unwrap(),
+        GraphNodeEvent::Error { message } if message.contains("Mapping aborted")
+    ));
+}
+
+#[smol_potat::test]
+async fn test_sync_timeout() {
+    let (port, node) = common::graph_node::start().await;
+    node.lock().unwrap().blocks_per_poll = 0;
+    let service = graph_node(port).with_sync_timeout(Duration::from_millis(50));
+
+    let events = run(&service, deploy("stuck")).await;
+    assert!(matches!(
+        events.last().unwrap(),
+        GraphNodeEvent::Error { message } if message.contains("did not reach the chain head")
+    ));
+}
+
+fn wait_for_sync(block: Option<u64>, timeout: Option<u64>) -> GraphNodeAction {
+    GraphNodeAction::WaitForSync {
+        deployment: MANIFEST.to_string(),
+        block,
+        timeout,
+    }
+}
+
+#[smol_potat::test]
+async fn test_wait_for_block() {
+    let (port, node) = common::graph_node::start().await;
+    node.lock().unwrap().deployments.insert(
+        MANIFEST.to_string(),
+        common::graph_node::Deployment {
+            start_block: 10,
+            ..Default::default()
+        },
+    );
+    let service = graph_node(port);
+
+    let events = run(&service, wait_for_sync(Some(50), None)).await;
+    let progress: Vec<u8> = events
+        .iter()
+        .filter_map(|e| match e {
+            GraphNodeEvent::DeploymentProgress { percent, .. } => Some(*percent),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(progress, vec![0, 62, 100]);
+    assert!(matches!(
+        events.last().unwrap(),
+        GraphNodeEvent::Synced { deployment_id, block_number: 60 } if deployment_id == MANIFEST
+    ));
+
+    // Then on to the chain head
+    let events = run(&service, wait_for_sync(None, None)).await;
+    assert!(matches!(
+        events.last().unwrap(),
+        GraphNodeEvent::Synced {
+            block_number: 100,
+            ..
+        }
+    ));
+}
+
+#[smol_potat::test]
+async fn test_wait_for_sync_reports_diagnostics() {
+    let (port, node) = common::graph_node::start().await;
+    let service = graph_node(port);
+
+    // Unknown deployment
+    let events = run(&service, wait_for_sync(None, Some(0))).await;
+    assert!(matches!(
+        &events[..],
+        [GraphNodeEvent::Error { message }] if message.contains("no indexing status")
+    ));
+
+    // Stuck below the target block
+    node.lock().unwrap().blocks_per_poll = 0;
+    node.lock()
+        .unwrap()
+        .deployments
+        .insert(MANIFEST.to_string(), Default::default());
+    let events = run(&service, wait_for_sync(Some(10), Some(0))).await;
+    assert!(matches!(
+        events.last().unwrap(),
+        GraphNodeEvent::Error { message }
+            if message.contains("did not reach block 10") && message.contains("last indexed block: 0")
+    ));
+
+    // Unhealthy deployments fail right away
+    node.lock()
+        .unwrap()
+        .deployments
+        .get_mut(MANIFEST)
+        .unwrap()
+        .non_fatal_errors
+        .push("Store error".to_string());
+    let events = run(&service, wait_for_sync(None, Some(60))).await;
+    assert!(matches!(
+        &events[..],
+        [GraphNodeEvent::Error { message }]
+            if message.contains("unhealthy") && message.contains("health: unhealthy") && message.contains("Store error")
+    ));
+}
+
+#[smol_potat::test]
+async fn test_query_and_remove() {
+    let (port, _node) = common::graph_node::start().await;
+    let service = graph_node(port);
+    run(&service, deploy("test/subgraph")).await;
+
+    let events = run(
+        &service,
+        GraphNodeAction::QuerySubgraph {
+            subgraph_name: "test/subgraph".to_string(),
+            query: "{ _meta { deployment } }".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [GraphNodeEvent::QueryResult { data }] if data["data"]["_meta"]["deployment"] == MANIFEST
+    ));
+
+    let events = run(
+        &service,
+        GraphNodeAction::RemoveSubgraph {
+            name: "test/subgraph".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [GraphNodeEvent::SubgraphRemoved { name }] if name == "test/subgraph"
+    ));
+
+    let events = run(
+        &service,
+        GraphNodeAction::QuerySubgraph {
+            subgraph_name: "test/subgraph".to_string(),
+            query: "{ _meta { deployment } }".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [GraphNodeEvent::Error { message }] if message.contains("not found")
+    ));
+}
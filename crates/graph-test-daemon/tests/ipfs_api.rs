@@ -0,0 +1,164 @@
+//! IpfsService against an in-process stand-in for Kubo's RPC API
+
+mod common;
+
+use futures::StreamExt;
+use graph_test_daemon::{IpfsAction, IpfsEvent, IpfsService};
+use harness_core::prelude::Service;
+
+async fn run(service: &IpfsService, action: IpfsAction) -> Vec<IpfsEvent> {
+    let events = service.dispatch_action(action).await.unwrap();
+    events.collect().await
+}
+
+#[smol_potat::test]
+async fn test_add_cat_and_pins() {
+    let (port, kubo) = common::ipfs::start().await;
+    let service = IpfsService::new(port, 8080);
+
+    let events = run(
+        &service,
+        IpfsAction::AddContent {
+            content: "Hello IPFS".to_string(),
+            pin: None,
+        },
+    )
+    .await;
+    let [IpfsEvent::ContentAdded { hash, size: 10 }] = &events[..] else {
+        panic!("unexpected events {:?}", events);
+    };
+    assert!(kubo.lock().unwrap().pins.contains(hash));
+
+    let events = run(&service, IpfsAction::Cat { hash: hash.clone() }).await;
+    assert!(matches!(
+        &events[..],
+        [IpfsEvent::ContentRetrieved { content, .. }] if content == "Hello IPFS"
+    ));
+
+    let events = run(&service, IpfsAction::Unpin { hash: hash.clone() }).await;
+    assert!(matches!(&events[..], [IpfsEvent::Unpinned { .. }]));
+    assert!(kubo.lock().unwrap().pins.is_empty());
+
+    let events = run(&service, IpfsAction::Unpin { hash: hash.clone() }).await;
+    assert!(matches!(
+        &events[..],
+        [IpfsEvent::Error { message }] if message.contains("not pinned")
+    ));
+
+    let events = run(&service, IpfsAction::Pin { hash: hash.clone() }).await;
+    assert!(matches!(&events[..], [IpfsEvent::Pinned { .. }]));
+    assert!(kubo.lock().unwrap().pins.contains(hash));
+
+    let events = run(
+        &service,
+        IpfsAction::Cat {
+            hash: "QmMissing".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(&events[..], [IpfsEvent::Error { .. }]));
+}
+
+#[smol_potat::test]
+async fn test_add_subgraph_build_directory() {
+    let (port, kubo) = common::ipfs::start().await;
+    let service = IpfsService::new(port, 8080);
+
+    let build = tempfile::tempdir().unwrap();
+    let dir = build.path().join("build");
+    std::fs::create_dir_all(dir.join("Contract/abis")).unwrap();
+    std::fs::write(dir.join("schema.graphql"), "type Token @entity { id: ID! }").unwrap();
+    std::fs::write(dir.join("Contract/Contract.wasm"), b"\0asm").unwrap();
+    std::fs::write(dir.join("Contract/abis/Contract.json"), "[]").unwrap();
+    std::fs::write(
+        dir.join("subgraph.yaml"),
+        "specVersion: 0.0.5\n\
+         schema:\n  file: schema.graphql\n\
+         dataSources:\n  - mapping:\n      file: Contract/Contract.wasm\n      \
+         abis:\n        - name: Contract\n          file: Contract/abis/Contract.json\n",
+    )
+    .unwrap();
+
+    let events = run(
+        &service,
+        IpfsAction::AddDirectory {
+            path: dir.to_string_lossy().to_string(),
+            pin: None,
+        },
+    )
+    .await;
+    let [
+        IpfsEvent::DirectoryAdded {
+            hash,
+            entries,
+            manifest: Some(manifest),
+        },
+    ] = &events[..]
+    else {
+        panic!("unexpected events {:?}", events);
+    };
+
+    let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+    assert!(names.contains(&"build/Contract/abis/Contract.json"));
+    assert_eq!(entries.last().unwrap().name, "build");
+    assert_eq!(&entries.last().unwrap().hash, hash);
+
+    let kubo = kubo.lock().unwrap();
+    assert!(kubo.pins.contains(hash) && kubo.pins.contains(manifest));
+    assert_eq!(
+        kubo.cat(&format!("{}/Contract/Contract.wasm", hash))
+            .unwrap(),
+        b"\0asm"
+    );
+
+    // The uploaded manifest links into the directory
+    let uploaded: serde_yaml::Value = serde_yaml::from_slice(&kubo.cat(manifest).unwrap()).unwrap();
+    let link = |value: &serde_yaml::Value| value["/"].as_str().unwrap().to_string();
+    assert_eq!(
+        link(&uploaded["schema"]["file"]),
+        format!("/ipfs/{}/schema.graphql", hash)
+    );
+    let mapping = &uploaded["dataSources"][0]["mapping"];
+    assert_eq!(
+        link(&mapping["abis"][0]["file"]),
+        format!("/ipfs/{}/Contract/abis/Contract.json", hash)
+    );
+    for value in [&uploaded["schema"]["file"], &mapping["file"]] {
+        let path = link(value);
+        assert!(kubo.cat(&path).is_some(), "{} does not resolve", path);
+    }
+}
+
+#[smol_potat::test]
+async fn test_add_directory_without_manifest() {
+    let (port, _kubo) = common::ipfs::start().await;
+    let service = IpfsService::new(port, 8080);
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("data.txt"), "data").unwrap();
+    let events = run(
+        &service,
+        IpfsAction::AddDirectory {
+            path: dir.path().to_string_lossy().to_string(),
+            pin: Some(false),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [IpfsEvent::DirectoryAdded { entries, manifest: None, .. }] if entries.len() == 2
+    ));
+
+    let events = run(
+        &service,
+        IpfsAction::AddDirectory {
+            path: dir.path().join("missing").to_string_lossy().to_string(),
+            pin: None,
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [IpfsEvent::Error { message }] if message.contains("Failed to read")
+    ));
+}
@@ -0,0 +1,218 @@
+//! PostgresService against a throwaway cluster, when the PostgreSQL server
+//! binaries are installed and the tests don't run as root
+
+use futures::StreamExt;
+use graph_test_daemon::{PostgresAction, PostgresEvent, PostgresService};
+use harness_core::prelude::Service;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+async fn run(service: &PostgresService, action: PostgresAction) -> Vec<PostgresEvent> {
+    let events = service.dispatch_action(action).await.unwrap();
+    events.collect().await
+}
+
+/// A cluster in a temporary directory, stopped on drop
+struct Cluster {
+    port: u16,
+    server: Child,
+    _dir: tempfile::TempDir,
+}
+
+impl Cluster {
+    fn start(initdb: &Path, postgres: &Path) -> Option<Self> {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join("data");
+        let initialized = Command::new(initdb)
+            .args(["-U", "postgres", "-A", "trust", "--no-sync", "-D"])
+            .arg(&data)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .ok()?
+            .success();
+        if !initialized {
+            // initdb refuses to run as root
+            return None;
+        }
+
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let server = Command::new(postgres)
+            .arg("-D")
+            .arg(&data)
+            .args(["-p", &port.to_string(), "-c", "listen_addresses=127.0.0.1"])
+            .arg("-k")
+            .arg(dir.path())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        Some(Self {
+            port,
+            server,
+            _dir: dir,
+        })
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        let _ = self.server.kill();
+        let _ = self.server.wait();
+    }
+}
+
+#[smol_potat::test]
+async fn test_real_postgres_when_installed() {
+    let (Some(initdb), Some(postgres)) = (which("initdb"), which("postgres")) else {
+        eprintln!("PostgreSQL server not installed; skipping");
+        return;
+    };
+    let Some(cluster) = Cluster::start(&initdb, &postgres) else {
+        eprintln!("initdb failed (running as root?); skipping");
+        return;
+    };
+
+    let service = PostgresService::new("graph-node".to_string(), cluster.port);
+    let mut ready = false;
+    for _ in 0..50 {
+        let events = run(
+            &service,
+            PostgresAction::ExecuteQuery {
+                query: "SELECT 1".to_string(),
+                database: Some("postgres".to_string()),
+            },
+        )
+        .await;
+        if matches!(&events[..], [PostgresEvent::QueryExecuted { .. }]) {
+            ready = true;
+            break;
+        }
+        smol::Timer::after(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(ready, "postgres did not start");
+
+    // A database the way graph-node wants it
+    let events = run(
+        &service,
+        PostgresAction::CreateDatabase {
+            name: "graph-node".to_string(),
+            locale: None,
+            extensions: None,
+        },
+    )
+    .await;
+    assert!(
+        matches!(&events[..], [PostgresEvent::DatabaseCreated { name }] if name == "graph-node"),
+        "{:?}",
+        events
+    );
+    let events = run(
+        &service,
+        PostgresAction::ExecuteQuery {
+            query: "SELECT datcollate, \
+                    (SELECT string_agg(extname, ',' ORDER BY extname) FROM pg_extension) AS extensions \
+                    FROM pg_database WHERE datname = current_database()"
+                .to_string(),
+            database: None,
+        },
+    )
+    .await;
+    assert!(
+        matches!(&events[..], [PostgresEvent::QueryExecuted { rows, .. }]
+            if rows[0]["datcollate"] == "C"
+                && rows[0]["extensions"] == "btree_gist,pg_trgm,plpgsql,postgres_fdw"),
+        "{:?}",
+        events
+    );
+
+    let query = |query: &str| PostgresAction::ExecuteQuery {
+        query: query.to_string(),
+        database: None,
+    };
+    run(
+        &service,
+        query("CREATE TABLE tokens (id int PRIMARY KEY, symbol text)"),
+    )
+    .await;
+    let events = run(
+        &service,
+        query("INSERT INTO tokens VALUES (1, 'GRT'), (2, NULL)"),
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [PostgresEvent::QueryExecuted { rows_affected: 2, rows }] if rows.is_empty()
+    ));
+
+    let dir = tempfile::tempdir().unwrap();
+    let backup = dir.path().join("graph-node.sql");
+    let events = run(
+        &service,
+        PostgresAction::Backup {
+            backup_path: backup.to_string_lossy().to_string(),
+            database: None,
+        },
+    )
+    .await;
+    let [PostgresEvent::BackupCompleted { size_bytes, .. }] = &events[..] else {
+        panic!("unexpected events {:?}", events);
+    };
+    assert_eq!(*size_bytes, std::fs::metadata(&backup).unwrap().len());
+
+    run(&service, query("DELETE FROM tokens")).await;
+    let events = run(
+        &service,
+        PostgresAction::Restore {
+            backup_path: backup.to_string_lossy().to_string(),
+            database: None,
+        },
+    )
+    .await;
+    assert!(
+        matches!(&events[..], [PostgresEvent::Restored { .. }]),
+        "{:?}",
+        events
+    );
+
+    let events = run(&service, query("SELECT id, symbol FROM tokens ORDER BY id")).await;
+    assert!(
+        matches!(&events[..], [PostgresEvent::QueryExecuted { rows_affected: 2, rows }]
+        if rows == &[
+            serde_json::json!({ "id": "1", "symbol": "GRT" }),
+            serde_json::json!({ "id": "2", "symbol": null }),
+        ]),
+        "{:?}",
+        events
+    );
+
+    let events = run(&service, query("SELECT * FROM missing")).await;
+    assert!(matches!(
+        &events[..],
+        [PostgresEvent::Error { message }] if message.contains("does not exist")
+    ));
+
+    let events = run(
+        &service,
+        PostgresAction::DropDatabase {
+            name: "graph-node".to_string(),
+        },
+    )
+    .await;
+    assert!(matches!(
+        &events[..],
+        [PostgresEvent::DatabaseDropped { .. }]
+    ));
+}
+
+fn which(binary: &str) -> Option<PathBuf> {
+    std::env::var_os("PATH")
+        .into_iter()
+        .flat_map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .map(|dir| dir.join(binary))
+        .find(|path| path.is_file())
+}
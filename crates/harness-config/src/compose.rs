@@ -0,0 +1,343 @@
+//! Import docker compose files as service configurations
+//!
+//! Every compose service becomes a `type: compose` service that the
+//! orchestrator starts on its own. `depends_on` is mapped to dependencies and
+//! `healthcheck` to a health check running the same test in the container
+//! with `docker compose exec`.
+
+use crate::{
+    Config, ConfigError, HealthCheck, HealthCheckType, Network, Result, Service, ServiceType,
+};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Network imported services are attached to
+pub const IMPORT_NETWORK: &str = "local";
+
+/// The parts of a compose file the importer maps
+#[derive(Debug, Deserialize)]
+struct ComposeFile {
+    /// Project name
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    services: BTreeMap<String, ComposeService>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ComposeService {
+    depends_on: DependsOn,
+    healthcheck: Option<ComposeHealthcheck>,
+}
+
+/// `depends_on` in its short (list) or long (map) syntax
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum DependsOn {
+    List(Vec<String>),
+    Map(BTreeMap<String, serde_yaml::Value>),
+}
+
+impl Default for DependsOn {
+    fn default() -> Self {
+        DependsOn::List(Vec::new())
+    }
+}
+
+impl DependsOn {
+    fn names(&self) -> Vec<String> {
+        match self {
+            DependsOn::List(names) => names.clone(),
+            DependsOn::Map(services) => services.keys().cloned().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ComposeHealthcheck {
+    test: Option<HealthcheckTest>,
+    interval: Option<String>,
+    timeout: Option<String>,
+    retries: Option<u32>,
+    start_period: Option<String>,
+    disable: bool,
+}
+
+/// `test` as a shell string or an exec-form list
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum HealthcheckTest {
+    Shell(String),
+    List(Vec<String>),
+}
+
+/// Import a compose file
+///
+/// The project name defaults to the file's top-level `name`.
+pub fn import_file(path: impl AsRef<Path>, project_name: Option<&str>) -> Result<Config> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    import_str(&content, &path.to_string_lossy(), project_name)
+}
+
+/// Import compose file content, referring to it as `compose_file`
+pub fn import_str(content: &str, compose_file: &str, project_name: Option<&str>) -> Result<Config> {
+    let compose: ComposeFile = serde_yaml::from_str(content)?;
+    if compose.services.is_empty() {
+        return Err(ConfigError::ValidationError(format!(
+            "Compose file '{}' defines no services",
+            compose_file
+        )));
+    }
+    let project_name = project_name.map(str::to_string).or(compose.name);
+
+    let mut services = HashMap::new();
+    for (name, service) in &compose.services {
+        let dependencies = service.depends_on.names();
+        if let Some(dep) = dependencies
+            .iter()
+            .find(|dep| !compose.services.contains_key(*dep))
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "Compose service '{}' depends on unknown service '{}'",
+                name, dep
+            )));
+        }
+
+        let health_check = service
+            .healthcheck
+            .as_ref()
+            .map(|healthcheck| {
+                convert_healthcheck(healthcheck, compose_file, project_name.as_deref(), name)
+            })
+            .transpose()?
+            .flatten();
+
+        services.insert(
+            name.clone(),
+            Service {
+                service_type: ServiceType::Compose {
+                    compose_file: compose_file.to_string(),
+                    service: name.clone(),
+                    project_name: project_name.clone(),
+                },
+                network: IMPORT_NETWORK.to_string(),
+                env: HashMap::new(),
+                dependencies,
+                health_check,
+                startup_timeout: None,
+                shutdown_timeout: None,
+            },
+        );
+    }
+
+    Ok(Config {
+        version: "1.0".to_string(),
+        name: project_name,
+        description: Some(format!("Imported from {}", compose_file)),
+        settings: Default::default(),
+        networks: HashMap::from([(IMPORT_NETWORK.to_string(), Network::Local { subnet: None })]),
+        services,
+    })
+}
+
+/// Map a compose healthcheck to one running its test in the service's
+/// container, or `None` if it is disabled
+fn convert_healthcheck(
+    healthcheck: &ComposeHealthcheck,
+    compose_file: &str,
+    project_name: Option<&str>,
+    service: &str,
+) -> Result<Option<HealthCheck>> {
+    let test = match &healthcheck.test {
+        _ if healthcheck.disable => return Ok(None),
+        None => return Ok(None),
+        Some(HealthcheckTest::Shell(command)) => vec!["sh".into(), "-c".into(), command.clone()],
+        Some(HealthcheckTest::List(test)) => match test.split_first() {
+            Some((kind, command)) if kind == "CMD" => command.to_vec(),
+            Some((kind, command)) if kind == "CMD-SHELL" => {
+                vec!["sh".into(), "-c".into(), command.join(" ")]
+            }
+            Some((kind, _)) if kind == "NONE" => return Ok(None),
+            _ => {
+                return Err(ConfigError::ValidationError(format!(
+                    "Unsupported healthcheck test {:?} for compose service '{}'",
+                    test, service
+                )));
+            }
+        },
+    };
+
+    let mut args = vec![
+        "compose".to_string(),
+        "--file".to_string(),
+        compose_file.into(),
+    ];
+    if let Some(project) = project_name {
+        args.extend(["--project-name".to_string(), project.to_string()]);
+    }
+    args.extend(["exec".to_string(), "-T".to_string(), service.to_string()]);
+    args.extend(test);
+
+    // The harness's defaults where the healthcheck doesn't set a value, with
+    // the timeout kept below the interval
+    let seconds = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|value| parse_duration(value, service))
+            .transpose()
+    };
+    let interval = seconds(&healthcheck.interval)?.unwrap_or_else(crate::default_interval);
+    let timeout = seconds(&healthcheck.timeout)?
+        .unwrap_or_else(|| crate::default_timeout().min(interval.saturating_sub(1).max(1)));
+    Ok(Some(HealthCheck {
+        check_type: HealthCheckType::Command {
+            command: "docker".to_string(),
+            args,
+        },
+        interval,
+        retries: healthcheck.retries.unwrap_or_else(crate::default_retries),
+        timeout,
+        start_period: seconds(&healthcheck.start_period)?.unwrap_or_default(),
+    }))
+}
+
+/// Parse a compose duration such as `1m30s` or `500ms` into whole seconds,
+/// rounding up
+fn parse_duration(value: &str, service: &str) -> Result<u64> {
+    let invalid = || {
+        ConfigError::ValidationError(format!(
+            "Invalid duration '{}' in healthcheck of compose service '{}'",
+            value, service
+        ))
+    };
+
+    let mut micros: u64 = 0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let units = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let scale = match &rest[..units] {
+            "h" => 3_600_000_000,
+            "m" => 60_000_000,
+            "s" => 1_000_000,
+            "ms" => 1_000,
+            "us" => 1,
+            _ => return Err(invalid()),
+        };
+        micros += number * scale;
+        rest = &rest[units..];
+    }
+    Ok(micros.div_ceil(1_000_000))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPOSE: &str = r#"
+name: graph
+services:
+  postgres:
+    image: postgres:15
+    healthcheck:
+      test: ["CMD", "pg_isready", "-U", "graph-node"]
+      interval: 10s
+      timeout: 5s
+      retries: 10
+  ipfs:
+    image: ipfs/kubo:v0.22.0
+    healthcheck:
+      test: ipfs id
+      start_period: 1m30s
+  graph-node:
+    image: graphprotocol/graph-node
+    depends_on:
+      postgres:
+        condition: service_healthy
+      ipfs:
+        condition: service_started
+    healthcheck:
+      disable: true
+  indexer-agent:
+    image: ghcr.io/graphprotocol/indexer-agent
+    depends_on: [graph-node]
+"#;
+
+    #[test]
+    fn test_import_compose() {
+        let config = import_str(COMPOSE, "docker-compose.yml", None).unwrap();
+        assert_eq!(config.name.as_deref(), Some("graph"));
+        assert_eq!(config.services.len(), 4);
+
+        let node = &config.services["graph-node"];
+        assert!(matches!(
+            &node.service_type,
+            ServiceType::Compose { compose_file, service, project_name: Some(project) }
+                if compose_file == "docker-compose.yml" && service == "graph-node" && project == "graph"
+        ));
+        assert_eq!(node.dependencies, vec!["ipfs", "postgres"]);
+        assert!(node.health_check.is_none());
+        assert_eq!(
+            config.services["indexer-agent"].dependencies,
+            vec!["graph-node"]
+        );
+
+        let postgres = config.services["postgres"].health_check.as_ref().unwrap();
+        let HealthCheckType::Command { command, args } = &postgres.check_type else {
+            panic!("expected a command health check");
+        };
+        assert_eq!(command, "docker");
+        assert_eq!(
+            args.join(" "),
+            "compose --file docker-compose.yml --project-name graph exec -T postgres \
+             pg_isready -U graph-node"
+        );
+        assert_eq!(
+            (postgres.interval, postgres.timeout, postgres.retries),
+            (10, 5, 10)
+        );
+
+        let ipfs = config.services["ipfs"].health_check.as_ref().unwrap();
+        let HealthCheckType::Command { args, .. } = &ipfs.check_type else {
+            panic!("expected a command health check");
+        };
+        assert_eq!(args[args.len() - 3..], ["sh", "-c", "ipfs id"]);
+        assert_eq!((ipfs.interval, ipfs.start_period), (30, 90));
+    }
+
+    #[test]
+    fn test_import_validates() {
+        let config = import_str(COMPOSE, "docker-compose.yml", Some("test")).unwrap();
+        let yaml = crate::parser::to_yaml_string(&config).unwrap();
+        let parsed = crate::parser::parse_str(&yaml).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("test"));
+        assert_eq!(parsed.services.len(), 4);
+
+        let unknown = "services:\n  a:\n    image: x\n    depends_on: [b]\n";
+        assert!(import_str(unknown, "compose.yml", None).is_err());
+        assert!(import_str("services: {}\n", "compose.yml", None).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("10s", "svc").unwrap(), 10);
+        assert_eq!(parse_duration("1m30s", "svc").unwrap(), 90);
+        assert_eq!(parse_duration("1h", "svc").unwrap(), 3600);
+        assert_eq!(parse_duration("500ms", "svc").unwrap(), 1);
+        assert!(parse_duration("10", "svc").is_err());
+        assert!(parse_duration("ten seconds", "svc").is_err());
+    }
+}
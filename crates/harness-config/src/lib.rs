@@ -11,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use thiserror::Error;
 
+pub mod compose;
 pub mod parser;
 pub mod resolver;
 
@@ -124,6 +125,9 @@ pub enum Network {
         /// WireGuard config path
         #[serde(skip_serializing_if = "Option::is_none")]
         config_path: Option<String>,
+        /// Interface name on every node (defaults to wg0)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        interface: Option<String>,
         /// Pre-defined nodes
         #[serde(default)]
         nodes: Vec<WireGuardNode>,
@@ -161,6 +165,15 @@ pub struct WireGuardNode {
     /// Enable package deployment
     #[serde(default)]
     pub package_deploy: bool,
+    /// WireGuard listen port (defaults to 51820)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listen_port: Option<u16>,
+    /// Fixed mesh address (allocated from the subnet if unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Local network namespace to run the node in (for testing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netns: Option<String>,
 }
 
 /// Service definition
@@ -217,6 +230,18 @@ pub enum ServiceType {
         entrypoint: Option<Vec<String>>,
     },
 
+    /// Service of a docker compose project
+    #[serde(rename = "compose")]
+    Compose {
+        /// Path to the compose file
+        compose_file: String,
+        /// Service name in the compose file
+        service: String,
+        /// Compose project name
+        #[serde(skip_serializing_if = "Option::is_none")]
+        project_name: Option<String>,
+    },
+
     /// Local process service
     #[serde(rename = "process")]
     Process {
@@ -231,6 +256,9 @@ pub enum ServiceType {
         /// Run as user
         #[serde(skip_serializing_if = "Option::is_none")]
         user: Option<String>,
+        /// Resource limits
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limits: Option<Limits>,
     },
 
     /// Remote service (via SSH)
@@ -264,6 +292,34 @@ pub enum ServiceType {
     },
 }
 
+/// Resource limits of a process service
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limits {
+    /// Maximum memory: bytes, or a size such as "512M" or "2G"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_max: Option<Quantity>,
+    /// CPU quota in percent of one CPU, such as "150%"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_quota: Option<Quantity>,
+    /// Maximum number of open files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nofile: Option<u64>,
+    /// Nice level, from -20 (highest priority) to 19
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+}
+
+/// A limit given as a plain number or with a unit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Quantity {
+    /// Plain number
+    Number(u64),
+    /// Number with a unit, e.g. "512M" or "150%"
+    Text(String),
+}
+
 /// Port mapping for Docker containers
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -1,11 +1,15 @@
 //! Configuration parser with environment variable substitution
 
 use crate::{
-    Config, ConfigError, HealthCheck, HealthCheckType, PortMapping, Result, Service, ServiceType,
+    Config, ConfigError, HealthCheck, HealthCheckType, Limits, Network, PortMapping, Quantity,
+    Result, Service, ServiceType,
     resolver::{ResolutionContext, resolve_service_env, validate_references},
 };
 use regex::Regex;
-use service_orchestration::{HealthCheck as OrchestratorHealthCheck, ServiceConfig, ServiceTarget};
+use service_orchestration::{
+    HealthCheck as OrchestratorHealthCheck, ResourceLimits, ServiceConfig, ServiceTarget,
+};
+use service_registry::network::wireguard::{self, WireGuardMeshConfig, WireGuardNodeConfig};
 use std::collections::HashMap;
 use std::path::Path;
 
@@ -22,6 +26,22 @@ pub fn parse_str(content: &str) -> Result<Config> {
     Ok(config)
 }
 
+/// Serialize a configuration to YAML, with networks and services in name
+/// order
+pub fn to_yaml_string(config: &Config) -> Result<String> {
+    let mut value = serde_yaml::to_value(config)?;
+    if let Some(root) = value.as_mapping_mut() {
+        for key in ["networks", "services"] {
+            if let Some(serde_yaml::Value::Mapping(entries)) = root.get_mut(key) {
+                let mut sorted: Vec<_> = std::mem::take(entries).into_iter().collect();
+                sorted.sort_by(|(a, _), (b, _)| a.as_str().cmp(&b.as_str()));
+                entries.extend(sorted);
+            }
+        }
+    }
+    Ok(serde_yaml::to_string(&value)?)
+}
+
 /// Validate configuration
 fn validate_config(config: &Config) -> Result<()> {
     // Check version
@@ -41,6 +61,14 @@ fn validate_config(config: &Config) -> Result<()> {
             )));
         }
 
+        if let ServiceType::Process {
+            limits: Some(limits),
+            ..
+        } = &service.service_type
+        {
+            convert_limits(limits, name)?;
+        }
+
         // Check dependencies exist
         for dep in &service.dependencies {
             if !config.services.contains_key(dep) {
@@ -58,6 +86,78 @@ fn validate_config(config: &Config) -> Result<()> {
     Ok(())
 }
 
+/// Convert a process service's limits, checking their values
+pub fn convert_limits(limits: &Limits, service_name: &str) -> Result<ResourceLimits> {
+    let invalid = |field: &str, value: &dyn std::fmt::Debug| {
+        ConfigError::ValidationError(format!(
+            "Invalid {} {:?} in limits of service '{}'",
+            field, value, service_name
+        ))
+    };
+
+    let memory_max = match &limits.memory_max {
+        None => None,
+        Some(Quantity::Number(bytes)) => Some(*bytes),
+        Some(Quantity::Text(size)) => {
+            Some(parse_size(size).ok_or_else(|| invalid("memory_max", size))?)
+        }
+    };
+    let cpu_quota = match &limits.cpu_quota {
+        None => None,
+        Some(Quantity::Number(percent)) => {
+            Some(u32::try_from(*percent).map_err(|_| invalid("cpu_quota", percent))?)
+        }
+        Some(Quantity::Text(percent)) => Some(
+            percent
+                .trim()
+                .trim_end_matches('%')
+                .trim_end()
+                .parse()
+                .map_err(|_| invalid("cpu_quota", percent))?,
+        ),
+    };
+
+    if memory_max == Some(0) {
+        return Err(invalid("memory_max", &0));
+    }
+    if cpu_quota == Some(0) {
+        return Err(invalid("cpu_quota", &"0%"));
+    }
+    if limits.nofile == Some(0) {
+        return Err(invalid("nofile", &0));
+    }
+    if let Some(nice) = limits.nice
+        && !(-20..=19).contains(&nice)
+    {
+        return Err(invalid("nice", &nice));
+    }
+
+    Ok(ResourceLimits {
+        memory_max,
+        cpu_quota,
+        nofile: limits.nofile,
+        nice: limits.nice,
+    })
+}
+
+/// Parse a size such as `512M` into bytes, with binary units up to `T`
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let digits = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let number: u64 = size[..digits].parse().ok()?;
+    let shift = match size[digits..].trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        "T" | "TB" | "TIB" => 40,
+        _ => return None,
+    };
+    number.checked_mul(1 << shift)
+}
+
 /// Substitute environment variables in a string
 pub fn substitute_env_vars(input: &str) -> Result<String> {
     let re = Regex::new(r"\$\{([^}]+)\}").unwrap();
@@ -185,16 +285,37 @@ pub fn convert_to_orchestrator_with_context(
             }
         }
 
+        ServiceType::Compose {
+            compose_file,
+            service,
+            project_name,
+        } => ServiceTarget::Compose {
+            compose_file: compose_file.clone(),
+            service: service.clone(),
+            project_name: project_name.clone(),
+            env,
+        },
+
         ServiceType::Process {
             binary,
             args,
             working_dir,
-            ..
+            user,
+            limits,
         } => ServiceTarget::Process {
             binary: binary.clone(),
             args: args.clone(),
             env,
             working_dir: working_dir.clone(),
+            user: user.clone(),
+            limits: limits
+                .as_ref()
+                .map(|limits| convert_limits(limits, service_name))
+                .transpose()?
+                .unwrap_or_default(),
+            shutdown_timeout: service
+                .shutdown_timeout
+                .or(config.settings.shutdown_timeout),
         },
 
         ServiceType::Remote {
@@ -242,6 +363,79 @@ pub fn convert_to_orchestrator_with_context(
     })
 }
 
+/// Convert a WireGuard network definition into a mesh configuration
+pub fn convert_wireguard_network(
+    config: &Config,
+    network_name: &str,
+) -> Result<WireGuardMeshConfig> {
+    let network = config.networks.get(network_name).ok_or_else(|| {
+        ConfigError::ValidationError(format!("Unknown network '{}'", network_name))
+    })?;
+
+    let Network::WireGuard {
+        subnet,
+        config_path,
+        interface,
+        nodes,
+    } = network
+    else {
+        return Err(ConfigError::ValidationError(format!(
+            "Network '{}' is not a WireGuard network",
+            network_name
+        )));
+    };
+
+    let subnet = subnet.parse().map_err(|e| {
+        ConfigError::ValidationError(format!(
+            "Invalid subnet '{}' for network '{}': {}",
+            subnet, network_name, e
+        ))
+    })?;
+
+    let nodes = nodes
+        .iter()
+        .map(|node| {
+            let address = node
+                .address
+                .as_ref()
+                .map(|addr| {
+                    addr.parse().map_err(|e| {
+                        ConfigError::ValidationError(format!(
+                            "Invalid address '{}' for node '{}': {}",
+                            addr, node.host, e
+                        ))
+                    })
+                })
+                .transpose()?;
+
+            Ok(WireGuardNodeConfig {
+                name: node.name.clone().unwrap_or_else(|| node.host.clone()),
+                host: node.host.clone(),
+                ssh_user: node.ssh_user.clone(),
+                ssh_key: node.ssh_key.as_ref().map(Into::into),
+                listen_port: node.listen_port.unwrap_or(wireguard::DEFAULT_LISTEN_PORT),
+                address,
+                netns: node.netns.clone(),
+                config_dir: None,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(WireGuardMeshConfig {
+        name: network_name.to_string(),
+        interface: interface
+            .clone()
+            .unwrap_or_else(|| wireguard::DEFAULT_INTERFACE.to_string()),
+        subnet,
+        // `config_path` names the directory rendered interface configs go to
+        config_dir: config_path
+            .as_ref()
+            .map(Into::into)
+            .unwrap_or_else(|| "/etc/wireguard".into()),
+        nodes,
+    })
+}
+
 /// Convert health check configuration
 fn convert_health_check(hc: &HealthCheck) -> OrchestratorHealthCheck {
     let (command, args) = match &hc.check_type {
@@ -349,4 +543,45 @@ services:
         assert_eq!(config.services.len(), 1);
         assert!(config.services.contains_key("test"));
     }
+
+    #[test]
+    fn test_convert_wireguard_network() {
+        let yaml = r#"
+version: "1.0"
+networks:
+  local:
+    type: local
+  mesh:
+    type: wireguard
+    subnet: "10.42.0.0/16"
+    nodes:
+      - host: "203.0.113.10"
+        name: "indexer"
+        ssh_user: "ubuntu"
+        ssh_key: "~/.ssh/id_ed25519"
+      - host: "203.0.113.11"
+        ssh_user: "root"
+        listen_port: 51821
+        address: "10.42.0.50"
+services:
+  test:
+    type: process
+    network: local
+    binary: "/usr/bin/echo"
+"#;
+
+        let config = parse_str(yaml).unwrap();
+        let mesh = convert_wireguard_network(&config, "mesh").unwrap();
+        assert_eq!(mesh.interface, "wg0");
+        assert_eq!(mesh.subnet.to_string(), "10.42.0.0/16");
+        assert_eq!(mesh.nodes.len(), 2);
+        assert_eq!(mesh.nodes[0].name, "indexer");
+        assert_eq!(mesh.nodes[0].listen_port, 51820);
+        assert_eq!(mesh.nodes[1].name, "203.0.113.11");
+        assert_eq!(mesh.nodes[1].listen_port, 51821);
+        assert_eq!(mesh.nodes[1].address, Some("10.42.0.50".parse().unwrap()));
+
+        assert!(convert_wireguard_network(&config, "local").is_err());
+        assert!(convert_wireguard_network(&config, "missing").is_err());
+    }
 }
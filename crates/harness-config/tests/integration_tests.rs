@@ -190,6 +190,121 @@ services:
     assert_eq!(hc.timeout, 2);
 }
 
+#[test]
+fn test_compose_service_conversion() {
+    let yaml = r#"
+version: "1.0"
+networks:
+  local:
+    type: local
+services:
+  postgres:
+    type: compose
+    network: local
+    compose_file: "./docker-compose.yml"
+    service: postgres
+    project_name: graph
+    env:
+      POSTGRES_TAG: "15"
+  graph-node:
+    type: compose
+    network: local
+    compose_file: "./docker-compose.yml"
+    service: graph-node
+    dependencies:
+      - postgres
+"#;
+
+    let config = parser::parse_str(yaml).unwrap();
+    let service_config = parser::convert_to_orchestrator(&config, "postgres").unwrap();
+    assert_eq!(
+        service_config.target,
+        service_orchestration::ServiceTarget::Compose {
+            compose_file: "./docker-compose.yml".to_string(),
+            service: "postgres".to_string(),
+            project_name: Some("graph".to_string()),
+            env: HashMap::from([("POSTGRES_TAG".to_string(), "15".to_string())]),
+        }
+    );
+
+    let service_config = parser::convert_to_orchestrator(&config, "graph-node").unwrap();
+    assert!(matches!(
+        service_config.target,
+        service_orchestration::ServiceTarget::Compose {
+            project_name: None,
+            ..
+        }
+    ));
+    assert_eq!(service_config.dependencies.len(), 1);
+}
+
+#[test]
+fn test_process_limits_conversion() {
+    let yaml = r#"
+version: "1.0"
+networks:
+  local:
+    type: local
+services:
+  anvil:
+    type: process
+    network: local
+    binary: anvil
+    user: nobody
+    shutdown_timeout: 5
+    limits:
+      memory_max: 512M
+      cpu_quota: 150%
+      nofile: 4096
+      nice: 10
+"#;
+
+    let config = parser::parse_str(yaml).unwrap();
+    let service_config = parser::convert_to_orchestrator(&config, "anvil").unwrap();
+    let service_orchestration::ServiceTarget::Process {
+        user,
+        limits,
+        shutdown_timeout,
+        ..
+    } = service_config.target
+    else {
+        panic!("expected a process target");
+    };
+    assert_eq!(user.as_deref(), Some("nobody"));
+    assert_eq!(shutdown_timeout, Some(5));
+    assert_eq!(
+        limits,
+        service_orchestration::ResourceLimits {
+            memory_max: Some(512 << 20),
+            cpu_quota: Some(150),
+            nofile: Some(4096),
+            nice: Some(10),
+        }
+    );
+
+    for (limits, error) in [
+        ("memory_max: 12 parsecs", "memory_max"),
+        ("cpu_quota: lots", "cpu_quota"),
+        ("nice: 20", "nice"),
+        ("nofile: 0", "nofile"),
+    ] {
+        let yaml = format!(
+            "version: \"1.0\"\nnetworks:\n  local:\n    type: local\nservices:\n  anvil:\n    \
+             type: process\n    network: local\n    binary: anvil\n    limits:\n      {}\n",
+            limits
+        );
+        let result = parser::parse_str(&yaml);
+        assert!(
+            result
+                .as_ref()
+                .is_err_and(|e| e.to_string().contains(error)),
+            "{}: {:?}",
+            limits,
+            result.map(|_| ())
+        );
+    }
+}
+
 #[test]
 fn test_validation_errors() {
     // Test invalid version
@@ -2,14 +2,39 @@
 //!
 //! This module provides client abstractions for connecting to and interacting
 //! with harness daemons, including action invocation and event streaming.
+//!
+//! [`TestClient`] doesn't need a background task: whichever request is
+//! waiting reads the next frame off the socket and routes it to the request
+//! it belongs to, so the client works on any async runtime.
 
+use async_channel::{Receiver, Sender};
+use async_net::TcpStream;
 use async_trait::async_trait;
-use serde_json::Value;
+use async_tungstenite::tungstenite::Message;
+use async_tungstenite::{WebSocketReceiver, WebSocketSender, client_async};
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::stream::{BoxStream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use service_registry::TlsClientConfig;
+use service_registry::tls::TlsConnector;
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use tracing::{debug, info};
-
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::action::ActionInfo;
+use crate::protocol::{ClientRequest, DaemonSchema, Method, ServerMessage, ServiceInfo, TaskInfo};
+use crate::service::ServiceState;
 use crate::{Error, Result};
 
+/// Delay between polls in the `wait_for` helpers
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// Trait for clients that can communicate with harness daemons
 #[async_trait]
 pub trait Client: Send + Sync {
@@ -28,10 +53,178 @@ pub trait Client: Send + Sync {
     async fn disconnect(&self) -> Result<()>;
 }
 
+/// Byte stream under the WebSocket, plain TCP or TLS
+trait Transport: AsyncRead + AsyncWrite + Send + Sync + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin> Transport for T {}
+
+/// Open WebSocket connection shared by a client and its event streams
+struct Connection {
+    sender: WebSocketSender<Box<dyn Transport>>,
+    /// Held by whichever request is currently reading from the socket
+    receiver: futures::lock::Mutex<WebSocketReceiver<Box<dyn Transport>>>,
+    /// Frame queues of in-flight requests, by request id
+    queues: Mutex<HashMap<String, Sender<ServerMessage>>>,
+    closed: AtomicBool,
+    next_id: AtomicU64,
+}
+
+impl Connection {
+    /// Send a request, returning the queue its frames will arrive on
+    async fn send(&self, method: Method) -> Result<Receiver<ServerMessage>> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(Error::client("Client not connected"));
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst).to_string();
+        let (tx, rx) = async_channel::unbounded();
+        self.queues.lock().unwrap().insert(id.clone(), tx);
+
+        let request = ClientRequest {
+            id: id.clone(),
+            method,
+        };
+        let text = serde_json::to_string(&request)?;
+
+        if let Err(e) = self.sender.send(Message::Text(text.into())).await {
+            self.queues.lock().unwrap().remove(&id);
+            return Err(Error::websocket(e.to_string()));
+        }
+
+        Ok(rx)
+    }
+
+    /// Next frame for a request, reading from the socket if none is queued
+    async fn next_frame(&self, frames: &Receiver<ServerMessage>) -> Result<ServerMessage> {
+        loop {
+            if let Ok(frame) = frames.try_recv() {
+                return Ok(frame);
+            }
+
+            let mut receiver = self.receiver.lock().await;
+
+            // Another request may have read our frame while we waited
+            if let Ok(frame) = frames.try_recv() {
+                return Ok(frame);
+            }
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(Error::client("Connection closed"));
+            }
+
+            match receiver.next().await {
+                Some(Ok(Message::Text(text))) => self.route(serde_json::from_str(&text)?),
+                Some(Ok(Message::Close(_))) | None => {
+                    self.mark_closed();
+                    return Err(Error::client("Connection closed by daemon"));
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    self.mark_closed();
+                    return Err(Error::websocket(e.to_string()));
+                }
+            }
+        }
+    }
+
+    /// Queue a frame for the request it belongs to
+    fn route(&self, frame: ServerMessage) {
+        let mut queues = self.queues.lock().unwrap();
+        let id = frame.id().to_string();
+        let terminal = frame.is_terminal();
+
+        let Some(queue) = queues.get(&id) else {
+            if let ServerMessage::Error { message, .. } = &frame {
+                warn!("Daemon rejected a request: {}", message);
+            } else {
+                debug!("Dropping frame for unknown request {}", id);
+            }
+            return;
+        };
+
+        // Queued frames stay readable after the sender is dropped
+        if queue.try_send(frame).is_err() || terminal {
+            queues.remove(&id);
+        }
+    }
+
+    fn mark_closed(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.queues.lock().unwrap().clear();
+    }
+
+    /// Send a request and wait for its result
+    async fn call(&self, method: Method) -> Result<Value> {
+        let frames = self.send(method).await?;
+
+        loop {
+            match self.next_frame(&frames).await? {
+                ServerMessage::Event { .. } => continue,
+                ServerMessage::Result { data, .. } => return Ok(data),
+                ServerMessage::Error { message, .. } => return Err(Error::client(message)),
+            }
+        }
+    }
+
+    /// Send a streaming request
+    async fn stream(self: &Arc<Self>, method: Method) -> Result<EventStream> {
+        let frames = self.send(method).await?;
+        let connection = self.clone();
+
+        let events = futures::stream::unfold(Some(frames), move |frames| {
+            let connection = connection.clone();
+            async move {
+                let frames = frames?;
+                match connection.next_frame(&frames).await {
+                    Ok(ServerMessage::Event { data, .. }) => Some((Ok(data), Some(frames))),
+                    Ok(ServerMessage::Result { .. }) => None,
+                    Ok(ServerMessage::Error { message, .. }) => {
+                        Some((Err(Error::client(message)), None))
+                    }
+                    Err(e) => Some((Err(e), None)),
+                }
+            }
+        });
+
+        Ok(EventStream {
+            inner: events.boxed(),
+        })
+    }
+}
+
+/// Events streamed back by a service action or task
+///
+/// Ends when the daemon reports the request finished; a failure is yielded
+/// as a final `Err`.
+pub struct EventStream {
+    inner: BoxStream<'static, Result<Value>>,
+}
+
+/// Events deserialized into a service's or task's event type
+pub type TypedEventStream<E> = BoxStream<'static, Result<E>>;
+
+impl EventStream {
+    /// Deserialize each event into `E`
+    pub fn typed<E>(self) -> TypedEventStream<E>
+    where
+        E: DeserializeOwned + Send + 'static,
+    {
+        self.map(|event| Ok(serde_json::from_value(event?)?))
+            .boxed()
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Value>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
 /// Test client implementation for integration testing
 pub struct TestClient {
     endpoint: SocketAddr,
-    connected: bool,
+    connection: Option<Arc<Connection>>,
 }
 
 impl TestClient {
@@ -39,19 +232,126 @@ impl TestClient {
     pub fn new(endpoint: SocketAddr) -> Self {
         Self {
             endpoint,
-            connected: false,
+            connection: None,
         }
     }
 
     /// Check if the client is connected
     pub fn is_connected(&self) -> bool {
-        self.connected
+        self.connection
+            .as_ref()
+            .is_some_and(|c| !c.closed.load(Ordering::SeqCst))
     }
 
     /// Get the endpoint this client connects to
     pub fn endpoint(&self) -> SocketAddr {
         self.endpoint
     }
+
+    /// Connect to a daemon serving its endpoint over TLS
+    ///
+    /// `server_name` is checked against the daemon's certificate.
+    pub async fn connect_tls(
+        endpoint: SocketAddr,
+        tls_config: TlsClientConfig,
+        server_name: &str,
+    ) -> Result<Self> {
+        info!("Connecting test client to {} (TLS)", endpoint);
+
+        let server_name = rustls::pki_types::ServerName::try_from(server_name.to_owned())
+            .map_err(|e| Error::client(format!("Invalid server name: {}", e)))?;
+        let stream = TcpStream::connect(endpoint).await?;
+        let stream = TlsConnector::from(tls_config.config)
+            .connect(server_name, stream)
+            .await?;
+
+        Self::handshake(endpoint, format!("wss://{}", endpoint), Box::new(stream)).await
+    }
+
+    /// Complete the WebSocket handshake over an open stream
+    async fn handshake(
+        endpoint: SocketAddr,
+        url: String,
+        stream: Box<dyn Transport>,
+    ) -> Result<Self> {
+        let (ws, _) = client_async(url, stream)
+            .await
+            .map_err(|e| Error::websocket(e.to_string()))?;
+        let (sender, receiver) = ws.split();
+
+        Ok(Self {
+            endpoint,
+            connection: Some(Arc::new(Connection {
+                sender,
+                receiver: futures::lock::Mutex::new(receiver),
+                queues: Mutex::new(HashMap::new()),
+                closed: AtomicBool::new(false),
+                next_id: AtomicU64::new(1),
+            })),
+        })
+    }
+
+    fn connection(&self) -> Result<&Arc<Connection>> {
+        self.connection
+            .as_ref()
+            .ok_or_else(|| Error::client("Client not connected"))
+    }
+
+    /// List daemon actions with their parameter and return schemas
+    pub async fn actions(&self) -> Result<Vec<ActionInfo>> {
+        let data = self.connection()?.call(Method::ListActions).await?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// List service instances with their state and action schemas
+    pub async fn services(&self) -> Result<Vec<ServiceInfo>> {
+        let data = self.connection()?.call(Method::ListServices).await?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// List task instances with their input and event schemas
+    pub async fn tasks(&self) -> Result<Vec<TaskInfo>> {
+        let data = self.connection()?.call(Method::ListTasks).await?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// Get the daemon's published schema
+    pub async fn schema(&self) -> Result<DaemonSchema> {
+        let data = self.connection()?.call(Method::Schema).await?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// Get the state of a service instance
+    pub async fn service_state(&self, service: &str) -> Result<ServiceState> {
+        let data = self
+            .connection()?
+            .call(Method::ServiceState {
+                service: service.to_string(),
+            })
+            .await?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// Dispatch an action to a service instance and stream its events
+    pub async fn dispatch(&self, service: &str, action: &str, input: Value) -> Result<EventStream> {
+        self.connection()?
+            .stream(Method::Dispatch {
+                service: service.to_string(),
+                action: action.to_string(),
+                input,
+            })
+            .await
+    }
+
+    /// Execute a task and stream its events
+    pub async fn execute_task(&self, task: &str, input: Value) -> Result<EventStream> {
+        self.connection()?
+            .stream(Method::ExecuteTask {
+                task: task.to_string(),
+                input,
+            })
+            .await
+    }
 }
 
 #[async_trait]
@@ -59,53 +359,52 @@ impl Client for TestClient {
     async fn connect(endpoint: SocketAddr) -> Result<Self> {
         info!("Connecting test client to {}", endpoint);
 
-        // TODO: Implement actual WebSocket connection
-        // For now, we'll just simulate a connection
-
-        Ok(Self {
-            endpoint,
-            connected: true,
-        })
+        let stream = TcpStream::connect(endpoint).await?;
+        Self::handshake(endpoint, format!("ws://{}", endpoint), Box::new(stream)).await
     }
 
     async fn action(&self, name: &str, params: Value) -> Result<Value> {
-        if !self.connected {
+        if !self.is_connected() {
             return Err(Error::client("Client not connected"));
         }
 
         debug!("Invoking action '{}' with params: {}", name, params);
 
-        // TODO: Implement actual WebSocket communication
-        // For now, return a placeholder response
-        Ok(serde_json::json!({
-            "action": name,
-            "params": params,
-            "status": "not_implemented",
-            "message": "Action invocation not yet implemented"
-        }))
+        self.connection()?
+            .call(Method::InvokeAction {
+                action: name.to_string(),
+                params,
+            })
+            .await
     }
 
     async fn list_actions(&self) -> Result<Vec<Value>> {
-        if !self.connected {
+        if !self.is_connected() {
             return Err(Error::client("Client not connected"));
         }
 
         debug!("Listing available actions");
 
-        // TODO: Implement actual action discovery
-        Ok(vec![serde_json::json!({
-            "name": "example",
-            "description": "Example action",
-            "category": "test"
-        })])
+        let data = self.connection()?.call(Method::ListActions).await?;
+        Ok(serde_json::from_value(data)?)
     }
 
     async fn disconnect(&self) -> Result<()> {
         info!("Disconnecting test client from {}", self.endpoint);
 
-        // TODO: Implement actual WebSocket disconnection
+        let Some(connection) = &self.connection else {
+            return Ok(());
+        };
+        if connection.closed.load(Ordering::SeqCst) {
+            return Ok(());
+        }
 
-        Ok(())
+        connection.mark_closed();
+        connection
+            .sender
+            .close(None)
+            .await
+            .map_err(|e| Error::websocket(e.to_string()))
     }
 }
 
@@ -118,15 +417,15 @@ impl TestClient {
         Fut: std::future::Future<Output = bool>,
     {
         let start = std::time::Instant::now();
-        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let timeout = Duration::from_millis(timeout_ms);
 
         while start.elapsed() < timeout {
             if condition().await {
                 return Ok(());
             }
 
-            // Yield control to allow other tasks to run
-            futures::future::ready(()).await;
+            let remaining = timeout.saturating_sub(start.elapsed());
+            async_io::Timer::after(POLL_INTERVAL.min(remaining)).await;
         }
 
         Err(Error::client(format!(
@@ -136,6 +435,9 @@ impl TestClient {
     }
 
     /// Wait for a service to be in a specific state
+    ///
+    /// `expected_state` is a [`ServiceState`] variant name such as `Running`
+    /// or `Failed`, compared case-insensitively.
     pub async fn wait_for_service_state(
         &self,
         service_name: &str,
@@ -144,9 +446,13 @@ impl TestClient {
     ) -> Result<()> {
         self.wait_for(
             || async {
-                // TODO: Query service status
-                // For now, just return false to simulate waiting
-                false
+                match self.service_state(service_name).await {
+                    Ok(state) => state_name(&state).eq_ignore_ascii_case(expected_state),
+                    Err(e) => {
+                        debug!("Failed to query state of {}: {}", service_name, e);
+                        false
+                    }
+                }
             },
             timeout_ms,
         )
@@ -157,13 +463,76 @@ impl TestClient {
     pub async fn wait_for_healthy(&self, timeout_ms: u64) -> Result<()> {
         self.wait_for(
             || async {
-                // TODO: Check all service health
-                false
+                match self.services().await {
+                    Ok(services) => services.iter().all(|s| s.state.is_healthy()),
+                    Err(e) => {
+                        debug!("Failed to query services: {}", e);
+                        false
+                    }
+                }
             },
             timeout_ms,
         )
         .await
     }
+
+    /// Wait for a graph-node service to index `deployment` up to `block`, or
+    /// to the chain head when no block is given
+    ///
+    /// Dispatches the service's `WaitForSync` action and returns the latest
+    /// indexed block. Fails with the daemon's diagnostic (health, last
+    /// indexed block, errors) if the deployment fails, becomes unhealthy or
+    /// doesn't get there within the timeout.
+    pub async fn wait_for_subgraph_sync(
+        &self,
+        service: &str,
+        deployment: &str,
+        block: Option<u64>,
+        timeout_ms: u64,
+    ) -> Result<u64> {
+        let input = json!({
+            "type": "WaitForSync",
+            "deployment": deployment,
+            "block": block,
+            "timeout": timeout_ms.div_ceil(1000),
+        });
+        let mut events = self.dispatch(service, "default", input).await?;
+
+        while let Some(event) = events.next().await {
+            let event = event?;
+            match event["event"].as_str() {
+                Some("Synced") => {
+                    return event["block_number"]
+                        .as_u64()
+                        .ok_or_else(|| Error::client(format!("Invalid Synced event: {}", event)));
+                }
+                Some("Error") => {
+                    return Err(Error::client(
+                        event["message"].as_str().unwrap_or("unknown error"),
+                    ));
+                }
+                _ => debug!("Waiting for {} to sync: {}", deployment, event),
+            }
+        }
+
+        Err(Error::client(format!(
+            "{} stopped reporting before {} synced",
+            service, deployment
+        )))
+    }
+}
+
+/// Variant name of a service state
+fn state_name(state: &ServiceState) -> &'static str {
+    match state {
+        ServiceState::NotStarted => "NotStarted",
+        ServiceState::Starting => "Starting",
+        ServiceState::Running => "Running",
+        ServiceState::SetupRequired => "SetupRequired",
+        ServiceState::SettingUp => "SettingUp",
+        ServiceState::SetupComplete => "SetupComplete",
+        ServiceState::Failed(_) => "Failed",
+    }
 }
 
 #[cfg(test)]
@@ -181,12 +550,13 @@ mod tests {
     }
 
     #[smol_potat::test]
-    async fn test_client_connection() {
-        let endpoint = "127.0.0.1:9443".parse().unwrap();
-        let client = TestClient::connect(endpoint).await.unwrap();
+    async fn test_client_connection_refused() {
+        // Bind and drop a listener to get a port nothing listens on
+        let listener = async_net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = listener.local_addr().unwrap();
+        drop(listener);
 
-        assert!(client.is_connected());
-        assert_eq!(client.endpoint(), endpoint);
+        assert!(TestClient::connect(endpoint).await.is_err());
     }
 
     #[smol_potat::test]
@@ -202,7 +572,7 @@ mod tests {
     #[smol_potat::test]
     async fn test_wait_for_timeout() {
         let endpoint = "127.0.0.1:9443".parse().unwrap();
-        let client = TestClient::connect(endpoint).await.unwrap();
+        let client = TestClient::new(endpoint);
 
         let result = client
             .wait_for(|| async { false }, 100) // Always false, should timeout
@@ -211,4 +581,21 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Timeout"));
     }
+
+    #[test]
+    fn test_state_names_match_serde() {
+        for state in [
+            ServiceState::Running,
+            ServiceState::SetupComplete,
+            ServiceState::Failed("boom".to_string()),
+        ] {
+            let value = serde_json::to_value(&state).unwrap();
+            let serde_name = match &value {
+                Value::String(name) => name.clone(),
+                Value::Object(map) => map.keys().next().unwrap().clone(),
+                _ => unreachable!(),
+            };
+            assert_eq!(state_name(&state), serde_name);
+        }
+    }
 }
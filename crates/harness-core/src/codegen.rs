@@ -0,0 +1,1114 @@
+//! Typed Rust client generation from a daemon's published schema
+//!
+//! [`generate`] turns a [`DaemonSchema`] into the source of a Rust module with
+//! one typed method per daemon action, a handle per service and task
+//! instance, and Rust types for every action input and event schema. The
+//! generated code wraps [`TestClient`](crate::client::TestClient), so a
+//! change to e.g. a service's action enum shows up as a compile error in code
+//! using the regenerated client instead of a runtime deserialization failure.
+//!
+//! The JSON schemas handled are the ones `schemars` derives for plain serde
+//! types: structs, internally and externally tagged enums, unit enums,
+//! options, sequences, maps and `$ref`s. Anything else becomes a
+//! `serde_json::Value`.
+
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+use crate::protocol::DaemonSchema;
+use crate::schema::{
+    common_tag, description, has_properties, nullable, one_of, resolve, string_enum, tag_value,
+};
+
+/// Rust keywords that need a raw identifier
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+/// Generate a typed client module for a daemon
+///
+/// `client` names the generated top-level client struct.
+pub fn generate(schema: &DaemonSchema, client: &str) -> String {
+    let mut types = TypeGen::default();
+    types.reserve(client);
+
+    let mut client_methods = Vec::new();
+    let mut handles = Vec::new();
+    let mut client_names = Names::new(&["connect", "connect_tls", "new", "inner"]);
+
+    for action in &schema.actions {
+        let method = client_names.claim(&snake_case(&action.name));
+        let params = match &action.params_schema {
+            Some(s) => types.root_type(s, &format!("{}Params", pascal_case(&action.name))),
+            None => VALUE.to_string(),
+        };
+        let returns = match &action.returns_schema {
+            Some(s) => types.root_type(s, &format!("{}Result", pascal_case(&action.name))),
+            None => VALUE.to_string(),
+        };
+        client_methods.push(ClientMethod::Action {
+            method,
+            name: action.name.clone(),
+            doc: action.description.clone(),
+            params,
+            returns,
+        });
+    }
+
+    // Instances of the same service or task share a handle type
+    let mut service_handles: HashMap<String, String> = HashMap::new();
+    for service in &schema.services {
+        let handle = match service_handles.get(&service.name) {
+            Some(handle) => handle.clone(),
+            None => {
+                let handle = types.reserve(&format!("{}Service", pascal_case(&service.name)));
+                let mut names = Names::new(&["instance", "state"]);
+                let mut methods = Vec::new();
+                for action in &service.actions {
+                    let input = types.root_type(&action.input_schema, &format!("{}Input", handle));
+                    let event = types.root_type(&action.event_schema, &format!("{}Event", handle));
+                    let method = if action.name == "default" {
+                        names.claim("dispatch")
+                    } else {
+                        names.claim(&snake_case(&action.name))
+                    };
+                    let prefix = (action.name != "default").then(|| method.clone());
+                    methods.push(HandleMethod {
+                        method,
+                        name: action.name.clone(),
+                        doc: action.description.clone(),
+                        helpers: types.helpers(&input, prefix.as_deref(), &mut names),
+                        input,
+                        event,
+                    });
+                }
+                handles.push(Handle {
+                    kind: HandleKind::Service,
+                    name: handle.clone(),
+                    doc: format!("Handle for `{}` service instances", service.name),
+                    methods,
+                });
+                service_handles.insert(service.name.clone(), handle.clone());
+                handle
+            }
+        };
+        client_methods.push(ClientMethod::Instance {
+            method: client_names.claim(&snake_case(&service.instance)),
+            instance: service.instance.clone(),
+            doc: service.description.clone(),
+            handle,
+        });
+    }
+
+    let mut task_handles: HashMap<String, String> = HashMap::new();
+    for task in &schema.tasks {
+        let handle = match task_handles.get(&task.name) {
+            Some(handle) => handle.clone(),
+            None => {
+                let handle = types.reserve(&format!("{}Task", pascal_case(&task.name)));
+                let mut names = Names::new(&["instance", "execute"]);
+                let input = types.root_type(&task.input_schema, &format!("{}Input", handle));
+                let event = types.root_type(&task.event_schema, &format!("{}Event", handle));
+                handles.push(Handle {
+                    kind: HandleKind::Task,
+                    name: handle.clone(),
+                    doc: format!("Handle for `{}` task instances", task.name),
+                    methods: vec![HandleMethod {
+                        method: "execute".to_string(),
+                        name: String::new(),
+                        doc: task.description.clone(),
+                        helpers: types.helpers(&input, None, &mut names),
+                        input,
+                        event,
+                    }],
+                });
+                task_handles.insert(task.name.clone(), handle.clone());
+                handle
+            }
+        };
+        client_methods.push(ClientMethod::Instance {
+            method: client_names.claim(&snake_case(&task.instance)),
+            instance: task.instance.clone(),
+            doc: task.description.clone(),
+            handle,
+        });
+    }
+
+    let mut out = String::new();
+    out.push_str(HEADER);
+    if !handles.is_empty() {
+        out.push_str("use harness_core::client::{Client, TestClient, TypedEventStream};\n");
+    } else {
+        out.push_str("use harness_core::client::{Client, TestClient};\n");
+    }
+    if !service_handles.is_empty() {
+        out.push_str("use harness_core::service::ServiceState;\n");
+    }
+    out.push_str("use serde::{Deserialize, Serialize};\nuse std::net::SocketAddr;\n");
+    render_client(&mut out, client, &client_methods);
+    for handle in &handles {
+        render_handle(&mut out, handle);
+    }
+    for item in &types.items {
+        render_item(&mut out, item);
+    }
+    out
+}
+
+const VALUE: &str = "serde_json::Value";
+
+const HEADER: &str = "\
+//! Typed daemon client
+//!
+//! Generated from the daemon's published schema by `cargo xtask codegen`.
+//! Do not edit by hand.
+
+#![allow(missing_docs, clippy::all)]
+
+use harness_core::{Result, TlsClientConfig};
+";
+
+enum ClientMethod {
+    Action {
+        method: String,
+        name: String,
+        doc: String,
+        params: String,
+        returns: String,
+    },
+    Instance {
+        method: String,
+        instance: String,
+        doc: String,
+        handle: String,
+    },
+}
+
+#[derive(PartialEq)]
+enum HandleKind {
+    Service,
+    Task,
+}
+
+struct Handle {
+    kind: HandleKind,
+    name: String,
+    doc: String,
+    methods: Vec<HandleMethod>,
+}
+
+struct HandleMethod {
+    method: String,
+    /// Service action name (unused for tasks)
+    name: String,
+    doc: String,
+    input: String,
+    event: String,
+    helpers: Vec<Helper>,
+}
+
+/// Convenience method building one variant of an input enum
+struct Helper {
+    method: String,
+    doc: Option<String>,
+    variant: String,
+    fields: Vec<Field>,
+}
+
+enum Item {
+    Struct {
+        name: String,
+        doc: Option<String>,
+        fields: Vec<Field>,
+    },
+    Enum {
+        name: String,
+        doc: Option<String>,
+        /// Tag property for internally tagged enums
+        tag: Option<String>,
+        variants: Vec<Variant>,
+    },
+    Alias {
+        name: String,
+        doc: Option<String>,
+        target: String,
+    },
+}
+
+#[derive(Clone)]
+struct Field {
+    ident: String,
+    rename: Option<String>,
+    ty: String,
+    doc: Option<String>,
+    /// Not in `required`, so it may be left out on the wire
+    optional: bool,
+}
+
+struct Variant {
+    ident: String,
+    rename: Option<String>,
+    doc: Option<String>,
+    kind: VariantKind,
+}
+
+enum VariantKind {
+    Unit,
+    Struct(Vec<Field>),
+    Newtype(String),
+}
+
+/// Unique identifiers within one scope
+struct Names(BTreeSet<String>);
+
+impl Names {
+    fn new(reserved: &[&str]) -> Self {
+        Self(reserved.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn claim(&mut self, name: &str) -> String {
+        let mut candidate = name.to_string();
+        let mut n = 2;
+        while !self.0.insert(candidate.clone()) {
+            candidate = format!("{}_{}", name, n);
+            n += 1;
+        }
+        candidate
+    }
+}
+
+/// Rust types generated from JSON schemas
+#[derive(Default)]
+struct TypeGen {
+    items: Vec<Item>,
+    /// Schema each generated name was created from, to share identical types
+    defined: HashMap<String, Option<Value>>,
+}
+
+impl TypeGen {
+    /// Claim a type name that isn't generated from a schema
+    fn reserve(&mut self, name: &str) -> String {
+        let name = self.unique(name);
+        self.defined.insert(name.clone(), None);
+        name
+    }
+
+    fn unique(&self, name: &str) -> String {
+        let mut candidate = name.to_string();
+        let mut n = 2;
+        while self.defined.contains_key(&candidate) {
+            candidate = format!("{}{}", name, n);
+            n += 1;
+        }
+        candidate
+    }
+
+    /// Type for a top-level schema, named by its title when it has one
+    fn root_type(&mut self, schema: &Value, fallback: &str) -> String {
+        let name = schema
+            .get("title")
+            .and_then(Value::as_str)
+            .map(pascal_case)
+            .unwrap_or_else(|| fallback.to_string());
+        self.define(&name, schema, schema)
+    }
+
+    /// Rust type for a schema, defining named types as needed
+    fn type_of(&mut self, schema: &Value, root: &Value, hint: &str) -> String {
+        let Some(object) = schema.as_object() else {
+            return VALUE.to_string();
+        };
+
+        if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+            return match resolve(root, reference) {
+                Some((name, target)) => {
+                    let target = target.clone();
+                    self.define(&pascal_case(name), &target, root)
+                }
+                None => VALUE.to_string(),
+            };
+        }
+
+        if let Some(inner) = nullable(object) {
+            return format!("Option<{}>", self.type_of(&inner, root, hint));
+        }
+
+        if object.contains_key("oneOf") || object.contains_key("anyOf") {
+            return self.define(hint, schema, root);
+        }
+
+        match object.get("type").and_then(Value::as_str) {
+            Some("string") if object.contains_key("enum") => self.define(hint, schema, root),
+            Some("string") => "String".to_string(),
+            Some("boolean") => "bool".to_string(),
+            Some("null") => "()".to_string(),
+            Some("integer") => integer_type(object).to_string(),
+            Some("number") => match object.get("format").and_then(Value::as_str) {
+                Some("float") => "f32".to_string(),
+                _ => "f64".to_string(),
+            },
+            Some("array") => match object.get("items") {
+                Some(items) => format!(
+                    "Vec<{}>",
+                    self.type_of(items, root, &format!("{}Item", hint))
+                ),
+                None => format!("Vec<{}>", VALUE),
+            },
+            Some("object") if has_properties(object) => self.define(hint, schema, root),
+            Some("object") => match object.get("additionalProperties") {
+                Some(values) if values.is_object() => format!(
+                    "std::collections::HashMap<String, {}>",
+                    self.type_of(values, root, &format!("{}Value", hint))
+                ),
+                _ => VALUE.to_string(),
+            },
+            _ => VALUE.to_string(),
+        }
+    }
+
+    /// Define a named type for a schema, reusing an identical definition
+    fn define(&mut self, name: &str, schema: &Value, root: &Value) -> String {
+        let mut name = name.to_string();
+        let shape = shape(schema);
+        match self.defined.get(&name) {
+            Some(Some(existing)) if *existing == shape => return name,
+            Some(_) => name = self.unique(&name),
+            None => {}
+        }
+        // Claim the name first so recursive schemas terminate
+        self.defined.insert(name.clone(), Some(shape));
+
+        let object = schema.as_object().cloned().unwrap_or_default();
+        let doc = description(&object);
+
+        let item = if let Some(variants) = one_of(&object) {
+            if let Some(tag) = common_tag(&variants) {
+                let variants = variants
+                    .iter()
+                    .map(|v| self.tagged_variant(v, &tag, root, &name))
+                    .collect();
+                Item::Enum {
+                    name: name.clone(),
+                    doc,
+                    tag: Some(tag),
+                    variants,
+                }
+            } else if let Some(variants) = self.external_variants(&variants, root, &name) {
+                Item::Enum {
+                    name: name.clone(),
+                    doc,
+                    tag: None,
+                    variants,
+                }
+            } else {
+                Item::Alias {
+                    name: name.clone(),
+                    doc,
+                    target: VALUE.to_string(),
+                }
+            }
+        } else if let Some(values) = string_enum(&object) {
+            Item::Enum {
+                name: name.clone(),
+                doc,
+                tag: None,
+                variants: values.iter().map(|v| unit_variant(v, None)).collect(),
+            }
+        } else if has_properties(&object) {
+            Item::Struct {
+                name: name.clone(),
+                doc,
+                fields: self.fields(&object, None, root, &name),
+            }
+        } else {
+            // Strip the title so the alias target isn't defined as itself
+            let mut inner = object.clone();
+            inner.remove("title");
+            inner.remove("description");
+            let target = match inner.get("type").and_then(Value::as_str) {
+                Some("object") if !has_properties(&inner) => {
+                    self.type_of(&Value::Object(inner), root, &format!("{}Inner", name))
+                }
+                Some("object") | None => VALUE.to_string(),
+                Some(_) => self.type_of(&Value::Object(inner), root, &format!("{}Inner", name)),
+            };
+            Item::Alias {
+                name: name.clone(),
+                doc,
+                target,
+            }
+        };
+
+        self.items.push(item);
+        name
+    }
+
+    fn tagged_variant(&mut self, schema: &Value, tag: &str, root: &Value, parent: &str) -> Variant {
+        let object = schema.as_object().cloned().unwrap_or_default();
+        let value = tag_value(&object, tag).unwrap_or_default();
+        let fields = self.fields(
+            &object,
+            Some(tag),
+            root,
+            &format!("{}{}", parent, pascal_case(&value)),
+        );
+
+        let mut variant = unit_variant(&value, description(&object));
+        if !fields.is_empty() {
+            variant.kind = VariantKind::Struct(fields);
+        }
+        variant
+    }
+
+    /// Variants of an externally tagged enum, if every alternative is one
+    fn external_variants(
+        &mut self,
+        alternatives: &[Value],
+        root: &Value,
+        parent: &str,
+    ) -> Option<Vec<Variant>> {
+        let mut variants = Vec::new();
+
+        for alternative in alternatives {
+            let object = alternative.as_object()?;
+            let doc = description(object);
+
+            if let Some(values) = string_enum(object) {
+                let doc = if values.len() == 1 { doc } else { None };
+                variants.extend(values.iter().map(|v| unit_variant(v, doc.clone())));
+                continue;
+            }
+
+            let properties = object.get("properties")?.as_object()?;
+            if properties.len() != 1 {
+                return None;
+            }
+            let (key, inner) = properties.iter().next()?;
+            let hint = format!("{}{}", parent, pascal_case(key));
+
+            let mut variant = unit_variant(key, doc);
+            variant.kind = match inner.as_object() {
+                Some(fields)
+                    if fields.get("type").and_then(Value::as_str) == Some("object")
+                        && has_properties(fields) =>
+                {
+                    VariantKind::Struct(self.fields(fields, None, root, &hint))
+                }
+                _ => VariantKind::Newtype(self.type_of(inner, root, &hint)),
+            };
+            variants.push(variant);
+        }
+
+        Some(variants)
+    }
+
+    /// Struct fields: required ones in declared order, then optional ones
+    fn fields(
+        &mut self,
+        object: &Map<String, Value>,
+        skip: Option<&str>,
+        root: &Value,
+        parent: &str,
+    ) -> Vec<Field> {
+        let Some(properties) = object.get("properties").and_then(Value::as_object) else {
+            return Vec::new();
+        };
+        let required: Vec<&str> = object
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut keys: Vec<&String> = required
+            .iter()
+            .filter_map(|r| properties.get_key_value(*r).map(|(k, _)| k))
+            .collect();
+        keys.extend(
+            properties
+                .keys()
+                .filter(|k| !required.contains(&k.as_str())),
+        );
+
+        let mut names = Names::new(&[]);
+        keys.into_iter()
+            .filter(|key| Some(key.as_str()) != skip)
+            .map(|key| {
+                let schema = &properties[key];
+                let optional = !required.contains(&key.as_str());
+                let mut ty = self.type_of(schema, root, &format!("{}{}", parent, pascal_case(key)));
+                if optional && !ty.starts_with("Option<") {
+                    ty = format!("Option<{}>", ty);
+                }
+                let ident = names.claim(&snake_case(key));
+                Field {
+                    rename: (ident.trim_start_matches("r#") != key).then(|| key.clone()),
+                    ident,
+                    ty,
+                    doc: schema.as_object().and_then(description),
+                    optional,
+                }
+            })
+            .collect()
+    }
+
+    /// Helper methods for each struct and unit variant of an input enum
+    fn helpers(&self, input: &str, prefix: Option<&str>, names: &mut Names) -> Vec<Helper> {
+        let Some(Item::Enum { variants, .. }) = self.items.iter().find(|item| match item {
+            Item::Enum { name, .. } => name == input,
+            _ => false,
+        }) else {
+            return Vec::new();
+        };
+
+        variants
+            .iter()
+            .filter_map(|variant| {
+                let fields = match &variant.kind {
+                    VariantKind::Unit => Vec::new(),
+                    VariantKind::Struct(fields) => fields.clone(),
+                    VariantKind::Newtype(_) => return None,
+                };
+                let method = match prefix {
+                    Some(prefix) => format!("{}_{}", prefix, snake_case(&variant.ident)),
+                    None => snake_case(&variant.ident),
+                };
+                Some(Helper {
+                    method: names.claim(&method),
+                    doc: variant.doc.clone(),
+                    variant: variant.ident.clone(),
+                    fields,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Schema without the keys a root schema carries in addition to its
+/// definition, so a type used as both shares one definition
+fn shape(schema: &Value) -> Value {
+    let mut schema = schema.clone();
+    if let Some(object) = schema.as_object_mut() {
+        for key in ["$schema", "title", "definitions", "$defs"] {
+            object.remove(key);
+        }
+    }
+    schema
+}
+
+fn integer_type(object: &Map<String, Value>) -> &'static str {
+    match object.get("format").and_then(Value::as_str) {
+        Some("uint8") => "u8",
+        Some("uint16") => "u16",
+        Some("uint32") => "u32",
+        Some("uint64") => "u64",
+        Some("uint128") => "u128",
+        Some("uint") => "usize",
+        Some("int8") => "i8",
+        Some("int16") => "i16",
+        Some("int32") => "i32",
+        Some("int64") => "i64",
+        Some("int128") => "i128",
+        Some("int") => "isize",
+        _ if object.get("minimum").and_then(Value::as_f64) >= Some(0.0) => "u64",
+        _ => "i64",
+    }
+}
+
+fn unit_variant(value: &str, doc: Option<String>) -> Variant {
+    let ident = pascal_case(value);
+    Variant {
+        rename: (ident != value).then(|| value.to_string()),
+        ident,
+        doc,
+        kind: VariantKind::Unit,
+    }
+}
+
+/// Split an identifier into lowercase words at separators and case changes
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let chars: Vec<char> = name.chars().collect();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let boundary = c.is_uppercase()
+            && !current.is_empty()
+            && (chars[i - 1].is_lowercase()
+                || chars[i - 1].is_ascii_digit()
+                || chars.get(i + 1).is_some_and(|n| n.is_lowercase()));
+        if boundary {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn pascal_case(name: &str) -> String {
+    let ident: String = words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect();
+
+    match ident.chars().next() {
+        None => "Unnamed".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", ident),
+        Some(_) => ident,
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let ident = words(name).join("_");
+
+    match ident.chars().next() {
+        None => "unnamed".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", ident),
+        Some(_) if KEYWORDS.contains(&ident.as_str()) => format!("r#{}", ident),
+        Some(_) => ident,
+    }
+}
+
+fn render_doc(out: &mut String, doc: Option<&str>, indent: &str) {
+    if let Some(doc) = doc.filter(|d| !d.trim().is_empty()) {
+        for line in doc.trim().lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                let _ = writeln!(out, "{}///", indent);
+            } else {
+                let _ = writeln!(out, "{}/// {}", indent, line);
+            }
+        }
+    }
+}
+
+fn render_client(out: &mut String, client: &str, methods: &[ClientMethod]) {
+    let _ = write!(
+        out,
+        "
+/// Typed client for the daemon
+pub struct {client} {{
+    inner: TestClient,
+}}
+
+impl {client} {{
+    /// Connect to the daemon
+    pub async fn connect(endpoint: SocketAddr) -> Result<Self> {{
+        Ok(Self::new(TestClient::connect(endpoint).await?))
+    }}
+
+    /// Connect to a daemon serving its endpoint over TLS
+    pub async fn connect_tls(endpoint: SocketAddr, tls_config: TlsClientConfig, server_name: &str) -> Result<Self> {{
+        Ok(Self::new(TestClient::connect_tls(endpoint, tls_config, server_name).await?))
+    }}
+
+    /// Wrap a connected client
+    pub fn new(inner: TestClient) -> Self {{
+        Self {{ inner }}
+    }}
+
+    /// Untyped client underneath
+    pub fn inner(&self) -> &TestClient {{
+        &self.inner
+    }}
+"
+    );
+
+    for method in methods {
+        out.push('\n');
+        match method {
+            ClientMethod::Action {
+                method,
+                name,
+                doc,
+                params,
+                returns,
+            } => {
+                render_doc(out, Some(doc), "    ");
+                let _ = writeln!(
+                    out,
+                    "    pub async fn {method}(&self, params: {params}) -> Result<{returns}> {{"
+                );
+                let _ = writeln!(
+                    out,
+                    "        let result = self.inner.action({name:?}, serde_json::to_value(params)?).await?;"
+                );
+                let _ = writeln!(out, "        Ok(serde_json::from_value(result)?)");
+                let _ = writeln!(out, "    }}");
+            }
+            ClientMethod::Instance {
+                method,
+                instance,
+                doc,
+                handle,
+            } => {
+                let _ = writeln!(out, "    /// `{}`: {}", instance, doc.trim());
+                let _ = writeln!(out, "    pub fn {method}(&self) -> {handle}<'_> {{");
+                let _ = writeln!(
+                    out,
+                    "        {handle} {{ client: &self.inner, instance: {instance:?} }}"
+                );
+                let _ = writeln!(out, "    }}");
+            }
+        }
+    }
+
+    out.push_str("}\n");
+}
+
+fn render_handle(out: &mut String, handle: &Handle) {
+    let name = &handle.name;
+    out.push('\n');
+    render_doc(out, Some(&handle.doc), "");
+    let _ = write!(
+        out,
+        "#[derive(Clone, Copy)]
+pub struct {name}<'a> {{
+    client: &'a TestClient,
+    instance: &'static str,
+}}
+
+impl {name}<'_> {{
+    /// Instance name
+    pub fn instance(&self) -> &'static str {{
+        self.instance
+    }}
+"
+    );
+
+    if handle.kind == HandleKind::Service {
+        let _ = write!(
+            out,
+            "
+    /// Current state of the instance
+    pub async fn state(&self) -> Result<ServiceState> {{
+        self.client.service_state(self.instance).await
+    }}
+"
+        );
+    }
+
+    for method in &handle.methods {
+        let HandleMethod {
+            method: fn_name,
+            name,
+            doc,
+            input,
+            event,
+            helpers,
+        } = method;
+
+        out.push('\n');
+        render_doc(out, Some(doc), "    ");
+        let _ = writeln!(
+            out,
+            "    pub async fn {fn_name}(&self, input: {input}) -> Result<TypedEventStream<{event}>> {{"
+        );
+        let call = match handle.kind {
+            HandleKind::Service => format!(
+                "self.client.dispatch(self.instance, {name:?}, serde_json::to_value(input)?)"
+            ),
+            HandleKind::Task => {
+                "self.client.execute_task(self.instance, serde_json::to_value(input)?)".to_string()
+            }
+        };
+        let _ = writeln!(out, "        Ok({call}.await?.typed())");
+        let _ = writeln!(out, "    }}");
+
+        for helper in helpers {
+            out.push('\n');
+            render_doc(out, helper.doc.as_deref(), "    ");
+            let params: String = helper
+                .fields
+                .iter()
+                .map(|f| format!(", {}: {}", f.ident, f.ty))
+                .collect();
+            let _ = writeln!(
+                out,
+                "    pub async fn {}(&self{}) -> Result<TypedEventStream<{}>> {{",
+                helper.method, params, event
+            );
+            let construct = if helper.fields.is_empty() {
+                format!("{}::{}", input, helper.variant)
+            } else {
+                let fields: Vec<&str> = helper.fields.iter().map(|f| f.ident.as_str()).collect();
+                format!("{}::{} {{ {} }}", input, helper.variant, fields.join(", "))
+            };
+            let _ = writeln!(out, "        self.{}({}).await", fn_name, construct);
+            let _ = writeln!(out, "    }}");
+        }
+    }
+
+    out.push_str("}\n");
+}
+
+/// Render fields; `vis` is empty for enum variants, which can't have one
+fn render_fields(out: &mut String, fields: &[Field], indent: &str, vis: &str) {
+    for field in fields {
+        render_doc(out, field.doc.as_deref(), indent);
+        if let Some(rename) = &field.rename {
+            let _ = writeln!(out, "{}#[serde(rename = {:?})]", indent, rename);
+        }
+        if field.optional {
+            let _ = writeln!(
+                out,
+                "{}#[serde(default, skip_serializing_if = \"Option::is_none\")]",
+                indent
+            );
+        }
+        let _ = writeln!(out, "{}{}{}: {},", indent, vis, field.ident, field.ty);
+    }
+}
+
+fn render_item(out: &mut String, item: &Item) {
+    const DERIVE: &str = "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]";
+    out.push('\n');
+
+    match item {
+        Item::Struct { name, doc, fields } => {
+            render_doc(out, doc.as_deref(), "");
+            let _ = writeln!(out, "{}", DERIVE);
+            let _ = writeln!(out, "pub struct {} {{", name);
+            render_fields(out, fields, "    ", "pub ");
+            out.push_str("}\n");
+        }
+        Item::Enum {
+            name,
+            doc,
+            tag,
+            variants,
+        } => {
+            render_doc(out, doc.as_deref(), "");
+            let _ = writeln!(out, "{}", DERIVE);
+            if let Some(tag) = tag {
+                let _ = writeln!(out, "#[serde(tag = {:?})]", tag);
+            }
+            let _ = writeln!(out, "pub enum {} {{", name);
+            for variant in variants {
+                render_doc(out, variant.doc.as_deref(), "    ");
+                if let Some(rename) = &variant.rename {
+                    let _ = writeln!(out, "    #[serde(rename = {:?})]", rename);
+                }
+                match &variant.kind {
+                    VariantKind::Unit => {
+                        let _ = writeln!(out, "    {},", variant.ident);
+                    }
+                    VariantKind::Newtype(ty) => {
+                        let _ = writeln!(out, "    {}({}),", variant.ident, ty);
+                    }
+                    VariantKind::Struct(fields) => {
+                        let _ = writeln!(out, "    {} {{", variant.ident);
+                        render_fields(out, fields, "        ", "");
+                        out.push_str("    },\n");
+                    }
+                }
+            }
+            out.push_str("}\n");
+        }
+        Item::Alias { name, doc, target } => {
+            render_doc(out, doc.as_deref(), "");
+            let _ = writeln!(out, "pub type {} = {};", name, target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::ActionInfo;
+    use crate::protocol::{ServiceSchema, TaskSchema};
+    use crate::service::ActionDescriptor;
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    /// Actions for a test service
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    #[allow(dead_code)]
+    enum CounterAction {
+        /// Count up
+        CountTo { to: u32, step: Option<u32> },
+        /// Start over
+        Reset,
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    enum CounterEvent {
+        Counted(u64),
+        Done { total: u64, labels: Vec<String> },
+        Idle,
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Nested {
+        r#type: String,
+        inner: Inner,
+        extra: std::collections::HashMap<String, bool>,
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Inner {
+        #[serde(rename = "blockNumber")]
+        block_number: i64,
+    }
+
+    fn schema_of<T: JsonSchema>() -> Value {
+        serde_json::to_value(schemars::schema_for!(T)).unwrap()
+    }
+
+    fn daemon_schema() -> DaemonSchema {
+        DaemonSchema {
+            actions: vec![ActionInfo {
+                name: "reset-all".to_string(),
+                description: "Reset everything".to_string(),
+                params_schema: Some(schema_of::<Nested>()),
+                returns_schema: None,
+                category: None,
+                deprecated: false,
+            }],
+            services: ["counter-1", "counter-2"]
+                .into_iter()
+                .map(|instance| ServiceSchema {
+                    instance: instance.to_string(),
+                    name: "counter".to_string(),
+                    description: "Counts".to_string(),
+                    actions: vec![ActionDescriptor {
+                        name: "default".to_string(),
+                        description: "Counts".to_string(),
+                        input_schema: schema_of::<CounterAction>(),
+                        event_schema: schema_of::<CounterEvent>(),
+                    }],
+                })
+                .collect(),
+            tasks: vec![TaskSchema {
+                instance: "setup".to_string(),
+                name: "setup".to_string(),
+                description: "Sets things up".to_string(),
+                input_schema: json!({ "title": "SetupInput", "type": "null" }),
+                event_schema: schema_of::<CounterEvent>(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_identifier_cases() {
+        assert_eq!(snake_case("MineBlocks"), "mine_blocks");
+        assert_eq!(snake_case("setup-test-stack"), "setup_test_stack");
+        assert_eq!(snake_case("blockNumber"), "block_number");
+        assert_eq!(snake_case("type"), "r#type");
+        assert_eq!(pascal_case("graph-node"), "GraphNode");
+        assert_eq!(pascal_case("count_to"), "CountTo");
+        assert_eq!(pascal_case("IPFSHash"), "IpfsHash");
+    }
+
+    #[test]
+    fn test_internally_tagged_enum() {
+        let code = generate(&daemon_schema(), "CounterClient");
+
+        assert!(code.contains("#[serde(tag = \"type\")]\npub enum CounterAction {"));
+        assert!(code.contains("    #[serde(rename = \"count_to\")]\n    CountTo {"));
+        assert!(code.contains("        to: u32,"));
+        assert!(code.contains(
+            "        #[serde(default, skip_serializing_if = \"Option::is_none\")]\n        step: Option<u32>,"
+        ));
+        assert!(code.contains("    #[serde(rename = \"reset\")]\n    Reset,"));
+    }
+
+    #[test]
+    fn test_externally_tagged_enum() {
+        let code = generate(&daemon_schema(), "CounterClient");
+
+        assert!(code.contains("pub enum CounterEvent {"));
+        assert!(code.contains("    Counted(u64),"));
+        assert!(code.contains("    Done {\n        total: u64,\n        labels: Vec<String>,"));
+        assert!(code.contains("    Idle,"));
+        // Shared by the service and the task
+        assert_eq!(code.matches("pub enum CounterEvent").count(), 1);
+    }
+
+    #[test]
+    fn test_structs_and_refs() {
+        let code = generate(&daemon_schema(), "CounterClient");
+
+        assert!(code.contains("pub struct Nested {"));
+        assert!(code.contains("    pub r#type: String,"));
+        assert!(code.contains("    pub inner: Inner,"));
+        assert!(code.contains("    pub extra: std::collections::HashMap<String, bool>,"));
+        assert!(
+            code.contains("    #[serde(rename = \"blockNumber\")]\n    pub block_number: i64,")
+        );
+        assert!(code.contains("pub type SetupInput = ();"));
+    }
+
+    #[test]
+    fn test_client_methods() {
+        let code = generate(&daemon_schema(), "CounterClient");
+
+        assert!(code.contains("pub struct CounterClient {"));
+        assert!(code.contains(
+            "    pub async fn reset_all(&self, params: Nested) -> Result<serde_json::Value> {"
+        ));
+        assert!(code.contains("    pub fn counter_1(&self) -> CounterService<'_> {"));
+        assert!(code.contains("    pub fn counter_2(&self) -> CounterService<'_> {"));
+        assert_eq!(code.matches("pub struct CounterService").count(), 1);
+        assert!(code.contains(
+            "    pub async fn dispatch(&self, input: CounterAction) -> Result<TypedEventStream<CounterEvent>> {"
+        ));
+        assert!(code.contains(
+            "    pub async fn count_to(&self, to: u32, step: Option<u32>) -> Result<TypedEventStream<CounterEvent>> {\n        self.dispatch(CounterAction::CountTo { to, step }).await"
+        ));
+        assert!(code.contains("    pub fn setup(&self) -> SetupTask<'_> {"));
+        assert!(code.contains(
+            "    pub async fn execute(&self, input: SetupInput) -> Result<TypedEventStream<CounterEvent>> {"
+        ));
+    }
+
+    #[test]
+    fn test_root_and_referenced_types_are_shared() {
+        let mut schema = daemon_schema();
+        schema.actions.push(ActionInfo {
+            name: "list-inner".to_string(),
+            description: "List inners".to_string(),
+            params_schema: None,
+            returns_schema: Some(schema_of::<Vec<Inner>>()),
+            category: None,
+            deprecated: false,
+        });
+        schema.actions[0].returns_schema = Some(schema_of::<Inner>());
+        let code = generate(&schema, "CounterClient");
+
+        assert_eq!(code.matches("pub struct Inner").count(), 1);
+        assert!(!code.contains("Inner2"));
+    }
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        assert_eq!(
+            generate(&daemon_schema(), "CounterClient"),
+            generate(&daemon_schema(), "CounterClient")
+        );
+    }
+}
@@ -3,13 +3,18 @@
 //! This module provides the `Daemon` trait and `BaseDaemon` implementation
 //! that serves as the foundation for domain-specific daemons.
 
+use async_runtime_compat::Spawner;
+use async_runtime_compat::smol::SmolSpawner;
 use async_trait::async_trait;
 use serde_json::Value;
+use service_registry::TlsServerConfig;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tracing::info;
 
-use crate::action::{Action, ActionRegistry};
+use crate::action::{Action, ActionInfo, ActionRegistry};
+use crate::protocol::DaemonSchema;
+use crate::server::{Server, ServerState, TaskTracker};
 use crate::service::ServiceStack;
 use crate::task::TaskStack;
 use crate::{Error, Registry, Result, ServiceManager};
@@ -41,18 +46,33 @@ pub struct BaseDaemon {
     /// Service registry for discovery
     service_registry: Registry,
 
-    /// Action registry for custom functionality
-    action_registry: ActionRegistry,
+    /// Action registry for custom functionality (shared with the server)
+    action_registry: Arc<ActionRegistry>,
 
     /// Service stack for actionable services
-    service_stack: ServiceStack,
+    service_stack: Arc<ServiceStack>,
 
     /// Task stack for deployment tasks
-    task_stack: TaskStack,
+    task_stack: Arc<TaskStack>,
 
     /// WebSocket server address
     endpoint: SocketAddr,
 
+    /// TLS configuration for the WebSocket server (if enabled)
+    tls_config: Option<TlsServerConfig>,
+
+    /// Spawner for the server and its requests
+    spawner: Arc<dyn Spawner>,
+
+    /// Address the server is bound to while running
+    local_addr: Mutex<Option<SocketAddr>>,
+
+    /// Dropping this sender stops the server
+    shutdown: Mutex<Option<async_channel::Sender<()>>>,
+
+    /// Closes once the server, its connections and its requests have ended
+    finished: Mutex<Option<async_channel::Receiver<()>>>,
+
     /// Whether the daemon is running
     running: Arc<std::sync::atomic::AtomicBool>,
 }
@@ -73,9 +93,14 @@ impl BaseDaemon {
         &self.service_registry
     }
 
-    /// Get the endpoint
+    /// Get the endpoint, resolved to the bound address while running
     pub fn endpoint(&self) -> SocketAddr {
-        self.endpoint
+        self.local_addr.lock().unwrap().unwrap_or(self.endpoint)
+    }
+
+    /// Check whether the WebSocket server is running
+    pub fn is_running(&self) -> bool {
+        self.running.load(std::sync::atomic::Ordering::SeqCst)
     }
 
     /// Get the service stack
@@ -87,6 +112,11 @@ impl BaseDaemon {
     pub fn task_stack(&self) -> &TaskStack {
         &self.task_stack
     }
+
+    /// Get the schema this daemon publishes to clients
+    pub fn schema(&self) -> DaemonSchema {
+        DaemonSchema::collect(&self.action_registry, &self.service_stack, &self.task_stack)
+    }
 }
 
 #[async_trait]
@@ -95,8 +125,13 @@ impl Action for BaseDaemon {
         &self.action_registry
     }
 
+    /// # Panics
+    ///
+    /// Panics if called while the WebSocket server is running, since the
+    /// server holds a shared reference to the registry.
     fn actions_mut(&mut self) -> &mut ActionRegistry {
-        &mut self.action_registry
+        Arc::get_mut(&mut self.action_registry)
+            .expect("actions cannot be registered while the daemon is serving")
     }
 }
 
@@ -105,35 +140,57 @@ impl Daemon for BaseDaemon {
     async fn start(&self) -> Result<()> {
         info!("Starting base daemon on {}", self.endpoint);
 
+        if self.is_running() {
+            return Err(Error::daemon("Daemon is already running"));
+        }
+
+        let (tracker, shutdown_tx, finished_rx) = TaskTracker::new(self.spawner.clone());
+        let state = ServerState {
+            actions: self.action_registry.clone(),
+            services: self.service_stack.clone(),
+            tasks: self.task_stack.clone(),
+            spawner: self.spawner.clone(),
+            tracker: tracker.clone(),
+        };
+        let server = Server::bind(self.endpoint, self.tls_config.as_ref(), state).await?;
+        let local_addr = server.local_addr()?;
+
+        *self.shutdown.lock().unwrap() = Some(shutdown_tx);
+        *self.finished.lock().unwrap() = Some(finished_rx);
+        *self.local_addr.lock().unwrap() = Some(local_addr);
+
+        tracker.spawn(server.run());
+
         // Mark as running
         self.running
             .store(true, std::sync::atomic::Ordering::SeqCst);
 
-        // TODO: Start WebSocket server
-        // This would start the WebSocket server that handles:
-        // - Service management requests
-        // - Action discovery and invocation
-        // - Event streaming
-
-        info!("Base daemon started successfully");
+        info!("Base daemon started successfully on {}", local_addr);
         Ok(())
     }
 
     async fn stop(&self) -> Result<()> {
         info!("Stopping base daemon");
 
+        // Closing the shutdown channel cancels the server, its connections
+        // and their requests; wait for all of them to be dropped
+        self.shutdown.lock().unwrap().take();
+        let finished = self.finished.lock().unwrap().take();
+        if let Some(finished) = finished {
+            let _ = finished.recv().await;
+        }
+        self.local_addr.lock().unwrap().take();
+
         // Mark as stopped
         self.running
             .store(false, std::sync::atomic::Ordering::SeqCst);
 
-        // TODO: Stop WebSocket server and cleanup
-
         info!("Base daemon stopped");
         Ok(())
     }
 
     fn endpoint(&self) -> SocketAddr {
-        self.endpoint
+        BaseDaemon::endpoint(self)
     }
 
     fn service_manager(&self) -> &ServiceManager {
@@ -153,6 +210,8 @@ pub struct DaemonBuilder {
     service_stack: ServiceStack,
     task_stack: TaskStack,
     config: Option<Value>,
+    tls_config: Option<TlsServerConfig>,
+    spawner: Arc<dyn Spawner>,
     #[cfg(test)]
     test_mode: bool,
 }
@@ -167,6 +226,8 @@ impl DaemonBuilder {
             service_stack: ServiceStack::new(),
             task_stack: TaskStack::new(),
             config: None,
+            tls_config: None,
+            spawner: Arc::new(SmolSpawner),
             #[cfg(test)]
             test_mode: false,
         }
@@ -185,6 +246,20 @@ impl DaemonBuilder {
         self
     }
 
+    /// Serve the WebSocket endpoint over TLS
+    pub fn with_tls(mut self, tls_config: TlsServerConfig) -> Self {
+        self.tls_config = Some(tls_config);
+        self
+    }
+
+    /// Set the spawner used to run the WebSocket server and its requests
+    ///
+    /// Defaults to the smol spawner.
+    pub fn with_spawner(mut self, spawner: impl Spawner + 'static) -> Self {
+        self.spawner = Arc::new(spawner);
+        self
+    }
+
     /// Set the registry persistence path
     pub fn with_registry_path(mut self, path: impl Into<String>) -> Self {
         self.registry_path = Some(path.into());
@@ -201,6 +276,11 @@ impl DaemonBuilder {
         &mut self.task_stack
     }
 
+    /// Get the schema the built daemon will publish, without building it
+    pub fn schema(&self) -> DaemonSchema {
+        DaemonSchema::collect(&self.action_registry, &self.service_stack, &self.task_stack)
+    }
+
     /// Set configuration for validation
     pub fn with_config(mut self, config: Value) -> Self {
         self.config = Some(config);
@@ -223,6 +303,16 @@ impl DaemonBuilder {
         Ok(self)
     }
 
+    /// Register an action with its parameter and return schemas
+    pub fn register_action_with_info<F, Fut>(mut self, info: ActionInfo, action: F) -> Result<Self>
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<Value>> + Send + 'static,
+    {
+        self.action_registry.register(info, action)?;
+        Ok(self)
+    }
+
     /// Build the daemon
     pub async fn build(self) -> Result<BaseDaemon> {
         info!("Building daemon with endpoint {}", self.endpoint);
@@ -280,10 +370,15 @@ impl DaemonBuilder {
         Ok(BaseDaemon {
             service_manager,
             service_registry,
-            action_registry: self.action_registry,
-            service_stack: self.service_stack,
-            task_stack: self.task_stack,
+            action_registry: Arc::new(self.action_registry),
+            service_stack: Arc::new(self.service_stack),
+            task_stack: Arc::new(self.task_stack),
             endpoint: self.endpoint,
+            tls_config: self.tls_config,
+            spawner: self.spawner,
+            local_addr: Mutex::new(None),
+            shutdown: Mutex::new(None),
+            finished: Mutex::new(None),
             running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
         })
     }
@@ -514,6 +609,27 @@ mod tests {
         assert_eq!(result, json!({ "result": { "message": "hello" } }));
     }
 
+    #[smol_potat::test]
+    async fn test_action_with_info() {
+        let info = ActionInfo::new("sum", "Add numbers")
+            .with_params_schema(json!({ "type": "array", "items": { "type": "integer" } }));
+        let builder = BaseDaemon::builder()
+            .with_test_mode()
+            .register_action_with_info(info, |params| async move {
+                let numbers: Vec<i64> = serde_json::from_value(params)?;
+                Ok::<_, Error>(json!(numbers.iter().sum::<i64>()))
+            })
+            .unwrap();
+
+        let schema = builder.schema();
+        assert_eq!(schema.actions[0].name, "sum");
+        assert!(schema.actions[0].params_schema.is_some());
+
+        let daemon = builder.build().await.unwrap();
+        let result = daemon.invoke_action("sum", json!([1, 2, 3])).await.unwrap();
+        assert_eq!(result, json!(6));
+    }
+
     #[smol_potat::test]
     async fn test_validation_missing_service_type() {
         let config = json!({
@@ -8,8 +8,12 @@
 
 pub mod action;
 pub mod client;
+pub mod codegen;
 pub mod daemon;
 pub mod error;
+pub mod protocol;
+pub mod schema;
+mod server;
 pub mod service;
 pub mod task;
 pub mod typed_action;
@@ -19,7 +23,7 @@ pub use error::{Error, Result};
 /// Convenience prelude for harness-core users
 pub mod prelude {
     pub use crate::action::{Action, ActionRegistry};
-    pub use crate::client::TestClient;
+    pub use crate::client::{Client, EventStream, TestClient, TypedEventStream};
     pub use crate::daemon::{BaseDaemon, Daemon};
     pub use crate::error::{Error, Result};
     pub use crate::service::{
@@ -40,4 +44,4 @@ pub mod prelude {
 // Re-export key types from existing crates for convenience
 pub use harness_config::Config;
 pub use service_orchestration::{ServiceConfig, ServiceManager, ServiceStatus};
-pub use service_registry::Registry;
+pub use service_registry::{Registry, TlsClientConfig, TlsServerConfig};
@@ -0,0 +1,294 @@
+//! Wire protocol spoken by `BaseDaemon`'s WebSocket server
+//!
+//! Every client request carries an `id`. Requests that stream (service
+//! dispatch and task execution) are answered with zero or more
+//! [`ServerMessage::Event`] frames followed by exactly one terminal
+//! [`ServerMessage::Result`] or [`ServerMessage::Error`] frame with the same
+//! `id`. All other requests get just the terminal frame.
+//!
+//! ```json
+//! {"id":"1","method":"invoke_action","params":{"action":"echo","params":{}}}
+//! {"type":"result","id":"1","data":{}}
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::action::{ActionInfo, ActionRegistry};
+use crate::service::{ActionDescriptor, ServiceStack, ServiceState};
+use crate::task::TaskStack;
+
+/// A request sent by a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientRequest {
+    /// Correlates the server's frames with this request
+    pub id: String,
+
+    /// What to do
+    #[serde(flatten)]
+    pub method: Method,
+}
+
+/// Request methods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum Method {
+    /// List registered daemon actions
+    ListActions,
+
+    /// Invoke a daemon action
+    InvokeAction {
+        /// Action name
+        action: String,
+        /// Action parameters
+        #[serde(default)]
+        params: Value,
+    },
+
+    /// List service instances in the service stack
+    ListServices,
+
+    /// Get the state of a service instance
+    ServiceState {
+        /// Service instance name
+        service: String,
+    },
+
+    /// Dispatch an action to a service instance, streaming its events
+    Dispatch {
+        /// Service instance name
+        service: String,
+        /// Action name
+        action: String,
+        /// Action input
+        #[serde(default)]
+        input: Value,
+    },
+
+    /// List task instances in the task stack
+    ListTasks,
+
+    /// Execute a task, streaming its events
+    ExecuteTask {
+        /// Task instance name
+        task: String,
+        /// Task input
+        #[serde(default)]
+        input: Value,
+    },
+
+    /// Get the daemon's published schema
+    Schema,
+}
+
+/// A frame sent by the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Incremental event from a streaming request
+    Event {
+        /// Request id
+        id: String,
+        /// Event payload
+        data: Value,
+    },
+
+    /// Successful completion of a request
+    Result {
+        /// Request id
+        id: String,
+        /// Result payload
+        data: Value,
+    },
+
+    /// Failed request
+    Error {
+        /// Request id (empty if the request could not be parsed)
+        id: String,
+        /// Error message
+        message: String,
+    },
+}
+
+impl ServerMessage {
+    /// Request id this frame belongs to
+    pub fn id(&self) -> &str {
+        match self {
+            ServerMessage::Event { id, .. }
+            | ServerMessage::Result { id, .. }
+            | ServerMessage::Error { id, .. } => id,
+        }
+    }
+
+    /// Whether this is the last frame for its request
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, ServerMessage::Event { .. })
+    }
+}
+
+/// Service instance as listed by [`Method::ListServices`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceInfo {
+    /// Instance name in the service stack
+    pub instance: String,
+    /// Service name
+    pub name: String,
+    /// Service description
+    pub description: String,
+    /// Current state
+    pub state: ServiceState,
+    /// Actions the service accepts
+    pub actions: Vec<ActionDescriptor>,
+}
+
+/// Task instance as listed by [`Method::ListTasks`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskInfo {
+    /// Instance name in the task stack
+    pub instance: String,
+    /// Task name
+    pub name: String,
+    /// Task description
+    pub description: String,
+    /// Whether the task has already completed
+    pub completed: bool,
+    /// JSON schema for the task input
+    pub input_schema: Value,
+    /// JSON schema for the task events
+    pub event_schema: Value,
+}
+
+/// Everything a daemon exposes, as returned by [`Method::Schema`]
+///
+/// Unlike [`ServiceInfo`] and [`TaskInfo`] this carries no runtime state, so
+/// it is stable enough to generate typed clients from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonSchema {
+    /// Daemon actions in registration order
+    pub actions: Vec<ActionInfo>,
+    /// Service instances, sorted by instance name
+    pub services: Vec<ServiceSchema>,
+    /// Task instances, sorted by instance name
+    pub tasks: Vec<TaskSchema>,
+}
+
+impl DaemonSchema {
+    /// Collect the schema of a daemon's registries
+    pub(crate) fn collect(
+        actions: &ActionRegistry,
+        services: &ServiceStack,
+        tasks: &TaskStack,
+    ) -> Self {
+        let mut service_schemas: Vec<ServiceSchema> = services
+            .list()
+            .into_iter()
+            .map(|(instance, service)| ServiceSchema {
+                instance: instance.to_string(),
+                name: service.name().to_string(),
+                description: service.description().to_string(),
+                actions: service.available_actions(),
+            })
+            .collect();
+        service_schemas.sort_by(|a, b| a.instance.cmp(&b.instance));
+
+        let mut task_schemas: Vec<TaskSchema> = tasks
+            .list()
+            .into_iter()
+            .map(|(instance, task)| TaskSchema {
+                instance: instance.to_string(),
+                name: task.name().to_string(),
+                description: task.description().to_string(),
+                input_schema: task.action_schema().clone(),
+                event_schema: task.event_schema().clone(),
+            })
+            .collect();
+        task_schemas.sort_by(|a, b| a.instance.cmp(&b.instance));
+
+        Self {
+            actions: actions.list_actions().into_iter().cloned().collect(),
+            services: service_schemas,
+            tasks: task_schemas,
+        }
+    }
+}
+
+/// Service instance in a [`DaemonSchema`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceSchema {
+    /// Instance name in the service stack
+    pub instance: String,
+    /// Service name
+    pub name: String,
+    /// Service description
+    pub description: String,
+    /// Actions the service accepts
+    pub actions: Vec<ActionDescriptor>,
+}
+
+/// Task instance in a [`DaemonSchema`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSchema {
+    /// Instance name in the task stack
+    pub instance: String,
+    /// Task name
+    pub name: String,
+    /// Task description
+    pub description: String,
+    /// JSON schema for the task input
+    pub input_schema: Value,
+    /// JSON schema for the task events
+    pub event_schema: Value,
+}
+
+/// Summary returned in the final frame of a streaming request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamSummary {
+    /// Number of event frames sent
+    pub events: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_wire_format() {
+        let request: ClientRequest = serde_json::from_value(json!({
+            "id": "1",
+            "method": "dispatch",
+            "params": { "service": "anvil", "action": "mine", "input": { "blocks": 2 } }
+        }))
+        .unwrap();
+
+        assert_eq!(request.id, "1");
+        assert!(matches!(
+            request.method,
+            Method::Dispatch { ref service, .. } if service == "anvil"
+        ));
+
+        // Parameterless methods need no params
+        let request: ClientRequest =
+            serde_json::from_value(json!({ "id": "2", "method": "list_actions" })).unwrap();
+        assert!(matches!(request.method, Method::ListActions));
+    }
+
+    #[test]
+    fn test_terminal_frames() {
+        let event = ServerMessage::Event {
+            id: "1".to_string(),
+            data: json!({}),
+        };
+        let result = ServerMessage::Result {
+            id: "1".to_string(),
+            data: json!(null),
+        };
+
+        assert!(!event.is_terminal());
+        assert!(result.is_terminal());
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            json!({ "type": "result", "id": "1", "data": null })
+        );
+    }
+}
@@ -0,0 +1,599 @@
+//! JSON schema helpers for action inputs
+//!
+//! Action and event schemas are derived with `schemars`, so these helpers
+//! cover the subset of JSON Schema it produces rather than the whole
+//! specification: types, `required`, `properties`, `additionalProperties`,
+//! `items`, `const`/`enum`, `oneOf`/`anyOf`/`allOf`, numeric bounds and local
+//! `$ref`s. Other keywords are accepted without checking.
+
+use serde_json::{Map, Value};
+
+use crate::{Error, Result};
+
+/// Guard against `$ref` cycles
+const MAX_DEPTH: usize = 64;
+
+/// One variant of an internally tagged enum, e.g. `AnvilAction::MineBlocks`
+#[derive(Debug, Clone)]
+pub struct Variant {
+    /// Tag value selecting this variant
+    pub name: String,
+    /// Variant description
+    pub description: Option<String>,
+    /// Parameters other than the tag
+    pub params: Vec<Param>,
+}
+
+/// A named parameter of an object schema
+#[derive(Debug, Clone)]
+pub struct Param {
+    /// Property name
+    pub name: String,
+    /// Short human-readable type, e.g. `integer` or `array of string`
+    pub ty: String,
+    /// Whether the property must be present
+    pub required: bool,
+    /// Property description
+    pub description: Option<String>,
+    /// Property schema
+    pub schema: Value,
+}
+
+/// Internally tagged enum described by a schema
+#[derive(Debug, Clone)]
+pub struct TaggedEnum {
+    /// Property holding the variant name, e.g. `type`
+    pub tag: String,
+    /// Variants in schema order
+    pub variants: Vec<Variant>,
+}
+
+impl TaggedEnum {
+    /// Find a variant by tag value, matched with [`names_match`]
+    pub fn variant(&self, name: &str) -> Option<&Variant> {
+        self.variants.iter().find(|v| names_match(&v.name, name))
+    }
+}
+
+/// Compare names ignoring case and separators, so `MineBlocks`,
+/// `mine_blocks` and `mine-blocks` are the same
+pub fn names_match(a: &str, b: &str) -> bool {
+    let normalize = |name: &str| -> String {
+        name.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    normalize(a) == normalize(b)
+}
+
+/// Describe a schema as an internally tagged enum, if it is one
+pub fn tagged_enum(schema: &Value) -> Option<TaggedEnum> {
+    let alternatives = one_of(schema.as_object()?)?;
+    let tag = common_tag(&alternatives)?;
+
+    let variants = alternatives
+        .iter()
+        .filter_map(Value::as_object)
+        .map(|object| Variant {
+            name: tag_value(object, &tag).unwrap_or_default(),
+            description: description(object),
+            params: params(object, schema)
+                .into_iter()
+                .filter(|p| p.name != tag)
+                .collect(),
+        })
+        .collect();
+
+    Some(TaggedEnum { tag, variants })
+}
+
+/// Parameters of an object schema, required ones first
+pub fn params(object: &Map<String, Value>, root: &Value) -> Vec<Param> {
+    let Some(properties) = object.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let required: Vec<&str> = object
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut params: Vec<Param> = properties
+        .iter()
+        .map(|(name, schema)| Param {
+            name: name.clone(),
+            ty: describe_type(schema, root),
+            required: required.contains(&name.as_str()),
+            description: schema.as_object().and_then(description),
+            schema: schema.clone(),
+        })
+        .collect();
+    params.sort_by_key(|p| {
+        (
+            !p.required,
+            required
+                .iter()
+                .position(|r| *r == p.name)
+                .unwrap_or(usize::MAX),
+        )
+    });
+    params
+}
+
+/// Short human-readable type of a schema
+pub fn describe_type(schema: &Value, root: &Value) -> String {
+    let Some(object) = schema.as_object() else {
+        return "any".to_string();
+    };
+
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        return match resolve(root, reference) {
+            Some((name, _)) => name.to_string(),
+            None => "any".to_string(),
+        };
+    }
+    if let Some(inner) = nullable(object) {
+        return format!("{}?", describe_type(&inner, root));
+    }
+    if let Some(values) = string_enum(object) {
+        return values.join(" | ");
+    }
+
+    match object.get("type").and_then(Value::as_str) {
+        Some("array") => match object.get("items") {
+            Some(items) => format!("array of {}", describe_type(items, root)),
+            None => "array".to_string(),
+        },
+        Some(ty) => ty.to_string(),
+        None if object.contains_key("oneOf") || object.contains_key("anyOf") => {
+            "one of several".to_string()
+        }
+        None => "any".to_string(),
+    }
+}
+
+/// Check a value against a schema
+///
+/// Every problem found is reported in a single [`Error::Validation`], each
+/// prefixed with the path to the offending value.
+pub fn validate(schema: &Value, value: &Value) -> Result<()> {
+    let mut errors = Vec::new();
+    check(schema, value, schema, "$", &mut errors, 0);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::validation(errors.join("; ")))
+    }
+}
+
+fn check(
+    schema: &Value,
+    value: &Value,
+    root: &Value,
+    path: &str,
+    errors: &mut Vec<String>,
+    depth: usize,
+) {
+    let object = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(format!("{}: no value is allowed here", path));
+            return;
+        }
+        Value::Object(object) => object,
+        _ => return,
+    };
+    if depth > MAX_DEPTH {
+        return;
+    }
+
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        match resolve(root, reference) {
+            Some((_, target)) => check(target, value, root, path, errors, depth + 1),
+            None => errors.push(format!("{}: unresolvable reference {}", path, reference)),
+        }
+    }
+
+    if let Some(all) = object.get("allOf").and_then(Value::as_array) {
+        for schema in all {
+            check(schema, value, root, path, errors, depth + 1);
+        }
+    }
+
+    if let Some(alternatives) = one_of(object) {
+        check_alternatives(schema, &alternatives, value, root, path, errors, depth);
+    }
+
+    if let Some(expected) = object.get("const")
+        && value != expected
+    {
+        errors.push(format!("{}: expected {}, got {}", path, expected, value));
+    }
+    if let Some(allowed) = object.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+        errors.push(format!(
+            "{}: expected one of {}, got {}",
+            path,
+            allowed.join(", "),
+            value
+        ));
+    }
+
+    if let Some(ty) = object.get("type") {
+        let types: Vec<&str> = match ty {
+            Value::String(ty) => vec![ty.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|ty| has_type(value, ty)) {
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                path,
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = object.get("minimum").and_then(Value::as_f64)
+            && number < minimum
+        {
+            errors.push(format!("{}: {} is less than {}", path, value, minimum));
+        }
+        if let Some(maximum) = object.get("maximum").and_then(Value::as_f64)
+            && number > maximum
+        {
+            errors.push(format!("{}: {} is greater than {}", path, value, maximum));
+        }
+    }
+
+    if let Value::Object(fields) = value {
+        check_object(object, fields, root, path, errors, depth);
+    }
+
+    if let (Value::Array(items), Some(schema)) = (value, object.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check(
+                schema,
+                item,
+                root,
+                &format!("{}[{}]", path, i),
+                errors,
+                depth + 1,
+            );
+        }
+    }
+}
+
+fn check_object(
+    object: &Map<String, Value>,
+    fields: &Map<String, Value>,
+    root: &Value,
+    path: &str,
+    errors: &mut Vec<String>,
+    depth: usize,
+) {
+    if let Some(required) = object.get("required").and_then(Value::as_array) {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !fields.contains_key(name) {
+                errors.push(format!("{}: missing required field '{}'", path, name));
+            }
+        }
+    }
+
+    let properties = object.get("properties").and_then(Value::as_object);
+    for (name, field) in fields {
+        let field_path = format!("{}.{}", path, name);
+        match properties.and_then(|p| p.get(name)) {
+            Some(schema) => check(schema, field, root, &field_path, errors, depth + 1),
+            None => match object.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    errors.push(format!("{}: unknown field '{}'", path, name));
+                }
+                Some(schema) => check(schema, field, root, &field_path, errors, depth + 1),
+                None => {}
+            },
+        }
+    }
+}
+
+/// `oneOf`/`anyOf`, with focused errors for tagged enums
+fn check_alternatives(
+    schema: &Value,
+    alternatives: &[Value],
+    value: &Value,
+    root: &Value,
+    path: &str,
+    errors: &mut Vec<String>,
+    depth: usize,
+) {
+    let matches = |alternative: &Value| {
+        let mut errors = Vec::new();
+        check(alternative, value, root, path, &mut errors, depth + 1);
+        errors
+    };
+
+    if alternatives.iter().any(|a| matches(a).is_empty()) {
+        return;
+    }
+
+    // Report against the variant the tag selects rather than all of them
+    if let Some(tagged) = tagged_enum(schema) {
+        let names: Vec<&str> = tagged.variants.iter().map(|v| v.name.as_str()).collect();
+        match value.get(&tagged.tag).and_then(Value::as_str) {
+            Some(name) => match alternatives.iter().find(|a| {
+                a.as_object()
+                    .and_then(|o| tag_value(o, &tagged.tag))
+                    .is_some_and(|v| v == name)
+            }) {
+                Some(alternative) => errors.extend(matches(alternative)),
+                None => errors.push(format!(
+                    "{}: unknown {} '{}' (expected one of {})",
+                    path,
+                    tagged.tag,
+                    name,
+                    names.join(", ")
+                )),
+            },
+            None => errors.push(format!(
+                "{}: missing required field '{}' (one of {})",
+                path,
+                tagged.tag,
+                names.join(", ")
+            )),
+        }
+        return;
+    }
+
+    errors.push(format!(
+        "{}: does not match any of the allowed schemas",
+        path
+    ));
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Resolve a local `$ref` to its definition name and schema
+pub(crate) fn resolve<'a>(root: &'a Value, reference: &'a str) -> Option<(&'a str, &'a Value)> {
+    let name = reference
+        .strip_prefix("#/$defs/")
+        .or_else(|| reference.strip_prefix("#/definitions/"))?;
+    let target = root
+        .get("$defs")
+        .or_else(|| root.get("definitions"))?
+        .get(name)?;
+    Some((name, target))
+}
+
+/// Inner schema of `T | null`
+pub(crate) fn nullable(object: &Map<String, Value>) -> Option<Value> {
+    if let Some(types) = object.get("type").and_then(Value::as_array) {
+        let non_null: Vec<&Value> = types.iter().filter(|t| *t != "null").collect();
+        if non_null.len() == 1 && types.len() == 2 {
+            let mut inner = object.clone();
+            inner.insert("type".to_string(), non_null[0].clone());
+            return Some(Value::Object(inner));
+        }
+        return None;
+    }
+
+    let alternatives = object
+        .get("anyOf")
+        .or_else(|| object.get("oneOf"))?
+        .as_array()?;
+    if alternatives.len() != 2 {
+        return None;
+    }
+    let is_null = |v: &Value| v.get("type").and_then(Value::as_str) == Some("null");
+    match (is_null(&alternatives[0]), is_null(&alternatives[1])) {
+        (true, false) => Some(alternatives[1].clone()),
+        (false, true) => Some(alternatives[0].clone()),
+        _ => None,
+    }
+}
+
+/// Alternatives of a `oneOf` or `anyOf` schema
+pub(crate) fn one_of(object: &Map<String, Value>) -> Option<Vec<Value>> {
+    object
+        .get("oneOf")
+        .or_else(|| object.get("anyOf"))
+        .and_then(Value::as_array)
+        .cloned()
+}
+
+/// Values of a string enum, from `enum` or `const`
+pub(crate) fn string_enum(object: &Map<String, Value>) -> Option<Vec<String>> {
+    if let Some(value) = object.get("const").and_then(Value::as_str) {
+        return Some(vec![value.to_string()]);
+    }
+    object
+        .get("enum")?
+        .as_array()?
+        .iter()
+        .map(|v| v.as_str().map(str::to_string))
+        .collect()
+}
+
+/// Constant value of a variant's tag property
+pub(crate) fn tag_value(object: &Map<String, Value>, tag: &str) -> Option<String> {
+    let property = object.get("properties")?.get(tag)?.as_object()?;
+    match string_enum(property)?.as_slice() {
+        [value] => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// Tag property shared by every alternative of an internally tagged enum
+pub(crate) fn common_tag(alternatives: &[Value]) -> Option<String> {
+    let first = alternatives.first()?.as_object()?;
+    let candidates = first.get("properties")?.as_object()?.keys();
+
+    candidates
+        .filter(|tag| {
+            alternatives.iter().all(|alternative| {
+                alternative.as_object().is_some_and(|object| {
+                    tag_value(object, tag).is_some()
+                        && object
+                            .get("required")
+                            .and_then(Value::as_array)
+                            .is_some_and(|r| r.iter().any(|k| k == *tag))
+                })
+            })
+        })
+        .min_by_key(|tag| tag.as_str() != "type")
+        .cloned()
+}
+
+/// Whether an object schema declares any properties
+pub(crate) fn has_properties(object: &Map<String, Value>) -> bool {
+    object
+        .get("properties")
+        .and_then(Value::as_object)
+        .is_some_and(|p| !p.is_empty())
+}
+
+/// The schema's `description`
+pub(crate) fn description(object: &Map<String, Value>) -> Option<String> {
+    object
+        .get("description")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    /// Test actions
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[serde(tag = "type")]
+    #[allow(dead_code)]
+    enum TestAction {
+        /// Mine blocks
+        MineBlocks {
+            count: u64,
+            interval_secs: Option<u64>,
+        },
+        /// Set labels
+        Label {
+            labels: Vec<Label>,
+        },
+        Reset,
+    }
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Label {
+        key: String,
+        value: bool,
+    }
+
+    fn schema() -> Value {
+        serde_json::to_value(schemars::schema_for!(TestAction)).unwrap()
+    }
+
+    fn error(value: Value) -> String {
+        validate(&schema(), &value).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_tagged_enum_variants() {
+        let tagged = tagged_enum(&schema()).unwrap();
+        assert_eq!(tagged.tag, "type");
+
+        let names: Vec<&str> = tagged.variants.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["MineBlocks", "Label", "Reset"]);
+
+        let mine = tagged.variant("mine_blocks").unwrap();
+        assert_eq!(mine.description.as_deref(), Some("Mine blocks"));
+        let params: Vec<(&str, &str, bool)> = mine
+            .params
+            .iter()
+            .map(|p| (p.name.as_str(), p.ty.as_str(), p.required))
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                ("count", "integer", true),
+                ("interval_secs", "integer?", false)
+            ]
+        );
+
+        let label = tagged.variant("label").unwrap();
+        assert_eq!(label.params[0].ty, "array of Label");
+        assert!(tagged.variant("reset").unwrap().params.is_empty());
+        assert!(tagged.variant("missing").is_none());
+    }
+
+    #[test]
+    fn test_validate_accepts_valid_input() {
+        let schema = schema();
+        for value in [
+            json!({ "type": "MineBlocks", "count": 3 }),
+            json!({ "type": "MineBlocks", "count": 3, "interval_secs": null }),
+            json!({ "type": "Label", "labels": [{ "key": "a", "value": true }] }),
+            json!({ "type": "Reset" }),
+        ] {
+            validate(&schema, &value).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_problems_in_selected_variant() {
+        let message = error(json!({ "type": "MineBlocks", "count": "three" }));
+        assert!(message.contains("$.count: expected integer, got string"));
+
+        let message = error(json!({ "type": "MineBlocks" }));
+        assert!(message.contains("missing required field 'count'"));
+
+        let message = error(json!({ "type": "MineBlocks", "count": -1 }));
+        assert!(message.contains("$.count: -1 is less than 0"));
+
+        let message = error(json!({ "type": "Label", "labels": [{ "key": "a", "value": 1 }] }));
+        assert!(message.contains("$.labels[0].value: expected boolean, got integer"));
+    }
+
+    #[test]
+    fn test_validate_reports_unknown_variant() {
+        let message = error(json!({ "type": "Explode" }));
+        assert!(message.contains("unknown type 'Explode'"));
+        assert!(message.contains("MineBlocks, Label, Reset"));
+
+        let message = error(json!({ "count": 1 }));
+        assert!(message.contains("missing required field 'type'"));
+    }
+}
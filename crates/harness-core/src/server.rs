@@ -0,0 +1,335 @@
+//! WebSocket server behind `BaseDaemon`
+//!
+//! Serves a daemon's [`ActionRegistry`], [`ServiceStack`] and [`TaskStack`]
+//! using the wire format in [`crate::protocol`]. Each request runs as its own
+//! task on the daemon's spawner, so a long event stream doesn't block other
+//! requests on the same connection. Those tasks, and the connections they
+//! belong to, are tracked so that stopping the daemon cancels and awaits them.
+
+use async_channel::{Receiver, Sender};
+use async_net::{TcpListener, TcpStream};
+use async_runtime_compat::Spawner;
+use async_tungstenite::tungstenite::Message;
+use async_tungstenite::{WebSocketStream, accept_async};
+use futures::StreamExt;
+use futures::future::{self, Either};
+use futures::io::{AsyncRead, AsyncWrite};
+use serde_json::Value;
+use service_registry::TlsServerConfig;
+use service_registry::tls::TlsAcceptor;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::pin;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use crate::action::ActionRegistry;
+use crate::protocol::{
+    ClientRequest, DaemonSchema, Method, ServerMessage, ServiceInfo, StreamSummary, TaskInfo,
+};
+use crate::service::ServiceStack;
+use crate::task::TaskStack;
+use crate::{Error, Result};
+
+/// Everything a connection needs to answer requests
+#[derive(Clone)]
+pub(crate) struct ServerState {
+    pub actions: Arc<ActionRegistry>,
+    pub services: Arc<ServiceStack>,
+    pub tasks: Arc<TaskStack>,
+    pub spawner: Arc<dyn Spawner>,
+    pub tracker: TaskTracker,
+}
+
+/// Spawns the server's tasks so they can be cancelled and awaited together
+///
+/// Every tracked task holds a clone of `alive`; once the shutdown channel is
+/// closed each task drops its future, and the receiver returned by
+/// [`TaskTracker::new`] reports closed after the last one has finished.
+#[derive(Clone)]
+pub(crate) struct TaskTracker {
+    spawner: Arc<dyn Spawner>,
+    shutdown: Receiver<()>,
+    alive: Sender<()>,
+}
+
+impl TaskTracker {
+    /// Create a tracker, the sender that stops its tasks when dropped, and
+    /// the receiver that closes when they have all finished
+    pub fn new(spawner: Arc<dyn Spawner>) -> (Self, Sender<()>, Receiver<()>) {
+        let (shutdown_tx, shutdown_rx) = async_channel::bounded(1);
+        let (alive_tx, alive_rx) = async_channel::bounded(1);
+        let tracker = Self {
+            spawner,
+            shutdown: shutdown_rx,
+            alive: alive_tx,
+        };
+        (tracker, shutdown_tx, alive_rx)
+    }
+
+    /// Spawn a task that is cancelled on shutdown
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let shutdown = self.shutdown.clone();
+        let alive = self.alive.clone();
+        self.spawner.spawn(Box::pin(async move {
+            let _alive = alive;
+            let task = pin!(task);
+            let stopped = pin!(shutdown.recv());
+            future::select(task, stopped).await;
+        }));
+    }
+}
+
+/// Bound, not yet running, daemon server
+pub(crate) struct Server {
+    listener: TcpListener,
+    tls: Option<TlsAcceptor>,
+    state: ServerState,
+}
+
+impl Server {
+    /// Bind the listener
+    pub async fn bind(
+        endpoint: SocketAddr,
+        tls: Option<&TlsServerConfig>,
+        state: ServerState,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind(endpoint).await?;
+        info!(
+            "Daemon WebSocket server listening on {} ({})",
+            listener.local_addr()?,
+            if tls.is_some() { "TLS" } else { "no TLS" }
+        );
+
+        Ok(Self {
+            listener,
+            tls: tls.map(|config| TlsAcceptor::from(config.config.clone())),
+            state,
+        })
+    }
+
+    /// Address the listener is bound to
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accept connections, each served on its own tracked task
+    ///
+    /// Run this on the state's tracker so that shutdown stops it along with
+    /// the open connections and their requests.
+    pub async fn run(self) {
+        loop {
+            let (stream, peer) = match self.listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+
+            let tls = self.tls.clone();
+            let state = self.state.clone();
+            self.state.tracker.spawn(async move {
+                if let Err(e) = serve_connection(stream, peer, tls, state).await {
+                    debug!("Connection from {} ended with error: {}", peer, e);
+                }
+            });
+        }
+    }
+}
+
+/// Complete the TLS and WebSocket handshakes, then serve requests
+async fn serve_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    tls: Option<TlsAcceptor>,
+    state: ServerState,
+) -> Result<()> {
+    match tls {
+        Some(acceptor) => {
+            let stream = acceptor.accept(stream).await?;
+            let ws = accept_async(stream)
+                .await
+                .map_err(|e| Error::websocket(e.to_string()))?;
+            debug!("New daemon connection from {} (TLS)", peer);
+            serve(ws, peer, state).await
+        }
+        None => {
+            let ws = accept_async(stream)
+                .await
+                .map_err(|e| Error::websocket(e.to_string()))?;
+            debug!("New daemon connection from {} (plain)", peer);
+            serve(ws, peer, state).await
+        }
+    }
+}
+
+/// Read requests and write frames until the client goes away
+async fn serve<S>(ws: WebSocketStream<S>, peer: SocketAddr, state: ServerState) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (sink, mut incoming) = ws.split();
+    let (frames_tx, frames_rx) = async_channel::unbounded::<ServerMessage>();
+
+    let writer = async move {
+        while let Ok(frame) = frames_rx.recv().await {
+            let text = serde_json::to_string(&frame)?;
+            sink.send(Message::Text(text.into()))
+                .await
+                .map_err(|e| Error::websocket(e.to_string()))?;
+        }
+        Ok::<_, Error>(())
+    };
+
+    let reader = async move {
+        while let Some(message) = incoming.next().await {
+            let text = match message {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => break,
+                Ok(_) => continue,
+                Err(e) => return Err(Error::websocket(e.to_string())),
+            };
+
+            match serde_json::from_str::<ClientRequest>(&text) {
+                Ok(request) => {
+                    debug!("Request {} from {}: {:?}", request.id, peer, request.method);
+                    let state_for_request = state.clone();
+                    let frames = frames_tx.clone();
+                    state.tracker.spawn(async move {
+                        handle_request(state_for_request, request, frames).await;
+                    });
+                }
+                Err(e) => {
+                    let _ = frames_tx
+                        .send(ServerMessage::Error {
+                            id: String::new(),
+                            message: format!("Invalid request: {}", e),
+                        })
+                        .await;
+                }
+            }
+        }
+        Ok(())
+    };
+
+    let result = match future::select(pin!(reader), pin!(writer)).await {
+        Either::Left((result, _)) | Either::Right((result, _)) => result,
+    };
+    debug!("Daemon connection from {} closed", peer);
+    result
+}
+
+/// Run one request and send its terminal frame
+async fn handle_request(state: ServerState, request: ClientRequest, frames: Sender<ServerMessage>) {
+    let id = request.id;
+    let frame = match respond(&state, &id, request.method, &frames).await {
+        Ok(data) => ServerMessage::Result { id, data },
+        Err(e) => ServerMessage::Error {
+            id,
+            message: e.to_string(),
+        },
+    };
+
+    // The client may already be gone
+    let _ = frames.send(frame).await;
+}
+
+async fn respond(
+    state: &ServerState,
+    id: &str,
+    method: Method,
+    frames: &Sender<ServerMessage>,
+) -> Result<Value> {
+    match method {
+        Method::ListActions => Ok(serde_json::to_value(state.actions.list_actions())?),
+
+        Method::InvokeAction { action, params } => state.actions.invoke(&action, params).await,
+
+        Method::ListServices => {
+            let mut services = Vec::new();
+            for (instance, service) in state.services.list() {
+                services.push(ServiceInfo {
+                    instance: instance.to_string(),
+                    name: service.name().to_string(),
+                    description: service.description().to_string(),
+                    state: service.get_state().await?,
+                    actions: service.available_actions(),
+                });
+            }
+            services.sort_by(|a, b| a.instance.cmp(&b.instance));
+            Ok(serde_json::to_value(services)?)
+        }
+
+        Method::ServiceState { service } => {
+            let instance = state.services.get(&service).ok_or_else(|| {
+                Error::service_type(format!("Service instance '{}' not found", service))
+            })?;
+            Ok(serde_json::to_value(instance.get_state().await?)?)
+        }
+
+        Method::Dispatch {
+            service,
+            action,
+            input,
+        } => {
+            let events = state
+                .services
+                .dispatch(&service, &action, input, state.spawner.as_ref())
+                .await?;
+            stream_events(id, events, frames).await
+        }
+
+        Method::ListTasks => {
+            let mut tasks = Vec::new();
+            for (instance, task) in state.tasks.list() {
+                tasks.push(TaskInfo {
+                    instance: instance.to_string(),
+                    name: task.name().to_string(),
+                    description: task.description().to_string(),
+                    completed: task.is_completed().await?,
+                    input_schema: task.action_schema().clone(),
+                    event_schema: task.event_schema().clone(),
+                });
+            }
+            tasks.sort_by(|a, b| a.instance.cmp(&b.instance));
+            Ok(serde_json::to_value(tasks)?)
+        }
+
+        Method::ExecuteTask { task, input } => {
+            let events = state
+                .tasks
+                .execute(&task, input, state.spawner.as_ref())
+                .await?;
+            stream_events(id, events, frames).await
+        }
+
+        Method::Schema => Ok(serde_json::to_value(DaemonSchema::collect(
+            &state.actions,
+            &state.services,
+            &state.tasks,
+        ))?),
+    }
+}
+
+/// Forward events as incremental frames until the stream ends
+async fn stream_events(
+    id: &str,
+    events: Receiver<Value>,
+    frames: &Sender<ServerMessage>,
+) -> Result<Value> {
+    let mut count = 0;
+
+    while let Ok(data) = events.recv().await {
+        frames
+            .send(ServerMessage::Event {
+                id: id.to_string(),
+                data,
+            })
+            .await
+            .map_err(|_| Error::websocket("Connection closed while streaming events"))?;
+        count += 1;
+    }
+
+    Ok(serde_json::to_value(StreamSummary { events: count })?)
+}
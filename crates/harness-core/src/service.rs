@@ -559,7 +559,7 @@ impl ServiceStack {
     }
 
     /// Dispatch an action to a specific service instance
-    pub async fn dispatch<Sp: Spawner>(
+    pub async fn dispatch<Sp: Spawner + ?Sized>(
         &self,
         instance_name: &str,
         action_name: &str,
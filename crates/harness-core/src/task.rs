@@ -291,7 +291,7 @@ impl TaskStack {
     }
 
     /// Execute a task
-    pub async fn execute<S: Spawner>(
+    pub async fn execute<S: Spawner + ?Sized>(
         &self,
         instance_name: &str,
         input: Value,
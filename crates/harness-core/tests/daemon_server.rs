@@ -0,0 +1,253 @@
+//! End-to-end tests for the BaseDaemon WebSocket server
+
+use async_channel::Receiver;
+use async_net::TcpStream;
+use async_runtime_compat::smol::SmolSpawner;
+use async_trait::async_trait;
+use async_tungstenite::client_async;
+use async_tungstenite::tungstenite::Message;
+use futures::StreamExt;
+use harness_core::daemon::DaemonBuilder;
+use harness_core::prelude::*;
+use harness_core::protocol::{ServerMessage, ServiceInfo};
+use schemars::JsonSchema;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct CountAction {
+    to: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct CountEvent {
+    n: u32,
+}
+
+struct Counter;
+
+#[async_trait]
+impl Service for Counter {
+    type Action = CountAction;
+    type Event = CountEvent;
+
+    fn service_type() -> &'static str {
+        "counter"
+    }
+
+    fn name(&self) -> &str {
+        "counter"
+    }
+
+    fn description(&self) -> &str {
+        "Counts up to a number"
+    }
+
+    async fn dispatch_action(&self, action: Self::Action) -> Result<Receiver<Self::Event>> {
+        let (tx, rx) = async_channel::unbounded();
+        for n in 1..=action.to {
+            tx.send(CountEvent { n }).await.unwrap();
+        }
+        Ok(rx)
+    }
+}
+
+type Ws = async_tungstenite::WebSocketStream<TcpStream>;
+
+async fn start_daemon() -> BaseDaemon {
+    let dir = tempfile::tempdir().unwrap().keep();
+
+    let mut builder = DaemonBuilder::new()
+        .with_endpoint("127.0.0.1:0".parse().unwrap())
+        .with_spawner(SmolSpawner)
+        .with_registry_path(dir.join("registry").to_string_lossy().to_string());
+    builder
+        .service_stack_mut()
+        .register("counter-1".to_string(), Counter)
+        .unwrap();
+
+    let daemon = builder
+        .register_action("echo", "Echo the input", |params| async move {
+            Ok(json!({ "echo": params }))
+        })
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    daemon.start().await.unwrap();
+    daemon
+}
+
+async fn connect(daemon: &BaseDaemon) -> Ws {
+    let endpoint = daemon.endpoint();
+    let stream = TcpStream::connect(endpoint).await.unwrap();
+    let (ws, _) = client_async(format!("ws://{}", endpoint), stream)
+        .await
+        .unwrap();
+    ws
+}
+
+async fn send(ws: &mut Ws, request: Value) {
+    ws.send(Message::Text(request.to_string().into()))
+        .await
+        .unwrap();
+}
+
+async fn recv(ws: &mut Ws) -> ServerMessage {
+    loop {
+        match ws.next().await.unwrap().unwrap() {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            _ => continue,
+        }
+    }
+}
+
+#[smol_potat::test]
+async fn test_invoke_action_over_websocket() {
+    let daemon = start_daemon().await;
+    assert!(daemon.is_running());
+    assert_ne!(daemon.endpoint().port(), 0);
+
+    let mut ws = connect(&daemon).await;
+
+    send(
+        &mut ws,
+        json!({ "id": "1", "method": "invoke_action", "params": { "action": "echo", "params": { "x": 1 } } }),
+    )
+    .await;
+    match recv(&mut ws).await {
+        ServerMessage::Result { id, data } => {
+            assert_eq!(id, "1");
+            assert_eq!(data, json!({ "echo": { "x": 1 } }));
+        }
+        other => panic!("Expected result, got {:?}", other),
+    }
+
+    send(
+        &mut ws,
+        json!({ "id": "2", "method": "invoke_action", "params": { "action": "missing" } }),
+    )
+    .await;
+    match recv(&mut ws).await {
+        ServerMessage::Error { id, message } => {
+            assert_eq!(id, "2");
+            assert!(message.contains("not found"));
+        }
+        other => panic!("Expected error, got {:?}", other),
+    }
+
+    daemon.stop().await.unwrap();
+}
+
+#[smol_potat::test]
+async fn test_dispatch_streams_events() {
+    let daemon = start_daemon().await;
+    let mut ws = connect(&daemon).await;
+
+    send(
+        &mut ws,
+        json!({ "id": "services", "method": "list_services" }),
+    )
+    .await;
+    let services: Vec<ServiceInfo> = match recv(&mut ws).await {
+        ServerMessage::Result { data, .. } => serde_json::from_value(data).unwrap(),
+        other => panic!("Expected result, got {:?}", other),
+    };
+    assert_eq!(services.len(), 1);
+    assert_eq!(services[0].instance, "counter-1");
+
+    send(
+        &mut ws,
+        json!({
+            "id": "count",
+            "method": "dispatch",
+            "params": { "service": "counter-1", "action": "default", "input": { "to": 3 } }
+        }),
+    )
+    .await;
+
+    let mut seen = Vec::new();
+    loop {
+        let frame = recv(&mut ws).await;
+        assert_eq!(frame.id(), "count");
+        match frame {
+            ServerMessage::Event { data, .. } => seen.push(data["n"].as_u64().unwrap()),
+            ServerMessage::Result { data, .. } => {
+                assert_eq!(data, json!({ "events": 3 }));
+                break;
+            }
+            ServerMessage::Error { message, .. } => panic!("Dispatch failed: {}", message),
+        }
+    }
+    assert_eq!(seen, vec![1, 2, 3]);
+
+    daemon.stop().await.unwrap();
+}
+
+#[smol_potat::test]
+async fn test_stop_closes_server() {
+    let daemon = start_daemon().await;
+    let endpoint = daemon.endpoint();
+    let mut ws = connect(&daemon).await;
+
+    daemon.stop().await.unwrap();
+    assert!(!daemon.is_running());
+
+    // The open connection is dropped and no new ones are accepted
+    assert!(matches!(ws.next().await, None | Some(Err(_))));
+    smol::Timer::after(std::time::Duration::from_millis(50)).await;
+    assert!(TcpStream::connect(endpoint).await.is_err());
+}
+
+/// Signals on the channel when dropped
+struct DropSignal(async_channel::Sender<()>);
+
+impl Drop for DropSignal {
+    fn drop(&mut self) {
+        let _ = self.0.try_send(());
+    }
+}
+
+#[smol_potat::test]
+async fn test_stop_cancels_requests() {
+    let (dropped_tx, dropped_rx) = async_channel::bounded::<()>(1);
+    let (started_tx, started_rx) = async_channel::bounded::<()>(1);
+
+    // No spawner configured, so the daemon runs on the default one
+    let daemon = DaemonBuilder::new()
+        .with_endpoint("127.0.0.1:0".parse().unwrap())
+        .with_registry_path(
+            tempfile::tempdir()
+                .unwrap()
+                .keep()
+                .join("registry")
+                .to_string_lossy()
+                .to_string(),
+        )
+        .register_action("hang", "Never returns", move |_| {
+            let signal = DropSignal(dropped_tx.clone());
+            let started = started_tx.clone();
+            async move {
+                let _signal = signal;
+                let _ = started.send(()).await;
+                futures::future::pending::<()>().await;
+                Ok(Value::Null)
+            }
+        })
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    daemon.start().await.unwrap();
+
+    let mut ws = connect(&daemon).await;
+    send(
+        &mut ws,
+        json!({ "id": "1", "method": "invoke_action", "params": { "action": "hang" } }),
+    )
+    .await;
+    started_rx.recv().await.unwrap();
+
+    // stop() returns only after the hung request has been dropped
+    daemon.stop().await.unwrap();
+    assert!(dropped_rx.try_recv().is_ok());
+}
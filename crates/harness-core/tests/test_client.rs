@@ -0,0 +1,359 @@
+//! TestClient against a running BaseDaemon
+
+use async_channel::Receiver;
+use async_runtime_compat::smol::SmolSpawner;
+use async_trait::async_trait;
+use futures::StreamExt;
+use harness_core::daemon::DaemonBuilder;
+use harness_core::prelude::*;
+use harness_core::{TlsClientConfig, TlsServerConfig};
+use schemars::JsonSchema;
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct CountAction {
+    to: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct CountEvent {
+    n: u32,
+}
+
+struct Counter;
+
+#[async_trait]
+impl Service for Counter {
+    type Action = CountAction;
+    type Event = CountEvent;
+
+    fn service_type() -> &'static str {
+        "counter"
+    }
+
+    fn name(&self) -> &str {
+        "counter"
+    }
+
+    fn description(&self) -> &str {
+        "Counts up to a number"
+    }
+
+    async fn dispatch_action(&self, action: Self::Action) -> Result<Receiver<Self::Event>> {
+        let (tx, rx) = async_channel::unbounded();
+        for n in 1..=action.to {
+            tx.send(CountEvent { n }).await.unwrap();
+        }
+        Ok(rx)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+enum IndexerAction {
+    WaitForSync {
+        deployment: String,
+        block: Option<u64>,
+        timeout: Option<u64>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "event")]
+enum IndexerEvent {
+    DeploymentProgress {
+        deployment_id: String,
+        percent: u8,
+    },
+    Synced {
+        deployment_id: String,
+        block_number: u64,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Indexes up to block 100; deployment `QmBroken` has failed
+struct Indexer;
+
+#[async_trait]
+impl Service for Indexer {
+    type Action = IndexerAction;
+    type Event = IndexerEvent;
+
+    fn service_type() -> &'static str {
+        "indexer"
+    }
+
+    fn name(&self) -> &str {
+        "indexer"
+    }
+
+    fn description(&self) -> &str {
+        "Pretends to index subgraphs"
+    }
+
+    async fn dispatch_action(&self, action: Self::Action) -> Result<Receiver<Self::Event>> {
+        let (tx, rx) = async_channel::unbounded();
+        let IndexerAction::WaitForSync {
+            deployment, block, ..
+        } = action;
+
+        if deployment == "QmBroken" {
+            tx.send(IndexerEvent::Error {
+                message: "Deployment QmBroken is failed (health: failed)".to_string(),
+            })
+            .await
+            .unwrap();
+        } else {
+            tx.send(IndexerEvent::DeploymentProgress {
+                deployment_id: deployment.clone(),
+                percent: 50,
+            })
+            .await
+            .unwrap();
+            tx.send(IndexerEvent::Synced {
+                deployment_id: deployment,
+                block_number: block.unwrap_or(100),
+            })
+            .await
+            .unwrap();
+        }
+        Ok(rx)
+    }
+}
+
+async fn start_daemon() -> BaseDaemon {
+    start_daemon_with(|builder| builder).await
+}
+
+async fn start_daemon_with(configure: impl FnOnce(DaemonBuilder) -> DaemonBuilder) -> BaseDaemon {
+    let dir = tempfile::tempdir().unwrap().keep();
+
+    let mut builder = configure(
+        DaemonBuilder::new()
+            .with_endpoint("127.0.0.1:0".parse().unwrap())
+            .with_spawner(SmolSpawner)
+            .with_registry_path(dir.join("registry").to_string_lossy().to_string()),
+    );
+    builder
+        .service_stack_mut()
+        .register("counter-1".to_string(), Counter)
+        .unwrap();
+    builder
+        .service_stack_mut()
+        .register("indexer".to_string(), Indexer)
+        .unwrap();
+
+    let daemon = builder
+        .register_action("echo", "Echo the input", |params| async move {
+            Ok(json!({ "echo": params }))
+        })
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+
+    daemon.start().await.unwrap();
+    daemon
+}
+
+#[smol_potat::test]
+async fn test_invoke_and_list_actions() {
+    let daemon = start_daemon().await;
+    let client = TestClient::connect(daemon.endpoint()).await.unwrap();
+    assert!(client.is_connected());
+
+    let result = client.action("echo", json!({ "x": 1 })).await.unwrap();
+    assert_eq!(result, json!({ "echo": { "x": 1 } }));
+
+    let err = client.action("missing", json!({})).await.unwrap_err();
+    assert!(err.to_string().contains("not found"));
+
+    let actions = client.actions().await.unwrap();
+    let echo = actions.iter().find(|a| a.name == "echo").unwrap();
+    assert_eq!(echo.description, "Echo the input");
+    assert_eq!(client.list_actions().await.unwrap().len(), actions.len());
+
+    client.disconnect().await.unwrap();
+    assert!(!client.is_connected());
+    assert!(client.action("echo", json!({})).await.is_err());
+
+    daemon.stop().await.unwrap();
+}
+
+/// Self-signed `localhost` certificate as a server config and a client
+/// config that trusts it
+fn self_signed_tls() -> (TlsServerConfig, TlsClientConfig) {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_der = rustls::pki_types::CertificateDer::from(cert.serialize_der().unwrap());
+    let key = rustls::pki_types::PrivateKeyDer::try_from(cert.serialize_private_key_der()).unwrap();
+
+    let server = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert_der.clone()], key)
+        .unwrap();
+
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(cert_der).unwrap();
+    let client = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    (
+        TlsServerConfig {
+            config: Arc::new(server),
+        },
+        TlsClientConfig {
+            config: Arc::new(client),
+        },
+    )
+}
+
+#[smol_potat::test]
+async fn test_connect_tls() {
+    let (server_tls, client_tls) = self_signed_tls();
+    let daemon = start_daemon_with(|builder| builder.with_tls(server_tls)).await;
+
+    let client = TestClient::connect_tls(daemon.endpoint(), client_tls, "localhost")
+        .await
+        .unwrap();
+    let result = client.action("echo", json!({ "x": 1 })).await.unwrap();
+    assert_eq!(result, json!({ "echo": { "x": 1 } }));
+
+    let events: Vec<_> = client
+        .dispatch("counter-1", "default", json!({ "to": 2 }))
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(events.len(), 2);
+
+    // A plain WebSocket handshake is refused
+    assert!(TestClient::connect(daemon.endpoint()).await.is_err());
+
+    client.disconnect().await.unwrap();
+    daemon.stop().await.unwrap();
+}
+
+#[smol_potat::test]
+async fn test_dispatch_streams_events() {
+    let daemon = start_daemon().await;
+    let client = TestClient::connect(daemon.endpoint()).await.unwrap();
+
+    let services = client.services().await.unwrap();
+    let counter = services.iter().find(|s| s.instance == "counter-1").unwrap();
+    assert!(!counter.actions.is_empty());
+
+    let events: Vec<Value> = client
+        .dispatch("counter-1", "default", json!({ "to": 3 }))
+        .await
+        .unwrap()
+        .map(|event| event.unwrap())
+        .collect()
+        .await;
+    let seen: Vec<u64> = events.iter().map(|e| e["n"].as_u64().unwrap()).collect();
+    assert_eq!(seen, vec![1, 2, 3]);
+
+    // Failures arrive as the last item of the stream
+    let mut events = client
+        .dispatch("missing", "default", json!({}))
+        .await
+        .unwrap();
+    assert!(events.next().await.unwrap().is_err());
+    assert!(events.next().await.is_none());
+
+    daemon.stop().await.unwrap();
+}
+
+#[smol_potat::test]
+async fn test_concurrent_requests_share_connection() {
+    let daemon = start_daemon().await;
+    let client = TestClient::connect(daemon.endpoint()).await.unwrap();
+
+    let stream = client
+        .dispatch("counter-1", "default", json!({ "to": 5 }))
+        .await
+        .unwrap();
+    let (events, echo) = futures::join!(
+        stream.collect::<Vec<_>>(),
+        client.action("echo", json!("hi"))
+    );
+
+    assert_eq!(events.len(), 5);
+    assert!(events.iter().all(|e| e.is_ok()));
+    assert_eq!(echo.unwrap(), json!({ "echo": "hi" }));
+
+    daemon.stop().await.unwrap();
+}
+
+#[smol_potat::test]
+async fn test_wait_helpers() {
+    let daemon = start_daemon().await;
+    let client = TestClient::connect(daemon.endpoint()).await.unwrap();
+
+    let state = client.service_state("counter-1").await.unwrap();
+    assert!(state.is_healthy());
+
+    client
+        .wait_for_service_state("counter-1", "running", 1000)
+        .await
+        .unwrap();
+    client.wait_for_healthy(1000).await.unwrap();
+
+    let err = client
+        .wait_for_service_state("counter-1", "Failed", 200)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Timeout"));
+
+    daemon.stop().await.unwrap();
+}
+
+#[smol_potat::test]
+async fn test_typed_event_stream() {
+    let daemon = start_daemon().await;
+    let client = TestClient::connect(daemon.endpoint()).await.unwrap();
+
+    let schema = client.schema().await.unwrap();
+    assert!(schema.services.iter().any(|s| s.name == "counter"));
+    assert!(schema.actions.iter().any(|a| a.name == "echo"));
+
+    let events: Vec<CountEvent> = client
+        .dispatch("counter-1", "default", json!({ "to": 2 }))
+        .await
+        .unwrap()
+        .typed::<CountEvent>()
+        .map(|event| event.unwrap())
+        .collect()
+        .await;
+    assert_eq!(events.iter().map(|e| e.n).collect::<Vec<_>>(), vec![1, 2]);
+
+    daemon.stop().await.unwrap();
+}
+
+#[smol_potat::test]
+async fn test_wait_for_subgraph_sync() {
+    let daemon = start_daemon().await;
+    let client = TestClient::connect(daemon.endpoint()).await.unwrap();
+
+    let block = client
+        .wait_for_subgraph_sync("indexer", "QmSubgraph", Some(42), 1000)
+        .await
+        .unwrap();
+    assert_eq!(block, 42);
+    let block = client
+        .wait_for_subgraph_sync("indexer", "QmSubgraph", None, 1000)
+        .await
+        .unwrap();
+    assert_eq!(block, 100);
+
+    let err = client
+        .wait_for_subgraph_sync("indexer", "QmBroken", None, 1000)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("health: failed"));
+
+    daemon.stop().await.unwrap();
+}
@@ -0,0 +1,593 @@
+//! Discover and invoke domain actions on an action daemon
+//!
+//! Action daemons are `BaseDaemon`s such as `graph-test-daemon`. Services
+//! whose input is a tagged enum (e.g. `AnvilAction`) expose each variant as
+//! its own action, so `MineBlocks` is invoked as
+//! `harness action invoke anvil MineBlocks --param count=3`.
+
+use anyhow::{Context, Result, anyhow, bail};
+use comfy_table::{Cell, Table};
+use futures::StreamExt;
+use harness_core::client::{Client, TestClient};
+use harness_core::protocol::DaemonSchema;
+use harness_core::schema::{self, Param};
+use serde_json::{Map, Value, json};
+use std::net::SocketAddr;
+use std::path::Path;
+
+/// Default action daemon endpoint (the `graph-test-daemon` default)
+pub const DEFAULT_ACTION_ENDPOINT: &str = "127.0.0.1:9443";
+
+/// Pseudo-service that daemon-level actions are listed under
+const DAEMON: &str = "daemon";
+
+/// Where an action is sent
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    /// Daemon-level action from the action registry
+    Daemon { action: String },
+    /// Service instance action
+    Service { instance: String, action: String },
+    /// Task instance
+    Task { instance: String },
+}
+
+/// An invocable action with its parameters
+#[derive(Debug, Clone)]
+struct Entry {
+    service: String,
+    action: String,
+    description: String,
+    params: Vec<Param>,
+    /// Tag property and value selecting an enum variant
+    tag: Option<(String, String)>,
+    input_schema: Option<Value>,
+    event_schema: Option<Value>,
+    target: Target,
+}
+
+/// List actions with their parameters
+pub async fn list(endpoint: SocketAddr, service: Option<&str>, format: &str) -> Result<()> {
+    let client = connect(endpoint).await?;
+    let schema = client.schema().await?;
+    client.disconnect().await?;
+
+    let entries: Vec<Entry> = catalog(&schema)
+        .into_iter()
+        .filter(|e| service.is_none_or(|s| e.service == s))
+        .collect();
+    if let Some(service) = service
+        && entries.is_empty()
+    {
+        bail!("No actions found for '{}'", service);
+    }
+
+    match format {
+        "json" => {
+            let entries: Vec<Value> = entries
+                .iter()
+                .map(|e| {
+                    json!({
+                        "service": e.service,
+                        "action": e.action,
+                        "description": e.description,
+                        "params": e.params.iter().map(|p| json!({
+                            "name": p.name,
+                            "type": p.ty,
+                            "required": p.required,
+                            "description": p.description,
+                            "schema": p.schema,
+                        })).collect::<Vec<_>>(),
+                        "input_schema": e.input_schema,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        }
+        _ => {
+            if entries.is_empty() {
+                println!("No actions available");
+                return Ok(());
+            }
+
+            let mut table = Table::new();
+            table.set_header(vec!["SERVICE", "ACTION", "PARAMETERS", "DESCRIPTION"]);
+
+            for entry in &entries {
+                table.add_row(vec![
+                    Cell::new(&entry.service),
+                    Cell::new(&entry.action),
+                    Cell::new(describe_params(&entry.params)),
+                    Cell::new(&entry.description),
+                ]);
+            }
+
+            println!("{}", table);
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke an action, streaming its events to stdout
+///
+/// Fails if the input doesn't match the action's schema, the daemon reports
+/// an error, or the action emits an `Error` event.
+pub async fn invoke(
+    endpoint: SocketAddr,
+    service: &str,
+    action: &str,
+    params: &[String],
+    json_file: Option<&Path>,
+) -> Result<()> {
+    let client = connect(endpoint).await?;
+    let schema = client.schema().await?;
+    let entries = catalog(&schema);
+    let entry = find(&entries, service, action)?;
+
+    let input = build_input(entry, params, json_file)?;
+    if let Some(input_schema) = &entry.input_schema {
+        schema::validate(input_schema, &input)
+            .map_err(|e| anyhow!("Invalid input for {} {}: {}", service, entry.action, e))?;
+    }
+
+    let mut events = match &entry.target {
+        Target::Daemon { action } => {
+            let result = client.action(action, input).await?;
+            println!("{}", serde_json::to_string_pretty(&result)?);
+            client.disconnect().await?;
+            return Ok(());
+        }
+        Target::Service { instance, action } => client.dispatch(instance, action, input).await?,
+        Target::Task { instance } => client.execute_task(instance, input).await?,
+    };
+
+    let mut count = 0;
+    let mut failure = None;
+    while let Some(event) = events.next().await {
+        let event = event?;
+        println!("{}", serde_json::to_string(&event)?);
+        count += 1;
+
+        if failure.is_none() {
+            failure = error_message(&event, entry.event_schema.as_ref());
+        }
+    }
+    client.disconnect().await?;
+
+    match failure {
+        Some(message) => bail!("{} {} failed: {}", service, entry.action, message),
+        None => {
+            eprintln!(
+                "✓ {} {} completed ({} events)",
+                service, entry.action, count
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn connect(endpoint: SocketAddr) -> Result<TestClient> {
+    TestClient::connect(endpoint).await.map_err(|e| {
+        anyhow!(
+            "Cannot connect to an action daemon at {}: {}\n\n\
+            Start one with e.g.:\n  \
+            graph-test-daemon --config <stack.yaml> --endpoint {}",
+            endpoint,
+            e,
+            endpoint
+        )
+    })
+}
+
+/// Flatten a daemon schema into invocable actions
+fn catalog(schema: &DaemonSchema) -> Vec<Entry> {
+    let mut entries = Vec::new();
+
+    for action in &schema.actions {
+        entries.push(Entry {
+            service: DAEMON.to_string(),
+            action: action.name.clone(),
+            description: action.description.clone(),
+            params: object_params(action.params_schema.as_ref()),
+            tag: None,
+            input_schema: action.params_schema.clone(),
+            event_schema: None,
+            target: Target::Daemon {
+                action: action.name.clone(),
+            },
+        });
+    }
+
+    for service in &schema.services {
+        for descriptor in &service.actions {
+            let target = Target::Service {
+                instance: service.instance.clone(),
+                action: descriptor.name.clone(),
+            };
+            entries.extend(expand(
+                &service.instance,
+                &descriptor.name,
+                &descriptor.description,
+                &descriptor.input_schema,
+                &descriptor.event_schema,
+                target,
+            ));
+        }
+    }
+
+    for task in &schema.tasks {
+        let target = Target::Task {
+            instance: task.instance.clone(),
+        };
+        entries.extend(expand(
+            &task.instance,
+            "execute",
+            &task.description,
+            &task.input_schema,
+            &task.event_schema,
+            target,
+        ));
+    }
+
+    entries
+}
+
+/// One entry per variant of a tagged-enum input, or a single entry otherwise
+fn expand(
+    service: &str,
+    action: &str,
+    description: &str,
+    input_schema: &Value,
+    event_schema: &Value,
+    target: Target,
+) -> Vec<Entry> {
+    let Some(tagged) = schema::tagged_enum(input_schema) else {
+        return vec![Entry {
+            service: service.to_string(),
+            action: action.to_string(),
+            description: description.to_string(),
+            params: object_params(Some(input_schema)),
+            tag: None,
+            input_schema: Some(input_schema.clone()),
+            event_schema: Some(event_schema.clone()),
+            target,
+        }];
+    };
+
+    tagged
+        .variants
+        .into_iter()
+        .map(|variant| Entry {
+            service: service.to_string(),
+            action: variant.name.clone(),
+            description: variant
+                .description
+                .unwrap_or_else(|| description.to_string()),
+            params: variant.params,
+            tag: Some((tagged.tag.clone(), variant.name)),
+            input_schema: Some(input_schema.clone()),
+            event_schema: Some(event_schema.clone()),
+            target: target.clone(),
+        })
+        .collect()
+}
+
+fn object_params(schema: Option<&Value>) -> Vec<Param> {
+    match schema.and_then(Value::as_object) {
+        Some(object) => schema::params(object, schema.unwrap_or(&Value::Null)),
+        None => Vec::new(),
+    }
+}
+
+fn find<'a>(entries: &'a [Entry], service: &str, action: &str) -> Result<&'a Entry> {
+    let for_service: Vec<&Entry> = entries.iter().filter(|e| e.service == service).collect();
+    if for_service.is_empty() {
+        let mut services: Vec<&str> = entries.iter().map(|e| e.service.as_str()).collect();
+        services.dedup();
+        bail!(
+            "Unknown service '{}' (available: {})",
+            service,
+            services.join(", ")
+        );
+    }
+
+    for_service
+        .iter()
+        .find(|e| e.action == action)
+        .or_else(|| {
+            for_service
+                .iter()
+                .find(|e| schema::names_match(&e.action, action))
+        })
+        .copied()
+        .ok_or_else(|| {
+            let actions: Vec<&str> = for_service.iter().map(|e| e.action.as_str()).collect();
+            anyhow!(
+                "Unknown action '{}' for {} (available: {})",
+                action,
+                service,
+                actions.join(", ")
+            )
+        })
+}
+
+/// Merge `--json` and `--param` input and add the variant tag
+fn build_input(entry: &Entry, params: &[String], json_file: Option<&Path>) -> Result<Value> {
+    let mut input = match json_file {
+        Some(path) => {
+            let text = if path == Path::new("-") {
+                std::io::read_to_string(std::io::stdin())?
+            } else {
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?
+            };
+            serde_json::from_str(&text)
+                .with_context(|| format!("Invalid JSON in {}", path.display()))?
+        }
+        None if entry.tag.is_some() || !entry.params.is_empty() => Value::Object(Map::new()),
+        None => Value::Null,
+    };
+
+    if !params.is_empty() {
+        let Value::Object(fields) = &mut input else {
+            bail!("--param can only be used with object input");
+        };
+        for param in params {
+            let (name, value) = parse_param(entry, param)?;
+            fields.insert(name, value);
+        }
+    }
+
+    if let Some((tag, variant)) = &entry.tag {
+        match &mut input {
+            Value::Object(fields) => {
+                fields.insert(tag.clone(), Value::String(variant.clone()));
+            }
+            _ => bail!("Input for {} must be a JSON object", entry.action),
+        }
+    }
+
+    Ok(input)
+}
+
+/// Parse `name=value`, reading the value as JSON unless the parameter is a string
+fn parse_param(entry: &Entry, param: &str) -> Result<(String, Value)> {
+    let (name, raw) = param
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid parameter '{}': expected name=value", param))?;
+
+    let known = entry.params.iter().find(|p| p.name == name);
+    if known.is_none() && !entry.params.is_empty() {
+        let names: Vec<&str> = entry.params.iter().map(|p| p.name.as_str()).collect();
+        bail!(
+            "Unknown parameter '{}' for {} (expected {})",
+            name,
+            entry.action,
+            names.join(", ")
+        );
+    }
+
+    let is_string = known
+        .is_some_and(|p| p.ty.trim_end_matches('?') == "string" || p.schema.get("enum").is_some());
+    let value = if is_string {
+        Value::String(raw.to_string())
+    } else {
+        serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+    };
+
+    Ok((name.to_string(), value))
+}
+
+/// Message of an `Error` event
+fn error_message(event: &Value, event_schema: Option<&Value>) -> Option<String> {
+    let tag = event_schema
+        .and_then(schema::tagged_enum)
+        .map(|tagged| tagged.tag)
+        .unwrap_or_else(|| "event".to_string());
+
+    let kind = event.get(&tag)?.as_str()?;
+    if !schema::names_match(kind, "error") {
+        return None;
+    }
+
+    Some(
+        event
+            .get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| event.to_string()),
+    )
+}
+
+fn describe_params(params: &[Param]) -> String {
+    if params.is_empty() {
+        return "-".to_string();
+    }
+
+    params
+        .iter()
+        .map(|p| {
+            if p.required {
+                format!("{}: {}", p.name, p.ty)
+            } else {
+                format!("[{}: {}]", p.name, p.ty)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harness_core::action::ActionInfo;
+    use harness_core::protocol::{ServiceSchema, TaskSchema};
+    use harness_core::service::ActionDescriptor;
+
+    fn anvil_input() -> Value {
+        json!({
+            "oneOf": [
+                {
+                    "description": "Mine a number of blocks",
+                    "type": "object",
+                    "properties": {
+                        "count": { "type": "integer", "format": "uint64", "minimum": 0 },
+                        "interval_secs": { "type": ["integer", "null"], "minimum": 0 },
+                        "type": { "type": "string", "const": "MineBlocks" }
+                    },
+                    "required": ["type", "count"]
+                },
+                {
+                    "description": "Set account balance",
+                    "type": "object",
+                    "properties": {
+                        "address": { "type": "string" },
+                        "balance": { "type": "string" },
+                        "type": { "type": "string", "const": "SetBalance" }
+                    },
+                    "required": ["type", "address", "balance"]
+                }
+            ]
+        })
+    }
+
+    fn anvil_events() -> Value {
+        json!({
+            "oneOf": [
+                {
+                    "type": "object",
+                    "properties": {
+                        "block_number": { "type": "integer" },
+                        "event": { "type": "string", "const": "BlockMined" }
+                    },
+                    "required": ["event", "block_number"]
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "message": { "type": "string" },
+                        "event": { "type": "string", "const": "Error" }
+                    },
+                    "required": ["event", "message"]
+                }
+            ]
+        })
+    }
+
+    fn daemon_schema() -> DaemonSchema {
+        DaemonSchema {
+            actions: vec![ActionInfo::new("health-check", "Check health")],
+            services: vec![ServiceSchema {
+                instance: "anvil".to_string(),
+                name: "anvil".to_string(),
+                description: "Anvil".to_string(),
+                actions: vec![ActionDescriptor {
+                    name: "default".to_string(),
+                    description: "Anvil".to_string(),
+                    input_schema: anvil_input(),
+                    event_schema: anvil_events(),
+                }],
+            }],
+            tasks: vec![TaskSchema {
+                instance: "deploy".to_string(),
+                name: "deploy".to_string(),
+                description: "Deploy contracts".to_string(),
+                input_schema: json!({ "type": "null" }),
+                event_schema: json!(true),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_catalog_expands_tagged_inputs() {
+        let entries = catalog(&daemon_schema());
+        let names: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|e| (e.service.as_str(), e.action.as_str()))
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                ("daemon", "health-check"),
+                ("anvil", "MineBlocks"),
+                ("anvil", "SetBalance"),
+                ("deploy", "execute"),
+            ]
+        );
+        assert_eq!(
+            describe_params(&entries[1].params),
+            "count: integer\n[interval_secs: integer?]"
+        );
+    }
+
+    #[test]
+    fn test_find_matches_loosely() {
+        let entries = catalog(&daemon_schema());
+
+        assert_eq!(
+            find(&entries, "anvil", "mine_blocks").unwrap().action,
+            "MineBlocks"
+        );
+        assert_eq!(
+            find(&entries, "anvil", "mine-blocks").unwrap().action,
+            "MineBlocks"
+        );
+
+        let err = find(&entries, "anvil", "explode").unwrap_err().to_string();
+        assert!(err.contains("MineBlocks, SetBalance"));
+        let err = find(&entries, "ipfs", "pin").unwrap_err().to_string();
+        assert!(err.contains("Unknown service 'ipfs'"));
+    }
+
+    #[test]
+    fn test_build_input_from_params() {
+        let entries = catalog(&daemon_schema());
+
+        let mine = find(&entries, "anvil", "MineBlocks").unwrap();
+        let input = build_input(mine, &["count=3".to_string()], None).unwrap();
+        assert_eq!(input, json!({ "type": "MineBlocks", "count": 3 }));
+        schema::validate(mine.input_schema.as_ref().unwrap(), &input).unwrap();
+
+        // String parameters are taken verbatim, even if they look like JSON
+        let balance = find(&entries, "anvil", "SetBalance").unwrap();
+        let input = build_input(
+            balance,
+            &["address=0x01".to_string(), "balance=100".to_string()],
+            None,
+        )
+        .unwrap();
+        assert_eq!(input["balance"], json!("100"));
+
+        let err = build_input(mine, &["blocks=3".to_string()], None).unwrap_err();
+        assert!(err.to_string().contains("Unknown parameter 'blocks'"));
+
+        let input = build_input(mine, &["count=many".to_string()], None).unwrap();
+        assert!(schema::validate(mine.input_schema.as_ref().unwrap(), &input).is_err());
+
+        let deploy = find(&entries, "deploy", "execute").unwrap();
+        assert_eq!(build_input(deploy, &[], None).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_error_events() {
+        let events = anvil_events();
+
+        assert_eq!(
+            error_message(
+                &json!({ "event": "Error", "message": "boom" }),
+                Some(&events)
+            ),
+            Some("boom".to_string())
+        );
+        assert_eq!(
+            error_message(
+                &json!({ "event": "BlockMined", "block_number": 1 }),
+                Some(&events)
+            ),
+            None
+        );
+        assert_eq!(error_message(&json!(42), None), None);
+    }
+}
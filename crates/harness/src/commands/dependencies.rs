@@ -241,6 +241,7 @@ mod tests {
                     args: vec![],
                     working_dir: None,
                     user: None,
+                    limits: None,
                 },
                 network: "local".to_string(),
                 env: HashMap::new(),
@@ -259,6 +260,7 @@ mod tests {
                     args: vec![],
                     working_dir: None,
                     user: None,
+                    limits: None,
                 },
                 network: "local".to_string(),
                 env: HashMap::new(),
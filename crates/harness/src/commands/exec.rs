@@ -0,0 +1,505 @@
+//! Run a command in a service's execution context
+//!
+//! The command runs where the service does: `docker exec` in its container,
+//! over SSH on its remote host, or locally as its user, with its environment
+//! and working directory. Its output is streamed as it runs and its exit code becomes
+//! ours. With `-t` it gets a pseudo-terminal sized to ours, which is put in
+//! raw mode for the session and kept in sync when resized.
+
+use crate::commands::client;
+use anyhow::{Context, Result, anyhow, bail};
+use command_executor::backends::{LocalLauncher, LocalProcessHandle};
+use command_executor::layered::{DockerLayer, LayeredExecutor, LocalLayer, SshLayer};
+use command_executor::{
+    Command, ManagedProcess, ProcessEvent, ProcessEventType, ProcessHandle, PtySize, Target,
+};
+use futures::io::AsyncReadExt;
+use futures::{Stream, StreamExt};
+use harness::protocol::{Request, Response};
+use harness_config::{parser, resolver};
+use nix::sys::termios::{self, SetArg, Termios};
+use service_orchestration::{RemoteMode, ServiceTarget};
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// How often the terminal size is checked for changes
+const RESIZE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Run `command` in the context of `service`, returning its exit code
+pub async fn run(
+    config_path: &Path,
+    service: &str,
+    command: &[String],
+    interactive: bool,
+    tty: bool,
+) -> Result<i32> {
+    let Some((program, args)) = command.split_first() else {
+        bail!("No command given");
+    };
+
+    let (target, container) = resolve(config_path, service).await?;
+    let target = upgrade_legacy(config_path, service, target)?;
+    let (executor, launch) =
+        executor_for(service, &target, container.as_deref(), interactive, tty)?;
+    let mut command = Command::builder(program).args(args).build();
+    if tty {
+        command.pty(terminal_size().unwrap_or_default());
+    }
+
+    let (events, mut handle) = executor
+        .execute(command, &launch)
+        .await
+        .with_context(|| format!("Failed to run {} in {}", program, service))?;
+    if interactive {
+        forward_stdin(&mut handle);
+    } else {
+        // Like `docker exec` without `-i`: the command reads no input
+        drop(handle.take_stdin());
+    }
+
+    if tty {
+        terminal_session(events, &mut handle).await
+    } else {
+        relay(
+            events,
+            &mut handle,
+            &mut std::io::stdout(),
+            &mut std::io::stderr(),
+        )
+        .await
+    }
+}
+
+/// The service's target, and its container if it runs in one
+///
+/// A service that isn't running is looked up in the configuration: commands
+/// can still run where it would, unless that is its container.
+async fn resolve(config_path: &Path, service: &str) -> Result<(ServiceTarget, Option<String>)> {
+    let mut daemon = client::connect_to_daemon().await?;
+    let request = Request::GetServiceInfo {
+        name: service.to_string(),
+    };
+    match daemon.send_request(request).await? {
+        Response::ServiceInfo {
+            service: Some(running),
+        } => Ok((running.config.target, running.container_id)),
+        Response::ServiceInfo { service: None } => {
+            let config =
+                parser::parse_file(config_path).context("Failed to parse configuration")?;
+            let service_config = parser::convert_to_orchestrator(&config, service)?;
+            Ok((service_config.target, None))
+        }
+        Response::Error { message } => Err(anyhow!("Daemon error: {}", message)),
+        _ => Err(anyhow!("Unexpected response from daemon")),
+    }
+}
+
+/// A legacy `RemoteLan` or `Wireguard` target as the `Remote` target it
+/// stands for, with the service's environment
+///
+/// The legacy targets don't carry the environment, so it is read from the
+/// configuration. Other targets are returned as they are.
+#[allow(deprecated)]
+fn upgrade_legacy(
+    config_path: &Path,
+    service: &str,
+    target: ServiceTarget,
+) -> Result<ServiceTarget> {
+    let (host, user, mode) = match target {
+        ServiceTarget::RemoteLan {
+            host,
+            user,
+            binary,
+            args,
+        } => (host, user, RemoteMode::Process { binary, args }),
+        ServiceTarget::Wireguard {
+            host,
+            user,
+            package,
+        } => (host, user, RemoteMode::Package { package }),
+        target => return Ok(target),
+    };
+    let config = parser::parse_file(config_path).context("Failed to parse configuration")?;
+    let env = match config.services.get(service) {
+        Some(service) => {
+            resolver::resolve_service_env(service, &resolver::ResolutionContext::new())?
+        }
+        None => HashMap::new(),
+    };
+    Ok(ServiceTarget::Remote {
+        host,
+        user,
+        mode,
+        env,
+    })
+}
+
+/// Executor running commands where the service runs, and the target to
+/// launch them with
+fn executor_for(
+    service: &str,
+    target: &ServiceTarget,
+    container: Option<&str>,
+    interactive: bool,
+    tty: bool,
+) -> Result<(LayeredExecutor<LocalLauncher>, Target)> {
+    let executor = LayeredExecutor::new(LocalLauncher);
+    let mut launch = Target::Command;
+    #[allow(deprecated)]
+    let executor = match target {
+        ServiceTarget::Process {
+            env,
+            working_dir,
+            user,
+            ..
+        } => {
+            let mut layer = LocalLayer::new();
+            for (key, value) in env {
+                layer = layer.with_env(key, value);
+            }
+            if let Some(dir) = working_dir {
+                layer = layer.with_working_dir(dir);
+            }
+            // As the service's user, not ours
+            if let Some(user) = user {
+                launch = Target::ManagedProcess(ManagedProcess::new().with_user(user));
+            }
+            executor.with_layer(layer)
+        }
+        ServiceTarget::ProcessAttach { env, .. } => {
+            let mut layer = LocalLayer::new();
+            for (key, value) in env {
+                layer = layer.with_env(key, value);
+            }
+            executor.with_layer(layer)
+        }
+        // The container already has the service's environment
+        ServiceTarget::Docker { .. } | ServiceTarget::Compose { .. } => {
+            let container = container.ok_or_else(|| {
+                anyhow!(
+                    "Service '{}' is not running; start it to run commands in its container",
+                    service
+                )
+            })?;
+            executor.with_layer(
+                DockerLayer::new(container)
+                    .with_interactive(interactive)
+                    .with_tty(tty),
+            )
+        }
+        ServiceTarget::DockerAttach { container, env } => {
+            let mut layer = DockerLayer::new(container)
+                .with_interactive(interactive)
+                .with_tty(tty);
+            for (key, value) in env {
+                layer = layer.with_env(key, value);
+            }
+            executor.with_layer(layer)
+        }
+        ServiceTarget::Remote {
+            host, user, env, ..
+        } => {
+            let mut layer = SshLayer::new(format!("{}@{}", user, host)).with_tty(tty);
+            for (key, value) in env {
+                layer = layer.with_env(key, value);
+            }
+            executor.with_layer(layer)
+        }
+        // Without the service's environment: see `upgrade_legacy`
+        ServiceTarget::RemoteLan { host, user, .. }
+        | ServiceTarget::Wireguard { host, user, .. } => {
+            executor.with_layer(SshLayer::new(format!("{}@{}", user, host)).with_tty(tty))
+        }
+    };
+    Ok((executor, launch))
+}
+
+/// Copy our stdin to the command until either side closes
+fn forward_stdin(handle: &mut LocalProcessHandle) {
+    let Some(mut input) = handle.take_stdin() else {
+        return;
+    };
+    smol::spawn(async move {
+        let mut stdin = smol::Unblock::new(std::io::stdin());
+        let mut buf = [0u8; 1024];
+        while let Ok(n) = stdin.read(&mut buf).await {
+            if n == 0 || input.write(&buf[..n]).await.is_err() {
+                break;
+            }
+        }
+        input.close();
+    })
+    .detach();
+}
+
+/// Copy a command's output to `out` and `err` until it exits, returning its
+/// exit code
+async fn relay(
+    mut events: impl Stream<Item = ProcessEvent> + Unpin,
+    handle: &mut LocalProcessHandle,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> Result<i32> {
+    while let Some(event) = events.next().await {
+        match (event.event_type, event.data) {
+            (ProcessEventType::Stdout, Some(line)) => writeln!(out, "{}", line)?,
+            (ProcessEventType::Stderr, Some(line)) => writeln!(err, "{}", line)?,
+            _ => {}
+        }
+    }
+
+    let status = handle.wait().await?;
+    Ok(exit_code(status.code, status.signal))
+}
+
+/// Relay a command's terminal to ours until it exits, returning its exit
+/// code
+async fn terminal_session(
+    mut events: impl Stream<Item = ProcessEvent> + Unpin,
+    handle: &mut LocalProcessHandle,
+) -> Result<i32> {
+    let _raw = RawMode::enable()?;
+
+    let mut size = terminal_size();
+    let mut stdout = std::io::stdout();
+    loop {
+        let next = smol::future::or(async { Some(events.next().await) }, async {
+            smol::Timer::after(RESIZE_POLL_INTERVAL).await;
+            None
+        });
+        match next.await {
+            Some(Some(event)) => {
+                if event.event_type == ProcessEventType::Output
+                    && let Some(data) = &event.data
+                {
+                    stdout.write_all(data.as_bytes())?;
+                    stdout.flush()?;
+                }
+            }
+            Some(None) => break,
+            None => {
+                let current = terminal_size();
+                if current != size
+                    && let Some(current) = current
+                {
+                    handle.resize_pty(current)?;
+                    size = Some(current);
+                }
+            }
+        }
+    }
+
+    let status = handle.wait().await?;
+    Ok(exit_code(status.code, status.signal))
+}
+
+/// Shell convention: the exit code, or 128 plus the signal that killed it
+fn exit_code(code: Option<i32>, signal: Option<i32>) -> i32 {
+    code.or(signal.map(|signal| 128 + signal)).unwrap_or(1)
+}
+
+/// Size of the terminal stdout goes to, if it is one
+fn terminal_size() -> Option<PtySize> {
+    let mut winsize = nix::libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    #[allow(unsafe_code)]
+    // SAFETY: `TIOCGWINSZ` writes a `winsize` through the pointer, which
+    // outlives the call
+    let result = unsafe { nix::libc::ioctl(1, nix::libc::TIOCGWINSZ, &mut winsize) };
+    (result == 0 && winsize.ws_row > 0 && winsize.ws_col > 0)
+        .then(|| PtySize::new(winsize.ws_row, winsize.ws_col))
+}
+
+/// Our terminal in raw mode: input goes to the command byte by byte,
+/// unechoed, and its control characters reach it as typed. Restored on drop.
+struct RawMode {
+    original: Option<Termios>,
+}
+
+impl RawMode {
+    fn enable() -> Result<Self> {
+        let stdin = std::io::stdin();
+        if !stdin.is_terminal() {
+            return Ok(Self { original: None });
+        }
+        let original = termios::tcgetattr(&stdin).context("Failed to read terminal mode")?;
+        let mut raw = original.clone();
+        termios::cfmakeraw(&mut raw);
+        termios::tcsetattr(&stdin, SetArg::TCSANOW, &raw)
+            .context("Failed to put terminal in raw mode")?;
+        Ok(Self {
+            original: Some(original),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Some(original) = &self.original {
+            let _ = termios::tcsetattr(std::io::stdin(), SetArg::TCSANOW, original);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(env: &[(&str, &str)], working_dir: Option<&str>) -> ServiceTarget {
+        ServiceTarget::Process {
+            binary: "anvil".to_string(),
+            args: vec![],
+            env: env
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            working_dir: working_dir.map(str::to_string),
+            user: None,
+            limits: Default::default(),
+            shutdown_timeout: None,
+        }
+    }
+
+    #[test]
+    fn test_executor_for_targets() {
+        let mut target = process(&[("RUST_LOG", "debug")], Some("/srv"));
+        let (executor, launch) = executor_for("anvil", &target, None, false, true).unwrap();
+        assert_eq!(executor.layer_descriptions(), vec!["Local execution"]);
+        assert!(matches!(launch, Target::Command));
+
+        if let ServiceTarget::Process { user, .. } = &mut target {
+            *user = Some("nobody".to_string());
+        }
+        let (_, launch) = executor_for("anvil", &target, None, false, true).unwrap();
+        let Target::ManagedProcess(managed) = launch else {
+            panic!("expected a managed process, got {:?}", launch);
+        };
+        assert_eq!(managed.user(), Some("nobody"));
+
+        let docker = ServiceTarget::Docker {
+            image: "postgres".to_string(),
+            env: HashMap::new(),
+            ports: vec![],
+            volumes: vec![],
+        };
+        let error = executor_for("postgres", &docker, None, true, true).unwrap_err();
+        assert!(error.to_string().contains("not running"));
+        let (executor, _) = executor_for("postgres", &docker, Some("abc123"), true, true).unwrap();
+        assert_eq!(executor.layer_descriptions(), vec!["Docker exec in abc123"]);
+
+        let attached = ServiceTarget::DockerAttach {
+            container: "db".to_string(),
+            env: HashMap::new(),
+        };
+        let (executor, _) = executor_for("db", &attached, None, false, false).unwrap();
+        assert_eq!(executor.layer_descriptions(), vec!["Docker exec in db"]);
+
+        let remote = ServiceTarget::Remote {
+            host: "10.0.0.2".to_string(),
+            user: "ubuntu".to_string(),
+            mode: RemoteMode::Process {
+                binary: "graph-node".to_string(),
+                args: vec![],
+            },
+            env: HashMap::new(),
+        };
+        let (executor, _) = executor_for("graph-node", &remote, None, true, true).unwrap();
+        assert_eq!(
+            executor.layer_descriptions(),
+            vec!["SSH to ubuntu@10.0.0.2"]
+        );
+    }
+
+    #[test]
+    fn test_upgrade_legacy_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("harness.yaml");
+        std::fs::write(
+            &config_path,
+            r#"
+version: "1.0"
+name: "test"
+networks:
+  lan:
+    type: lan
+    subnet: "192.168.1.0/24"
+services:
+  worker:
+    type: remote
+    network: lan
+    host: "192.168.1.100"
+    binary: "/opt/worker/bin/worker"
+    env:
+      API_URL: "http://localhost:8080"
+"#,
+        )
+        .unwrap();
+
+        #[allow(deprecated)]
+        let legacy = ServiceTarget::RemoteLan {
+            host: "192.168.1.100".to_string(),
+            user: "root".to_string(),
+            binary: "/opt/worker/bin/worker".to_string(),
+            args: vec![],
+        };
+        let target = upgrade_legacy(&config_path, "worker", legacy).unwrap();
+        assert_eq!(
+            target,
+            ServiceTarget::Remote {
+                host: "192.168.1.100".to_string(),
+                user: "root".to_string(),
+                mode: RemoteMode::Process {
+                    binary: "/opt/worker/bin/worker".to_string(),
+                    args: vec![],
+                },
+                env: HashMap::from([("API_URL".to_string(), "http://localhost:8080".to_string())]),
+            }
+        );
+
+        // Other targets don't need the configuration
+        let target = process(&[], None);
+        let upgraded = upgrade_legacy(Path::new("/nonexistent"), "anvil", target.clone());
+        assert_eq!(upgraded.unwrap(), target);
+    }
+
+    #[test]
+    fn test_relay_process_context() {
+        smol::block_on(async {
+            let dir = tempfile::tempdir().unwrap();
+            let target = process(&[("GREETING", "hello")], Some(dir.path().to_str().unwrap()));
+            let (executor, _) = executor_for("anvil", &target, None, false, false).unwrap();
+            let command = Command::builder("sh")
+                .arg("-c")
+                .arg("echo $GREETING; pwd; echo oops >&2; exit 4")
+                .build();
+            let (events, mut handle) = executor.execute_command(command).await.unwrap();
+
+            let (mut out, mut err) = (Vec::new(), Vec::new());
+            let code = relay(events, &mut handle, &mut out, &mut err)
+                .await
+                .unwrap();
+
+            assert_eq!(code, 4);
+            let cwd = std::fs::canonicalize(dir.path()).unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                format!("hello\n{}\n", cwd.display())
+            );
+            assert_eq!(String::from_utf8(err).unwrap(), "oops\n");
+        });
+    }
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(Some(0), None), 0);
+        assert_eq!(exit_code(Some(3), None), 3);
+        assert_eq!(exit_code(None, Some(9)), 137);
+        assert_eq!(exit_code(None, None), 1);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::commands::client;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use comfy_table::{Cell, Table};
+use harness::protocol::{Request, Response};
+use service_registry::models::{HistoryEvent, HistoryKind, HistoryQuery};
+
+/// Show the recorded history of a service
+pub async fn run(
+    service: &str,
+    since: Option<&str>,
+    until: Option<&str>,
+    limit: Option<usize>,
+    format: &str,
+) -> Result<()> {
+    let query = HistoryQuery {
+        service: Some(service.to_string()),
+        since: since.map(parse_time).transpose()?,
+        until: until.map(parse_time).transpose()?,
+        limit,
+    };
+
+    let mut daemon = client::connect_to_daemon().await?;
+
+    let events = match daemon.send_request(Request::GetHistory { query }).await? {
+        Response::History { events } => events,
+        Response::Error { message } => return Err(anyhow!("Daemon error: {}", message)),
+        _ => return Err(anyhow!("Unexpected response from daemon")),
+    };
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&events)?),
+        _ => {
+            if events.is_empty() {
+                println!("No history for {}", service);
+                return Ok(());
+            }
+
+            let mut table = Table::new();
+            table.set_header(vec!["TIME", "EVENT", "DETAILS", "CAUSE"]);
+
+            for event in &events {
+                let (name, details) = describe(event);
+                table.add_row(vec![
+                    Cell::new(event.at.format("%Y-%m-%d %H:%M:%S UTC")),
+                    Cell::new(name),
+                    Cell::new(details),
+                    Cell::new(event.cause.as_deref().unwrap_or("-")),
+                ]);
+            }
+
+            println!("{}", table);
+        }
+    }
+
+    Ok(())
+}
+
+/// Short event name and human-readable details
+fn describe(event: &HistoryEvent) -> (&'static str, String) {
+    match &event.kind {
+        HistoryKind::Registered { version } => ("registered", format!("v{}", version)),
+        HistoryKind::Updated { version } => ("updated", format!("v{}", version)),
+        HistoryKind::StateChanged { from, to } => {
+            ("state", format!("{:?} → {:?}", from, to).to_lowercase())
+        }
+        HistoryKind::EndpointsUpdated { endpoints } => (
+            "endpoints",
+            endpoints
+                .iter()
+                .map(|e| format!("{}={}", e.name, e.address))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        HistoryKind::HealthChecked { status } => {
+            let result = if status.healthy {
+                "✓ healthy"
+            } else {
+                "✗ unhealthy"
+            };
+            let details = match &status.message {
+                Some(message) => format!("{} ({}ms): {}", result, status.duration_ms, message),
+                None => format!("{} ({}ms)", result, status.duration_ms),
+            };
+            ("health", details)
+        }
+        HistoryKind::Deregistered => ("deregistered", String::new()),
+    }
+}
+
+/// Parse an RFC 3339 timestamp or a relative age such as `30s`, `10m`, `2h`, `1d`
+fn parse_time(input: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let unit_at = input.len() - input.chars().last().map_or(0, char::len_utf8);
+    let (amount, unit) = input.split_at(unit_at);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| anyhow!("Invalid time '{}': expected RFC 3339 or e.g. 10m", input))?;
+    let age = match unit {
+        "s" => Duration::seconds(amount),
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        _ => {
+            return Err(anyhow!(
+                "Invalid time unit in '{}': use s, m, h or d",
+                input
+            ));
+        }
+    };
+
+    Ok(Utc::now() - age)
+}
@@ -0,0 +1,37 @@
+use anyhow::{Context, Result};
+use harness_config::{compose, parser};
+use std::path::Path;
+
+/// Turn a docker compose file into a services.yaml skeleton
+pub async fn compose(
+    file: &Path,
+    project_name: Option<&str>,
+    output: Option<&Path>,
+    force: bool,
+) -> Result<()> {
+    let config = compose::import_file(file, project_name)
+        .with_context(|| format!("Failed to import {}", file.display()))?;
+    let yaml = parser::to_yaml_string(&config)?;
+
+    match output {
+        Some(output) => {
+            if output.exists() && !force {
+                anyhow::bail!(
+                    "{} already exists (use --force to overwrite)",
+                    output.display()
+                );
+            }
+            std::fs::write(output, &yaml)
+                .with_context(|| format!("Failed to write {:?}", output))?;
+            eprintln!(
+                "Imported {} service(s) from {} to {}",
+                config.services.len(),
+                file.display(),
+                output.display()
+            );
+        }
+        None => print!("{}", yaml),
+    }
+
+    Ok(())
+}
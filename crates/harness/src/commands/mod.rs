@@ -1,8 +1,15 @@
+pub mod action;
 pub mod client;
 pub mod daemon;
 pub mod dependencies;
 pub mod env;
+pub mod exec;
+pub mod history;
+pub mod import;
+pub mod network;
+pub mod registry;
 pub mod start;
 pub mod status;
 pub mod stop;
+pub mod top;
 pub mod validate;
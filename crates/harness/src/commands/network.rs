@@ -0,0 +1,138 @@
+use crate::commands::client;
+use anyhow::{Context, Result, anyhow};
+use comfy_table::{Cell, Table};
+use harness::protocol::{Request, Response};
+use harness_config::{Config, Network, parser};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Provision WireGuard networks and bring their interfaces up
+pub async fn up(config_path: &Path, networks: Vec<String>) -> Result<()> {
+    let config = parser::parse_file(config_path).context("Failed to parse configuration")?;
+    let networks = select_wireguard_networks(&config, networks)?;
+
+    let mut daemon = client::connect_to_daemon().await?;
+    let mut failures = 0;
+
+    for network in &networks {
+        print!("Bringing up WireGuard network {}...", network);
+        io::stdout().flush()?;
+
+        let mesh = parser::convert_wireguard_network(&config, network)?;
+        match daemon
+            .send_request(Request::WireGuardUp { config: mesh })
+            .await?
+        {
+            Response::Success => println!(" ✓"),
+            Response::Error { message } => {
+                println!(" ✗");
+                eprintln!("  Error: {}", message);
+                failures += 1;
+            }
+            _ => return Err(anyhow!("Unexpected response from daemon")),
+        }
+    }
+
+    if failures > 0 {
+        anyhow::bail!("{} network(s) failed to come up", failures);
+    }
+    Ok(())
+}
+
+/// Bring WireGuard networks down
+pub async fn down(config_path: &Path, networks: Vec<String>) -> Result<()> {
+    let config = parser::parse_file(config_path).context("Failed to parse configuration")?;
+    let networks = select_wireguard_networks(&config, networks)?;
+
+    let mut daemon = client::connect_to_daemon().await?;
+    let mut failures = 0;
+
+    for network in &networks {
+        print!("Bringing down WireGuard network {}...", network);
+        io::stdout().flush()?;
+
+        match daemon
+            .send_request(Request::WireGuardDown {
+                network: network.clone(),
+            })
+            .await?
+        {
+            Response::Success => println!(" ✓"),
+            Response::Error { message } => {
+                println!(" ✗");
+                eprintln!("  Error: {}", message);
+                failures += 1;
+            }
+            _ => return Err(anyhow!("Unexpected response from daemon")),
+        }
+    }
+
+    if failures > 0 {
+        anyhow::bail!("{} network(s) failed to come down", failures);
+    }
+    Ok(())
+}
+
+/// Show the IP leases held by services
+pub async fn allocations(format: &str) -> Result<()> {
+    let mut daemon = client::connect_to_daemon().await?;
+
+    let allocations = match daemon.send_request(Request::ListIpAllocations).await? {
+        Response::IpAllocations { allocations } => allocations,
+        Response::Error { message } => return Err(anyhow!("Daemon error: {}", message)),
+        _ => return Err(anyhow!("Unexpected response from daemon")),
+    };
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&allocations)?),
+        _ => {
+            if allocations.is_empty() {
+                println!("No IP allocations");
+                return Ok(());
+            }
+
+            let mut table = Table::new();
+            table.set_header(vec!["SERVICE", "IP", "SUBNET", "ALLOCATED"]);
+
+            for allocation in &allocations {
+                table.add_row(vec![
+                    Cell::new(&allocation.service),
+                    Cell::new(allocation.ip),
+                    Cell::new(allocation.subnet),
+                    Cell::new(allocation.allocated_at.format("%Y-%m-%d %H:%M:%S UTC")),
+                ]);
+            }
+
+            println!("{}", table);
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve the requested networks, defaulting to every WireGuard network
+fn select_wireguard_networks(config: &Config, requested: Vec<String>) -> Result<Vec<String>> {
+    if requested.is_empty() {
+        let mut all: Vec<String> = config
+            .networks
+            .iter()
+            .filter(|(_, net)| matches!(net, Network::WireGuard { .. }))
+            .map(|(name, _)| name.clone())
+            .collect();
+        all.sort();
+
+        if all.is_empty() {
+            anyhow::bail!("No WireGuard networks defined in configuration");
+        }
+        return Ok(all);
+    }
+
+    for name in &requested {
+        match config.networks.get(name) {
+            Some(Network::WireGuard { .. }) => {}
+            Some(_) => anyhow::bail!("Network '{}' is not a WireGuard network", name),
+            None => anyhow::bail!("Network '{}' not found in configuration", name),
+        }
+    }
+    Ok(requested)
+}
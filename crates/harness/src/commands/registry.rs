@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use service_orchestration::ServiceManager;
+use service_registry::StorageConfig;
+use service_registry::backend::{self, RegistrySnapshot};
+use std::path::{Path, PathBuf};
+
+/// Registry storage to open, defaulting to the daemon's sled database
+fn default_storage(backend: &str, path: Option<PathBuf>) -> Result<StorageConfig> {
+    match (backend, path) {
+        ("sled", Some(path)) => Ok(StorageConfig::Sled { path }),
+        ("sled", None) => Ok(ServiceManager::default_storage(
+            &ServiceManager::default_state_dir(),
+        )),
+        ("jsonl", Some(path)) => Ok(StorageConfig::Jsonl { path }),
+        ("jsonl", None) => anyhow::bail!("The jsonl backend needs its directory as --path"),
+        (other, _) => anyhow::bail!("Unknown registry backend '{}' (use sled or jsonl)", other),
+    }
+}
+
+async fn open_backend(storage: &StorageConfig) -> Result<Box<dyn backend::RegistryBackend>> {
+    backend::open(storage).await.with_context(|| {
+        format!(
+            "Failed to open registry {:?} (stop the daemon if it is using it)",
+            storage
+        )
+    })
+}
+
+/// Write a snapshot of a registry backend to a file or stdout
+pub async fn export(backend: &str, path: Option<PathBuf>, output: Option<&Path>) -> Result<()> {
+    let storage = default_storage(backend, path)?;
+    let source = open_backend(&storage).await?;
+
+    let snapshot = RegistrySnapshot::capture(source.as_ref()).await?;
+    let json = serde_json::to_string_pretty(&snapshot)?;
+
+    match output {
+        Some(output) => {
+            std::fs::write(output, json + "\n")
+                .with_context(|| format!("Failed to write {:?}", output))?;
+            eprintln!(
+                "Exported {} service(s) and {} IP lease(s) to {}",
+                snapshot.services.len(),
+                snapshot.allocations.len(),
+                output.display()
+            );
+        }
+        None => println!("{}", json),
+    }
+
+    Ok(())
+}
+
+/// Load a snapshot file into a registry backend
+pub async fn import(
+    input: &Path,
+    backend: &str,
+    path: Option<PathBuf>,
+    replace: bool,
+) -> Result<()> {
+    let data = std::fs::read(input).with_context(|| format!("Failed to read {:?}", input))?;
+    let snapshot: RegistrySnapshot =
+        serde_json::from_slice(&data).context("Invalid registry snapshot")?;
+
+    let storage = default_storage(backend, path)?;
+    let target = open_backend(&storage).await?;
+
+    snapshot.restore(target.as_ref(), replace).await?;
+
+    println!(
+        "Imported {} service(s) and {} IP lease(s) ✓",
+        snapshot.services.len(),
+        snapshot.allocations.len()
+    );
+    Ok(())
+}
@@ -1,9 +1,11 @@
 use crate::commands::client;
+use crate::commands::top::{format_bytes, format_cpu, format_uptime};
 use anyhow::{Context, Result};
 use comfy_table::{Cell, Color, Table};
-use harness::protocol::{DetailedServiceInfo, Request, Response};
-use harness_config::parser;
-use service_orchestration::ServiceStatus;
+use harness::protocol::{DetailedServiceInfo, Request, Response, WireGuardNetworkStatus};
+use harness_config::{Network, parser};
+use service_orchestration::{ServiceMetrics, ServiceStatus};
+use std::collections::HashMap;
 use std::path::Path;
 use std::time::Duration;
 
@@ -27,6 +29,28 @@ async fn run_once(config_path: &Path, format: &str, detailed: bool) -> Result<()
     // Connect to daemon
     let mut daemon = client::connect_to_daemon().await?;
 
+    // Uptime and resource usage for the table
+    let metrics = if format == "table" {
+        match daemon
+            .send_request(Request::GetMetrics {
+                services: Vec::new(),
+            })
+            .await?
+        {
+            Response::Metrics { services } => services
+                .into_iter()
+                .map(|metrics| (metrics.name.clone(), metrics))
+                .collect(),
+            Response::Error { message } => {
+                eprintln!("Failed to get metrics: {}", message);
+                HashMap::new()
+            }
+            _ => anyhow::bail!("Unexpected response from daemon"),
+        }
+    } else {
+        HashMap::new()
+    };
+
     if detailed {
         // Get detailed service information
         let request = Request::ListServicesDetailed;
@@ -41,7 +65,7 @@ async fn run_once(config_path: &Path, format: &str, detailed: bool) -> Result<()
         if format == "json" {
             println!("{}", serde_json::to_string_pretty(&detailed_services)?);
         } else {
-            display_detailed_table(&detailed_services, &config)?;
+            display_detailed_table(&detailed_services, &metrics, &config)?;
         }
     } else {
         // Get basic service status
@@ -57,7 +81,22 @@ async fn run_once(config_path: &Path, format: &str, detailed: bool) -> Result<()
         if format == "json" {
             println!("{}", serde_json::to_string_pretty(&services_status)?);
         } else {
-            display_basic_table(&services_status, &config)?;
+            display_basic_table(&services_status, &metrics, &config)?;
+        }
+    }
+
+    // Peer handshake state for WireGuard networks
+    let has_wireguard = config
+        .networks
+        .values()
+        .any(|net| matches!(net, Network::WireGuard { .. }));
+    if has_wireguard && format == "table" {
+        match daemon.send_request(Request::WireGuardStatus).await? {
+            Response::WireGuardStatus { networks } => display_wireguard_table(&networks)?,
+            Response::Error { message } => {
+                eprintln!("Failed to get WireGuard status: {}", message);
+            }
+            _ => anyhow::bail!("Unexpected response from daemon"),
         }
     }
 
@@ -80,12 +119,25 @@ async fn run_watch_mode(config_path: &Path, format: &str, detailed: bool) -> Res
     }
 }
 
+/// Uptime, CPU and RSS cells of a service
+fn usage_cells(metrics: Option<&ServiceMetrics>) -> [Cell; 3] {
+    let latest = metrics.and_then(|metrics| metrics.latest());
+    [
+        Cell::new(format_uptime(
+            metrics.and_then(|metrics| metrics.uptime(chrono::Utc::now())),
+        )),
+        Cell::new(format_cpu(latest.and_then(|sample| sample.cpu_percent))),
+        Cell::new(latest.map_or("-".to_string(), |sample| format_bytes(sample.rss_bytes))),
+    ]
+}
+
 fn display_basic_table(
-    services_status: &std::collections::HashMap<String, ServiceStatus>,
+    services_status: &HashMap<String, ServiceStatus>,
+    metrics: &HashMap<String, ServiceMetrics>,
     config: &harness_config::Config,
 ) -> Result<()> {
     let mut table = Table::new();
-    table.set_header(vec!["SERVICE", "STATUS", "HEALTH"]);
+    table.set_header(vec!["SERVICE", "STATUS", "HEALTH", "UPTIME", "CPU", "RSS"]);
 
     // Get status for each service
     for (service_name, service_config) in &config.services {
@@ -102,11 +154,13 @@ fn display_basic_table(
             ServiceStatus::Failed(ref msg) => ("failed", Color::Red, msg.as_str()),
         };
 
-        table.add_row(vec![
+        let mut row = vec![
             Cell::new(service_name),
             Cell::new(status_str).fg(status_color),
             Cell::new(health_str),
-        ]);
+        ];
+        row.extend(usage_cells(metrics.get(service_name)));
+        table.add_row(row);
     }
 
     println!("{}", table);
@@ -115,6 +169,7 @@ fn display_basic_table(
 
 fn display_detailed_table(
     detailed_services: &[DetailedServiceInfo],
+    metrics: &HashMap<String, ServiceMetrics>,
     config: &harness_config::Config,
 ) -> Result<()> {
     let mut table = Table::new();
@@ -123,12 +178,16 @@ fn display_detailed_table(
         "STATUS",
         "NETWORK",
         "PID/CONTAINER",
+        "UPTIME",
+        "CPU",
+        "RSS",
         "DEPENDENCIES",
         "ENDPOINTS",
+        "LIMITS",
     ]);
 
     // Create a map for quick lookup
-    let service_map: std::collections::HashMap<_, _> = detailed_services
+    let service_map: HashMap<_, _> = detailed_services
         .iter()
         .map(|s| (s.name.clone(), s))
         .collect();
@@ -196,16 +255,95 @@ fn display_detailed_table(
             "-".to_string()
         };
 
-        table.add_row(vec![
+        let limits = service_info
+            .map(|info| {
+                info.limits
+                    .iter()
+                    .cloned()
+                    .chain(info.user.iter().map(|user| format!("user {}", user)))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|limits| !limits.is_empty())
+            .map_or_else(|| "-".to_string(), |limits| limits.join("\n"));
+
+        let mut row = vec![
             Cell::new(service_name),
             Cell::new(status_str).fg(status_color),
             Cell::new(&network_info),
             Cell::new(&process_info),
+        ];
+        row.extend(usage_cells(metrics.get(service_name)));
+        row.extend([
             Cell::new(deps_display),
             Cell::new(&endpoints),
+            Cell::new(&limits),
         ]);
+        table.add_row(row);
     }
 
     println!("{}", table);
     Ok(())
 }
+
+fn display_wireguard_table(networks: &[WireGuardNetworkStatus]) -> Result<()> {
+    if networks.is_empty() {
+        println!("\nWireGuard: no networks provisioned (run `harness network up`)");
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now();
+    let mut table = Table::new();
+    table.set_header(vec![
+        "NETWORK",
+        "NODE",
+        "MESH IP",
+        "PEER",
+        "ENDPOINT",
+        "HANDSHAKE",
+        "RX/TX",
+    ]);
+
+    for network in networks {
+        for node in &network.nodes {
+            if let Some(error) = &node.error {
+                table.add_row(vec![
+                    Cell::new(&network.network),
+                    Cell::new(&node.node),
+                    Cell::new(node.address),
+                    Cell::new("-"),
+                    Cell::new("-"),
+                    Cell::new(format!("down: {}", error)).fg(Color::Red),
+                    Cell::new("-"),
+                ]);
+                continue;
+            }
+
+            for peer in &node.peers {
+                let (handshake, color) = match peer.latest_handshake {
+                    Some(at) => {
+                        let ago = format!("{}s ago", (now - at).num_seconds().max(0));
+                        if peer.is_connected(now) {
+                            (ago, Color::Green)
+                        } else {
+                            (ago, Color::Yellow)
+                        }
+                    }
+                    None => ("never".to_string(), Color::Red),
+                };
+
+                table.add_row(vec![
+                    Cell::new(&network.network),
+                    Cell::new(&node.node),
+                    Cell::new(node.address),
+                    Cell::new(peer.node.as_deref().unwrap_or(&peer.public_key)),
+                    Cell::new(peer.endpoint.as_deref().unwrap_or("-")),
+                    Cell::new(handshake).fg(color),
+                    Cell::new(format!("{}/{}", peer.rx_bytes, peer.tx_bytes)),
+                ]);
+            }
+        }
+    }
+
+    println!("\n{}", table);
+    Ok(())
+}
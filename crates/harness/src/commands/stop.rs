@@ -10,6 +10,7 @@ pub async fn run(
     services: Vec<String>,
     force: bool,
     timeout: Option<u64>,
+    remove: bool,
 ) -> Result<()> {
     // Parse configuration to get service list
     let config = parser::parse_file(config_path).context("Failed to parse configuration")?;
@@ -26,16 +27,18 @@ pub async fn run(
         _ => anyhow::bail!("Unexpected response from daemon"),
     };
 
-    // Filter to only running services
+    // Filter to only running services; stopped ones can still be removed
     let running_services: Vec<String> = service_status
         .iter()
         .filter_map(|(name, status)| {
-            if matches!(
-                status,
-                service_orchestration::ServiceStatus::Running
-                    | service_orchestration::ServiceStatus::Starting
-                    | service_orchestration::ServiceStatus::Unhealthy
-            ) {
+            if remove
+                || matches!(
+                    status,
+                    service_orchestration::ServiceStatus::Running
+                        | service_orchestration::ServiceStatus::Starting
+                        | service_orchestration::ServiceStatus::Unhealthy
+                )
+            {
                 Some(name.clone())
             } else {
                 None
@@ -105,8 +108,14 @@ pub async fn run(
         io::stdout().flush()?;
 
         // Send stop request to daemon
-        let request = Request::StopService {
-            name: service_name.clone(),
+        let request = if remove {
+            Request::RemoveService {
+                name: service_name.clone(),
+            }
+        } else {
+            Request::StopService {
+                name: service_name.clone(),
+            }
         };
 
         match daemon.send_request(request).await {
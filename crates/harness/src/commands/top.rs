@@ -0,0 +1,229 @@
+use crate::commands::client;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use comfy_table::{Cell, CellAlignment, Color, Table};
+use harness::protocol::{Request, Response};
+use service_orchestration::ServiceMetrics;
+use std::time::Duration;
+
+/// Characters of the CPU history, lowest to highest
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Samples shown in the CPU history column
+const HISTORY_WIDTH: usize = 30;
+
+/// Lowest CPU percentage the history is scaled to, so idle noise stays flat
+const HISTORY_MIN_SCALE: f64 = 10.0;
+
+/// Live view of running services' resource usage
+pub async fn run(
+    services: Vec<String>,
+    interval: u64,
+    sort: &str,
+    iterations: Option<u64>,
+) -> Result<()> {
+    if !["cpu", "rss", "uptime", "name"].contains(&sort) {
+        anyhow::bail!(
+            "Invalid sort key: {}. Must be 'cpu', 'rss', 'uptime' or 'name'",
+            sort
+        );
+    }
+
+    let mut daemon = client::connect_to_daemon().await?;
+    let mut shown = 0;
+
+    loop {
+        let request = Request::GetMetrics {
+            services: services.clone(),
+        };
+        let mut metrics = match daemon.send_request(request).await? {
+            Response::Metrics { services } => services,
+            Response::Error { message } => return Err(anyhow!("Daemon error: {}", message)),
+            _ => return Err(anyhow!("Unexpected response from daemon")),
+        };
+
+        let now = Utc::now();
+        sort_metrics(&mut metrics, sort, now);
+
+        // Clear screen and move cursor to top
+        print!("\x1B[2J\x1B[1;1H");
+        println!(
+            "harness top - {} - {} running, refreshing every {}s (Ctrl+C to exit)\n",
+            now.format("%H:%M:%S"),
+            metrics.len(),
+            interval
+        );
+        display_table(&metrics, now);
+
+        shown += 1;
+        if iterations.is_some_and(|iterations| shown >= iterations) {
+            return Ok(());
+        }
+        smol::Timer::after(Duration::from_secs(interval.max(1))).await;
+    }
+}
+
+fn sort_metrics(metrics: &mut [ServiceMetrics], sort: &str, now: DateTime<Utc>) {
+    let cpu = |m: &ServiceMetrics| m.latest().and_then(|s| s.cpu_percent).unwrap_or(0.0);
+    let rss = |m: &ServiceMetrics| m.latest().map_or(0, |s| s.rss_bytes);
+    match sort {
+        "cpu" => metrics.sort_by(|a, b| cpu(b).total_cmp(&cpu(a))),
+        "rss" => metrics.sort_by_key(|m| std::cmp::Reverse(rss(m))),
+        "uptime" => metrics.sort_by_key(|m| std::cmp::Reverse(m.uptime(now))),
+        _ => metrics.sort_by(|a, b| a.name.cmp(&b.name)),
+    }
+}
+
+fn display_table(metrics: &[ServiceMetrics], now: DateTime<Utc>) {
+    if metrics.is_empty() {
+        println!("No running services");
+        return;
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec![
+        "SERVICE",
+        "UPTIME",
+        "CPU",
+        "RSS",
+        "PROCS",
+        "CPU HISTORY",
+    ]);
+
+    for service in metrics {
+        let latest = service.latest();
+        let cpu = latest.and_then(|sample| sample.cpu_percent);
+        let cpu_color = match cpu {
+            Some(cpu) if cpu >= 90.0 => Color::Red,
+            Some(cpu) if cpu >= 50.0 => Color::Yellow,
+            _ => Color::Reset,
+        };
+
+        table.add_row(vec![
+            Cell::new(&service.name),
+            Cell::new(format_uptime(service.uptime(now))).set_alignment(CellAlignment::Right),
+            Cell::new(format_cpu(cpu))
+                .fg(cpu_color)
+                .set_alignment(CellAlignment::Right),
+            Cell::new(latest.map_or("-".to_string(), |sample| format_bytes(sample.rss_bytes)))
+                .set_alignment(CellAlignment::Right),
+            Cell::new(latest.map_or("-".to_string(), |sample| sample.processes.to_string()))
+                .set_alignment(CellAlignment::Right),
+            Cell::new(cpu_history(service)),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+/// Sparkline of the recent CPU samples, scaled to the busiest one
+fn cpu_history(service: &ServiceMetrics) -> String {
+    let start = service.samples.len().saturating_sub(HISTORY_WIDTH);
+    let values: Vec<f64> = service.samples[start..]
+        .iter()
+        .filter_map(|sample| sample.cpu_percent)
+        .collect();
+    let max = values.iter().copied().fold(HISTORY_MIN_SCALE, f64::max);
+    values
+        .iter()
+        .map(|value| {
+            let level = (value / max * (SPARKS.len() - 1) as f64).round() as usize;
+            SPARKS[level.min(SPARKS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Uptime such as `3d 4h`, `2h 5m`, `4m 10s` or `12s`
+pub fn format_uptime(uptime: Option<chrono::Duration>) -> String {
+    let Some(uptime) = uptime else {
+        return "-".to_string();
+    };
+    let secs = uptime.num_seconds();
+    let (days, hours, minutes, seconds) = (
+        secs / 86_400,
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+    );
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// CPU usage in percent of one CPU
+pub fn format_cpu(cpu: Option<f64>) -> String {
+    cpu.map_or("-".to_string(), |cpu| format!("{:.1}%", cpu))
+}
+
+/// Byte count in binary units, e.g. `12.5M`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use service_orchestration::MetricsSample;
+
+    fn sample(cpu_percent: Option<f64>) -> MetricsSample {
+        MetricsSample {
+            timestamp: Utc::now(),
+            cpu_percent,
+            rss_bytes: 0,
+            processes: 1,
+        }
+    }
+
+    #[test]
+    fn test_formatting() {
+        let secs = chrono::Duration::seconds;
+        assert_eq!(format_uptime(None), "-");
+        assert_eq!(format_uptime(Some(secs(12))), "12s");
+        assert_eq!(format_uptime(Some(secs(250))), "4m 10s");
+        assert_eq!(format_uptime(Some(secs(7_500))), "2h 5m");
+        assert_eq!(format_uptime(Some(secs(273_600))), "3d 4h");
+
+        assert_eq!(format_cpu(Some(12.345)), "12.3%");
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(12_800 << 10), "12.5M");
+        assert_eq!(format_bytes(3 << 30), "3.0G");
+    }
+
+    #[test]
+    fn test_cpu_history() {
+        let metrics = ServiceMetrics {
+            name: "anvil".to_string(),
+            started_at: None,
+            samples: vec![
+                sample(None),
+                sample(Some(0.0)),
+                sample(Some(50.0)),
+                sample(Some(100.0)),
+            ],
+        };
+        assert_eq!(cpu_history(&metrics), "▁▅█");
+
+        // Idle services stay flat instead of scaling noise up
+        let idle = ServiceMetrics {
+            samples: vec![sample(Some(0.1)), sample(Some(0.2))],
+            ..metrics
+        };
+        assert_eq!(cpu_history(&idle), "▁▁");
+    }
+}
@@ -56,6 +56,16 @@ pub async fn handle_request(request: Request, state: Arc<DaemonState>) -> Result
             }
         }
 
+        Request::RemoveService { name } => {
+            info!("Removing service: {}", name);
+            match state.service_manager.remove_service(&name).await {
+                Ok(_) => Ok(Response::Success),
+                Err(e) => Ok(Response::Error {
+                    message: format!("Failed to remove service: {}", e),
+                }),
+            }
+        }
+
         Request::GetServiceStatus { name } => {
             match state.service_manager.get_service_status(&name).await {
                 Ok(status) => Ok(Response::ServiceStatus { status }),
@@ -165,6 +175,8 @@ pub async fn handle_request(request: Request, state: Arc<DaemonState>) -> Result
                                 }
                             })
                             .collect(),
+                        limits: running.metadata.get("limits").cloned(),
+                        user: running.metadata.get("user").cloned(),
                     }
                 } else {
                     // Service exists but not running, provide basic info
@@ -177,6 +189,8 @@ pub async fn handle_request(request: Request, state: Arc<DaemonState>) -> Result
                         container_id: None,
                         start_time: None,
                         dependencies: Vec::new(),
+                        limits: None,
+                        user: None,
                     }
                 };
 
@@ -229,5 +243,74 @@ pub async fn handle_request(request: Request, state: Arc<DaemonState>) -> Result
 
             Ok(Response::EnvironmentVariables { variables })
         }
+
+        Request::WireGuardUp { config } => {
+            info!("Bringing up WireGuard network: {}", config.name);
+            match state.wireguard.up(config).await {
+                Ok(()) => Ok(Response::Success),
+                Err(e) => Ok(Response::Error {
+                    message: format!("Failed to bring up WireGuard network: {}", e),
+                }),
+            }
+        }
+
+        Request::WireGuardDown { network } => {
+            info!("Bringing down WireGuard network: {}", network);
+            match state.wireguard.down(&network).await {
+                Ok(()) => Ok(Response::Success),
+                Err(e) => Ok(Response::Error {
+                    message: format!("Failed to bring down WireGuard network: {}", e),
+                }),
+            }
+        }
+
+        Request::WireGuardStatus => Ok(Response::WireGuardStatus {
+            networks: state.wireguard.status().await,
+        }),
+
+        Request::ListIpAllocations => {
+            match state
+                .service_manager
+                .service_registry()
+                .ip_allocations()
+                .await
+            {
+                Ok(allocations) => Ok(Response::IpAllocations { allocations }),
+                Err(e) => Ok(Response::Error {
+                    message: format!("Failed to list IP allocations: {}", e),
+                }),
+            }
+        }
+        Request::GetHistory { query } => {
+            match state
+                .service_manager
+                .service_registry()
+                .history(&query)
+                .await
+            {
+                Ok(events) => Ok(Response::History { events }),
+                Err(e) => Ok(Response::Error {
+                    message: format!("Failed to query history: {}", e),
+                }),
+            }
+        }
+
+        Request::GetMetrics { services } => {
+            match state.service_manager.get_service_metrics(&services).await {
+                Ok(services) => Ok(Response::Metrics { services }),
+                Err(e) => Ok(Response::Error {
+                    message: format!("Failed to get metrics: {}", e),
+                }),
+            }
+        }
+
+        Request::GetServiceInfo { name } => {
+            match state.service_manager.get_service_info(&name).await {
+                Ok(service) => Ok(Response::ServiceInfo { service }),
+                Err(e) => Ok(Response::Error {
+                    message: format!("Failed to get service info: {}", e),
+                }),
+            }
+        }
     }
 }
@@ -0,0 +1,539 @@
+//! Prometheus metrics endpoint of the daemon
+//!
+//! Off unless a metrics address is configured. Serves the text exposition
+//! format on `GET /metrics` over plain HTTP, so it can be scraped without
+//! the daemon's client certificates.
+
+use crate::daemon::server::DaemonState;
+use anyhow::{Context, Result};
+use async_net::{TcpListener, TcpStream};
+use futures::{AsyncReadExt, AsyncWriteExt};
+use service_orchestration::{ServiceManager, ServiceStatus};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Upper bounds of the request latency buckets, in seconds
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Largest HTTP request head we read
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// States a service can be in, as reported in `harness_service_state`
+const STATES: [&str; 5] = ["stopped", "starting", "running", "unhealthy", "failed"];
+
+/// Latency histogram of one request type
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&le| seconds <= le) {
+            self.buckets[bucket] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Latency of handled requests by [`Request`](crate::protocol::Request) type
+#[derive(Debug, Default)]
+pub struct RequestMetrics {
+    latency: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+impl RequestMetrics {
+    /// Record how long a request took
+    pub fn observe(&self, request: &'static str, duration: Duration) {
+        self.latency
+            .lock()
+            .unwrap()
+            .entry(request)
+            .or_default()
+            .observe(duration.as_secs_f64());
+    }
+
+    fn write(&self, out: &mut Exposition) {
+        out.family(
+            "harness_request_duration_seconds",
+            "histogram",
+            "Time spent handling daemon requests, by request type",
+        );
+        for (request, histogram) in self.latency.lock().unwrap().iter() {
+            let mut cumulative = 0;
+            for (le, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                out.sample(
+                    "harness_request_duration_seconds_bucket",
+                    &[("request", request), ("le", &le.to_string())],
+                    cumulative as f64,
+                );
+            }
+            out.sample(
+                "harness_request_duration_seconds_bucket",
+                &[("request", request), ("le", "+Inf")],
+                histogram.count as f64,
+            );
+            out.sample(
+                "harness_request_duration_seconds_sum",
+                &[("request", request)],
+                histogram.sum,
+            );
+            out.sample(
+                "harness_request_duration_seconds_count",
+                &[("request", request)],
+                histogram.count as f64,
+            );
+        }
+    }
+}
+
+/// Writer for the Prometheus text exposition format
+#[derive(Debug, Default)]
+struct Exposition {
+    out: String,
+}
+
+impl Exposition {
+    /// Start a metric family
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+    }
+
+    /// Write a sample of the current family
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<_> = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.out, " {}", value);
+    }
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render all daemon metrics
+pub async fn render(state: &DaemonState) -> String {
+    let mut out = Exposition::default();
+    write_services(&mut out, &state.service_manager).await;
+
+    out.family(
+        "harness_registry_subscribers",
+        "gauge",
+        "Addresses subscribed to registry events",
+    );
+    // The daemon's registry is the service manager's
+    out.sample(
+        "harness_registry_subscribers",
+        &[],
+        state.registry.subscriber_count().await as f64,
+    );
+
+    state.request_metrics.write(&mut out);
+    out.out
+}
+
+/// Write state, restarts, resource usage and health checks of services
+async fn write_services(out: &mut Exposition, manager: &ServiceManager) {
+    let restarts = manager.restart_counts();
+    let mut services: BTreeSet<String> = restarts.keys().cloned().collect();
+    services.extend(manager.list_services().await.unwrap_or_default());
+
+    out.family(
+        "harness_service_state",
+        "gauge",
+        "Current state of a service, 1 for the state it is in",
+    );
+    for service in &services {
+        let status = manager
+            .get_service_status(service)
+            .await
+            .unwrap_or(ServiceStatus::Stopped);
+        let current = match status {
+            ServiceStatus::Stopped => "stopped",
+            ServiceStatus::Starting => "starting",
+            ServiceStatus::Running => "running",
+            ServiceStatus::Unhealthy => "unhealthy",
+            ServiceStatus::Failed(_) => "failed",
+        };
+        for state in STATES {
+            out.sample(
+                "harness_service_state",
+                &[("service", service), ("state", state)],
+                if state == current { 1.0 } else { 0.0 },
+            );
+        }
+    }
+
+    out.family(
+        "harness_service_restarts_total",
+        "counter",
+        "Times a service was started again since the daemon started",
+    );
+    for (service, count) in restarts.iter().collect::<BTreeMap<_, _>>() {
+        out.sample(
+            "harness_service_restarts_total",
+            &[("service", service)],
+            *count as f64,
+        );
+    }
+
+    let metrics = manager.get_service_metrics(&[]).await.unwrap_or_default();
+    let now = chrono::Utc::now();
+    out.family(
+        "harness_service_uptime_seconds",
+        "gauge",
+        "Time since a running service was started",
+    );
+    for service in &metrics {
+        if let Some(uptime) = service.uptime(now) {
+            out.sample(
+                "harness_service_uptime_seconds",
+                &[("service", &service.name)],
+                uptime.num_milliseconds() as f64 / 1000.0,
+            );
+        }
+    }
+    out.family(
+        "harness_service_cpu_percent",
+        "gauge",
+        "CPU usage of a service and its descendants, in percent of one CPU",
+    );
+    for service in &metrics {
+        if let Some(cpu) = service.latest().and_then(|sample| sample.cpu_percent) {
+            out.sample(
+                "harness_service_cpu_percent",
+                &[("service", &service.name)],
+                cpu,
+            );
+        }
+    }
+    out.family(
+        "harness_service_resident_memory_bytes",
+        "gauge",
+        "Resident memory of a service and its descendants",
+    );
+    for service in &metrics {
+        if let Some(sample) = service.latest() {
+            out.sample(
+                "harness_service_resident_memory_bytes",
+                &[("service", &service.name)],
+                sample.rss_bytes as f64,
+            );
+        }
+    }
+    out.family(
+        "harness_service_processes",
+        "gauge",
+        "Processes of a service",
+    );
+    for service in &metrics {
+        if let Some(sample) = service.latest() {
+            out.sample(
+                "harness_service_processes",
+                &[("service", &service.name)],
+                f64::from(sample.processes),
+            );
+        }
+    }
+
+    let health: BTreeMap<_, _> = manager.health_check_stats().into_iter().collect();
+    out.family(
+        "harness_health_checks_total",
+        "counter",
+        "Health checks run, by result",
+    );
+    for (service, stats) in &health {
+        for (result, count) in [("healthy", stats.healthy), ("unhealthy", stats.unhealthy)] {
+            out.sample(
+                "harness_health_checks_total",
+                &[("service", service), ("result", result)],
+                count as f64,
+            );
+        }
+    }
+    out.family(
+        "harness_health_check_duration_seconds",
+        "summary",
+        "Time spent running health checks",
+    );
+    for (service, stats) in &health {
+        out.sample(
+            "harness_health_check_duration_seconds_sum",
+            &[("service", service)],
+            stats.total_duration.as_secs_f64(),
+        );
+        out.sample(
+            "harness_health_check_duration_seconds_count",
+            &[("service", service)],
+            stats.count() as f64,
+        );
+    }
+    out.family(
+        "harness_health_check_last_duration_seconds",
+        "gauge",
+        "Duration of the most recent health check",
+    );
+    for (service, stats) in &health {
+        if let Some(duration) = stats.last_duration {
+            out.sample(
+                "harness_health_check_last_duration_seconds",
+                &[("service", service)],
+                duration.as_secs_f64(),
+            );
+        }
+    }
+}
+
+/// What an HTTP request asks for
+#[derive(Debug, PartialEq)]
+enum Route {
+    Metrics,
+    NotFound,
+    MethodNotAllowed,
+    BadRequest,
+}
+
+/// Route an HTTP request by its request line
+fn route(head: &str) -> Route {
+    let mut parts = head.lines().next().unwrap_or_default().split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(path)) => {
+            if path.split('?').next() == Some("/metrics") {
+                Route::Metrics
+            } else {
+                Route::NotFound
+            }
+        }
+        (Some(_), Some(_)) => Route::MethodNotAllowed,
+        _ => Route::BadRequest,
+    }
+}
+
+/// Serve `/metrics` on `addr` until the daemon exits
+pub async fn serve(addr: SocketAddr, state: Arc<DaemonState>) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind metrics endpoint to {}", addr))?;
+    info!("Metrics endpoint listening on http://{}/metrics", addr);
+
+    loop {
+        let (stream, peer_addr) = listener
+            .accept()
+            .await
+            .context("Failed to accept metrics connection")?;
+        let state = state.clone();
+        smol::spawn(async move {
+            if let Err(e) = handle_http(stream, &state).await {
+                debug!("Metrics request from {} failed: {}", peer_addr, e);
+            }
+        })
+        .detach();
+    }
+}
+
+/// Answer one HTTP request and close the connection
+async fn handle_http(mut stream: TcpStream, state: &DaemonState) -> Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || head.len() > MAX_REQUEST_HEAD {
+            break;
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+
+    let (status, content_type, body) = match route(&String::from_utf8_lossy(&head)) {
+        Route::Metrics => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            render(state).await,
+        ),
+        Route::NotFound => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        Route::MethodNotAllowed => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n".to_string(),
+        ),
+        Route::BadRequest => ("400 Bad Request", "text/plain", "Bad request\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.close().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daemon::wireguard::WireGuardState;
+    use service_orchestration::{ServiceConfig, ServiceTarget};
+    use service_registry::Registry;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(
+            route("GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n"),
+            Route::Metrics
+        );
+        assert_eq!(route("GET /metrics?x=1 HTTP/1.1\r\n\r\n"), Route::Metrics);
+        assert_eq!(route("GET / HTTP/1.1\r\n\r\n"), Route::NotFound);
+        assert_eq!(
+            route("POST /metrics HTTP/1.1\r\n\r\n"),
+            Route::MethodNotAllowed
+        );
+        assert_eq!(route(""), Route::BadRequest);
+    }
+
+    #[test]
+    fn test_request_latency() {
+        let metrics = RequestMetrics::default();
+        metrics.observe("ListServices", Duration::from_millis(3));
+        metrics.observe("ListServices", Duration::from_millis(70));
+        metrics.observe("StartService", Duration::from_secs(60));
+
+        let mut out = Exposition::default();
+        metrics.write(&mut out);
+        let text = out.out;
+        assert!(text.contains("# TYPE harness_request_duration_seconds histogram\n"));
+        for line in [
+            r#"harness_request_duration_seconds_bucket{request="ListServices",le="0.005"} 1"#,
+            r#"harness_request_duration_seconds_bucket{request="ListServices",le="0.05"} 1"#,
+            r#"harness_request_duration_seconds_bucket{request="ListServices",le="0.1"} 2"#,
+            r#"harness_request_duration_seconds_bucket{request="ListServices",le="+Inf"} 2"#,
+            r#"harness_request_duration_seconds_count{request="ListServices"} 2"#,
+            r#"harness_request_duration_seconds_bucket{request="StartService",le="30"} 0"#,
+            r#"harness_request_duration_seconds_bucket{request="StartService",le="+Inf"} 1"#,
+            r#"harness_request_duration_seconds_sum{request="StartService"} 60"#,
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {}", line);
+        }
+    }
+
+    #[test]
+    fn test_escape() {
+        let mut out = Exposition::default();
+        out.sample("m", &[("service", "a\"b\\c\nd")], 1.5);
+        assert_eq!(out.out, "m{service=\"a\\\"b\\\\c\\nd\"} 1.5\n");
+    }
+
+    #[test]
+    fn test_service_metrics() {
+        smol::block_on(async {
+            let dir = tempfile::tempdir().unwrap();
+            let manager = ServiceManager::with_state_dir(dir.path()).await.unwrap();
+            let config = ServiceConfig {
+                name: "sleeper".to_string(),
+                target: ServiceTarget::Process {
+                    binary: "sleep".to_string(),
+                    args: vec!["30".to_string()],
+                    env: Default::default(),
+                    working_dir: None,
+                    user: None,
+                    limits: Default::default(),
+                    shutdown_timeout: Some(1),
+                },
+                dependencies: vec![],
+                health_check: None,
+            };
+            manager.start_service("sleeper", config).await.unwrap();
+            manager.collect_metrics().await.unwrap();
+
+            let mut out = Exposition::default();
+            write_services(&mut out, &manager).await;
+            manager.stop_service("sleeper").await.unwrap();
+
+            let text = out.out;
+            for line in [
+                r#"harness_service_state{service="sleeper",state="running"} 1"#,
+                r#"harness_service_state{service="sleeper",state="stopped"} 0"#,
+                r#"harness_service_restarts_total{service="sleeper"} 0"#,
+                r#"harness_service_processes{service="sleeper"} 1"#,
+            ] {
+                assert!(text.lines().any(|l| l == line), "missing {}", line);
+            }
+            assert!(text.contains("harness_service_resident_memory_bytes{service=\"sleeper\"} "));
+            assert!(text.contains("harness_service_uptime_seconds{service=\"sleeper\"} "));
+        });
+    }
+
+    #[test]
+    fn test_http_endpoint() {
+        smol::block_on(async {
+            let dir = tempfile::tempdir().unwrap();
+            let state = DaemonState {
+                service_manager: Arc::new(
+                    ServiceManager::with_state_dir(dir.path()).await.unwrap(),
+                ),
+                registry: Arc::new(Registry::new().await),
+                wireguard: WireGuardState::load(dir.path()),
+                request_metrics: RequestMetrics::default(),
+            };
+            state
+                .request_metrics
+                .observe("ListServices", Duration::from_millis(1));
+
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let get = |request: &'static str| async move {
+                let mut client = TcpStream::connect(addr).await.unwrap();
+                client.write_all(request.as_bytes()).await.unwrap();
+                let mut response = String::new();
+                client.read_to_string(&mut response).await.unwrap();
+                response
+            };
+
+            let (response, _) = futures::join!(get("GET /metrics HTTP/1.1\r\n\r\n"), async {
+                let (stream, _) = listener.accept().await.unwrap();
+                handle_http(stream, &state).await.unwrap();
+            });
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+            assert!(response.contains("harness_registry_subscribers 0\n"));
+            assert_eq!(
+                response.matches("\nharness_registry_subscribers ").count(),
+                1
+            );
+            assert!(
+                response.contains(
+                    "harness_request_duration_seconds_count{request=\"ListServices\"} 1\n"
+                )
+            );
+
+            let (response, _) = futures::join!(get("GET / HTTP/1.1\r\n\r\n"), async {
+                let (stream, _) = listener.accept().await.unwrap();
+                handle_http(stream, &state).await.unwrap();
+            });
+            assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        });
+    }
+}
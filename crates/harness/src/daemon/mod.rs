@@ -2,18 +2,61 @@
 
 pub mod certificates;
 pub mod handlers;
+pub mod metrics;
 pub mod server;
+pub mod wireguard;
 
 use anyhow::Result;
+use service_registry::RegistryConfig;
+use std::net::SocketAddr;
 use std::path::Path;
 
+/// Default port of the daemon's WebSocket server
+pub const DEFAULT_PORT: u16 = 9443;
+
+/// Where the executor daemon listens and keeps its registry
+#[derive(Debug, Clone)]
+pub struct DaemonConfig {
+    /// Port of the WebSocket server on localhost
+    pub port: u16,
+    /// Address of the Prometheus `/metrics` endpoint, off if `None`
+    pub metrics_addr: Option<SocketAddr>,
+    /// Registry configuration, whose `storage` selects the registry backend
+    ///
+    /// Without one the registry is a sled database in the service manager's
+    /// state directory.
+    pub registry: Option<RegistryConfig>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            metrics_addr: None,
+            registry: None,
+        }
+    }
+}
+
 /// Run the executor daemon
 pub async fn run(data_dir: impl AsRef<Path>, port: u16) -> Result<()> {
+    run_with_config(
+        data_dir,
+        DaemonConfig {
+            port,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+/// Run the executor daemon with a metrics endpoint or other settings
+pub async fn run_with_config(data_dir: impl AsRef<Path>, config: DaemonConfig) -> Result<()> {
     let data_dir = data_dir.as_ref();
 
     // Ensure certificates exist and are valid
     certificates::ensure_valid_certificates(data_dir, false).await?;
 
     // Start the WebSocket server with TLS
-    server::start_server(data_dir, port).await
+    server::start_server(data_dir, &config).await
 }
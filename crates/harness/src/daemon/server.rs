@@ -1,6 +1,8 @@
 //! WebSocket server for the executor daemon
 
-use crate::daemon::handlers;
+use crate::daemon::metrics::{self, RequestMetrics};
+use crate::daemon::wireguard::WireGuardState;
+use crate::daemon::{DaemonConfig, handlers};
 use crate::protocol::{Request, Response};
 use anyhow::{Context, Result};
 use async_net::{TcpListener, TcpStream};
@@ -15,8 +17,12 @@ use service_registry::Registry;
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info};
 
+/// How often running services' resource usage is sampled
+const METRICS_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Daemon state shared between connections
 ///
 /// Both ServiceManager and Registry implement their own internal synchronization:
@@ -26,26 +32,57 @@ use tracing::{debug, error, info};
 pub struct DaemonState {
     pub service_manager: Arc<ServiceManager>,
     pub registry: Arc<Registry>,
+    pub wireguard: WireGuardState,
+    pub request_metrics: RequestMetrics,
 }
 
 /// Start the WebSocket server
-pub async fn start_server(data_dir: &Path, port: u16) -> Result<()> {
-    // Create service manager with persistent registry
-    let service_manager = ServiceManager::new()
+pub async fn start_server(data_dir: &Path, config: &DaemonConfig) -> Result<()> {
+    // Create service manager with the configured registry backend
+    let state_dir = ServiceManager::default_state_dir();
+    let storage = match &config.registry {
+        Some(registry) => registry.storage.clone(),
+        None => ServiceManager::default_storage(&state_dir),
+    };
+    info!("Registry storage: {:?}", storage);
+    let service_manager = ServiceManager::with_storage(state_dir, storage)
         .await
         .context("Failed to create service manager")?;
 
-    // Get registry from service manager
-    // For now, we'll create a new one (this will be refactored)
-    let registry =
-        Registry::with_persistence(data_dir.join("registry.json").to_string_lossy()).await;
+    // Share the service manager's registry
+    let registry = service_manager.service_registry().clone();
 
     // Create daemon state
     let state = Arc::new(DaemonState {
         service_manager: Arc::new(service_manager),
         registry: Arc::new(registry),
+        wireguard: WireGuardState::load(data_dir),
+        request_metrics: RequestMetrics::default(),
     });
 
+    // Sample resource usage of running services in the background
+    let metrics_state = state.clone();
+    smol::spawn(async move {
+        loop {
+            smol::Timer::after(METRICS_INTERVAL).await;
+            if let Err(e) = metrics_state.service_manager.collect_metrics().await {
+                debug!("Failed to collect metrics: {}", e);
+            }
+        }
+    })
+    .detach();
+
+    // Serve Prometheus metrics if configured
+    if let Some(metrics_addr) = config.metrics_addr {
+        let metrics_state = state.clone();
+        smol::spawn(async move {
+            if let Err(e) = metrics::serve(metrics_addr, metrics_state).await {
+                error!("Metrics endpoint failed: {:#}", e);
+            }
+        })
+        .detach();
+    }
+
     // Load TLS configuration
     let cert_path = data_dir.join("certs/server.crt");
     let key_path = data_dir.join("certs/server.key");
@@ -73,7 +110,7 @@ pub async fn start_server(data_dir: &Path, port: u16) -> Result<()> {
 
     let tls_acceptor = TlsAcceptor::from(Arc::new(tls_config));
 
-    let addr = format!("127.0.0.1:{}", port);
+    let addr = format!("127.0.0.1:{}", config.port);
     let listener = TcpListener::bind(&addr)
         .await
         .context("Failed to bind to address")?;
@@ -149,7 +186,10 @@ async fn handle_connection(
                 };
 
                 // Handle request
+                let kind = request.kind();
+                let started = Instant::now();
                 let response = handlers::handle_request(request, state.clone()).await?;
+                state.request_metrics.observe(kind, started.elapsed());
 
                 // Send response
                 let response_text = serde_json::to_string(&response)?;
@@ -0,0 +1,123 @@
+//! WireGuard mesh state owned by the daemon
+//!
+//! Provisioned meshes are kept in memory and mirrored to
+//! `<data_dir>/wireguard/<network>.json`, so node keys and mesh addresses
+//! survive daemon restarts and re-provisioning doesn't rotate keys.
+
+use crate::protocol::WireGuardNetworkStatus;
+use anyhow::{Context, Result, anyhow};
+use futures::lock::Mutex;
+use service_registry::network::wireguard::{
+    WireGuardMesh, WireGuardMeshConfig, WireGuardProvisioner,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Manages WireGuard meshes provisioned through the daemon
+pub struct WireGuardState {
+    /// Directory holding persisted mesh state
+    state_dir: PathBuf,
+    /// Provisioned meshes by network name
+    meshes: Mutex<HashMap<String, WireGuardMesh>>,
+    /// Provisioner used to reach the nodes
+    provisioner: WireGuardProvisioner,
+}
+
+impl WireGuardState {
+    /// Create the state, loading any meshes persisted under `data_dir`
+    pub fn load(data_dir: &Path) -> Self {
+        let state_dir = data_dir.join("wireguard");
+        let mut meshes = HashMap::new();
+
+        if let Ok(entries) = std::fs::read_dir(&state_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                    continue;
+                }
+                match read_mesh(&path) {
+                    Ok(mesh) => {
+                        meshes.insert(mesh.config.name.clone(), mesh);
+                    }
+                    Err(e) => warn!("Ignoring WireGuard state {:?}: {}", path, e),
+                }
+            }
+        }
+
+        Self {
+            state_dir,
+            meshes: Mutex::new(meshes),
+            provisioner: WireGuardProvisioner::new(),
+        }
+    }
+
+    /// Provision a mesh and bring it up, reusing keys from earlier runs
+    pub async fn up(&self, config: WireGuardMeshConfig) -> Result<()> {
+        let mut meshes = self.meshes.lock().await;
+        let name = config.name.clone();
+
+        let mesh = WireGuardMesh::with_previous(config, meshes.get(&name))?;
+        self.persist(&mesh)?;
+        meshes.insert(name.clone(), mesh.clone());
+        drop(meshes);
+
+        info!("Provisioning WireGuard mesh {}", name);
+        self.provisioner.up(&mesh).await?;
+        Ok(())
+    }
+
+    /// Bring a mesh down, keeping its keys for the next `up`
+    pub async fn down(&self, network: &str) -> Result<()> {
+        let mesh = self
+            .meshes
+            .lock()
+            .await
+            .get(network)
+            .cloned()
+            .ok_or_else(|| anyhow!("WireGuard network '{}' has not been provisioned", network))?;
+
+        info!("Tearing down WireGuard mesh {}", network);
+        self.provisioner.down(&mesh).await?;
+        Ok(())
+    }
+
+    /// Collect handshake state for every provisioned mesh
+    pub async fn status(&self) -> Vec<WireGuardNetworkStatus> {
+        let meshes: Vec<WireGuardMesh> = self.meshes.lock().await.values().cloned().collect();
+
+        let mut networks = Vec::with_capacity(meshes.len());
+        for mesh in meshes {
+            networks.push(WireGuardNetworkStatus {
+                network: mesh.config.name.clone(),
+                interface: mesh.config.interface.clone(),
+                nodes: self.provisioner.status(&mesh).await,
+            });
+        }
+        networks.sort_by(|a, b| a.network.cmp(&b.network));
+        networks
+    }
+
+    /// Write mesh state (including private keys) readable only by the owner
+    fn persist(&self, mesh: &WireGuardMesh) -> Result<()> {
+        std::fs::create_dir_all(&self.state_dir)
+            .with_context(|| format!("Failed to create {:?}", self.state_dir))?;
+
+        let path = self.state_dir.join(format!("{}.json", mesh.config.name));
+        std::fs::write(&path, serde_json::to_vec_pretty(mesh)?)
+            .with_context(|| format!("Failed to write {:?}", path))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_mesh(path: &Path) -> Result<WireGuardMesh> {
+    let data = std::fs::read(path)?;
+    Ok(serde_json::from_slice(&data)?)
+}
@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand};
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 mod commands;
@@ -43,6 +44,10 @@ enum Commands {
         /// Timeout in seconds to wait for services to stop
         #[arg(short, long)]
         timeout: Option<u64>,
+
+        /// Also forget the services, releasing their IP leases
+        #[arg(long)]
+        remove: bool,
     },
 
     /// Show service status
@@ -60,6 +65,42 @@ enum Commands {
         detailed: bool,
     },
 
+    /// Live view of running services' uptime, CPU and memory
+    Top {
+        /// Services to show (empty means all running)
+        services: Vec<String>,
+
+        /// Seconds between refreshes
+        #[arg(short, long, default_value = "2")]
+        interval: u64,
+
+        /// Sort by cpu, rss, uptime or name
+        #[arg(short, long, default_value = "cpu")]
+        sort: String,
+
+        /// Exit after this many refreshes
+        #[arg(short = 'n', long)]
+        iterations: Option<u64>,
+    },
+
+    /// Run a command in a service's container, remote host or local context
+    Exec {
+        /// Service to run the command for
+        service: String,
+
+        /// Forward stdin to the command
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Run the command on a terminal
+        #[arg(short, long)]
+        tty: bool,
+
+        /// Command and its arguments
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+
     /// Daemon management commands
     Daemon {
         #[command(subcommand)]
@@ -71,6 +112,108 @@ enum Commands {
         #[command(subcommand)]
         command: EnvCommands,
     },
+
+    /// Network management (WireGuard meshes, IP allocations)
+    Network {
+        #[command(subcommand)]
+        command: NetworkCommands,
+    },
+
+    /// Show the recorded history of a service
+    History {
+        /// Service name
+        service: String,
+
+        /// Only events since this time (RFC 3339 or age such as 10m, 2h, 1d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only events before this time (RFC 3339 or age such as 10m, 2h, 1d)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Show at most this many of the most recent events
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+
+        /// Output format (table or json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
+    /// Registry storage management
+    Registry {
+        #[command(subcommand)]
+        command: RegistryCommands,
+    },
+
+    /// Generate service configuration from other formats
+    Import {
+        #[command(subcommand)]
+        command: ImportCommands,
+    },
+
+    /// Discover and invoke service actions on an action daemon
+    Action {
+        /// Action daemon WebSocket endpoint
+        #[arg(short, long, global = true, default_value = commands::action::DEFAULT_ACTION_ENDPOINT)]
+        endpoint: SocketAddr,
+
+        #[command(subcommand)]
+        command: ActionCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ActionCommands {
+    /// List actions and their parameters
+    List {
+        /// Only show actions of this service
+        #[arg(short, long)]
+        service: Option<String>,
+
+        /// Output format (table or json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+
+    /// Invoke an action and stream its events
+    Invoke {
+        /// Service or task instance (`daemon` for daemon actions)
+        service: String,
+
+        /// Action name, e.g. MineBlocks
+        action: String,
+
+        /// Parameter as name=value (repeatable)
+        #[arg(short, long = "param", value_name = "NAME=VALUE")]
+        params: Vec<String>,
+
+        /// Read input from a JSON file (`-` for stdin)
+        #[arg(long, value_name = "FILE")]
+        json: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportCommands {
+    /// Turn a docker compose file into a services.yaml skeleton
+    Compose {
+        /// Compose file to import
+        file: PathBuf,
+
+        /// Compose project name (defaults to the file's `name`)
+        #[arg(short, long)]
+        project_name: Option<String>,
+
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it exists
+        #[arg(short, long)]
+        force: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -88,6 +231,64 @@ enum EnvCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum NetworkCommands {
+    /// Provision WireGuard networks and bring interfaces up
+    Up {
+        /// Networks to bring up (empty means all WireGuard networks)
+        networks: Vec<String>,
+    },
+
+    /// Bring WireGuard interfaces down
+    Down {
+        /// Networks to bring down (empty means all WireGuard networks)
+        networks: Vec<String>,
+    },
+
+    /// Show IP addresses leased to services
+    Allocations {
+        /// Output format (table or json)
+        #[arg(short, long, default_value = "table")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum RegistryCommands {
+    /// Export registry contents as a JSON snapshot
+    Export {
+        /// Storage backend to read (sled or jsonl)
+        #[arg(short, long, default_value = "sled")]
+        backend: String,
+
+        /// Backend location (required for jsonl; sled defaults to the daemon's database)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Import a JSON snapshot into a registry backend
+    Import {
+        /// Snapshot file produced by `harness registry export`
+        input: PathBuf,
+
+        /// Storage backend to write (sled or jsonl)
+        #[arg(short, long, default_value = "sled")]
+        backend: String,
+
+        /// Backend location (required for jsonl; sled defaults to the daemon's database)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Remove existing entries that aren't in the snapshot
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
 fn main() {
     let result = smol::block_on(async {
         let cli = Cli::parse();
@@ -99,16 +300,98 @@ fn main() {
                 services,
                 force,
                 timeout,
-            } => commands::stop::run(&cli.config, services, force, timeout).await,
+                remove,
+            } => commands::stop::run(&cli.config, services, force, timeout, remove).await,
             Commands::Status {
                 format,
                 watch,
                 detailed,
             } => commands::status::run(&cli.config, format, watch, detailed).await,
+            Commands::Top {
+                services,
+                interval,
+                sort,
+                iterations,
+            } => commands::top::run(services, interval, &sort, iterations).await,
+            Commands::Exec {
+                service,
+                interactive,
+                tty,
+                command,
+            } => match commands::exec::run(&cli.config, &service, &command, interactive, tty).await
+            {
+                Ok(0) => Ok(()),
+                Ok(code) => std::process::exit(code),
+                Err(e) => Err(e),
+            },
             Commands::Daemon { command } => commands::daemon::run(command).await,
             Commands::Env { command } => match command {
                 EnvCommands::Get { names } => commands::env::get(names).await,
             },
+            Commands::Network { command } => match command {
+                NetworkCommands::Up { networks } => {
+                    commands::network::up(&cli.config, networks).await
+                }
+                NetworkCommands::Down { networks } => {
+                    commands::network::down(&cli.config, networks).await
+                }
+                NetworkCommands::Allocations { format } => {
+                    commands::network::allocations(&format).await
+                }
+            },
+            Commands::History {
+                service,
+                since,
+                until,
+                limit,
+                format,
+            } => {
+                commands::history::run(&service, since.as_deref(), until.as_deref(), limit, &format)
+                    .await
+            }
+            Commands::Registry { command } => match command {
+                RegistryCommands::Export {
+                    backend,
+                    path,
+                    output,
+                } => commands::registry::export(&backend, path, output.as_deref()).await,
+                RegistryCommands::Import {
+                    input,
+                    backend,
+                    path,
+                    replace,
+                } => commands::registry::import(&input, &backend, path, replace).await,
+            },
+            Commands::Import { command } => match command {
+                ImportCommands::Compose {
+                    file,
+                    project_name,
+                    output,
+                    force,
+                } => {
+                    commands::import::compose(
+                        &file,
+                        project_name.as_deref(),
+                        output.as_deref(),
+                        force,
+                    )
+                    .await
+                }
+            },
+            Commands::Action { endpoint, command } => match command {
+                ActionCommands::List { service, format } => {
+                    commands::action::list(endpoint, service.as_deref(), &format).await
+                }
+                ActionCommands::Invoke {
+                    service,
+                    action,
+                    params,
+                    json,
+                } => {
+                    commands::action::invoke(endpoint, &service, &action, &params, json.as_deref())
+                        .await
+                }
+            },
         }
     });
 
@@ -1,7 +1,9 @@
 //! Protocol types for daemon communication
 
 use serde::{Deserialize, Serialize};
-use service_orchestration::{ServiceConfig, ServiceStatus};
+use service_orchestration::{RunningService, ServiceConfig, ServiceMetrics, ServiceStatus};
+use service_registry::models::{HistoryEvent, HistoryQuery, IpAllocation};
+use service_registry::network::wireguard::{NodeStatus, WireGuardMeshConfig};
 use std::collections::HashMap;
 
 /// Request messages from client to daemon
@@ -14,6 +16,9 @@ pub enum Request {
     /// Stop a service
     StopService { name: String },
 
+    /// Stop a service if running and forget it, releasing its IP lease
+    RemoveService { name: String },
+
     /// Get status of a specific service
     GetServiceStatus { name: String },
 
@@ -34,6 +39,65 @@ pub enum Request {
         /// Optional list of variable names to get. If empty, get all.
         names: Vec<String>,
     },
+
+    /// Provision a WireGuard mesh and bring its interfaces up
+    WireGuardUp { config: WireGuardMeshConfig },
+
+    /// Bring a provisioned WireGuard mesh down
+    WireGuardDown { network: String },
+
+    /// Get peer handshake state for all provisioned WireGuard meshes
+    WireGuardStatus,
+
+    /// List persisted IP leases
+    ListIpAllocations,
+
+    /// Query the registry history log
+    GetHistory { query: HistoryQuery },
+
+    /// Get uptime and recent resource usage of running services
+    GetMetrics {
+        /// Services to get metrics of. If empty, get all.
+        services: Vec<String>,
+    },
+
+    /// Get the configuration and container of a running service
+    GetServiceInfo { name: String },
+}
+
+impl Request {
+    /// Name of the request type, as in its `type` tag
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Request::StartService { .. } => "StartService",
+            Request::StopService { .. } => "StopService",
+            Request::RemoveService { .. } => "RemoveService",
+            Request::GetServiceStatus { .. } => "GetServiceStatus",
+            Request::ListServices => "ListServices",
+            Request::ListServicesDetailed => "ListServicesDetailed",
+            Request::RunHealthChecks => "RunHealthChecks",
+            Request::Shutdown => "Shutdown",
+            Request::GetEnvironmentVariables { .. } => "GetEnvironmentVariables",
+            Request::WireGuardUp { .. } => "WireGuardUp",
+            Request::WireGuardDown { .. } => "WireGuardDown",
+            Request::WireGuardStatus => "WireGuardStatus",
+            Request::ListIpAllocations => "ListIpAllocations",
+            Request::GetHistory { .. } => "GetHistory",
+            Request::GetMetrics { .. } => "GetMetrics",
+            Request::GetServiceInfo { .. } => "GetServiceInfo",
+        }
+    }
+}
+
+/// Handshake state of a provisioned WireGuard mesh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WireGuardNetworkStatus {
+    /// Network name
+    pub network: String,
+    /// Interface name on the nodes
+    pub interface: String,
+    /// Per-node status
+    pub nodes: Vec<NodeStatus>,
 }
 
 /// Service network information
@@ -68,6 +132,12 @@ pub struct DetailedServiceInfo {
     pub start_time: Option<String>,
     /// Service dependencies
     pub dependencies: Vec<String>,
+    /// Resource limits and how they are enforced (if any)
+    #[serde(default)]
+    pub limits: Option<String>,
+    /// User the service runs as (if switched)
+    #[serde(default)]
+    pub user: Option<String>,
 }
 
 /// Response messages from daemon to client
@@ -102,4 +172,21 @@ pub enum Response {
 
     /// Environment variables
     EnvironmentVariables { variables: HashMap<String, String> },
+
+    /// WireGuard mesh status
+    WireGuardStatus {
+        networks: Vec<WireGuardNetworkStatus>,
+    },
+
+    /// Persisted IP leases
+    IpAllocations { allocations: Vec<IpAllocation> },
+
+    /// Registry history events, oldest first
+    History { events: Vec<HistoryEvent> },
+
+    /// Uptime and recent resource usage of running services
+    Metrics { services: Vec<ServiceMetrics> },
+
+    /// A running service instance, or None if it isn't running
+    ServiceInfo { service: Option<RunningService> },
 }
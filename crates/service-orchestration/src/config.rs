@@ -3,6 +3,7 @@
 //! This module defines the configuration model for services that matches
 //! the ADR-007 specification for heterogeneous service orchestration.
 
+pub use command_executor::target::ResourceLimits;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -30,88 +31,124 @@ pub struct ServiceConfig {
     pub health_check: Option<HealthCheck>,
 }
 
-/// Service execution target specification
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(tag = "type", rename_all = "kebab-case")]
-pub enum ServiceTarget {
-    /// Local process execution (managed)
-    #[serde(rename = "process")]
-    Process {
-        /// Binary to execute
-        binary: String,
-        /// Command line arguments
-        args: Vec<String>,
-        /// Environment variables
-        env: HashMap<String, String>,
-        /// Working directory (optional)
-        working_dir: Option<String>,
-    },
-    /// Docker container execution (managed)
-    #[serde(rename = "docker")]
-    Docker {
-        /// Container image
-        image: String,
-        /// Environment variables
-        env: HashMap<String, String>,
-        /// Port mappings (host ports)
-        ports: Vec<u16>,
-        /// Volume mounts
-        #[serde(default)]
-        volumes: Vec<String>,
-    },
-    /// Attach to existing Docker container
-    #[serde(rename = "docker-attach")]
-    DockerAttach {
-        /// Container name or ID to attach to
-        container: String,
-        /// Environment variables
-        env: HashMap<String, String>,
-    },
-    /// Attach to existing local process
-    #[serde(rename = "process-attach")]
-    ProcessAttach {
-        /// Process ID to attach to
-        pid: Option<u32>,
-        /// Process name to search for
-        process_name: Option<String>,
-        /// Environment variables
-        env: HashMap<String, String>,
-    },
-    /// Remote execution via SSH (replaces RemoteLan/Wireguard)
-    #[serde(rename = "remote")]
-    Remote {
-        /// Remote host address
-        host: String,
-        /// SSH username
-        user: String,
-        /// Execution mode
-        #[serde(flatten)]
-        mode: RemoteMode,
-        /// Environment variables
-        env: HashMap<String, String>,
-    },
-    /// Remote LAN execution via SSH (deprecated, use Remote)
-    #[deprecated(note = "Use Remote variant instead")]
-    RemoteLan {
-        /// Remote host address
-        host: String,
-        /// SSH username
-        user: String,
-        /// Binary to execute on remote host
-        binary: String,
-        /// Command line arguments
-        args: Vec<String>,
-    },
-    /// WireGuard network execution with package deployment (deprecated, use Remote)
-    #[deprecated(note = "Use Remote variant instead")]
-    Wireguard {
-        /// WireGuard peer address
-        host: String,
-        /// SSH username
-        user: String,
-        /// Path to package tarball for deployment
-        package: String,
-    },
+pub use target::ServiceTarget;
+
+// Older serde_derive releases emit (de)serializers that warn on the legacy
+// `RemoteLan`/`Wireguard` variants, and a lint attribute on the enum itself
+// does not reach derived impls, so the allow is scoped to this module.
+#[allow(deprecated)]
+mod target {
+    use super::RemoteMode;
+    use super::ResourceLimits;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    /// Service execution target specification
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    #[serde(tag = "type", rename_all = "kebab-case")]
+    pub enum ServiceTarget {
+        /// Local process execution (managed)
+        #[serde(rename = "process")]
+        Process {
+            /// Binary to execute
+            binary: String,
+            /// Command line arguments
+            args: Vec<String>,
+            /// Environment variables
+            env: HashMap<String, String>,
+            /// Working directory (optional)
+            working_dir: Option<String>,
+            /// User to run the process as (optional)
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            user: Option<String>,
+            /// Resource limits
+            #[serde(default, skip_serializing_if = "ResourceLimits::is_empty")]
+            limits: ResourceLimits,
+            /// Seconds the process and its descendants get to exit after
+            /// `SIGTERM` before they are killed (optional)
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            shutdown_timeout: Option<u64>,
+        },
+        /// Docker container execution (managed)
+        #[serde(rename = "docker")]
+        Docker {
+            /// Container image
+            image: String,
+            /// Environment variables
+            env: HashMap<String, String>,
+            /// Port mappings (host ports)
+            ports: Vec<u16>,
+            /// Volume mounts
+            #[serde(default)]
+            volumes: Vec<String>,
+        },
+        /// Single service of a docker compose project (managed)
+        #[serde(rename = "compose")]
+        Compose {
+            /// Path to the compose file
+            compose_file: String,
+            /// Service name in the compose file
+            service: String,
+            /// Compose project name (defaults to compose's own choice)
+            #[serde(default)]
+            project_name: Option<String>,
+            /// Environment variables for interpolating the compose file
+            env: HashMap<String, String>,
+        },
+        /// Attach to existing Docker container
+        #[serde(rename = "docker-attach")]
+        DockerAttach {
+            /// Container name or ID to attach to
+            container: String,
+            /// Environment variables
+            env: HashMap<String, String>,
+        },
+        /// Attach to existing local process
+        #[serde(rename = "process-attach")]
+        ProcessAttach {
+            /// Process ID to attach to
+            pid: Option<u32>,
+            /// Process name to search for
+            process_name: Option<String>,
+            /// Environment variables
+            env: HashMap<String, String>,
+        },
+        /// Remote execution via SSH (replaces RemoteLan/Wireguard)
+        #[serde(rename = "remote")]
+        Remote {
+            /// Remote host address
+            host: String,
+            /// SSH username
+            user: String,
+            /// Execution mode
+            #[serde(flatten)]
+            mode: RemoteMode,
+            /// Environment variables
+            env: HashMap<String, String>,
+        },
+        /// Remote LAN execution via SSH (deprecated, use Remote)
+        #[deprecated(note = "Use Remote variant instead")]
+        RemoteLan {
+            /// Remote host address
+            host: String,
+            /// SSH username
+            user: String,
+            /// Binary to execute on remote host
+            binary: String,
+            /// Command line arguments
+            args: Vec<String>,
+        },
+        /// WireGuard network execution with package deployment (deprecated, use Remote)
+        #[deprecated(note = "Use Remote variant instead")]
+        Wireguard {
+            /// WireGuard peer address
+            host: String,
+            /// SSH username
+            user: String,
+            /// Path to package tarball for deployment
+            package: String,
+        },
+    }
 }
 
 /// Remote execution mode
@@ -138,6 +175,7 @@ impl ServiceTarget {
         match self {
             ServiceTarget::Process { env, .. } => env.clone(),
             ServiceTarget::Docker { env, .. } => env.clone(),
+            ServiceTarget::Compose { env, .. } => env.clone(),
             ServiceTarget::DockerAttach { env, .. } => env.clone(),
             ServiceTarget::ProcessAttach { env, .. } => env.clone(),
             ServiceTarget::Remote { env, .. } => env.clone(),
@@ -155,12 +193,18 @@ impl ServiceTarget {
                 binary,
                 args,
                 working_dir,
+                user,
+                limits,
+                shutdown_timeout,
                 ..
             } => ServiceTarget::Process {
                 binary: binary.clone(),
                 args: args.clone(),
                 env: new_env,
                 working_dir: working_dir.clone(),
+                user: user.clone(),
+                limits: limits.clone(),
+                shutdown_timeout: *shutdown_timeout,
             },
             ServiceTarget::Docker {
                 image,
@@ -173,6 +217,17 @@ impl ServiceTarget {
                 ports: ports.clone(),
                 volumes: volumes.clone(),
             },
+            ServiceTarget::Compose {
+                compose_file,
+                service,
+                project_name,
+                ..
+            } => ServiceTarget::Compose {
+                compose_file: compose_file.clone(),
+                service: service.clone(),
+                project_name: project_name.clone(),
+                env: new_env,
+            },
             ServiceTarget::DockerAttach { container, .. } => ServiceTarget::DockerAttach {
                 container: container.clone(),
                 env: new_env,
@@ -286,6 +341,13 @@ mod tests {
                 args: vec!["hello".to_string()],
                 env: HashMap::from([("FOO".to_string(), "bar".to_string())]),
                 working_dir: Some("/tmp".to_string()),
+                user: Some("nobody".to_string()),
+                limits: ResourceLimits {
+                    memory_max: Some(512 << 20),
+                    nofile: Some(4096),
+                    ..Default::default()
+                },
+                shutdown_timeout: Some(30),
             },
             dependencies: vec![Dependency::Service {
                 service: "database".to_string(),
@@ -315,6 +377,9 @@ mod tests {
             args: vec![],
             env: HashMap::new(),
             working_dir: None,
+            user: None,
+            limits: Default::default(),
+            shutdown_timeout: None,
         };
 
         let updated = target.with_env(env.clone());
@@ -336,6 +401,31 @@ mod tests {
         assert_eq!(target, deserialized);
     }
 
+    #[test]
+    fn test_compose_target_serialization() {
+        let yaml = r#"
+type: compose
+compose_file: ./docker-compose.yml
+service: postgres
+env:
+  POSTGRES_TAG: "15"
+"#;
+        let target: ServiceTarget = serde_yaml::from_str(yaml).expect("Failed to deserialize");
+        assert_eq!(
+            target,
+            ServiceTarget::Compose {
+                compose_file: "./docker-compose.yml".to_string(),
+                service: "postgres".to_string(),
+                project_name: None,
+                env: HashMap::from([("POSTGRES_TAG".to_string(), "15".to_string())]),
+            }
+        );
+
+        let yaml = serde_yaml::to_string(&target).expect("Failed to serialize");
+        assert!(yaml.contains("type: compose"));
+        assert_eq!(target, serde_yaml::from_str(&yaml).unwrap());
+    }
+
     #[test]
     fn test_dependency_parsing() {
         // Test service dependency
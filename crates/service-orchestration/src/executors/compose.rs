@@ -0,0 +1,336 @@
+//! Docker compose executor for services of a compose project.
+//!
+//! Each service is a detached [`Target::ComposeService`] brought up by the
+//! [`LocalLauncher`] without its dependencies, so the orchestrator starts,
+//! monitors and stops compose services individually and in its own
+//! dependency order.
+
+use super::docker::container_network_info;
+use super::{EventStream, RunningService, ServiceExecutor};
+use crate::{
+    Error,
+    config::{ServiceConfig, ServiceTarget},
+    health::{HealthChecker, HealthStatus},
+};
+use async_trait::async_trait;
+use command_executor::{
+    Command, Executor,
+    backends::LocalLauncher,
+    target::{ComposeService, Target},
+};
+use futures::stream::StreamExt;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// Executor for docker compose services
+pub struct ComposeExecutor {
+    executor: Executor<LocalLauncher>,
+    health_checker: HealthChecker,
+}
+
+/// A compose service, as named by a [`ServiceTarget::Compose`]
+struct ComposeProject<'a> {
+    service: ComposeService,
+    /// Used for interpolating the compose file
+    env: &'a HashMap<String, String>,
+}
+
+impl<'a> ComposeProject<'a> {
+    fn from_target(target: &'a ServiceTarget) -> Option<Self> {
+        let ServiceTarget::Compose {
+            compose_file,
+            service,
+            project_name,
+            env,
+        } = target
+        else {
+            return None;
+        };
+        let mut service = ComposeService::new(compose_file, service).with_detach(true);
+        if let Some(project) = project_name {
+            service = service.with_project_name(project);
+        }
+        Some(Self { service, env })
+    }
+
+    /// Name of the service in the compose file
+    fn name(&self) -> &str {
+        self.service.service_name()
+    }
+
+    /// `docker compose up --detach --no-deps <service>`, with the target's
+    /// environment
+    fn up(&self) -> (Target, Command) {
+        let mut cmd = Command::new("");
+        cmd.envs(self.env);
+        (Target::ComposeService(self.service.clone()), cmd)
+    }
+
+    /// Another compose subcommand on the service, with the target's
+    /// environment
+    fn command(&self, args: &[&str]) -> Command {
+        let mut cmd = self.service.command(args);
+        cmd.envs(self.env);
+        cmd
+    }
+}
+
+impl ComposeExecutor {
+    /// Create a new compose executor
+    pub fn new() -> Self {
+        Self {
+            executor: Executor::new("compose-executor".to_string(), LocalLauncher),
+            health_checker: HealthChecker::new(),
+        }
+    }
+
+    fn project(config: &ServiceConfig) -> std::result::Result<ComposeProject<'_>, Error> {
+        ComposeProject::from_target(&config.target).ok_or_else(|| {
+            crate::Error::Config("ComposeExecutor can only handle compose targets".to_string())
+        })
+    }
+
+    /// Run a compose command to completion, failing with its output if it
+    /// fails
+    async fn run(
+        &self,
+        project: &ComposeProject<'_>,
+        subcommand: &str,
+        target: &Target,
+        command: Command,
+    ) -> std::result::Result<String, Error> {
+        let result = self.executor.execute(target, command).await?;
+        if !result.success() {
+            return Err(crate::Error::Config(format!(
+                "docker compose {} {} failed: {}",
+                subcommand,
+                project.name(),
+                result.output.trim()
+            )));
+        }
+        Ok(result.output)
+    }
+
+    /// Run a compose subcommand on the service
+    async fn compose(
+        &self,
+        project: &ComposeProject<'_>,
+        args: &[&str],
+    ) -> std::result::Result<String, Error> {
+        self.run(
+            project,
+            &args.join(" "),
+            &Target::Command,
+            project.command(args),
+        )
+        .await
+    }
+}
+
+impl Default for ComposeExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ServiceExecutor for ComposeExecutor {
+    async fn start(&self, config: ServiceConfig) -> std::result::Result<RunningService, Error> {
+        let project = Self::project(&config)?;
+        info!(
+            "Starting compose service '{}' from {}",
+            project.name(),
+            project.service.compose_file().display()
+        );
+
+        // Dependencies are services of their own, started by the orchestrator
+        let (target, cmd) = project.up();
+        self.run(&project, "up", &target, cmd).await?;
+
+        let output = self.compose(&project, &["ps", "--quiet"]).await?;
+        let container_id = output.lines().next().unwrap_or_default().trim().to_string();
+        if container_id.is_empty() {
+            return Err(crate::Error::Config(format!(
+                "Compose service '{}' has no container after starting",
+                project.name()
+            )));
+        }
+
+        let network_info = container_network_info(&self.executor, &container_id).await?;
+        info!(
+            "Started compose service '{}' in container {} (IP={}, ports={:?})",
+            config.name,
+            &container_id[..12.min(container_id.len())],
+            network_info.ip,
+            network_info.ports
+        );
+
+        let service = project.name().to_string();
+        let running_service = RunningService::new(config.name.clone(), config)
+            .with_container_id(container_id)
+            .with_network_info(network_info)
+            .with_metadata("executor_type".to_string(), "compose".to_string())
+            .with_metadata("compose_service".to_string(), service);
+
+        Ok(running_service)
+    }
+
+    async fn stop(&self, service: &RunningService) -> std::result::Result<(), Error> {
+        info!("Stopping compose service: {}", service.name);
+        let project = Self::project(&service.config)?;
+
+        // Stop and remove only this service's container
+        if let Err(e) = self.compose(&project, &["rm", "--stop", "--force"]).await {
+            warn!("Failed to stop compose service {}: {}", service.name, e);
+        } else {
+            info!("Successfully stopped compose service: {}", service.name);
+        }
+
+        Ok(())
+    }
+
+    async fn health_check(
+        &self,
+        service: &RunningService,
+    ) -> std::result::Result<HealthStatus, Error> {
+        let Some(container_id) = &service.container_id else {
+            return Ok(HealthStatus::Unhealthy("No container ID".to_string()));
+        };
+
+        // Container state, and compose's own healthcheck status if it has one
+        let mut inspect_cmd = Command::new("docker");
+        inspect_cmd.args([
+            "inspect",
+            "--format",
+            "{{.State.Running}}|{{if .State.Health}}{{.State.Health.Status}}{{end}}",
+            container_id,
+        ]);
+        let result = self.executor.execute(&Target::Command, inspect_cmd).await?;
+        if !result.success() {
+            return Ok(HealthStatus::Unhealthy("Container not found".to_string()));
+        }
+
+        let output = result.output.trim();
+        let (running, health) = output.split_once('|').unwrap_or((output, ""));
+        if running != "true" {
+            return Ok(HealthStatus::Unhealthy("Container not running".to_string()));
+        }
+        match health {
+            "unhealthy" => {
+                return Ok(HealthStatus::Unhealthy(
+                    "Container healthcheck failing".to_string(),
+                ));
+            }
+            "starting" => return Ok(HealthStatus::Unknown),
+            _ => {}
+        }
+
+        // Configured health checks run on the host, like the manager's
+        match &service.config.health_check {
+            Some(health_check) => self.health_checker.check_health(health_check).await,
+            None => Ok(HealthStatus::Healthy),
+        }
+    }
+
+    async fn stream_events(
+        &self,
+        service: &RunningService,
+    ) -> std::result::Result<EventStream, Error> {
+        let project = Self::project(&service.config)?;
+        let cmd = project.command(&["logs", "--follow", "--no-log-prefix", "--tail", "0"]);
+        let (events, handle) = self.executor.launch(&Target::Command, cmd).await?;
+
+        // `docker compose logs` is killed once the stream is dropped
+        Ok(events
+            .map(move |event| {
+                let _ = &handle;
+                event
+            })
+            .boxed())
+    }
+
+    fn can_handle(&self, config: &ServiceConfig) -> bool {
+        matches!(config.target, ServiceTarget::Compose { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compose_config() -> ServiceConfig {
+        ServiceConfig {
+            name: "postgres".to_string(),
+            target: ServiceTarget::Compose {
+                compose_file: "/srv/graph/docker-compose.yml".to_string(),
+                service: "postgres".to_string(),
+                project_name: Some("graph".to_string()),
+                env: HashMap::from([("POSTGRES_TAG".to_string(), "15".to_string())]),
+            },
+            dependencies: vec![],
+            health_check: None,
+        }
+    }
+
+    #[test]
+    fn test_can_handle() {
+        let executor = ComposeExecutor::new();
+        assert!(executor.can_handle(&compose_config()));
+
+        let docker_config = ServiceConfig {
+            target: ServiceTarget::Docker {
+                image: "postgres:15".to_string(),
+                env: HashMap::new(),
+                ports: vec![],
+                volumes: vec![],
+            },
+            ..compose_config()
+        };
+        assert!(!executor.can_handle(&docker_config));
+    }
+
+    #[test]
+    fn test_compose_project() {
+        let config = compose_config();
+        let project = ComposeProject::from_target(&config.target).unwrap();
+
+        // Brought up detached and on its own
+        let (target, cmd) = project.up();
+        let Target::ComposeService(service) = target else {
+            panic!("Expected a compose service target");
+        };
+        assert_eq!(service.service_name(), "postgres");
+        assert_eq!(service.project_name(), Some("graph"));
+        assert!(service.is_detached());
+        assert!(!service.starts_dependencies());
+        assert_eq!(
+            cmd.get_envs().get(std::ffi::OsStr::new("POSTGRES_TAG")),
+            Some(&"15".into())
+        );
+
+        let cmd = project.command(&["ps", "--quiet"]);
+        let args: Vec<_> = cmd
+            .get_args()
+            .iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(cmd.get_program(), "docker");
+        assert_eq!(
+            args,
+            [
+                "compose",
+                "--file",
+                "/srv/graph/docker-compose.yml",
+                "--project-name",
+                "graph",
+                "ps",
+                "--quiet",
+                "postgres"
+            ]
+        );
+        assert_eq!(
+            cmd.get_envs().get(std::ffi::OsStr::new("POSTGRES_TAG")),
+            Some(&"15".into())
+        );
+    }
+}
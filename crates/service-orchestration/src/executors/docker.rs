@@ -91,7 +91,7 @@ impl DockerExecutor {
         );
 
         // Get network information
-        let network_info = self.get_container_network_info(&container_state.id).await?;
+        let network_info = container_network_info(&self.executor, &container_state.id).await?;
 
         // Create running service instance
         let running_service = RunningService::new(config.name.clone(), config)
@@ -102,58 +102,6 @@ impl DockerExecutor {
 
         Ok(running_service)
     }
-
-    /// Get network information for a container
-    async fn get_container_network_info(
-        &self,
-        container_id: &str,
-    ) -> std::result::Result<NetworkInfo, Error> {
-        // Get container IP address
-        let mut inspect_cmd = Command::new("docker");
-        inspect_cmd.args([
-            "inspect",
-            "--format",
-            "{{.NetworkSettings.IPAddress}}",
-            container_id,
-        ]);
-
-        let result = self.executor.execute(&Target::Command, inspect_cmd).await?;
-        if !result.success() {
-            return Err(crate::Error::Config(format!(
-                "Failed to get container IP: {}",
-                result.output
-            )));
-        }
-
-        let ip = result.output.trim().to_string();
-
-        // Get exposed ports
-        let mut port_cmd = Command::new("docker");
-        port_cmd.args(["port", container_id]);
-
-        let port_result = self.executor.execute(&Target::Command, port_cmd).await?;
-        let mut ports = Vec::new();
-
-        if port_result.success() {
-            // Parse port output (format: "80/tcp -> 0.0.0.0:8080")
-            for line in port_result.output.lines() {
-                if let Some((container_port, _)) = line.split_once(" -> ") {
-                    if let Some((port_str, _)) = container_port.split_once('/') {
-                        if let Ok(port) = port_str.parse::<u16>() {
-                            ports.push(port);
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(NetworkInfo {
-            ip,
-            port: ports.first().copied(),
-            ports,
-            hostname: container_id[..12].to_string(), // Use first 12 chars as hostname
-        })
-    }
 }
 
 impl Default for DockerExecutor {
@@ -252,7 +200,7 @@ impl ServiceExecutor for DockerExecutor {
         );
 
         // Get network information
-        let network_info = self.get_container_network_info(&container_id).await?;
+        let network_info = container_network_info(&self.executor, &container_id).await?;
         info!(
             "Container '{}' network info: IP={}, ports={:?}",
             config.name, network_info.ip, network_info.ports
@@ -388,6 +336,64 @@ impl ServiceExecutor for DockerExecutor {
     }
 }
 
+/// Get network information for a container
+pub(super) async fn container_network_info(
+    executor: &Executor<LocalLauncher>,
+    container_id: &str,
+) -> std::result::Result<NetworkInfo, Error> {
+    // Get container IP address on its first network, which covers both the
+    // default bridge and compose project networks
+    let mut inspect_cmd = Command::new("docker");
+    inspect_cmd.args([
+        "inspect",
+        "--format",
+        "{{range .NetworkSettings.Networks}}{{.IPAddress}} {{end}}",
+        container_id,
+    ]);
+
+    let result = executor.execute(&Target::Command, inspect_cmd).await?;
+    if !result.success() {
+        return Err(crate::Error::Config(format!(
+            "Failed to get container IP: {}",
+            result.output
+        )));
+    }
+
+    let ip = result
+        .output
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    // Get exposed ports
+    let mut port_cmd = Command::new("docker");
+    port_cmd.args(["port", container_id]);
+
+    let port_result = executor.execute(&Target::Command, port_cmd).await?;
+    let mut ports = Vec::new();
+
+    if port_result.success() {
+        // Parse port output (format: "80/tcp -> 0.0.0.0:8080")
+        for line in port_result.output.lines() {
+            if let Some((container_port, _)) = line.split_once(" -> ") {
+                if let Some((port_str, _)) = container_port.split_once('/') {
+                    if let Ok(port) = port_str.parse::<u16>() {
+                        ports.push(port);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(NetworkInfo {
+        ip,
+        port: ports.first().copied(),
+        ports,
+        hostname: container_id[..12].to_string(), // Use first 12 chars as hostname
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -418,6 +424,9 @@ mod tests {
                 args: vec![],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
@@ -4,12 +4,14 @@
 //! service execution environments.
 
 pub mod attached;
+pub mod compose;
 pub mod docker;
 pub mod process;
 pub mod stream_utils;
 pub mod traits;
 
 pub use attached::{DockerAttachedExecutor, LocalProcessAttachedExecutor, SystemdAttachedExecutor};
+pub use compose::ComposeExecutor;
 pub use docker::DockerExecutor;
 pub use process::ProcessExecutor;
 pub use traits::{
@@ -11,14 +11,24 @@ use crate::{
 };
 use async_trait::async_trait;
 use command_executor::{
-    Command, Executor, ProcessHandle, backends::LocalLauncher, event::ProcessEvent, target::Target,
+    Command, Executor, ProcessHandle,
+    backends::LocalLauncher,
+    event::ProcessEvent,
+    target::{ManagedProcess, Target},
 };
 use futures::lock::Mutex;
 use futures::stream::{self, Stream, StreamExt};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 
+/// How long a process gets to exit after `SIGTERM` unless configured
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a process stopped without a handle is checked
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Information about a running process
 struct ProcessInfo {
     handle: Box<dyn ProcessHandle>,
@@ -58,6 +68,60 @@ impl ProcessExecutor {
     }
 }
 
+impl ProcessExecutor {
+    /// Stop a process without a handle: `SIGTERM` and, after the grace
+    /// period, `SIGKILL` to the process group it leads (or the process alone)
+    async fn stop_pid(&self, pid: u32, grace: Duration) -> std::result::Result<(), Error> {
+        if !self.signal_pid(pid, "TERM").await? {
+            return Ok(());
+        }
+
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            async_io::Timer::after(STOP_POLL_INTERVAL).await;
+            if !self.signal_pid(pid, "0").await? {
+                return Ok(());
+            }
+        }
+
+        warn!(
+            "Process {} still running after {:?}, killing it",
+            pid, grace
+        );
+        self.signal_pid(pid, "KILL").await?;
+        Ok(())
+    }
+
+    /// Send a signal to the process group led by `pid`, or to the process if
+    /// it doesn't lead one; false if neither exists
+    async fn signal_pid(&self, pid: u32, signal: &str) -> std::result::Result<bool, Error> {
+        for target in [format!("-{}", pid), pid.to_string()] {
+            let mut kill_cmd = Command::new("kill");
+            kill_cmd.args([&format!("-{}", signal), "--", &target]);
+            if self
+                .executor
+                .execute(&Target::Command, kill_cmd)
+                .await?
+                .success()
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// The grace period of a process service's shutdown
+fn shutdown_timeout(config: &ServiceConfig) -> Duration {
+    match &config.target {
+        ServiceTarget::Process {
+            shutdown_timeout: Some(seconds),
+            ..
+        } => Duration::from_secs(*seconds),
+        _ => DEFAULT_SHUTDOWN_TIMEOUT,
+    }
+}
+
 impl Default for ProcessExecutor {
     fn default() -> Self {
         Self::new()
@@ -72,6 +136,9 @@ impl ServiceExecutor for ProcessExecutor {
             args,
             env,
             working_dir,
+            user,
+            limits,
+            ..
         } = &config.target
         else {
             return Err(crate::Error::Config(
@@ -97,7 +164,11 @@ impl ServiceExecutor for ProcessExecutor {
         }
 
         // Launch the command using ManagedProcess target to get a process handle
-        let target = Target::ManagedProcess(command_executor::target::ManagedProcess::new());
+        let mut process = ManagedProcess::new().with_limits(limits.clone());
+        if let Some(user) = user {
+            process = process.with_user(user);
+        }
+        let target = Target::ManagedProcess(process);
         let (event_stream, handle) = self.executor.launch(&target, cmd).await?;
 
         // Get the PID from the handle
@@ -108,10 +179,24 @@ impl ServiceExecutor for ProcessExecutor {
             config.name, pid
         );
 
+        // Record how the process is isolated, for status reporting
+        let mut metadata = vec![("executor_type", "process".to_string())];
+        if !limits.is_empty() {
+            let limits = match handle.limit_enforcement() {
+                Some(enforcement) => format!("{} ({})", limits, enforcement),
+                None => limits.to_string(),
+            };
+            metadata.push(("limits", limits));
+        }
+        if let Some(user) = user {
+            metadata.push(("user", user.clone()));
+        }
+
         // Create running service instance
-        let running_service = RunningService::new(config.name.clone(), config)
-            .with_pid(pid)
-            .with_metadata("executor_type".to_string(), "process".to_string());
+        let mut running_service = RunningService::new(config.name.clone(), config).with_pid(pid);
+        for (key, value) in metadata {
+            running_service = running_service.with_metadata(key.to_string(), value);
+        }
 
         // Store the process handle and event stream
         {
@@ -130,6 +215,7 @@ impl ServiceExecutor for ProcessExecutor {
 
     async fn stop(&self, service: &RunningService) -> std::result::Result<(), Error> {
         info!("Stopping service: {}", service.name);
+        let grace = shutdown_timeout(&service.config);
 
         // Remove and get the process info
         let process_info = {
@@ -138,20 +224,16 @@ impl ServiceExecutor for ProcessExecutor {
         };
 
         if let Some(mut process_info) = process_info {
-            // Use the handle to properly terminate the process
-            match process_info.handle.terminate().await {
-                Ok(_) => {
-                    info!("Successfully terminated service: {}", service.name);
+            // Stop the process and everything it started
+            match process_info.handle.shutdown(grace).await {
+                Ok(status) => {
+                    info!(
+                        "Successfully stopped service {} ({:?})",
+                        service.name, status
+                    );
                 }
                 Err(e) => {
-                    warn!(
-                        "Failed to terminate service {}: {}, trying kill",
-                        service.name, e
-                    );
-                    // Try force kill
-                    if let Err(e) = process_info.handle.kill().await {
-                        warn!("Failed to kill service {}: {}", service.name, e);
-                    }
+                    warn!("Failed to stop service {} cleanly: {}", service.name, e);
                 }
             }
         } else if let Some(pid) = service.pid {
@@ -160,32 +242,8 @@ impl ServiceExecutor for ProcessExecutor {
                 "No process handle found for service {}, using kill command",
                 service.name
             );
-            let mut kill_cmd = Command::new("kill");
-            kill_cmd.args(&[pid.to_string()]);
-
-            match self.executor.execute(&Target::Command, kill_cmd).await {
-                Ok(result) => {
-                    if result.success() {
-                        info!("Successfully stopped service: {}", service.name);
-                    } else {
-                        warn!(
-                            "Kill command failed for service: {}, trying SIGKILL",
-                            service.name
-                        );
-
-                        // Try force kill
-                        let mut force_kill_cmd = Command::new("kill");
-                        force_kill_cmd.args(["-9", &pid.to_string()]);
-                        self.executor
-                            .execute(&Target::Command, force_kill_cmd)
-                            .await?;
-                    }
-                }
-                Err(e) => {
-                    warn!("Failed to stop service {}: {}", service.name, e);
-                    return Err(e.into());
-                }
-            }
+            self.stop_pid(pid, grace).await?;
+            info!("Successfully stopped service: {}", service.name);
         } else {
             warn!("No PID found for service: {}", service.name);
         }
@@ -249,6 +307,7 @@ impl ServiceExecutor for ProcessExecutor {
 mod tests {
     use super::*;
     use crate::config::{ServiceConfig, ServiceTarget};
+    use command_executor::target::ResourceLimits;
     use std::collections::HashMap;
 
     #[test]
@@ -262,6 +321,9 @@ mod tests {
                 args: vec!["hello".to_string()],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
@@ -295,6 +357,9 @@ mod tests {
                 args: vec!["hello world".to_string()],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
@@ -317,6 +382,9 @@ mod tests {
                 args: vec!["0.1".to_string()],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
@@ -329,6 +397,9 @@ mod tests {
                 args: vec!["0.1".to_string()],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
@@ -374,6 +445,9 @@ mod tests {
                         args: vec!["0.1".to_string()],
                         env: HashMap::new(),
                         working_dir: None,
+                        user: None,
+                        limits: Default::default(),
+                        shutdown_timeout: None,
                     },
                     dependencies: vec![],
                     health_check: None,
@@ -398,6 +472,79 @@ mod tests {
         }
     }
 
+    #[smol_potat::test]
+    async fn test_process_limits_metadata() {
+        let executor = ProcessExecutor::new();
+
+        let config = ServiceConfig {
+            name: "limited".to_string(),
+            target: ServiceTarget::Process {
+                binary: "sleep".to_string(),
+                args: vec!["1".to_string()],
+                env: HashMap::new(),
+                working_dir: None,
+                user: None,
+                limits: ResourceLimits {
+                    nofile: Some(256),
+                    nice: Some(5),
+                    ..Default::default()
+                },
+                shutdown_timeout: None,
+            },
+            dependencies: vec![],
+            health_check: None,
+        };
+
+        let service = executor.start(config).await.unwrap();
+        assert_eq!(
+            service.metadata.get("limits").map(String::as_str),
+            Some("nofile 256, nice 5")
+        );
+        assert!(!service.metadata.contains_key("user"));
+
+        executor.stop(&service).await.unwrap();
+    }
+
+    #[smol_potat::test]
+    async fn test_stop_kills_after_shutdown_timeout() {
+        let executor = ProcessExecutor::new();
+
+        // A wrapper shell that, like its child, ignores SIGTERM
+        let config = ServiceConfig {
+            name: "stubborn".to_string(),
+            target: ServiceTarget::Process {
+                binary: "sh".to_string(),
+                args: vec!["-c".to_string(), "trap '' TERM; sleep 60".to_string()],
+                env: HashMap::new(),
+                working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: Some(1),
+            },
+            dependencies: vec![],
+            health_check: None,
+        };
+
+        let service = executor.start(config).await.unwrap();
+        let pid = service.pid.unwrap();
+        smol::Timer::after(Duration::from_millis(100)).await;
+
+        let started = Instant::now();
+        executor.stop(&service).await.unwrap();
+        let elapsed = started.elapsed();
+        assert!(
+            elapsed >= Duration::from_secs(1),
+            "stopped after {:?}",
+            elapsed
+        );
+        assert!(
+            elapsed < Duration::from_secs(5),
+            "stopped after {:?}",
+            elapsed
+        );
+        assert!(!std::path::Path::new(&format!("/proc/{}", pid)).exists());
+    }
+
     #[smol_potat::test]
     async fn test_process_cleanup_on_exit() {
         let executor = ProcessExecutor::new();
@@ -410,6 +557,9 @@ mod tests {
                 args: vec!["done".to_string()],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
@@ -448,6 +598,9 @@ mod tests {
                 ],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
@@ -500,6 +653,9 @@ mod tests {
                 ],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
@@ -515,6 +671,9 @@ mod tests {
                 ],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
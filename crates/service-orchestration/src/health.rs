@@ -10,6 +10,9 @@ use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 use tracing::{debug, warn};
 
+/// Most output of a timed-out check kept in its status, from the end
+const MAX_TIMEOUT_OUTPUT: usize = 512;
+
 /// Health status of a service
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum HealthStatus {
@@ -35,6 +38,9 @@ impl HealthChecker {
     }
 
     /// Run a single health check
+    ///
+    /// A check running longer than the configured timeout (if not zero) is
+    /// stopped and counts as failed.
     pub async fn check_health(
         &self,
         config: &HealthCheck,
@@ -43,6 +49,9 @@ impl HealthChecker {
 
         let mut cmd = Command::new(&config.command);
         cmd.args(&config.args);
+        if config.timeout > 0 {
+            cmd.timeout(Duration::from_secs(config.timeout));
+        }
 
         debug!(
             "Running health check: {} {}",
@@ -66,6 +75,16 @@ impl HealthChecker {
                     Ok(HealthStatus::Unhealthy(error))
                 }
             }
+            Err(command_executor::Error::Timeout { timeout, output }) => {
+                let mut error = format!("Health check timed out after {}s", timeout.as_secs());
+                let output = output.trim();
+                if !output.is_empty() {
+                    error.push_str(": ");
+                    error.push_str(&output_tail(output));
+                }
+                warn!("{}", error);
+                Ok(HealthStatus::Unhealthy(error))
+            }
             Err(e) => {
                 let error = format!("Health check execution failed: {}", e);
                 warn!("Health check execution failed: {}", e);
@@ -75,6 +94,18 @@ impl HealthChecker {
     }
 }
 
+/// The last [`MAX_TIMEOUT_OUTPUT`] bytes of output, marked if cut
+fn output_tail(output: &str) -> String {
+    if output.len() <= MAX_TIMEOUT_OUTPUT {
+        return output.to_string();
+    }
+    let mut start = output.len() - MAX_TIMEOUT_OUTPUT;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("...{}", &output[start..])
+}
+
 impl Default for HealthChecker {
     fn default() -> Self {
         Self::new()
@@ -88,12 +119,44 @@ pub trait HealthCheckable {
     async fn health_check(&self) -> std::result::Result<HealthStatus, Error>;
 }
 
+/// Results and latency of a service's health checks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HealthCheckStats {
+    /// Checks that passed
+    pub healthy: u64,
+    /// Checks that failed
+    pub unhealthy: u64,
+    /// Total time spent in checks
+    pub total_duration: Duration,
+    /// Duration of the most recent check
+    pub last_duration: Option<Duration>,
+}
+
+impl HealthCheckStats {
+    /// Count a check result
+    pub fn record(&mut self, status: &HealthStatus, duration: Duration) {
+        match status {
+            HealthStatus::Healthy => self.healthy += 1,
+            HealthStatus::Unhealthy(_) => self.unhealthy += 1,
+            HealthStatus::Unknown => return,
+        }
+        self.total_duration += duration;
+        self.last_duration = Some(duration);
+    }
+
+    /// Number of counted checks
+    pub fn count(&self) -> u64 {
+        self.healthy + self.unhealthy
+    }
+}
+
 /// Continuous health monitoring for a service
 pub struct HealthMonitor {
     checker: HealthChecker,
     pub(crate) config: HealthCheck,
     pub(crate) consecutive_failures: u32,
     pub(crate) last_status: HealthStatus,
+    pub(crate) stats: HealthCheckStats,
 }
 
 impl HealthMonitor {
@@ -104,12 +167,15 @@ impl HealthMonitor {
             config,
             consecutive_failures: 0,
             last_status: HealthStatus::Unknown,
+            stats: HealthCheckStats::default(),
         }
     }
 
     /// Run a health check and update internal state
     pub async fn check(&mut self) -> std::result::Result<HealthStatus, Error> {
+        let start = Instant::now();
         let status = self.checker.check_health(&self.config).await?;
+        self.stats.record(&status, start.elapsed());
 
         match &status {
             HealthStatus::Healthy => {
@@ -141,6 +207,11 @@ impl HealthMonitor {
         self.consecutive_failures
     }
 
+    /// Get the results and latency of the checks run so far
+    pub fn stats(&self) -> &HealthCheckStats {
+        &self.stats
+    }
+
     /// Get the health check interval
     pub fn interval(&self) -> Duration {
         Duration::from_secs(self.config.interval)
@@ -182,6 +253,30 @@ mod tests {
         assert_eq!(monitor.current_status(), &HealthStatus::Unknown);
     }
 
+    #[smol_potat::test]
+    async fn test_health_monitor_stats() {
+        let mut monitor = HealthMonitor::new(HealthCheck {
+            command: "false".to_string(),
+            args: vec![],
+            interval: 10,
+            retries: 1,
+            timeout: 5,
+        });
+        monitor.check().await.unwrap();
+        monitor.config.command = "true".to_string();
+        monitor.check().await.unwrap();
+        monitor.check().await.unwrap();
+
+        let stats = monitor.stats();
+        assert_eq!((stats.healthy, stats.unhealthy, stats.count()), (2, 1, 3));
+        assert!(stats.last_duration.is_some());
+        assert!(stats.total_duration >= stats.last_duration.unwrap());
+
+        let mut unknown = HealthCheckStats::default();
+        unknown.record(&HealthStatus::Unknown, Duration::from_secs(1));
+        assert_eq!(unknown, HealthCheckStats::default());
+    }
+
     #[smol_potat::test]
     async fn test_health_checker_success() {
         let checker = HealthChecker::new();
@@ -214,4 +309,40 @@ mod tests {
             _ => panic!("Expected unhealthy status"),
         }
     }
+
+    #[smol_potat::test]
+    async fn test_health_checker_timeout() {
+        let checker = HealthChecker::new();
+        let config = HealthCheck {
+            command: "sh".to_string(), // Hangs after some output
+            args: vec![
+                "-c".to_string(),
+                "echo waiting for database; sleep 30".to_string(),
+            ],
+            interval: 10,
+            retries: 1,
+            timeout: 1,
+        };
+
+        let start = Instant::now();
+        let status = checker.check_health(&config).await.unwrap();
+        assert_eq!(
+            status,
+            HealthStatus::Unhealthy(
+                "Health check timed out after 1s: waiting for database".to_string()
+            )
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_output_tail() {
+        assert_eq!(output_tail("short"), "short");
+
+        let long = format!("{}é{}", "a".repeat(600), "b".repeat(510));
+        let tail = output_tail(&long);
+        assert!(tail.starts_with("...é"));
+        assert!(tail.ends_with('b'));
+        assert!(tail.len() <= MAX_TIMEOUT_OUTPUT + 3);
+    }
 }
@@ -3,7 +3,8 @@
 //! Heterogeneous service orchestration implementing ADR-007.
 //!
 //! This crate provides the core orchestration logic for managing services across
-//! different execution environments (local processes, Docker containers, remote SSH)
+//! different execution environments (local processes, Docker containers, compose
+//! services, remote SSH)
 //! while providing unified networking and service discovery.
 //!
 //! ## Example
@@ -21,6 +22,9 @@
 //!         args: vec!["hello".to_string()],
 //!         env: Default::default(),
 //!         working_dir: None,
+//!         user: None,
+//!         limits: Default::default(),
+//!         shutdown_timeout: None,
 //!     },
 //!     dependencies: vec![],
 //!     health_check: None,
@@ -38,18 +42,22 @@ mod config;
 mod executors;
 mod health;
 mod manager;
+mod metrics;
 mod package;
 mod task_config;
 
 pub use config::{
-    Dependency, HealthCheck, RemoteMode, ServiceConfig, ServiceStatus, ServiceTarget,
+    Dependency, HealthCheck, RemoteMode, ResourceLimits, ServiceConfig, ServiceStatus,
+    ServiceTarget,
 };
 pub use executors::{
-    AttachedService, DockerAttachedExecutor, DockerExecutor, EventStream, EventStreamable,
-    ManagedService, ProcessExecutor, RunningService, ServiceExecutor, SystemdAttachedExecutor,
+    AttachedService, ComposeExecutor, DockerAttachedExecutor, DockerExecutor, EventStream,
+    EventStreamable, ManagedService, ProcessExecutor, RunningService, ServiceExecutor,
+    SystemdAttachedExecutor,
 };
-pub use health::{HealthCheckable, HealthChecker, HealthMonitor, HealthStatus};
+pub use health::{HealthCheckStats, HealthCheckable, HealthChecker, HealthMonitor, HealthStatus};
 pub use manager::ServiceManager;
+pub use metrics::{MetricsCollector, MetricsSample, ServiceMetrics};
 pub use package::{
     DeployedPackage, PackageBuilder, PackageDeployer, PackageHealthCheck, PackageManifest,
     PackageService, RemoteTarget,
@@ -6,11 +6,16 @@
 use crate::{
     Error,
     config::{HealthCheck, ServiceConfig, ServiceStatus},
-    executors::{DockerExecutor, ProcessExecutor, RunningService, ServiceExecutor},
-    health::{HealthChecker, HealthMonitor, HealthStatus},
+    executors::{
+        ComposeExecutor, DockerExecutor, ProcessExecutor, RunningService, ServiceExecutor,
+    },
+    health::{HealthCheckStats, HealthChecker, HealthMonitor, HealthStatus},
+    metrics::{MetricsCollector, ServiceMetrics},
     package::{DeployedPackage, PackageDeployer, RemoteTarget},
 };
+use futures::lock::Mutex;
 use service_registry::{
+    StorageConfig,
     network::{NetworkConfig, NetworkManager},
     registry::Registry,
 };
@@ -23,7 +28,7 @@ pub struct ServiceManager {
     /// Service registry for service discovery
     registry: Registry,
     /// Network manager for topology management
-    network_manager: NetworkManager,
+    network_manager: Mutex<NetworkManager>,
     /// Service executors by type
     executors: HashMap<String, Arc<dyn ServiceExecutor>>,
     /// Currently running services
@@ -32,19 +37,31 @@ pub struct ServiceManager {
     health_monitors: Arc<RwLock<HashMap<String, HealthMonitor>>>,
     /// Package deployer for remote services
     package_deployer: PackageDeployer,
+    /// Resource usage samples of running services
+    metrics: MetricsCollector,
+    /// How often each service was started by this manager
+    starts: RwLock<HashMap<String, u64>>,
 }
 
 impl ServiceManager {
     /// Create a new service manager
     pub async fn new() -> std::result::Result<Self, Error> {
         info!("Initializing ServiceManager");
+        Self::with_state_dir(Self::default_state_dir()).await
+    }
 
-        // Create harness directory if it doesn't exist
-        let state_dir = dirs::data_local_dir()
+    /// The state directory used by [`new`](Self::new)
+    pub fn default_state_dir() -> std::path::PathBuf {
+        dirs::data_local_dir()
             .unwrap_or_else(|| std::path::PathBuf::from("."))
-            .join("harness");
+            .join("harness")
+    }
 
-        Self::with_state_dir(state_dir).await
+    /// The registry storage used by [`with_state_dir`](Self::with_state_dir)
+    pub fn default_storage(state_dir: &std::path::Path) -> StorageConfig {
+        StorageConfig::Sled {
+            path: state_dir.join("registry_db"),
+        }
     }
 
     /// Create a new service manager with a specific state directory
@@ -57,29 +74,41 @@ impl ServiceManager {
             state_dir
         );
 
-        std::fs::create_dir_all(&state_dir).map_err(crate::Error::Io)?;
+        let storage = Self::default_storage(&state_dir);
+        Self::with_storage(state_dir, storage).await
+    }
 
-        // Create registry with persistence
-        let registry_db_path = state_dir.join("registry_db");
-        std::fs::create_dir_all(&registry_db_path).map_err(crate::Error::Io)?;
+    /// Create a new service manager with a specific registry storage backend
+    pub async fn with_storage(
+        state_dir: impl Into<std::path::PathBuf>,
+        storage: StorageConfig,
+    ) -> std::result::Result<Self, Error> {
+        let state_dir = state_dir.into();
+        std::fs::create_dir_all(&state_dir).map_err(crate::Error::Io)?;
 
-        let registry = Registry::with_persistence(registry_db_path.to_string_lossy()).await;
+        // Create registry with the selected backend
+        let registry = Registry::with_backend(service_registry::backend::open(&storage).await?);
 
+        // IP leases live alongside services so addresses survive restarts
         let network_config = NetworkConfig::default();
-        let network_manager = NetworkManager::new(network_config)?;
+        let network_manager =
+            NetworkManager::with_backend(network_config, registry.backend()).await?;
 
         // Initialize executors
         let mut executors: HashMap<String, Arc<dyn ServiceExecutor>> = HashMap::new();
         executors.insert("process".to_string(), Arc::new(ProcessExecutor::new()));
         executors.insert("docker".to_string(), Arc::new(DockerExecutor::new()));
+        executors.insert("compose".to_string(), Arc::new(ComposeExecutor::new()));
 
         Ok(Self {
             registry,
-            network_manager,
+            network_manager: Mutex::new(network_manager),
             executors,
             active_services: Arc::new(RwLock::new(HashMap::new())),
             health_monitors: Arc::new(RwLock::new(HashMap::new())),
             package_deployer: PackageDeployer::new(),
+            metrics: MetricsCollector::new(),
+            starts: RwLock::new(HashMap::new()),
         })
     }
 
@@ -118,7 +147,11 @@ impl ServiceManager {
         let executor = self.find_executor(&network_config)?;
 
         // Start the service
-        let running_service = executor.start(network_config.clone()).await?;
+        let mut running_service = executor.start(network_config.clone()).await?;
+        running_service
+            .metadata
+            .entry("start_time".to_string())
+            .or_insert_with(|| chrono::Utc::now().to_rfc3339());
 
         // Start health monitoring if configured
         if let Some(health_check) = &network_config.health_check {
@@ -134,6 +167,12 @@ impl ServiceManager {
             .write()
             .unwrap()
             .insert(name.to_string(), running_service.clone());
+        *self
+            .starts
+            .write()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default() += 1;
 
         // Register with service registry
         let execution_info = match &config.target {
@@ -151,6 +190,15 @@ impl ServiceManager {
                     name: Some(format!("orchestrator-{}", name)),
                 }
             }
+            crate::config::ServiceTarget::Compose {
+                compose_file,
+                service,
+                ..
+            } => service_registry::models::ExecutionInfo::ComposeService {
+                compose_file: compose_file.clone(),
+                service: service.clone(),
+                container_id: running_service.container_id.clone(),
+            },
             _ => {
                 // For remote services, we'll use ManagedProcess for now
                 service_registry::models::ExecutionInfo::ManagedProcess {
@@ -194,13 +242,18 @@ impl ServiceManager {
         let executor = self.find_executor(&service.config)?;
         executor.stop(&service).await?;
 
-        // Remove health monitor
+        // Remove health monitor and usage samples
         self.health_monitors.write().unwrap().remove(name);
+        self.metrics.forget(name);
 
         // Update service state in registry to stopped
         if let Err(e) = self
             .registry
-            .update_state(name, service_registry::models::ServiceState::Stopped)
+            .update_state_with_cause(
+                name,
+                service_registry::models::ServiceState::Stopped,
+                Some("stopped by service manager".to_string()),
+            )
             .await
         {
             warn!("Failed to update service state in registry: {}", e);
@@ -210,6 +263,31 @@ impl ServiceManager {
         Ok(())
     }
 
+    /// Remove a service entirely, releasing its registry entry and IP lease
+    ///
+    /// Unlike [`stop_service`](Self::stop_service), which keeps the lease so the
+    /// service comes back on the same address, this forgets the service.
+    pub async fn remove_service(&self, name: &str) -> std::result::Result<(), Error> {
+        info!("Removing service: {}", name);
+
+        let is_active = self.active_services.read().unwrap().contains_key(name);
+        if is_active {
+            self.stop_service(name).await?;
+        }
+
+        match self.registry.deregister(name).await {
+            Ok(_) => {}
+            Err(service_registry::Error::ServiceNotFound(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        if let Some(ip) = self.network_manager.lock().await.release_ip(name).await? {
+            info!("Released IP {} held by {}", ip, name);
+        }
+
+        Ok(())
+    }
+
     /// Deploy a package to a remote target
     pub async fn deploy_package(
         &self,
@@ -288,6 +366,60 @@ impl ServiceManager {
         Ok(active.get(name).cloned())
     }
 
+    /// Take a resource usage sample of every running service
+    pub async fn collect_metrics(&self) -> std::result::Result<(), Error> {
+        let services: Vec<RunningService> = {
+            let active = self.active_services.read().unwrap();
+            active.values().cloned().collect()
+        };
+
+        for service in services {
+            if let Err(e) = self.metrics.sample(&service).await {
+                debug!("Failed to sample metrics of {}: {}", service.name, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Uptime and recent resource usage of running services
+    ///
+    /// Returns all running services if `names` is empty; names that aren't
+    /// running are skipped.
+    pub async fn get_service_metrics(
+        &self,
+        names: &[String],
+    ) -> std::result::Result<Vec<ServiceMetrics>, Error> {
+        let active = self.active_services.read().unwrap();
+        let mut metrics: Vec<ServiceMetrics> = active
+            .values()
+            .filter(|service| names.is_empty() || names.contains(&service.name))
+            .map(|service| self.metrics.metrics(service))
+            .collect();
+        metrics.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(metrics)
+    }
+
+    /// How often each service was restarted, for every service started
+    /// since the manager was created
+    pub fn restart_counts(&self) -> HashMap<String, u64> {
+        self.starts
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, starts)| (name.clone(), starts.saturating_sub(1)))
+            .collect()
+    }
+
+    /// Results and latency of the health checks of monitored services
+    pub fn health_check_stats(&self) -> HashMap<String, HealthCheckStats> {
+        self.health_monitors
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, monitor)| (name.clone(), monitor.stats().clone()))
+            .collect()
+    }
+
     /// Run health checks for all monitored services
     pub async fn run_health_checks(
         &self,
@@ -314,6 +446,7 @@ impl ServiceManager {
                 }
             };
 
+            let started = std::time::Instant::now();
             let status = if has_monitor {
                 // Create a temporary checker to run the health check
                 let checker = HealthChecker::new();
@@ -356,12 +489,43 @@ impl ServiceManager {
                 HealthStatus::Unknown
             };
 
+            if has_monitor {
+                self.record_health(&service_name, &status, started.elapsed())
+                    .await;
+            }
+
             results.insert(service_name, status);
         }
 
         Ok(results)
     }
 
+    /// Record a health check result in the registry history
+    async fn record_health(
+        &self,
+        service_name: &str,
+        status: &HealthStatus,
+        duration: std::time::Duration,
+    ) {
+        if let Some(monitor) = self.health_monitors.write().unwrap().get_mut(service_name) {
+            monitor.stats.record(status, duration);
+        }
+
+        let record = service_registry::models::HealthStatus {
+            healthy: matches!(status, HealthStatus::Healthy),
+            message: match status {
+                HealthStatus::Unhealthy(msg) => Some(msg.clone()),
+                _ => None,
+            },
+            checked_at: chrono::Utc::now(),
+            duration_ms: duration.as_millis() as u64,
+        };
+
+        if let Err(e) = self.registry.record_health(service_name, record).await {
+            debug!("Not recording health of {}: {}", service_name, e);
+        }
+    }
+
     /// Inject network configuration into service config
     async fn inject_network_config(
         &self,
@@ -369,10 +533,26 @@ impl ServiceManager {
     ) -> std::result::Result<ServiceConfig, Error> {
         debug!("Injecting network config for service: {}", config.name);
 
-        // TODO: Implement network injection:
-        // 1. Register service with network manager
-        // 2. Resolve dependency IPs
-        // 3. Update environment variables
+        // Remote services are reached over the mesh, so give them a sticky
+        // mesh address. The lease is kept across stops and restarts.
+        #[allow(deprecated)]
+        let is_remote = matches!(
+            config.target,
+            crate::config::ServiceTarget::Remote { .. }
+                | crate::config::ServiceTarget::RemoteLan { .. }
+                | crate::config::ServiceTarget::Wireguard { .. }
+        );
+        if is_remote {
+            let mut network_manager = self.network_manager.lock().await;
+            if network_manager.config().enable_wireguard {
+                let ip = network_manager.allocate_ip(&config.name).await?;
+                debug!("Service {} holds mesh IP {}", config.name, ip);
+            }
+        }
+
+        // TODO: Implement remaining network injection:
+        // 1. Resolve dependency IPs
+        // 2. Update environment variables
 
         // For now, return config as-is
         Ok(config.clone())
@@ -396,7 +576,7 @@ impl ServiceManager {
     }
 
     /// Get network manager reference
-    pub fn network_manager(&self) -> &NetworkManager {
+    pub fn network_manager(&self) -> &Mutex<NetworkManager> {
         &self.network_manager
     }
 
@@ -419,6 +599,40 @@ mod tests {
         // Verify executors are registered
         assert!(manager.executors.contains_key("process"));
         assert!(manager.executors.contains_key("docker"));
+        assert!(manager.executors.contains_key("compose"));
+    }
+
+    #[smol_potat::test]
+    async fn test_jsonl_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageConfig::Jsonl {
+            path: dir.path().join("registry"),
+        };
+        let config = ServiceConfig {
+            name: "journaled".to_string(),
+            target: ServiceTarget::Process {
+                binary: "sleep".to_string(),
+                args: vec!["30".to_string()],
+                env: HashMap::new(),
+                working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: Some(1),
+            },
+            dependencies: vec![],
+            health_check: None,
+        };
+
+        {
+            let manager = ServiceManager::with_storage(dir.path(), storage.clone())
+                .await
+                .unwrap();
+            manager.start_service("journaled", config).await.unwrap();
+            manager.stop_service("journaled").await.unwrap();
+        }
+
+        let backend = service_registry::backend::open(&storage).await.unwrap();
+        assert!(backend.get_service("journaled").await.unwrap().is_some());
     }
 
     #[smol_potat::test]
@@ -432,6 +646,9 @@ mod tests {
                 args: vec![],
                 env: HashMap::new(),
                 working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![],
             health_check: None,
@@ -456,4 +673,119 @@ mod tests {
         let services = manager.list_services().await.unwrap();
         assert!(services.is_empty());
     }
+
+    #[smol_potat::test]
+    async fn test_collect_metrics() {
+        let manager = ServiceManager::new_for_tests().await.unwrap();
+        let config = ServiceConfig {
+            name: "sleeper".to_string(),
+            target: ServiceTarget::Process {
+                binary: "sleep".to_string(),
+                args: vec!["30".to_string()],
+                env: HashMap::new(),
+                working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: Some(1),
+            },
+            dependencies: vec![],
+            health_check: None,
+        };
+        let running = manager.start_service("sleeper", config).await.unwrap();
+        assert!(running.metadata.contains_key("start_time"));
+
+        manager.collect_metrics().await.unwrap();
+        manager.collect_metrics().await.unwrap();
+        let metrics = manager.get_service_metrics(&[]).await.unwrap();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "sleeper");
+        assert!(metrics[0].started_at.is_some());
+        assert_eq!(metrics[0].samples.len(), 2);
+        let latest = metrics[0].latest().unwrap();
+        assert_eq!(latest.processes, 1);
+        assert!(latest.rss_bytes > 0);
+        assert!(latest.cpu_percent.is_some());
+
+        let others = manager
+            .get_service_metrics(&["other".to_string()])
+            .await
+            .unwrap();
+        assert!(others.is_empty());
+
+        manager.stop_service("sleeper").await.unwrap();
+        assert!(manager.get_service_metrics(&[]).await.unwrap().is_empty());
+    }
+
+    #[smol_potat::test]
+    async fn test_restart_counts() {
+        let manager = ServiceManager::new_for_tests().await.unwrap();
+        let config = ServiceConfig {
+            name: "restarted".to_string(),
+            target: ServiceTarget::Process {
+                binary: "sleep".to_string(),
+                args: vec!["30".to_string()],
+                env: HashMap::new(),
+                working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: Some(1),
+            },
+            dependencies: vec![],
+            health_check: None,
+        };
+
+        manager
+            .start_service("restarted", config.clone())
+            .await
+            .unwrap();
+        assert_eq!(manager.restart_counts()["restarted"], 0);
+        manager.stop_service("restarted").await.unwrap();
+        manager.start_service("restarted", config).await.unwrap();
+        assert_eq!(manager.restart_counts()["restarted"], 1);
+        manager.stop_service("restarted").await.unwrap();
+        assert_eq!(manager.restart_counts()["restarted"], 1);
+    }
+
+    #[smol_potat::test]
+    async fn test_remove_service_releases_lease() {
+        let manager = ServiceManager::new_for_tests().await.unwrap();
+        let config = ServiceConfig {
+            name: "removed".to_string(),
+            target: ServiceTarget::Process {
+                binary: "sleep".to_string(),
+                args: vec!["30".to_string()],
+                env: HashMap::new(),
+                working_dir: None,
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: Some(1),
+            },
+            dependencies: vec![],
+            health_check: None,
+        };
+        manager.start_service("removed", config).await.unwrap();
+        let ip = manager
+            .network_manager
+            .lock()
+            .await
+            .allocate_ip("removed")
+            .await
+            .unwrap();
+
+        // Stopping keeps the lease, removing releases it
+        manager.stop_service("removed").await.unwrap();
+        assert_eq!(manager.registry.ip_allocations().await.unwrap()[0].ip, ip);
+
+        manager.remove_service("removed").await.unwrap();
+        assert!(manager.registry.get("removed").await.is_err());
+        assert!(manager.registry.ip_allocations().await.unwrap().is_empty());
+        assert!(
+            manager
+                .network_manager
+                .lock()
+                .await
+                .allocated_ip("removed")
+                .is_none()
+        );
+    }
 }
@@ -0,0 +1,452 @@
+//! Resource usage metrics for running services.
+//!
+//! Process services are sampled from `/proc`, counting the process and all
+//! of its descendants; containers are sampled with `docker stats`. Each
+//! service keeps a short ring buffer of samples for trends.
+
+use crate::{Error, executors::RunningService};
+use chrono::{DateTime, Utc};
+use command_executor::{Command, Executor, backends::LocalLauncher, target::Target};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Samples kept per service by default
+const DEFAULT_CAPACITY: usize = 60;
+
+/// How long `docker stats` may take before the sample is skipped
+///
+/// Shorter than the daemon's two second sampling interval, so a slow docker
+/// daemon can't hold up sampling of the other services. `docker stats`
+/// itself takes about a second to measure CPU usage.
+const DOCKER_STATS_TIMEOUT: Duration = Duration::from_millis(1800);
+
+/// A single resource usage sample
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetricsSample {
+    /// When the sample was taken
+    pub timestamp: DateTime<Utc>,
+    /// CPU usage in percent of one CPU, unknown for a service's first
+    /// process sample
+    pub cpu_percent: Option<f64>,
+    /// Resident memory in bytes
+    pub rss_bytes: u64,
+    /// Number of processes
+    pub processes: u32,
+}
+
+/// Uptime and recent samples of a service
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServiceMetrics {
+    /// Service name
+    pub name: String,
+    /// When the service was started, if known
+    pub started_at: Option<DateTime<Utc>>,
+    /// Samples, oldest first
+    pub samples: Vec<MetricsSample>,
+}
+
+impl ServiceMetrics {
+    /// The most recent sample
+    pub fn latest(&self) -> Option<&MetricsSample> {
+        self.samples.last()
+    }
+
+    /// How long the service has been running at `now`
+    pub fn uptime(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        self.started_at
+            .map(|started| (now - started).max(chrono::Duration::zero()))
+    }
+}
+
+/// Samples of one service
+#[derive(Debug, Default)]
+struct History {
+    samples: VecDeque<MetricsSample>,
+    /// CPU time of the process tree at the previous sample, for the rate
+    last_cpu: Option<(f64, Instant)>,
+}
+
+/// Collects resource usage samples of running services
+pub struct MetricsCollector {
+    executor: Executor<LocalLauncher>,
+    capacity: usize,
+    history: RwLock<HashMap<String, History>>,
+}
+
+impl MetricsCollector {
+    /// Create a collector keeping a minute or two of samples per service
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Create a collector keeping `capacity` samples per service
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            executor: Executor::new("metrics-collector".to_string(), LocalLauncher),
+            capacity: capacity.max(1),
+            history: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Take and record a sample of a service
+    ///
+    /// Returns `None` for services that can't be sampled, such as remote
+    /// services or processes that already exited.
+    pub async fn sample(
+        &self,
+        service: &RunningService,
+    ) -> std::result::Result<Option<MetricsSample>, Error> {
+        let sample = if let Some(pid) = service.pid {
+            let Some(usage) = ProcessUsage::of_tree(pid) else {
+                debug!("Process {} of {} is gone", pid, service.name);
+                return Ok(None);
+            };
+            let now = Instant::now();
+            let mut history = self.history.write().unwrap();
+            let entry = history.entry(service.name.clone()).or_default();
+            let cpu_percent = entry
+                .last_cpu
+                .map(|(cpu, at)| (usage.cpu_seconds - cpu, now.duration_since(at)))
+                .filter(|(_, elapsed)| !elapsed.is_zero())
+                .map(|(cpu, elapsed)| (cpu.max(0.0) / elapsed.as_secs_f64()) * 100.0);
+            entry.last_cpu = Some((usage.cpu_seconds, now));
+            MetricsSample {
+                timestamp: Utc::now(),
+                cpu_percent,
+                rss_bytes: usage.rss_bytes,
+                processes: usage.processes,
+            }
+        } else if let Some(container) = &service.container_id {
+            match self.docker_stats(container).await? {
+                Some(sample) => sample,
+                None => return Ok(None),
+            }
+        } else {
+            return Ok(None);
+        };
+
+        let mut history = self.history.write().unwrap();
+        let samples = &mut history.entry(service.name.clone()).or_default().samples;
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(sample.clone());
+        Ok(Some(sample))
+    }
+
+    /// Uptime and recorded samples of a service
+    pub fn metrics(&self, service: &RunningService) -> ServiceMetrics {
+        let samples = self
+            .history
+            .read()
+            .unwrap()
+            .get(&service.name)
+            .map(|history| history.samples.iter().cloned().collect())
+            .unwrap_or_default();
+        ServiceMetrics {
+            name: service.name.clone(),
+            started_at: service
+                .metadata
+                .get("start_time")
+                .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                .map(|time| time.with_timezone(&Utc)),
+            samples,
+        }
+    }
+
+    /// Drop the samples of a service
+    pub fn forget(&self, name: &str) {
+        self.history.write().unwrap().remove(name);
+    }
+
+    /// Sample a container with `docker stats`, if it answers in time
+    async fn docker_stats(
+        &self,
+        container: &str,
+    ) -> std::result::Result<Option<MetricsSample>, Error> {
+        let mut cmd = Command::new("docker");
+        cmd.args([
+            "stats",
+            "--no-stream",
+            "--format",
+            "{{.CPUPerc}}|{{.MemUsage}}|{{.PIDs}}",
+            container,
+        ]);
+        cmd.timeout(DOCKER_STATS_TIMEOUT);
+
+        let result = match self.executor.execute(&Target::Command, cmd).await {
+            Ok(result) => result,
+            Err(command_executor::Error::Timeout { .. }) => {
+                debug!("docker stats timed out for {}", container);
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if !result.success() {
+            debug!("docker stats failed for {}: {}", container, result.output);
+            return Ok(None);
+        }
+        Ok(parse_docker_stats(result.output.trim()))
+    }
+}
+
+impl Default for MetricsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse a `CPUPerc|MemUsage|PIDs` line of `docker stats`
+fn parse_docker_stats(line: &str) -> Option<MetricsSample> {
+    let mut parts = line.split('|');
+    let cpu = parts.next()?.trim().trim_end_matches('%');
+    // "12.5MiB / 1.944GiB"
+    let memory = parts.next()?.split('/').next()?.trim();
+    let processes = parts.next().and_then(|pids| pids.trim().parse().ok());
+
+    Some(MetricsSample {
+        timestamp: Utc::now(),
+        cpu_percent: cpu.parse().ok(),
+        rss_bytes: parse_docker_size(memory)?,
+        processes: processes.unwrap_or(0),
+    })
+}
+
+/// Parse a size such as `12.5MiB` or `3kB` as printed by docker
+fn parse_docker_size(size: &str) -> Option<u64> {
+    let split = size
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let multiplier: u64 = match unit.trim() {
+        "" | "B" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        "kB" | "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return None,
+    };
+    Some((number.parse::<f64>().ok()? * multiplier as f64) as u64)
+}
+
+/// Resource usage of a process and its descendants
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProcessUsage {
+    /// User and system CPU time in seconds
+    cpu_seconds: f64,
+    rss_bytes: u64,
+    processes: u32,
+}
+
+/// The fields of `/proc/<pid>/stat` we use
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy)]
+struct ProcStat {
+    ppid: u32,
+    session: u32,
+    /// utime + stime in clock ticks
+    ticks: u64,
+    /// Resident set size in pages
+    rss_pages: u64,
+}
+
+#[cfg(target_os = "linux")]
+impl ProcStat {
+    fn read(pid: u32) -> Option<Self> {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // `pid (comm) state ppid pgrp session ...`; comm may contain spaces
+        let (_, rest) = stat.rsplit_once(')')?;
+        let fields: Vec<_> = rest.split_whitespace().collect();
+        // Fields counted from `state`, which is field 3 of stat(5)
+        let field = |n: usize| fields.get(n - 3)?.parse::<u64>().ok();
+        if fields.first() == Some(&"Z") {
+            return None;
+        }
+        Some(Self {
+            ppid: field(4)? as u32,
+            session: field(6)? as u32,
+            ticks: field(14)? + field(15)?,
+            rss_pages: field(24)?,
+        })
+    }
+}
+
+impl ProcessUsage {
+    /// Usage of `pid` and its descendants, or `None` if it is gone
+    ///
+    /// Descendants are found through their parents and, since managed
+    /// processes lead a session of their own, through the session for those
+    /// that were reparented.
+    #[cfg(target_os = "linux")]
+    fn of_tree(pid: u32) -> Option<Self> {
+        use nix::unistd::{SysconfVar, sysconf};
+
+        let root = ProcStat::read(pid)?;
+        let all: HashMap<u32, ProcStat> = std::fs::read_dir("/proc")
+            .ok()?
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|&other| other != pid)
+            .filter_map(|other| Some((other, ProcStat::read(other)?)))
+            .collect();
+
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (&other, stat) in &all {
+            children.entry(stat.ppid).or_default().push(other);
+        }
+        let mut tree = vec![pid];
+        let mut pending = vec![pid];
+        while let Some(parent) = pending.pop() {
+            for &child in children.get(&parent).into_iter().flatten() {
+                if !tree.contains(&child) {
+                    tree.push(child);
+                    pending.push(child);
+                }
+            }
+        }
+        if root.session == pid {
+            tree.extend(
+                all.iter()
+                    .filter(|(other, stat)| stat.session == pid && !tree.contains(other))
+                    .map(|(&other, _)| other)
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        let ticks_per_second = sysconf(SysconfVar::CLK_TCK).ok().flatten().unwrap_or(100) as f64;
+        let page_size = sysconf(SysconfVar::PAGE_SIZE)
+            .ok()
+            .flatten()
+            .unwrap_or(4096) as u64;
+        let stats = tree
+            .iter()
+            .filter_map(|member| all.get(member).or((*member == pid).then_some(&root)));
+        let (ticks, pages, processes) = stats.fold((0, 0, 0), |(ticks, pages, count), stat| {
+            (ticks + stat.ticks, pages + stat.rss_pages, count + 1)
+        });
+        Some(Self {
+            cpu_seconds: ticks as f64 / ticks_per_second,
+            rss_bytes: pages * page_size,
+            processes,
+        })
+    }
+
+    /// Usage of a process; only available on Linux
+    #[cfg(not(target_os = "linux"))]
+    fn of_tree(_pid: u32) -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ServiceConfig, ServiceTarget};
+    use uuid::Uuid;
+
+    fn running(name: &str, pid: Option<u32>) -> RunningService {
+        RunningService {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            config: ServiceConfig {
+                name: name.to_string(),
+                target: ServiceTarget::Process {
+                    binary: "sh".to_string(),
+                    args: vec![],
+                    env: HashMap::new(),
+                    working_dir: None,
+                    user: None,
+                    limits: Default::default(),
+                    shutdown_timeout: None,
+                },
+                dependencies: vec![],
+                health_check: None,
+            },
+            pid,
+            container_id: None,
+            endpoints: HashMap::new(),
+            metadata: HashMap::new(),
+            network_info: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_docker_stats() {
+        let sample = parse_docker_stats("12.50%|12.5MiB / 1.944GiB|7").unwrap();
+        assert_eq!(sample.cpu_percent, Some(12.5));
+        assert_eq!(sample.rss_bytes, 13_107_200);
+        assert_eq!(sample.processes, 7);
+
+        assert_eq!(parse_docker_size("3kB"), Some(3000));
+        assert_eq!(parse_docker_size("512B"), Some(512));
+        assert_eq!(parse_docker_size("1GiB"), Some(1 << 30));
+        assert_eq!(parse_docker_size("lots"), None);
+        assert!(parse_docker_stats("--|-- / --|--").is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[smol_potat::test]
+    async fn test_process_tree_samples() {
+        // A shell with two children
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 10 & sleep 10 & wait"])
+            .spawn()
+            .unwrap();
+        let service = running("tree", Some(child.id()));
+        let collector = MetricsCollector::with_capacity(2);
+
+        let mut processes = 0;
+        for _ in 0..50 {
+            async_io::Timer::after(std::time::Duration::from_millis(20)).await;
+            processes = ProcessUsage::of_tree(child.id()).unwrap().processes;
+            if processes == 3 {
+                break;
+            }
+        }
+        assert_eq!(processes, 3);
+
+        let first = collector.sample(&service).await.unwrap().unwrap();
+        assert_eq!(first.cpu_percent, None);
+        assert!(first.rss_bytes > 0);
+        for _ in 0..2 {
+            let sample = collector.sample(&service).await.unwrap().unwrap();
+            assert!(sample.cpu_percent.is_some());
+        }
+        // Only the last two samples are kept
+        let metrics = collector.metrics(&service);
+        assert_eq!(metrics.samples.len(), 2);
+        assert_ne!(metrics.samples[0], first);
+
+        std::process::Command::new("pkill")
+            .args(["-P", &child.id().to_string()])
+            .status()
+            .unwrap();
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(collector.sample(&service).await.unwrap().is_none());
+
+        collector.forget("tree");
+        assert!(collector.metrics(&service).samples.is_empty());
+    }
+
+    #[test]
+    fn test_uptime() {
+        let mut service = running("up", None);
+        service
+            .metadata
+            .insert("start_time".to_string(), "2024-01-01T00:00:00Z".to_string());
+        let metrics = MetricsCollector::new().metrics(&service);
+        let now = "2024-01-01T01:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(metrics.uptime(now), Some(chrono::Duration::minutes(90)));
+        assert_eq!(metrics.latest(), None);
+
+        service.metadata.clear();
+        assert_eq!(MetricsCollector::new().metrics(&service).uptime(now), None);
+    }
+}
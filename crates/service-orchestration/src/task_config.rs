@@ -90,6 +90,9 @@ mod tests {
                 args: vec!["hardhat".to_string(), "deploy".to_string()],
                 env: HashMap::from([("NETWORK".to_string(), "localhost".to_string())]),
                 working_dir: Some("./contracts".to_string()),
+                user: None,
+                limits: Default::default(),
+                shutdown_timeout: None,
             },
             dependencies: vec![Dependency::Service {
                 service: "anvil".to_string(),
@@ -120,6 +123,9 @@ mod tests {
                         args: vec!["deploy".to_string()],
                         env: HashMap::new(),
                         working_dir: None,
+                        user: None,
+                        limits: Default::default(),
+                        shutdown_timeout: None,
                     },
                 ),
             )]),
@@ -21,6 +21,9 @@ fn test_service_config_yaml_roundtrip() {
                 ("PORT".to_string(), "8080".to_string()),
             ]),
             working_dir: Some("/tmp".to_string()),
+            user: None,
+            limits: Default::default(),
+            shutdown_timeout: None,
         },
         dependencies: vec![
             service_orchestration::Dependency::Service {
@@ -137,6 +140,9 @@ fn test_service_target_env_methods() {
         args: vec![],
         env: env.clone(),
         working_dir: None,
+        user: None,
+        limits: Default::default(),
+        shutdown_timeout: None,
     };
 
     // Test env() method
@@ -253,6 +259,9 @@ async fn test_service_manager_initialization() {
             args: vec!["test".to_string()],
             env: HashMap::new(),
             working_dir: None,
+            user: None,
+            limits: Default::default(),
+            shutdown_timeout: None,
         },
         dependencies: vec![],
         health_check: None,
@@ -318,6 +327,9 @@ fn test_executor_type_detection() {
             args: vec![],
             env: HashMap::new(),
             working_dir: None,
+            user: None,
+            limits: Default::default(),
+            shutdown_timeout: None,
         },
         dependencies: vec![],
         health_check: None,
@@ -375,6 +387,9 @@ fn test_service_config_env_injection() {
             args: vec![],
             env: original_env.clone(),
             working_dir: None,
+            user: None,
+            limits: Default::default(),
+            shutdown_timeout: None,
         },
         dependencies: vec![service_orchestration::Dependency::Service {
             service: "db".to_string(),
@@ -0,0 +1,551 @@
+//! Append-only JSON-lines backend for service registry
+//!
+//! State lives in a directory holding two human-readable files:
+//!
+//! - `snapshot.json` - a [`RegistrySnapshot`] of the registry at the last compaction
+//! - `journal.jsonl` - one [`JournalEntry`] per line for every change since
+//!
+//! Loading reads the snapshot and replays the journal on top of it. Entries
+//! are idempotent (history appends already present are skipped), so a crash
+//! between writing a snapshot and truncating the journal is harmless.
+//! History beyond the backend's limit is dropped in memory, and from disk at
+//! the next compaction.
+
+use super::memory::MemoryState;
+use super::{DEFAULT_HISTORY_LIMIT, EventSubscription, RegistryBackend, RegistrySnapshot};
+use crate::{error::Result, models::*};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::io::AsyncWriteExt;
+use futures::lock::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Snapshot file name inside the backend directory
+pub const SNAPSHOT_FILE: &str = "snapshot.json";
+
+/// Journal file name inside the backend directory
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// Journal length that triggers a compaction
+pub const COMPACT_THRESHOLD: usize = 1000;
+
+/// A single change recorded in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// When the change was made
+    pub at: DateTime<Utc>,
+    /// The change itself
+    #[serde(flatten)]
+    pub op: JournalOp,
+}
+
+/// Registry change operations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalOp {
+    /// Service stored or updated
+    PutService {
+        /// New service entry
+        service: Box<ServiceEntry>,
+    },
+    /// Service removed
+    RemoveService {
+        /// Service name
+        name: String,
+    },
+    /// Subscription stored
+    PutSubscription {
+        /// Client address
+        addr: String,
+        /// Subscription
+        subscription: EventSubscription,
+    },
+    /// Subscription removed
+    RemoveSubscription {
+        /// Client address
+        addr: String,
+    },
+    /// IP lease stored
+    PutAllocation {
+        /// Lease
+        allocation: IpAllocation,
+    },
+    /// IP lease released
+    RemoveAllocation {
+        /// Service that held the lease
+        service: String,
+    },
+    /// History event appended
+    AppendHistory {
+        /// Event
+        event: Box<HistoryEvent>,
+    },
+}
+
+impl JournalOp {
+    fn apply(self, state: &mut MemoryState) {
+        match self {
+            JournalOp::PutService { service } => {
+                state.services.insert(service.name.clone(), *service);
+            }
+            JournalOp::RemoveService { name } => {
+                state.services.remove(&name);
+            }
+            JournalOp::PutSubscription { addr, subscription } => {
+                state.subscriptions.insert(addr, subscription);
+            }
+            JournalOp::RemoveSubscription { addr } => {
+                state.subscriptions.remove(&addr);
+            }
+            JournalOp::PutAllocation { allocation } => {
+                state
+                    .allocations
+                    .insert(allocation.service.clone(), allocation);
+            }
+            JournalOp::RemoveAllocation { service } => {
+                state.allocations.remove(&service);
+            }
+            JournalOp::AppendHistory { event } => {
+                let seen = state
+                    .history
+                    .iter()
+                    .rev()
+                    .take_while(|e| e.at >= event.at)
+                    .any(|e| *e == *event);
+                if !seen {
+                    state.history.push(*event);
+                }
+            }
+        }
+    }
+}
+
+struct Inner {
+    state: MemoryState,
+    journal_len: usize,
+}
+
+/// JSON-lines registry backend
+pub struct JsonlBackend {
+    /// Directory holding the snapshot and journal
+    dir: PathBuf,
+    inner: Mutex<Inner>,
+    history_limit: usize,
+}
+
+impl JsonlBackend {
+    /// Open (or create) a JSON-lines backend in `dir`
+    pub async fn new(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        async_fs::create_dir_all(&dir).await?;
+
+        info!("Opening JSON-lines registry at {:?}", dir);
+
+        let mut state = MemoryState::default();
+
+        let snapshot_path = dir.join(SNAPSHOT_FILE);
+        if async_fs::metadata(&snapshot_path).await.is_ok() {
+            let data = async_fs::read(&snapshot_path).await?;
+            let snapshot: RegistrySnapshot = serde_json::from_slice(&data)?;
+            snapshot.apply_to(&mut state);
+        }
+
+        let journal_len = Self::replay(&dir.join(JOURNAL_FILE), &mut state).await?;
+        debug!("Replayed {} journal entries", journal_len);
+        state.prune_history(DEFAULT_HISTORY_LIMIT);
+
+        Ok(Self {
+            dir,
+            inner: Mutex::new(Inner { state, journal_len }),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+        })
+    }
+
+    /// Keep at most `limit` history events
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history_limit = limit;
+        self.inner.get_mut().state.prune_history(limit);
+        self
+    }
+
+    /// Replay journal entries onto `state`, returning how many were applied
+    async fn replay(path: &Path, state: &mut MemoryState) -> Result<usize> {
+        let contents = match async_fs::read_to_string(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let lines: Vec<&str> = contents.lines().filter(|l| !l.trim().is_empty()).collect();
+        let mut applied = 0;
+
+        for (i, line) in lines.iter().enumerate() {
+            match serde_json::from_str::<JournalEntry>(line) {
+                Ok(entry) => {
+                    entry.op.apply(state);
+                    applied += 1;
+                }
+                // A torn final line is left behind by a crash mid-write; drop
+                // it so later appends start on a clean line
+                Err(e) if i + 1 == lines.len() => {
+                    warn!("Dropping incomplete journal entry in {:?}: {}", path, e);
+                    let mut kept = lines[..i].join("\n");
+                    if !kept.is_empty() {
+                        kept.push('\n');
+                    }
+                    async_fs::write(path, kept).await?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(applied)
+    }
+
+    /// Record a change: append it to the journal, then apply it in memory
+    async fn record(&self, op: JournalOp) -> Result<()> {
+        let mut inner = self.inner.lock().await;
+
+        let entry = JournalEntry { at: Utc::now(), op };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+
+        let mut journal = async_fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(JOURNAL_FILE))
+            .await?;
+        journal.write_all(&line).await?;
+        journal.flush().await?;
+        journal.sync_data().await?;
+
+        entry.op.apply(&mut inner.state);
+        inner.state.prune_history(self.history_limit);
+        inner.journal_len += 1;
+
+        if inner.journal_len >= COMPACT_THRESHOLD {
+            self.compact_locked(&mut inner).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fold the journal into a fresh snapshot
+    pub async fn compact(&self) -> Result<()> {
+        let mut inner = self.inner.lock().await;
+        self.compact_locked(&mut inner).await
+    }
+
+    async fn compact_locked(&self, inner: &mut Inner) -> Result<()> {
+        debug!("Compacting {} journal entries", inner.journal_len);
+
+        let snapshot = RegistrySnapshot::from_state(&inner.state);
+        let tmp_path = self.dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+        async_fs::write(&tmp_path, serde_json::to_vec_pretty(&snapshot)?).await?;
+        async_fs::rename(&tmp_path, self.dir.join(SNAPSHOT_FILE)).await?;
+
+        async_fs::write(self.dir.join(JOURNAL_FILE), b"").await?;
+        inner.journal_len = 0;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RegistryBackend for JsonlBackend {
+    async fn init(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn put_service(&self, entry: &ServiceEntry) -> Result<()> {
+        debug!("Storing service: {}", entry.name);
+        self.record(JournalOp::PutService {
+            service: Box::new(entry.clone()),
+        })
+        .await
+    }
+
+    async fn get_service(&self, name: &str) -> Result<Option<ServiceEntry>> {
+        Ok(self.inner.lock().await.state.services.get(name).cloned())
+    }
+
+    async fn list_services(&self) -> Result<Vec<ServiceEntry>> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .state
+            .services
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn remove_service(&self, name: &str) -> Result<Option<ServiceEntry>> {
+        debug!("Removing service: {}", name);
+
+        let existing = self.get_service(name).await?;
+        if existing.is_some() {
+            self.record(JournalOp::RemoveService {
+                name: name.to_string(),
+            })
+            .await?;
+        }
+
+        Ok(existing)
+    }
+
+    async fn get_all_services(&self) -> Result<HashMap<String, ServiceEntry>> {
+        Ok(self.inner.lock().await.state.services.clone())
+    }
+
+    async fn put_subscription(&self, addr: &str, subscription: &EventSubscription) -> Result<()> {
+        self.record(JournalOp::PutSubscription {
+            addr: addr.to_string(),
+            subscription: subscription.clone(),
+        })
+        .await
+    }
+
+    async fn get_subscription(&self, addr: &str) -> Result<Option<EventSubscription>> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .state
+            .subscriptions
+            .get(addr)
+            .cloned())
+    }
+
+    async fn remove_subscription(&self, addr: &str) -> Result<()> {
+        self.record(JournalOp::RemoveSubscription {
+            addr: addr.to_string(),
+        })
+        .await
+    }
+
+    async fn list_subscriptions(&self) -> Result<HashMap<String, EventSubscription>> {
+        Ok(self.inner.lock().await.state.subscriptions.clone())
+    }
+
+    async fn put_allocation(&self, allocation: &IpAllocation) -> Result<()> {
+        self.record(JournalOp::PutAllocation {
+            allocation: allocation.clone(),
+        })
+        .await
+    }
+
+    async fn get_allocation(&self, service: &str) -> Result<Option<IpAllocation>> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .state
+            .allocations
+            .get(service)
+            .cloned())
+    }
+
+    async fn remove_allocation(&self, service: &str) -> Result<Option<IpAllocation>> {
+        let existing = self.get_allocation(service).await?;
+        if existing.is_some() {
+            self.record(JournalOp::RemoveAllocation {
+                service: service.to_string(),
+            })
+            .await?;
+        }
+
+        Ok(existing)
+    }
+
+    async fn list_allocations(&self) -> Result<Vec<IpAllocation>> {
+        Ok(self
+            .inner
+            .lock()
+            .await
+            .state
+            .allocations
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn append_history(&self, event: &HistoryEvent) -> Result<()> {
+        self.record(JournalOp::AppendHistory {
+            event: Box::new(event.clone()),
+        })
+        .await
+    }
+
+    async fn query_history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEvent>> {
+        Ok(query.apply(&self.inner.lock().await.state.history))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn service(name: &str) -> ServiceEntry {
+        ServiceEntry::new(
+            name.to_string(),
+            "1.0.0".to_string(),
+            ExecutionInfo::ManagedProcess {
+                pid: None,
+                command: "test".to_string(),
+                args: vec![],
+            },
+            Location::Local,
+        )
+        .unwrap()
+    }
+
+    #[smol_potat::test]
+    async fn test_jsonl_backend_replay() {
+        let dir = tempdir().unwrap();
+
+        {
+            let backend = JsonlBackend::new(dir.path()).await.unwrap();
+            backend.put_service(&service("service-0")).await.unwrap();
+            backend.put_service(&service("service-1")).await.unwrap();
+            backend.remove_service("service-0").await.unwrap();
+
+            let lease = IpAllocation::new(
+                "service-1",
+                "10.42.0.2".parse().unwrap(),
+                "10.42.0.0/16".parse().unwrap(),
+            );
+            backend.put_allocation(&lease).await.unwrap();
+        }
+
+        // Every change is one readable line
+        let journal = std::fs::read_to_string(dir.path().join(JOURNAL_FILE)).unwrap();
+        assert_eq!(journal.lines().count(), 4);
+        assert!(
+            journal
+                .lines()
+                .next()
+                .unwrap()
+                .contains("\"op\":\"put_service\"")
+        );
+
+        let backend = JsonlBackend::new(dir.path()).await.unwrap();
+        let services = backend.list_services().await.unwrap();
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].name, "service-1");
+        assert!(backend.get_allocation("service-1").await.unwrap().is_some());
+    }
+
+    #[smol_potat::test]
+    async fn test_jsonl_backend_compaction() {
+        let dir = tempdir().unwrap();
+
+        {
+            let backend = JsonlBackend::new(dir.path()).await.unwrap();
+            backend.put_service(&service("service-0")).await.unwrap();
+            backend.compact().await.unwrap();
+            backend.put_service(&service("service-1")).await.unwrap();
+        }
+
+        let journal = std::fs::read_to_string(dir.path().join(JOURNAL_FILE)).unwrap();
+        assert_eq!(journal.lines().count(), 1);
+
+        let snapshot: RegistrySnapshot =
+            serde_json::from_slice(&std::fs::read(dir.path().join(SNAPSHOT_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(snapshot.services.len(), 1);
+
+        let backend = JsonlBackend::new(dir.path()).await.unwrap();
+        assert_eq!(backend.list_services().await.unwrap().len(), 2);
+    }
+
+    #[smol_potat::test]
+    async fn test_jsonl_backend_history_survives_compaction() {
+        let dir = tempdir().unwrap();
+        let event = HistoryEvent::new("service-0", HistoryKind::Deregistered, None);
+
+        {
+            let backend = JsonlBackend::new(dir.path()).await.unwrap();
+            backend.append_history(&event).await.unwrap();
+            backend.compact().await.unwrap();
+        }
+
+        // Replaying an append already folded into the snapshot is a no-op,
+        // as after a crash before the journal was truncated
+        let line = serde_json::to_string(&JournalEntry {
+            at: Utc::now(),
+            op: JournalOp::AppendHistory {
+                event: Box::new(event.clone()),
+            },
+        })
+        .unwrap();
+        std::fs::write(dir.path().join(JOURNAL_FILE), format!("{}\n", line)).unwrap();
+
+        let backend = JsonlBackend::new(dir.path()).await.unwrap();
+        let history = backend
+            .query_history(&HistoryQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(history, vec![event]);
+    }
+
+    #[smol_potat::test]
+    async fn test_jsonl_backend_history_limit() {
+        let dir = tempdir().unwrap();
+
+        {
+            let backend = JsonlBackend::new(dir.path())
+                .await
+                .unwrap()
+                .with_history_limit(2);
+            for i in 0..3 {
+                let event =
+                    HistoryEvent::new(format!("service-{}", i), HistoryKind::Deregistered, None);
+                backend.append_history(&event).await.unwrap();
+            }
+            backend.compact().await.unwrap();
+        }
+
+        let snapshot: RegistrySnapshot =
+            serde_json::from_slice(&std::fs::read(dir.path().join(SNAPSHOT_FILE)).unwrap())
+                .unwrap();
+        let services: Vec<_> = snapshot
+            .history
+            .iter()
+            .map(|e| e.service.as_str())
+            .collect();
+        assert_eq!(services, vec!["service-1", "service-2"]);
+    }
+
+    #[smol_potat::test]
+    async fn test_jsonl_backend_torn_write() {
+        let dir = tempdir().unwrap();
+
+        {
+            let backend = JsonlBackend::new(dir.path()).await.unwrap();
+            backend.put_service(&service("service-0")).await.unwrap();
+        }
+
+        // Simulate a crash halfway through appending an entry
+        let mut journal = std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join(JOURNAL_FILE))
+            .unwrap();
+        std::io::Write::write_all(&mut journal, b"{\"at\":\"2024-").unwrap();
+
+        let backend = JsonlBackend::new(dir.path()).await.unwrap();
+        assert_eq!(backend.list_services().await.unwrap().len(), 1);
+
+        // Appends after recovery stay readable
+        backend.put_service(&service("service-1")).await.unwrap();
+        drop(backend);
+
+        let backend = JsonlBackend::new(dir.path()).await.unwrap();
+        assert_eq!(backend.list_services().await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,206 @@
+//! In-memory backend for service registry
+//!
+//! Nothing is persisted; state is lost when the backend is dropped. Intended
+//! for tests and throwaway registries.
+
+use super::{DEFAULT_HISTORY_LIMIT, EventSubscription, RegistryBackend};
+use crate::{error::Result, models::*};
+use async_trait::async_trait;
+use futures::lock::Mutex;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Registry contents held by the in-memory and JSON-lines backends
+#[derive(Debug, Default)]
+pub(crate) struct MemoryState {
+    /// Services by name
+    pub services: HashMap<String, ServiceEntry>,
+    /// Subscriptions by client address
+    pub subscriptions: HashMap<String, EventSubscription>,
+    /// IP leases by service name
+    pub allocations: HashMap<String, IpAllocation>,
+    /// History log, oldest first
+    pub history: Vec<HistoryEvent>,
+}
+
+impl MemoryState {
+    /// Drop the oldest history events beyond `limit`
+    pub fn prune_history(&mut self, limit: usize) {
+        if self.history.len() > limit {
+            let excess = self.history.len() - limit;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+/// In-memory registry backend
+pub struct MemoryBackend {
+    state: Mutex<MemoryState>,
+    history_limit: usize,
+}
+
+impl MemoryBackend {
+    /// Create an empty in-memory backend
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(MemoryState::default()),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+        }
+    }
+
+    /// Keep at most `limit` history events
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history_limit = limit;
+        self.state.get_mut().prune_history(limit);
+        self
+    }
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl RegistryBackend for MemoryBackend {
+    async fn init(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn put_service(&self, entry: &ServiceEntry) -> Result<()> {
+        debug!("Storing service: {}", entry.name);
+        self.state
+            .lock()
+            .await
+            .services
+            .insert(entry.name.clone(), entry.clone());
+        Ok(())
+    }
+
+    async fn get_service(&self, name: &str) -> Result<Option<ServiceEntry>> {
+        Ok(self.state.lock().await.services.get(name).cloned())
+    }
+
+    async fn list_services(&self) -> Result<Vec<ServiceEntry>> {
+        Ok(self.state.lock().await.services.values().cloned().collect())
+    }
+
+    async fn remove_service(&self, name: &str) -> Result<Option<ServiceEntry>> {
+        debug!("Removing service: {}", name);
+        Ok(self.state.lock().await.services.remove(name))
+    }
+
+    async fn get_all_services(&self) -> Result<HashMap<String, ServiceEntry>> {
+        Ok(self.state.lock().await.services.clone())
+    }
+
+    async fn put_subscription(&self, addr: &str, subscription: &EventSubscription) -> Result<()> {
+        self.state
+            .lock()
+            .await
+            .subscriptions
+            .insert(addr.to_string(), subscription.clone());
+        Ok(())
+    }
+
+    async fn get_subscription(&self, addr: &str) -> Result<Option<EventSubscription>> {
+        Ok(self.state.lock().await.subscriptions.get(addr).cloned())
+    }
+
+    async fn remove_subscription(&self, addr: &str) -> Result<()> {
+        self.state.lock().await.subscriptions.remove(addr);
+        Ok(())
+    }
+
+    async fn list_subscriptions(&self) -> Result<HashMap<String, EventSubscription>> {
+        Ok(self.state.lock().await.subscriptions.clone())
+    }
+
+    async fn put_allocation(&self, allocation: &IpAllocation) -> Result<()> {
+        self.state
+            .lock()
+            .await
+            .allocations
+            .insert(allocation.service.clone(), allocation.clone());
+        Ok(())
+    }
+
+    async fn get_allocation(&self, service: &str) -> Result<Option<IpAllocation>> {
+        Ok(self.state.lock().await.allocations.get(service).cloned())
+    }
+
+    async fn remove_allocation(&self, service: &str) -> Result<Option<IpAllocation>> {
+        Ok(self.state.lock().await.allocations.remove(service))
+    }
+
+    async fn list_allocations(&self) -> Result<Vec<IpAllocation>> {
+        Ok(self
+            .state
+            .lock()
+            .await
+            .allocations
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn append_history(&self, event: &HistoryEvent) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.history.push(event.clone());
+        state.prune_history(self.history_limit);
+        Ok(())
+    }
+
+    async fn query_history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEvent>> {
+        Ok(query.apply(&self.state.lock().await.history))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[smol_potat::test]
+    async fn test_memory_backend_basic() {
+        let backend = MemoryBackend::new();
+        backend.init().await.unwrap();
+
+        let service = ServiceEntry::new(
+            "test-service".to_string(),
+            "1.0.0".to_string(),
+            ExecutionInfo::ManagedProcess {
+                pid: None,
+                command: "test".to_string(),
+                args: vec![],
+            },
+            Location::Local,
+        )
+        .unwrap();
+
+        backend.put_service(&service).await.unwrap();
+        assert_eq!(backend.list_services().await.unwrap().len(), 1);
+        assert!(backend.get_service("test-service").await.unwrap().is_some());
+
+        let removed = backend.remove_service("test-service").await.unwrap();
+        assert!(removed.is_some());
+        assert!(backend.get_service("test-service").await.unwrap().is_none());
+    }
+
+    #[smol_potat::test]
+    async fn test_memory_backend_history_limit() {
+        let backend = MemoryBackend::new().with_history_limit(2);
+        for i in 0..3 {
+            let event =
+                HistoryEvent::new(format!("service-{}", i), HistoryKind::Deregistered, None);
+            backend.append_history(&event).await.unwrap();
+        }
+
+        let history = backend
+            .query_history(&HistoryQuery::default())
+            .await
+            .unwrap();
+        let services: Vec<_> = history.iter().map(|e| e.service.as_str()).collect();
+        assert_eq!(services, vec!["service-1", "service-2"]);
+    }
+}
@@ -1,10 +1,26 @@
 //! Registry backend implementations
+//!
+//! - [`sled::SledBackend`] - embedded sled database (default for persistence)
+//! - [`jsonl::JsonlBackend`] - append-only JSON-lines journal plus snapshot
+//! - [`memory::MemoryBackend`] - process-local, for tests
+//!
+//! Backends are selected through [`StorageConfig`] and can be migrated
+//! between with [`RegistrySnapshot`]. Each keeps the most recent
+//! [`DEFAULT_HISTORY_LIMIT`] history events unless given another limit.
 
+pub mod jsonl;
+pub mod memory;
 pub mod sled;
 
-use crate::{error::Result, models::*};
+use crate::{config::StorageConfig, error::Result, models::*};
 use async_trait::async_trait;
-use std::collections::HashMap;
+use memory::MemoryState;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tracing::info;
+
+/// History events a backend keeps; appending more drops the oldest
+pub const DEFAULT_HISTORY_LIMIT: usize = 10_000;
 
 /// Trait for registry storage backends
 #[async_trait]
@@ -38,6 +54,25 @@ pub trait RegistryBackend: Send + Sync {
 
     /// List all subscriptions
     async fn list_subscriptions(&self) -> Result<HashMap<String, EventSubscription>>;
+
+    /// Store an IP lease, keyed by service name
+    async fn put_allocation(&self, allocation: &IpAllocation) -> Result<()>;
+
+    /// Get the IP lease held by a service
+    async fn get_allocation(&self, service: &str) -> Result<Option<IpAllocation>>;
+
+    /// Remove the IP lease held by a service
+    async fn remove_allocation(&self, service: &str) -> Result<Option<IpAllocation>>;
+
+    /// List all IP leases
+    async fn list_allocations(&self) -> Result<Vec<IpAllocation>>;
+
+    /// Append an event to the history log, dropping the oldest events
+    /// beyond the backend's history limit
+    async fn append_history(&self, event: &HistoryEvent) -> Result<()>;
+
+    /// Query the history log, oldest first
+    async fn query_history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEvent>>;
 }
 
 /// Event subscription information for persistence
@@ -46,3 +81,243 @@ pub struct EventSubscription {
     /// Subscribed event types
     pub events: Vec<EventType>,
 }
+
+/// Current [`RegistrySnapshot`] format version
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Point-in-time copy of a registry backend's contents
+///
+/// Entries are sorted so snapshots of equal registries are byte-identical,
+/// which keeps them easy to diff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrySnapshot {
+    /// Snapshot format version
+    pub version: u32,
+    /// Registered services, sorted by name
+    pub services: Vec<ServiceEntry>,
+    /// IP leases, sorted by service name
+    #[serde(default)]
+    pub allocations: Vec<IpAllocation>,
+    /// Persisted event subscriptions
+    #[serde(default)]
+    pub subscriptions: BTreeMap<String, EventSubscription>,
+    /// History log, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<HistoryEvent>,
+}
+
+impl Default for RegistrySnapshot {
+    fn default() -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            services: Vec::new(),
+            allocations: Vec::new(),
+            subscriptions: BTreeMap::new(),
+            history: Vec::new(),
+        }
+    }
+}
+
+impl RegistrySnapshot {
+    /// Capture the contents of a backend
+    pub async fn capture(backend: &dyn RegistryBackend) -> Result<Self> {
+        let mut services = backend.list_services().await?;
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut allocations = backend.list_allocations().await?;
+        allocations.sort_by(|a, b| a.service.cmp(&b.service));
+
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            services,
+            allocations,
+            subscriptions: backend.list_subscriptions().await?.into_iter().collect(),
+            history: backend.query_history(&HistoryQuery::default()).await?,
+        })
+    }
+
+    /// Write the snapshot into a backend
+    ///
+    /// With `replace`, entries in the backend that aren't part of the snapshot
+    /// are removed first; otherwise snapshot entries are merged over them.
+    /// History is append-only, so it is only imported into a backend whose
+    /// history is still empty.
+    pub async fn restore(&self, backend: &dyn RegistryBackend, replace: bool) -> Result<()> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(crate::Error::Package(format!(
+                "Unsupported registry snapshot version {} (expected {})",
+                self.version, SNAPSHOT_VERSION
+            )));
+        }
+
+        if replace {
+            for service in backend.list_services().await? {
+                backend.remove_service(&service.name).await?;
+            }
+            for allocation in backend.list_allocations().await? {
+                backend.remove_allocation(&allocation.service).await?;
+            }
+            for addr in backend.list_subscriptions().await?.keys() {
+                backend.remove_subscription(addr).await?;
+            }
+        }
+
+        for service in &self.services {
+            backend.put_service(service).await?;
+        }
+        for allocation in &self.allocations {
+            backend.put_allocation(allocation).await?;
+        }
+        for (addr, subscription) in &self.subscriptions {
+            backend.put_subscription(addr, subscription).await?;
+        }
+        if backend
+            .query_history(&HistoryQuery {
+                limit: Some(1),
+                ..Default::default()
+            })
+            .await?
+            .is_empty()
+        {
+            for event in &self.history {
+                backend.append_history(event).await?;
+            }
+        }
+
+        info!(
+            "Restored {} service(s) and {} IP lease(s)",
+            self.services.len(),
+            self.allocations.len()
+        );
+        Ok(())
+    }
+
+    pub(crate) fn from_state(state: &MemoryState) -> Self {
+        let mut services: Vec<ServiceEntry> = state.services.values().cloned().collect();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut allocations: Vec<IpAllocation> = state.allocations.values().cloned().collect();
+        allocations.sort_by(|a, b| a.service.cmp(&b.service));
+
+        Self {
+            version: SNAPSHOT_VERSION,
+            services,
+            allocations,
+            subscriptions: state
+                .subscriptions
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            history: state.history.clone(),
+        }
+    }
+
+    pub(crate) fn apply_to(self, state: &mut MemoryState) {
+        for service in self.services {
+            state.services.insert(service.name.clone(), service);
+        }
+        for allocation in self.allocations {
+            state
+                .allocations
+                .insert(allocation.service.clone(), allocation);
+        }
+        state.subscriptions.extend(self.subscriptions);
+        state.history.extend(self.history);
+    }
+}
+
+/// Open the backend described by `config`
+pub async fn open(config: &StorageConfig) -> Result<Box<dyn RegistryBackend>> {
+    let backend: Box<dyn RegistryBackend> = match config {
+        StorageConfig::Sled { path } => Box::new(sled::SledBackend::new(path).await?),
+        StorageConfig::Jsonl { path } => Box::new(jsonl::JsonlBackend::new(path).await?),
+        StorageConfig::Memory => Box::new(memory::MemoryBackend::new()),
+    };
+    backend.init().await?;
+    Ok(backend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[smol_potat::test]
+    async fn test_snapshot_migrates_between_backends() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let source = open(&StorageConfig::Sled {
+            path: dir.path().join("registry_db"),
+        })
+        .await
+        .unwrap();
+
+        for name in ["ipfs", "anvil"] {
+            let service = ServiceEntry::new(
+                name.to_string(),
+                "1.0.0".to_string(),
+                ExecutionInfo::ManagedProcess {
+                    pid: None,
+                    command: name.to_string(),
+                    args: vec![],
+                },
+                Location::Local,
+            )
+            .unwrap();
+            source.put_service(&service).await.unwrap();
+        }
+        let lease = IpAllocation::new(
+            "anvil",
+            "10.42.0.2".parse().unwrap(),
+            "10.42.0.0/16".parse().unwrap(),
+        );
+        source.put_allocation(&lease).await.unwrap();
+
+        let snapshot = RegistrySnapshot::capture(source.as_ref()).await.unwrap();
+        assert_eq!(snapshot.services[0].name, "anvil");
+
+        // Round-trip through the serialized form, as export/import does
+        let json = serde_json::to_string_pretty(&snapshot).unwrap();
+        let snapshot: RegistrySnapshot = serde_json::from_str(&json).unwrap();
+
+        let target = open(&StorageConfig::Jsonl {
+            path: dir.path().join("registry_jsonl"),
+        })
+        .await
+        .unwrap();
+        snapshot.restore(target.as_ref(), true).await.unwrap();
+
+        let restored = RegistrySnapshot::capture(target.as_ref()).await.unwrap();
+        assert_eq!(serde_json::to_string_pretty(&restored).unwrap(), json);
+    }
+
+    #[smol_potat::test]
+    async fn test_restore_replace() {
+        let backend = memory::MemoryBackend::new();
+        let stale = ServiceEntry::new(
+            "stale".to_string(),
+            "1.0.0".to_string(),
+            ExecutionInfo::ManagedProcess {
+                pid: None,
+                command: "stale".to_string(),
+                args: vec![],
+            },
+            Location::Local,
+        )
+        .unwrap();
+        backend.put_service(&stale).await.unwrap();
+
+        // Merging keeps existing entries
+        RegistrySnapshot::default()
+            .restore(&backend, false)
+            .await
+            .unwrap();
+        assert_eq!(backend.list_services().await.unwrap().len(), 1);
+
+        // Replacing drops them
+        RegistrySnapshot::default()
+            .restore(&backend, true)
+            .await
+            .unwrap();
+        assert!(backend.list_services().await.unwrap().is_empty());
+    }
+}
@@ -1,10 +1,11 @@
 //! Sled database backend for service registry
 
-use super::{EventSubscription, RegistryBackend};
+use super::{DEFAULT_HISTORY_LIMIT, EventSubscription, RegistryBackend};
 use crate::{error::Result, models::*};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tracing::{debug, error, info};
 
 /// Sled-based registry backend
@@ -15,6 +16,14 @@ pub struct SledBackend {
     services: sled::Tree,
     /// Subscriptions tree
     subscriptions: sled::Tree,
+    /// IP allocations tree
+    allocations: sled::Tree,
+    /// History log tree, keyed by monotonically increasing id
+    history: sled::Tree,
+    /// Events in the history tree, which sled can only count by iterating
+    history_len: AtomicUsize,
+    /// History events kept before the oldest are dropped
+    history_limit: usize,
 }
 
 impl SledBackend {
@@ -31,16 +40,7 @@ impl SledBackend {
 
         // Open database
         let db = sled::open(path)?;
-
-        // Open trees
-        let services = db.open_tree("services")?;
-        let subscriptions = db.open_tree("subscriptions")?;
-
-        Ok(Self {
-            db,
-            services,
-            subscriptions,
-        })
+        Self::with_db(db)
     }
 
     /// Create an in-memory sled backend (for testing)
@@ -49,16 +49,47 @@ impl SledBackend {
 
         // Create temporary database
         let db = sled::Config::new().temporary(true).open()?;
+        Self::with_db(db)
+    }
+
+    /// Keep at most `limit` history events
+    pub fn with_history_limit(mut self, limit: usize) -> Result<Self> {
+        self.history_limit = limit;
+        self.prune_history()?;
+        Ok(self)
+    }
 
+    fn with_db(db: sled::Db) -> Result<Self> {
         // Open trees
         let services = db.open_tree("services")?;
         let subscriptions = db.open_tree("subscriptions")?;
+        let allocations = db.open_tree("allocations")?;
+        let history = db.open_tree("history")?;
+        let history_len = AtomicUsize::new(history.len());
 
-        Ok(Self {
+        let backend = Self {
             db,
             services,
             subscriptions,
-        })
+            allocations,
+            history,
+            history_len,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+        };
+        backend.prune_history()?;
+        Ok(backend)
+    }
+
+    /// Drop the oldest history events beyond the limit
+    fn prune_history(&self) -> Result<()> {
+        while self.history_len.load(Ordering::Relaxed) > self.history_limit {
+            if self.history.pop_min()?.is_none() {
+                self.history_len.store(0, Ordering::Relaxed);
+                break;
+            }
+            self.history_len.fetch_sub(1, Ordering::Relaxed);
+        }
+        Ok(())
     }
 }
 
@@ -195,6 +226,109 @@ impl RegistryBackend for SledBackend {
 
         Ok(map)
     }
+
+    async fn put_allocation(&self, allocation: &IpAllocation) -> Result<()> {
+        debug!(
+            "Storing IP allocation for {}: {}",
+            allocation.service, allocation.ip
+        );
+
+        // Serialize allocation
+        let value = serde_json::to_vec(allocation)?;
+
+        // Store in database
+        self.allocations
+            .insert(allocation.service.as_bytes(), value)?;
+
+        // Flush to disk
+        self.allocations.flush_async().await?;
+
+        Ok(())
+    }
+
+    async fn get_allocation(&self, service: &str) -> Result<Option<IpAllocation>> {
+        debug!("Getting IP allocation for: {}", service);
+
+        match self.allocations.get(service.as_bytes())? {
+            Some(bytes) => {
+                let allocation: IpAllocation = serde_json::from_slice(&bytes)?;
+                Ok(Some(allocation))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn remove_allocation(&self, service: &str) -> Result<Option<IpAllocation>> {
+        debug!("Removing IP allocation for: {}", service);
+
+        let existing = self.get_allocation(service).await?;
+
+        if existing.is_some() {
+            self.allocations.remove(service.as_bytes())?;
+
+            // Flush to disk
+            self.allocations.flush_async().await?;
+        }
+
+        Ok(existing)
+    }
+
+    async fn list_allocations(&self) -> Result<Vec<IpAllocation>> {
+        debug!("Listing all IP allocations");
+
+        let mut allocations = Vec::new();
+
+        for result in self.allocations.iter() {
+            let (_, value) = result?;
+            let allocation: IpAllocation = serde_json::from_slice(&value)?;
+            allocations.push(allocation);
+        }
+
+        Ok(allocations)
+    }
+
+    async fn append_history(&self, event: &HistoryEvent) -> Result<()> {
+        // Big-endian ids keep the tree in insertion order
+        let id = self.db.generate_id()?;
+        let value = serde_json::to_vec(event)?;
+
+        self.history.insert(id.to_be_bytes(), value)?;
+        self.history_len.fetch_add(1, Ordering::Relaxed);
+        self.prune_history()?;
+        self.history.flush_async().await?;
+
+        Ok(())
+    }
+
+    async fn query_history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEvent>> {
+        debug!("Querying history: {:?}", query);
+
+        if query.limit == Some(0) {
+            return Ok(Vec::new());
+        }
+
+        // Walk back from the newest event, stopping once the window or the
+        // limit is covered
+        let mut events = Vec::new();
+        for result in self.history.iter().rev() {
+            let (_, value) = result?;
+            let event: HistoryEvent = serde_json::from_slice(&value)?;
+
+            // Ids follow append order, so everything further back is older
+            if query.since.is_some_and(|since| event.at < since) {
+                break;
+            }
+            if query.matches(&event) {
+                events.push(event);
+                if query.limit.is_some_and(|limit| events.len() >= limit) {
+                    break;
+                }
+            }
+        }
+
+        events.reverse();
+        Ok(events)
+    }
 }
 
 impl Drop for SledBackend {
@@ -285,6 +419,47 @@ mod tests {
         assert!(retrieved.is_none());
     }
 
+    #[smol_potat::test]
+    async fn test_sled_backend_allocations() {
+        use tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let subnet: ipnet::IpNet = "10.42.0.0/16".parse().unwrap();
+
+        {
+            let backend = SledBackend::new(&db_path).await.unwrap();
+            backend.init().await.unwrap();
+
+            let allocation = IpAllocation::new("postgres", "10.42.0.2".parse().unwrap(), subnet);
+            backend.put_allocation(&allocation).await.unwrap();
+        }
+
+        // Leases survive a reopen
+        let backend = SledBackend::new(&db_path).await.unwrap();
+        backend.init().await.unwrap();
+
+        let allocation = backend.get_allocation("postgres").await.unwrap().unwrap();
+        assert_eq!(
+            allocation.ip,
+            "10.42.0.2".parse::<std::net::IpAddr>().unwrap()
+        );
+        assert_eq!(allocation.subnet, subnet);
+        assert_eq!(backend.list_allocations().await.unwrap().len(), 1);
+
+        // Removing returns the released lease
+        let removed = backend.remove_allocation("postgres").await.unwrap();
+        assert_eq!(removed, Some(allocation));
+        assert!(backend.get_allocation("postgres").await.unwrap().is_none());
+        assert!(
+            backend
+                .remove_allocation("postgres")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
     #[smol_potat::test]
     async fn test_sled_backend_persistence() {
         use tempfile::tempdir;
@@ -329,4 +504,49 @@ mod tests {
             assert!(names.contains(&"service-4".to_string()));
         }
     }
+
+    #[smol_potat::test]
+    async fn test_sled_backend_history() {
+        let backend = SledBackend::in_memory()
+            .await
+            .unwrap()
+            .with_history_limit(3)
+            .unwrap();
+
+        let mut events = Vec::new();
+        for i in 0..4 {
+            let event = HistoryEvent::new(
+                format!("service-{}", i % 2),
+                HistoryKind::Deregistered,
+                None,
+            );
+            backend.append_history(&event).await.unwrap();
+            events.push(event);
+        }
+
+        // The oldest event was dropped
+        let history = backend
+            .query_history(&HistoryQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(history, events[1..].to_vec());
+
+        let latest = backend
+            .query_history(&HistoryQuery {
+                limit: Some(1),
+                ..HistoryQuery::for_service("service-0")
+            })
+            .await
+            .unwrap();
+        assert_eq!(latest, vec![events[2].clone()]);
+
+        let since = backend
+            .query_history(&HistoryQuery {
+                since: Some(events[2].at),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(since, events[2..].to_vec());
+    }
 }
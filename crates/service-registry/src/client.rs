@@ -362,6 +362,13 @@ impl WsClientHandle {
         }
     }
 
+    /// Query the registry history log
+    pub async fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEvent>> {
+        let params = serde_json::to_value(query)?;
+        let data = self.request(Action::GetHistory, params).await?;
+        Ok(serde_json::from_value(data)?)
+    }
+
     /// Deploy a package
     pub async fn deploy_package(
         &self,
@@ -15,6 +15,28 @@ pub struct RegistryConfig {
     /// Package management configuration
     #[serde(default)]
     pub packages: PackageConfig,
+    /// Storage backend configuration
+    #[serde(default)]
+    pub storage: StorageConfig,
+}
+
+/// Registry storage backend selection
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StorageConfig {
+    /// Embedded sled database
+    Sled {
+        /// Database directory
+        path: PathBuf,
+    },
+    /// Append-only JSON-lines journal with snapshots
+    Jsonl {
+        /// Directory holding `snapshot.json` and `journal.jsonl`
+        path: PathBuf,
+    },
+    /// In-memory only, nothing persisted
+    #[default]
+    Memory,
 }
 
 /// Server configuration
@@ -86,6 +108,7 @@ impl Default for RegistryConfig {
             },
             client: None,
             packages: PackageConfig::default(),
+            storage: StorageConfig::default(),
         }
     }
 }
@@ -128,6 +151,7 @@ impl RegistryConfig {
             },
             client: None,
             packages: PackageConfig::default(),
+            storage: StorageConfig::default(),
         }
     }
 }
@@ -144,6 +168,29 @@ mod tests {
         assert_eq!(parsed.server.listen_addr, config.server.listen_addr);
     }
 
+    #[test]
+    fn test_storage_config() {
+        let config: RegistryConfig = serde_yaml::from_str(
+            r#"
+server:
+  listen_addr: "127.0.0.1:8080"
+storage:
+  type: jsonl
+  path: /var/lib/harness/registry
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.storage,
+            StorageConfig::Jsonl {
+                path: PathBuf::from("/var/lib/harness/registry")
+            }
+        );
+
+        // Defaults to in-memory
+        assert_eq!(RegistryConfig::default().storage, StorageConfig::Memory);
+    }
+
     #[test]
     fn test_tls_config() {
         let config = RegistryConfig::with_tls(
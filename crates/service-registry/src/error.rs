@@ -50,6 +50,14 @@ pub enum Error {
     #[error("Command execution error: {0}")]
     CommandExecution(#[from] command_executor::Error),
 
+    /// Network allocation or resolution error
+    #[error("Network error: {0}")]
+    Network(String),
+
+    /// WireGuard provisioning error
+    #[error("WireGuard error: {0}")]
+    WireGuard(String),
+
     /// Database error
     #[error("Database error: {0}")]
     Database(#[from] sled::Error),
@@ -50,7 +50,9 @@ pub mod tls;
 pub mod websocket;
 
 pub use client::{WsClient, WsClientHandle};
-pub use config::{ClientConfig, ClientTlsConfig, RegistryConfig, ServerConfig, TlsConfig};
+pub use config::{
+    ClientConfig, ClientTlsConfig, RegistryConfig, ServerConfig, StorageConfig, TlsConfig,
+};
 pub use error::{Error, Result};
 pub use models::*;
 pub use package::{Package, PackageBuilder};
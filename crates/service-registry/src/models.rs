@@ -1,9 +1,10 @@
 //! Data models for the service registry
 
 use chrono::{DateTime, Utc};
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 /// A registered service entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +74,15 @@ pub enum ExecutionInfo {
         /// Unit name
         unit_name: String,
     },
+    /// Service of a docker compose project
+    ComposeService {
+        /// Path to the compose file
+        compose_file: String,
+        /// Service name in the compose file
+        service: String,
+        /// Container ID
+        container_id: Option<String>,
+    },
 }
 
 /// Where a service runs
@@ -93,7 +103,7 @@ pub enum Location {
 }
 
 /// Network endpoint for a service
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Endpoint {
     /// Endpoint name (e.g., "http", "grpc", "metrics")
     pub name: String,
@@ -109,7 +119,7 @@ pub struct Endpoint {
 }
 
 /// Network protocol
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Protocol {
     /// HTTP protocol
@@ -145,7 +155,7 @@ pub enum ServiceState {
 }
 
 /// Health check status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HealthStatus {
     /// Is the service healthy?
     pub healthy: bool,
@@ -160,6 +170,155 @@ pub struct HealthStatus {
     pub duration_ms: u64,
 }
 
+/// A persisted IP lease held by a service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpAllocation {
+    /// Service holding the lease
+    pub service: String,
+
+    /// Allocated address
+    pub ip: IpAddr,
+
+    /// Subnet the address was allocated from
+    pub subnet: IpNet,
+
+    /// When the lease was first granted
+    pub allocated_at: DateTime<Utc>,
+}
+
+impl IpAllocation {
+    /// Create a lease granted now
+    pub fn new(service: impl Into<String>, ip: IpAddr, subnet: IpNet) -> Self {
+        Self {
+            service: service.into(),
+            ip,
+            subnet,
+            allocated_at: Utc::now(),
+        }
+    }
+}
+
+/// A recorded change to a service, kept in the registry's history log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    /// Service the event concerns
+    pub service: String,
+
+    /// When the event happened
+    pub at: DateTime<Utc>,
+
+    /// What happened
+    #[serde(flatten)]
+    pub kind: HistoryKind,
+
+    /// Why it happened, if known (e.g. who requested it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+}
+
+/// Kinds of history events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HistoryKind {
+    /// Service was registered
+    Registered {
+        /// Registered version
+        version: String,
+    },
+    /// Service entry was replaced
+    Updated {
+        /// New version
+        version: String,
+    },
+    /// Service changed state
+    StateChanged {
+        /// Previous state
+        from: ServiceState,
+        /// New state
+        to: ServiceState,
+    },
+    /// Service endpoints were replaced
+    EndpointsUpdated {
+        /// New endpoints
+        endpoints: Vec<Endpoint>,
+    },
+    /// A health check turned the service healthy or unhealthy
+    HealthChecked {
+        /// Check result
+        status: HealthStatus,
+    },
+    /// Service was deregistered
+    Deregistered,
+}
+
+impl HistoryEvent {
+    /// Create an event that happened now
+    pub fn new(service: impl Into<String>, kind: HistoryKind, cause: Option<String>) -> Self {
+        Self {
+            service: service.into(),
+            at: Utc::now(),
+            kind,
+            cause,
+        }
+    }
+}
+
+/// Filter for history queries
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    /// Only events for this service
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+
+    /// Only events at or after this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only events before this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+
+    /// Return at most this many of the most recent matching events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    /// Query all events for a service
+    pub fn for_service(service: impl Into<String>) -> Self {
+        Self {
+            service: Some(service.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Check whether an event matches the service and time window
+    pub fn matches(&self, event: &HistoryEvent) -> bool {
+        self.service.as_ref().is_none_or(|s| *s == event.service)
+            && self.since.is_none_or(|since| event.at >= since)
+            && self.until.is_none_or(|until| event.at < until)
+    }
+
+    /// Filter events (oldest first) and apply the limit
+    pub fn apply<'a>(
+        &self,
+        events: impl IntoIterator<Item = &'a HistoryEvent>,
+    ) -> Vec<HistoryEvent> {
+        let mut matched: Vec<HistoryEvent> = events
+            .into_iter()
+            .filter(|e| self.matches(e))
+            .cloned()
+            .collect();
+
+        if let Some(limit) = self.limit
+            && matched.len() > limit
+        {
+            matched.drain(..matched.len() - limit);
+        }
+        matched
+    }
+}
+
 /// WebSocket message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -211,6 +370,8 @@ pub enum Action {
     Subscribe,
     /// Unsubscribe from events
     Unsubscribe,
+    /// Query the history log
+    GetHistory,
 }
 
 /// Service actions
@@ -1,6 +1,7 @@
 //! IP address allocation for WireGuard mesh network
 
 use crate::error::{Error, Result};
+use crate::models::IpAllocation;
 use ipnet::IpNet;
 use std::collections::{HashMap, HashSet};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
@@ -102,6 +103,21 @@ impl IpAllocator {
         Ok(())
     }
 
+    /// Restore a previously persisted lease
+    ///
+    /// Leases from a different subnet are rejected, since the address would not
+    /// be routable on the current mesh.
+    pub fn restore(&mut self, allocation: &IpAllocation) -> Result<()> {
+        if allocation.subnet != self.subnet {
+            return Err(Error::Network(format!(
+                "Lease for {} was allocated from {}, not {}",
+                allocation.service, allocation.subnet, self.subnet
+            )));
+        }
+
+        self.allocate_specific(&allocation.service, allocation.ip)
+    }
+
     /// Release an IP allocation
     pub fn release(&mut self, service_name: &str) -> Option<IpAddr> {
         if let Some(ip) = self.allocations.remove(service_name) {
@@ -267,6 +283,29 @@ mod tests {
         assert_eq!(new_ip, ip);
     }
 
+    #[test]
+    fn test_restore_lease() {
+        let subnet: IpNet = "10.42.0.0/16".parse().unwrap();
+        let mut allocator = IpAllocator::new(subnet).unwrap();
+
+        let lease = IpAllocation::new("postgres", "10.42.0.7".parse().unwrap(), subnet);
+        allocator.restore(&lease).unwrap();
+        assert_eq!(allocator.allocate("postgres").unwrap(), lease.ip);
+
+        // Fresh allocations skip the restored address
+        let lease = IpAllocation::new("ipfs", "10.42.0.2".parse().unwrap(), subnet);
+        allocator.restore(&lease).unwrap();
+        assert_eq!(
+            allocator.allocate("graph-node").unwrap(),
+            "10.42.0.3".parse::<IpAddr>().unwrap()
+        );
+
+        // Leases from another subnet are rejected
+        let other: IpNet = "10.43.0.0/16".parse().unwrap();
+        let lease = IpAllocation::new("anvil", "10.43.0.2".parse().unwrap(), other);
+        assert!(allocator.restore(&lease).is_err());
+    }
+
     #[test]
     fn test_subnet_bounds() {
         let subnet: IpNet = "10.42.0.0/24".parse().unwrap(); // Only 256 addresses
@@ -6,23 +6,29 @@
 //! - WireGuard configuration generation
 //! - Network path optimization
 
+use crate::backend::RegistryBackend;
 use crate::error::Result;
+use crate::models::IpAllocation;
 use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::IpAddr;
+use std::sync::Arc;
+use tracing::{info, warn};
 
 pub mod ip_allocator;
 pub mod resolver;
 pub mod topology;
-
-// TODO: Implement wireguard module when adding WireGuard support
-// #[cfg(feature = "wireguard")]
-// pub mod wireguard;
+#[cfg(feature = "wireguard")]
+pub mod wireguard;
 
 pub use ip_allocator::IpAllocator;
-pub use resolver::ServiceResolver;
+pub use resolver::{AddressCandidate, NetworkPath, ReachabilityProbe, ServiceResolver, TcpProbe};
 pub use topology::{NetworkLocation, NetworkTopology};
+#[cfg(feature = "wireguard")]
+pub use wireguard::{
+    WireGuardMesh, WireGuardMeshConfig, WireGuardNodeConfig, WireGuardProvisioner,
+};
 
 /// Network configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,6 +99,8 @@ pub struct NetworkManager {
     topology: NetworkTopology,
     ip_allocator: IpAllocator,
     resolver: ServiceResolver,
+    /// Backend persisting IP leases, if any
+    backend: Option<Arc<dyn RegistryBackend>>,
 }
 
 impl NetworkManager {
@@ -107,9 +115,87 @@ impl NetworkManager {
             topology,
             ip_allocator,
             resolver,
+            backend: None,
         })
     }
 
+    /// Create a network manager whose IP leases are persisted in `backend`
+    ///
+    /// Leases recorded by earlier runs are restored, so a service keeps its
+    /// address across restarts. Leases from a different subnet are dropped.
+    pub async fn with_backend(
+        config: NetworkConfig,
+        backend: Arc<dyn RegistryBackend>,
+    ) -> Result<Self> {
+        let mut manager = Self::new(config)?;
+
+        let allocations = backend.list_allocations().await?;
+        for allocation in &allocations {
+            if let Err(e) = manager.ip_allocator.restore(allocation) {
+                warn!("Dropping stale IP lease for {}: {}", allocation.service, e);
+                backend.remove_allocation(&allocation.service).await?;
+            }
+        }
+        info!(
+            "Restored {} IP lease(s) in {}",
+            manager.ip_allocator.all_allocations().len(),
+            manager.config.wireguard_subnet
+        );
+
+        manager.backend = Some(backend);
+        Ok(manager)
+    }
+
+    /// Allocate a mesh IP for a service, reusing its existing lease if any
+    ///
+    /// A reused lease is written back if the backend lost it, so the backend
+    /// never hands out an address that is still in use.
+    pub async fn allocate_ip(&mut self, service_name: &str) -> Result<IpAddr> {
+        let ip = match self.ip_allocator.get_allocation(service_name) {
+            Some(ip) => ip,
+            None => self.ip_allocator.allocate(service_name)?,
+        };
+        self.persist_allocation(service_name, ip).await?;
+        Ok(ip)
+    }
+
+    /// Release the IP lease held by a service
+    pub async fn release_ip(&mut self, service_name: &str) -> Result<Option<IpAddr>> {
+        let released = self.ip_allocator.release(service_name);
+
+        if let Some(backend) = &self.backend {
+            backend.remove_allocation(service_name).await?;
+        }
+
+        Ok(released)
+    }
+
+    /// Get the IP allocated to a service
+    pub fn allocated_ip(&self, service_name: &str) -> Option<IpAddr> {
+        self.ip_allocator.get_allocation(service_name)
+    }
+
+    /// Record a lease in the backend, keeping the original grant time
+    async fn persist_allocation(&self, service_name: &str, ip: IpAddr) -> Result<()> {
+        let Some(backend) = &self.backend else {
+            return Ok(());
+        };
+
+        if let Some(existing) = backend.get_allocation(service_name).await?
+            && existing.ip == ip
+        {
+            return Ok(());
+        }
+
+        let allocation = IpAllocation::new(service_name, ip, self.config.wireguard_subnet);
+        backend.put_allocation(&allocation).await
+    }
+
+    /// Get the network configuration
+    pub fn config(&self) -> &NetworkConfig {
+        &self.config
+    }
+
     /// Discover network topology from current services
     pub async fn discover_topology(&mut self) -> Result<&NetworkTopology> {
         self.topology.discover(&self.config).await?;
@@ -118,12 +204,20 @@ impl NetworkManager {
 
     /// Register a service with its network information
     pub async fn register_service(&mut self, service: ServiceNetwork) -> Result<()> {
+        let mut service = service;
+
         // Allocate WireGuard IP if needed
-        if matches!(service.location, NetworkLocation::WireGuard { .. })
-            && service.wireguard_ip.is_none()
-        {
-            let ip = self.ip_allocator.allocate(&service.service_name)?;
-            // Would update service here in real implementation
+        if matches!(service.location, NetworkLocation::WireGuard { .. }) {
+            match service.wireguard_ip {
+                Some(ip) => {
+                    self.ip_allocator
+                        .allocate_specific(&service.service_name, ip)?;
+                    self.persist_allocation(&service.service_name, ip).await?;
+                }
+                None => {
+                    service.wireguard_ip = Some(self.allocate_ip(&service.service_name).await?);
+                }
+            }
         }
 
         self.topology.add_service(service.clone());
@@ -137,6 +231,40 @@ impl NetworkManager {
             .resolve(from_service, to_service, &self.topology)
     }
 
+    /// Resolve the best IP address for reaching a port on another service,
+    /// skipping addresses that recently failed a probe of that port
+    pub fn resolve_service_ip_for_port(
+        &self,
+        from_service: &str,
+        to_service: &str,
+        port: u16,
+    ) -> Result<IpAddr> {
+        self.resolver
+            .resolve_for_port(from_service, to_service, &self.topology, port)
+    }
+
+    /// Ordered candidate addresses for service-to-service communication
+    pub fn service_candidates(
+        &self,
+        from_service: &str,
+        to_service: &str,
+    ) -> Result<Vec<AddressCandidate>> {
+        self.resolver
+            .candidates(from_service, to_service, &self.topology)
+    }
+
+    /// Resolve the first reachable address, falling back across paths
+    pub async fn resolve_reachable_ip<P: ReachabilityProbe + ?Sized>(
+        &self,
+        from_service: &str,
+        to_service: &str,
+        probe: &P,
+    ) -> Result<AddressCandidate> {
+        self.resolver
+            .resolve_reachable(from_service, to_service, &self.topology, probe)
+            .await
+    }
+
     /// Get all services requiring WireGuard
     pub fn services_requiring_wireguard(&self) -> Vec<&ServiceNetwork> {
         self.topology.services_requiring_wireguard()
@@ -188,4 +316,70 @@ mod tests {
         let manager = NetworkManager::new(config).unwrap();
         assert!(!manager.requires_wireguard());
     }
+
+    #[smol_potat::test]
+    async fn test_allocations_survive_restart() {
+        use crate::backend::sled::SledBackend;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("registry.db");
+
+        let (postgres_ip, ipfs_ip) = {
+            let backend: Arc<dyn RegistryBackend> =
+                Arc::new(SledBackend::new(&db_path).await.unwrap());
+            let mut manager = NetworkManager::with_backend(NetworkConfig::default(), backend)
+                .await
+                .unwrap();
+
+            let postgres_ip = manager.allocate_ip("postgres").await.unwrap();
+            let ipfs_ip = manager.allocate_ip("ipfs").await.unwrap();
+            (postgres_ip, ipfs_ip)
+        };
+
+        // Allocation order no longer matters after a restart
+        let backend: Arc<dyn RegistryBackend> = Arc::new(SledBackend::new(&db_path).await.unwrap());
+        let mut manager = NetworkManager::with_backend(NetworkConfig::default(), backend.clone())
+            .await
+            .unwrap();
+        assert_eq!(manager.allocate_ip("ipfs").await.unwrap(), ipfs_ip);
+        assert_eq!(manager.allocate_ip("postgres").await.unwrap(), postgres_ip);
+
+        // Released leases are removed from the backend and reused
+        assert_eq!(
+            manager.release_ip("postgres").await.unwrap(),
+            Some(postgres_ip)
+        );
+        assert!(backend.get_allocation("postgres").await.unwrap().is_none());
+        assert_eq!(
+            manager.allocate_ip("graph-node").await.unwrap(),
+            postgres_ip
+        );
+
+        // A cached lease missing from the backend is persisted again
+        backend.remove_allocation("ipfs").await.unwrap();
+        assert_eq!(manager.allocate_ip("ipfs").await.unwrap(), ipfs_ip);
+        assert_eq!(
+            backend.get_allocation("ipfs").await.unwrap().unwrap().ip,
+            ipfs_ip
+        );
+    }
+
+    #[smol_potat::test]
+    async fn test_stale_subnet_leases_dropped() {
+        use crate::backend::sled::SledBackend;
+
+        let backend: Arc<dyn RegistryBackend> = Arc::new(SledBackend::in_memory().await.unwrap());
+        let lease = IpAllocation::new(
+            "anvil",
+            "10.43.0.2".parse().unwrap(),
+            "10.43.0.0/16".parse().unwrap(),
+        );
+        backend.put_allocation(&lease).await.unwrap();
+
+        let manager = NetworkManager::with_backend(NetworkConfig::default(), backend.clone())
+            .await
+            .unwrap();
+        assert!(manager.allocated_ip("anvil").is_none());
+        assert!(backend.list_allocations().await.unwrap().is_empty());
+    }
 }
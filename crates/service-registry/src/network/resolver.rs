@@ -1,41 +1,179 @@
 //! Service IP resolution logic
+//!
+//! Resolution produces an ordered list of candidate addresses for each
+//! service pair, one per network path (local, LAN, WireGuard). The first
+//! candidate is the preferred path; later ones are fallbacks used when a
+//! probe finds the preferred path down. Probe results are kept per address
+//! and port, so a service that is down on one port doesn't hide the others
+//! on the same host.
 
 use super::{NetworkLocation, NetworkTopology, ServiceNetwork};
 use crate::error::{Error, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How long a failed probe keeps an address and port out of resolution
+pub const DOWN_TTL: Duration = Duration::from_secs(30);
+
+/// Network path used to reach a service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkPath {
+    /// Host or Docker bridge address
+    Local,
+    /// LAN address
+    Lan,
+    /// WireGuard mesh address
+    WireGuard,
+}
+
+/// A candidate address for reaching a service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressCandidate {
+    /// Address to connect to
+    pub ip: IpAddr,
+    /// Path the address belongs to
+    pub path: NetworkPath,
+}
+
+/// Checks whether an address is currently reachable
+#[async_trait]
+pub trait ReachabilityProbe: Send + Sync {
+    /// Port the probe checks, which its results are remembered under
+    fn port(&self) -> u16;
+
+    /// Return true if `ip` can be reached from the harness host
+    async fn is_reachable(&self, ip: IpAddr) -> bool;
+}
+
+/// Probe that attempts a TCP connection to a fixed port
+///
+/// A refused connection still proves the host is reachable, so only timeouts
+/// and routing errors count as down.
+#[derive(Debug, Clone)]
+pub struct TcpProbe {
+    /// Port to connect to
+    pub port: u16,
+    /// Connection timeout
+    pub timeout: Duration,
+}
+
+impl TcpProbe {
+    /// Create a probe for the given port with a 2 second timeout
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+#[async_trait]
+impl ReachabilityProbe for TcpProbe {
+    fn port(&self) -> u16 {
+        self.port
+    }
+
+    async fn is_reachable(&self, ip: IpAddr) -> bool {
+        use futures::FutureExt;
+
+        let addr = SocketAddr::new(ip, self.port);
+        let connect = async_net::TcpStream::connect(addr).fuse();
+        let timeout = async_io::Timer::after(self.timeout).fuse();
+        futures::pin_mut!(connect, timeout);
+
+        futures::select! {
+            result = connect => match result {
+                Ok(_) => true,
+                Err(e) => e.kind() == std::io::ErrorKind::ConnectionRefused,
+            },
+            _ = timeout => false,
+        }
+    }
+}
 
 /// Service resolver determines the best IP address for service-to-service communication
 pub struct ServiceResolver {
-    /// Cache of resolution decisions
-    resolution_cache: HashMap<(String, String), IpAddr>,
+    /// Cache of ordered candidates per (from, to) pair
+    resolution_cache: RwLock<HashMap<(String, String), Vec<AddressCandidate>>>,
+    /// Addresses and ports that recently failed a probe
+    down: RwLock<HashMap<SocketAddr, Instant>>,
 }
 
 impl ServiceResolver {
     /// Create a new service resolver
     pub fn new() -> Self {
         Self {
-            resolution_cache: HashMap::new(),
+            resolution_cache: RwLock::new(HashMap::new()),
+            down: RwLock::new(HashMap::new()),
         }
     }
 
-    /// Add a service to the resolver
-    pub fn add_service(&mut self, _service: ServiceNetwork) {
-        // Clear cache when services change
-        self.resolution_cache.clear();
+    /// Add or update a service in the resolver
+    pub fn add_service(&mut self, service: ServiceNetwork) {
+        self.invalidate(&service.service_name);
+    }
+
+    /// Drop cached resolutions involving a service
+    pub fn invalidate(&self, service_name: &str) {
+        self.resolution_cache
+            .write()
+            .unwrap()
+            .retain(|(from, to), _| from != service_name && to != service_name);
     }
 
     /// Resolve the best IP address for communication from one service to another
+    ///
+    /// Returns the preferred candidate. Use
+    /// [`resolve_for_port`](Self::resolve_for_port) to skip addresses that
+    /// recently failed a probe.
     pub fn resolve(
         &self,
         from_service: &str,
         to_service: &str,
         topology: &NetworkTopology,
     ) -> Result<IpAddr> {
+        let candidates = self.candidates(from_service, to_service, topology)?;
+        Ok(candidates[0].ip)
+    }
+
+    /// Resolve the best IP address for reaching a port on another service
+    ///
+    /// Returns the first candidate that hasn't recently failed a probe of
+    /// `port`, or the preferred candidate if all of them have.
+    pub fn resolve_for_port(
+        &self,
+        from_service: &str,
+        to_service: &str,
+        topology: &NetworkTopology,
+        port: u16,
+    ) -> Result<IpAddr> {
+        let candidates = self.candidates(from_service, to_service, topology)?;
+        let now = Instant::now();
+
+        let chosen = candidates
+            .iter()
+            .find(|c| !self.is_down(SocketAddr::new(c.ip, port), now))
+            .unwrap_or(&candidates[0]);
+        Ok(chosen.ip)
+    }
+
+    /// Ordered candidate addresses for reaching `to_service` from `from_service`
+    pub fn candidates(
+        &self,
+        from_service: &str,
+        to_service: &str,
+        topology: &NetworkTopology,
+    ) -> Result<Vec<AddressCandidate>> {
         // Check cache first
         let cache_key = (from_service.to_string(), to_service.to_string());
-        if let Some(&ip) = self.resolution_cache.get(&cache_key) {
-            return Ok(ip);
+        if let Some(candidates) = self.resolution_cache.read().unwrap().get(&cache_key) {
+            return Ok(candidates.clone());
         }
 
         // Get service information
@@ -46,57 +184,102 @@ impl ServiceResolver {
             .get_service(to_service)
             .ok_or_else(|| Error::ServiceNotFound(to_service.to_string()))?;
 
-        // Determine best IP based on network locations
-        let ip = self.determine_best_ip(from, to)?;
+        let candidates = Self::rank_candidates(from, to)?;
+        self.resolution_cache
+            .write()
+            .unwrap()
+            .insert(cache_key, candidates.clone());
 
-        // Cache the result (in a real implementation)
-        // self.resolution_cache.insert(cache_key, ip);
-
-        Ok(ip)
+        Ok(candidates)
     }
 
-    /// Determine the best IP address based on network topology
-    fn determine_best_ip(&self, from: &ServiceNetwork, to: &ServiceNetwork) -> Result<IpAddr> {
-        match (&from.location, &to.location) {
-            // Both services are local - use host/Docker IP
-            (NetworkLocation::Local, NetworkLocation::Local) => to.host_ip.ok_or_else(|| {
-                Error::Package(format!("Service {} has no host IP", to.service_name))
-            }),
-
-            // Both services on LAN - use LAN IP
-            (NetworkLocation::RemoteLAN { .. }, NetworkLocation::RemoteLAN { .. }) => {
-                to.lan_ip.ok_or_else(|| {
-                    Error::Package(format!("Service {} has no LAN IP", to.service_name))
-                })
+    /// Order the target's addresses by preferred path
+    fn rank_candidates(
+        from: &ServiceNetwork,
+        to: &ServiceNetwork,
+    ) -> Result<Vec<AddressCandidate>> {
+        use NetworkLocation::*;
+        use NetworkPath as Path;
+
+        let paths: &[NetworkPath] = match (&from.location, &to.location) {
+            // Both services are local - host/Docker IP first
+            (Local, Local) => &[Path::Local, Path::Lan, Path::WireGuard],
+
+            // Reaching a LAN service - LAN IP, mesh as fallback
+            (Local, RemoteLAN { .. }) | (RemoteLAN { .. }, RemoteLAN { .. }) => {
+                &[Path::Lan, Path::WireGuard]
             }
 
-            // From local to LAN - use LAN IP if available
-            (NetworkLocation::Local, NetworkLocation::RemoteLAN { .. }) => {
-                to.lan_ip.ok_or_else(|| {
-                    Error::Package(format!("Service {} has no LAN IP", to.service_name))
-                })
+            // From LAN to local - the harness' LAN address, then host IP
+            (RemoteLAN { .. }, Local) => &[Path::Lan, Path::Local, Path::WireGuard],
+
+            // Mesh peers are only reached over the mesh; a LAN address is
+            // a fallback for peers that also sit on our LAN
+            (WireGuard { .. }, _) => &[Path::WireGuard],
+            (_, WireGuard { .. }) => &[Path::WireGuard, Path::Lan],
+        };
+
+        let mut candidates: Vec<AddressCandidate> = Vec::with_capacity(paths.len());
+        for &path in paths {
+            let ip = match path {
+                Path::Local => to.host_ip,
+                Path::Lan => to.lan_ip,
+                Path::WireGuard => to.wireguard_ip,
+            };
+            if let Some(ip) = ip
+                && !candidates.iter().any(|c| c.ip == ip)
+            {
+                candidates.push(AddressCandidate { ip, path });
             }
+        }
 
-            // From LAN to local - check if harness has LAN interface
-            (NetworkLocation::RemoteLAN { .. }, NetworkLocation::Local) => {
-                // If the local service has a LAN IP, use it
-                if let Some(lan_ip) = to.lan_ip {
-                    Ok(lan_ip)
-                } else {
-                    // Otherwise use host IP and hope for routing
-                    to.host_ip.ok_or_else(|| {
-                        Error::Package(format!("Service {} has no accessible IP", to.service_name))
-                    })
-                }
-            }
+        if candidates.is_empty() {
+            let expected = match paths[0] {
+                Path::Local => "host",
+                Path::Lan => "LAN",
+                Path::WireGuard => "WireGuard",
+            };
+            return Err(Error::Network(format!(
+                "Service {} has no {} IP",
+                to.service_name, expected
+            )));
+        }
+
+        Ok(candidates)
+    }
 
-            // Any WireGuard endpoint - must use WireGuard IP
-            (_, NetworkLocation::WireGuard { .. }) | (NetworkLocation::WireGuard { .. }, _) => {
-                to.wireguard_ip.ok_or_else(|| {
-                    Error::Package(format!("Service {} has no WireGuard IP", to.service_name))
-                })
+    /// Probe a service's candidates in order and return the first reachable one
+    ///
+    /// Failed addresses are remembered with the probe's port for
+    /// [`DOWN_TTL`], so later calls to
+    /// [`resolve_for_port`](Self::resolve_for_port) skip them as well.
+    pub async fn resolve_reachable<P: ReachabilityProbe + ?Sized>(
+        &self,
+        from_service: &str,
+        to_service: &str,
+        topology: &NetworkTopology,
+        probe: &P,
+    ) -> Result<AddressCandidate> {
+        let candidates = self.candidates(from_service, to_service, topology)?;
+
+        for candidate in &candidates {
+            let addr = SocketAddr::new(candidate.ip, probe.port());
+            if probe.is_reachable(candidate.ip).await {
+                self.down.write().unwrap().remove(&addr);
+                return Ok(*candidate);
             }
+
+            debug!(
+                "{} unreachable via {:?} ({}), trying next path",
+                to_service, candidate.path, candidate.ip
+            );
+            self.down.write().unwrap().insert(addr, Instant::now());
         }
+
+        Err(Error::Network(format!(
+            "Service {} is unreachable on all paths",
+            to_service
+        )))
     }
 
     /// Resolve multiple services at once
@@ -116,9 +299,39 @@ impl ServiceResolver {
         Ok(results)
     }
 
-    /// Clear the resolution cache
+    /// Resolve multiple services at once, falling back to other paths when
+    /// the preferred one is down
+    pub async fn resolve_many_reachable<P: ReachabilityProbe + ?Sized>(
+        &self,
+        from_service: &str,
+        to_services: &[String],
+        topology: &NetworkTopology,
+        probe: &P,
+    ) -> Result<HashMap<String, IpAddr>> {
+        let mut results = HashMap::new();
+
+        for to_service in to_services {
+            let candidate = self
+                .resolve_reachable(from_service, to_service, topology, probe)
+                .await?;
+            results.insert(to_service.clone(), candidate.ip);
+        }
+
+        Ok(results)
+    }
+
+    /// Clear the resolution cache and probe results
     pub fn clear_cache(&mut self) {
-        self.resolution_cache.clear();
+        self.resolution_cache.write().unwrap().clear();
+        self.down.write().unwrap().clear();
+    }
+
+    fn is_down(&self, addr: SocketAddr, now: Instant) -> bool {
+        self.down
+            .read()
+            .unwrap()
+            .get(&addr)
+            .is_some_and(|at| now.duration_since(*at) < DOWN_TTL)
     }
 }
 
@@ -227,8 +440,22 @@ mod tests {
         assert_eq!(ip, "10.42.0.10".parse::<IpAddr>().unwrap());
     }
 
-    #[test]
-    fn test_resolve_many() {
+    /// Probe of port 5432 treating a fixed set of addresses as down
+    struct StaticProbe(Vec<IpAddr>);
+
+    #[async_trait]
+    impl ReachabilityProbe for StaticProbe {
+        fn port(&self) -> u16 {
+            5432
+        }
+
+        async fn is_reachable(&self, ip: IpAddr) -> bool {
+            !self.0.contains(&ip)
+        }
+    }
+
+    #[smol_potat::test]
+    async fn test_resolve_many() {
         let topology = create_test_topology();
         let resolver = ServiceResolver::new();
 
@@ -237,6 +464,16 @@ mod tests {
         let results = resolver
             .resolve_many("local-service", &to_services, &topology)
             .unwrap();
+        let reachable = resolver
+            .resolve_many_reachable(
+                "local-service",
+                &to_services,
+                &topology,
+                &StaticProbe(vec![]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results, reachable);
 
         assert_eq!(results.len(), 2);
         assert_eq!(
@@ -249,6 +486,154 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_candidate_ordering() {
+        let mut topology = create_test_topology();
+        let resolver = ServiceResolver::new();
+
+        // A LAN peer that also joined the mesh
+        topology.add_service(ServiceNetwork {
+            service_name: "meshed-lan-service".to_string(),
+            location: NetworkLocation::RemoteLAN {
+                ip: "192.168.1.60".parse().unwrap(),
+            },
+            host_ip: None,
+            lan_ip: Some("192.168.1.60".parse().unwrap()),
+            wireguard_ip: Some("10.42.0.11".parse().unwrap()),
+            wireguard_public_key: None,
+            interfaces: vec!["eth0".to_string(), "wg0".to_string()],
+        });
+
+        let candidates = resolver
+            .candidates("local-service", "meshed-lan-service", &topology)
+            .unwrap();
+        assert_eq!(
+            candidates,
+            vec![
+                AddressCandidate {
+                    ip: "192.168.1.60".parse().unwrap(),
+                    path: NetworkPath::Lan,
+                },
+                AddressCandidate {
+                    ip: "10.42.0.11".parse().unwrap(),
+                    path: NetworkPath::WireGuard,
+                },
+            ]
+        );
+
+        // Mesh peers never resolve to LAN addresses
+        let candidates = resolver
+            .candidates("remote-service", "meshed-lan-service", &topology)
+            .unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].path, NetworkPath::WireGuard);
+    }
+
+    #[smol_potat::test]
+    async fn test_fallback_when_path_down() {
+        let mut topology = create_test_topology();
+        let resolver = ServiceResolver::new();
+
+        topology.add_service(ServiceNetwork {
+            service_name: "meshed-lan-service".to_string(),
+            location: NetworkLocation::RemoteLAN {
+                ip: "192.168.1.60".parse().unwrap(),
+            },
+            host_ip: None,
+            lan_ip: Some("192.168.1.60".parse().unwrap()),
+            wireguard_ip: Some("10.42.0.11".parse().unwrap()),
+            wireguard_public_key: None,
+            interfaces: vec![],
+        });
+
+        let lan_down = StaticProbe(vec!["192.168.1.60".parse().unwrap()]);
+        let targets = vec!["meshed-lan-service".to_string()];
+        let results = resolver
+            .resolve_many_reachable("local-service", &targets, &topology, &lan_down)
+            .await
+            .unwrap();
+        assert_eq!(
+            results["meshed-lan-service"],
+            "10.42.0.11".parse::<IpAddr>().unwrap()
+        );
+
+        // The failed probe is remembered for its port only
+        let ip = resolver
+            .resolve_for_port("local-service", "meshed-lan-service", &topology, 5432)
+            .unwrap();
+        assert_eq!(ip, "10.42.0.11".parse::<IpAddr>().unwrap());
+        let ip = resolver
+            .resolve_for_port("local-service", "meshed-lan-service", &topology, 8080)
+            .unwrap();
+        assert_eq!(ip, "192.168.1.60".parse::<IpAddr>().unwrap());
+        let ip = resolver
+            .resolve("local-service", "meshed-lan-service", &topology)
+            .unwrap();
+        assert_eq!(ip, "192.168.1.60".parse::<IpAddr>().unwrap());
+
+        // No path left
+        let all_down = StaticProbe(vec![
+            "192.168.1.60".parse().unwrap(),
+            "10.42.0.11".parse().unwrap(),
+        ]);
+        assert!(
+            resolver
+                .resolve_many_reachable("local-service", &targets, &topology, &all_down)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cache_invalidated_per_service() {
+        let mut topology = create_test_topology();
+        let mut resolver = ServiceResolver::new();
+
+        assert_eq!(
+            resolver
+                .resolve("local-service", "lan-service", &topology)
+                .unwrap(),
+            "192.168.1.50".parse::<IpAddr>().unwrap()
+        );
+        resolver
+            .resolve("local-service", "remote-service", &topology)
+            .unwrap();
+
+        // Move the LAN service to a new address
+        let moved = ServiceNetwork {
+            service_name: "lan-service".to_string(),
+            location: NetworkLocation::RemoteLAN {
+                ip: "192.168.1.51".parse().unwrap(),
+            },
+            host_ip: None,
+            lan_ip: Some("192.168.1.51".parse().unwrap()),
+            wireguard_ip: None,
+            wireguard_public_key: None,
+            interfaces: vec!["eth0".to_string()],
+        };
+
+        // Stale until the resolver hears about the change
+        topology.add_service(moved.clone());
+        assert_eq!(
+            resolver
+                .resolve("local-service", "lan-service", &topology)
+                .unwrap(),
+            "192.168.1.50".parse::<IpAddr>().unwrap()
+        );
+
+        resolver.add_service(moved);
+        assert_eq!(
+            resolver
+                .resolve("local-service", "lan-service", &topology)
+                .unwrap(),
+            "192.168.1.51".parse::<IpAddr>().unwrap()
+        );
+
+        // Unrelated entries stay cached
+        let cache = resolver.resolution_cache.read().unwrap();
+        assert!(cache.contains_key(&("local-service".to_string(), "remote-service".to_string())));
+    }
+
     #[test]
     fn test_missing_service() {
         let topology = create_test_topology();
@@ -0,0 +1,838 @@
+//! WireGuard mesh provisioning
+//!
+//! This module handles:
+//! - Keypair generation for mesh nodes
+//! - Mesh address assignment from the WireGuard subnet
+//! - Rendering `wg-quick` configuration for each node
+//! - Pushing configuration to nodes and bringing interfaces up/down
+//! - Reading peer handshake state back from `wg show`
+//!
+//! Nodes are reached over SSH by default. A node can instead be bound to a
+//! local network namespace, which makes it possible to run a full mesh on a
+//! single Linux box for testing.
+
+use super::{IpAllocator, NetworkLocation, ServiceNetwork};
+use crate::error::{Error, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, TimeZone, Utc};
+use command_executor::layered::{ExecutionContext, ExecutionLayer, SshLayer};
+use command_executor::{Command, Executor, backends::LocalLauncher, target::Target};
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use tracing::{debug, info};
+use wireguard_control::{Key, KeyPair};
+
+/// Default WireGuard listen port
+pub const DEFAULT_LISTEN_PORT: u16 = 51820;
+
+/// Default WireGuard interface name
+pub const DEFAULT_INTERFACE: &str = "wg0";
+
+/// Seconds after which a peer without a fresh handshake is considered down
+pub const HANDSHAKE_TIMEOUT_SECS: i64 = 180;
+
+/// Configuration for a WireGuard mesh
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WireGuardMeshConfig {
+    /// Network name (from services.yaml)
+    pub name: String,
+
+    /// Interface name on every node (e.g., "wg0")
+    #[serde(default = "default_interface")]
+    pub interface: String,
+
+    /// Mesh subnet (e.g., 10.42.0.0/16)
+    pub subnet: IpNet,
+
+    /// Directory the rendered config is written to on each node
+    #[serde(default = "default_config_dir")]
+    pub config_dir: PathBuf,
+
+    /// Nodes participating in the mesh
+    pub nodes: Vec<WireGuardNodeConfig>,
+}
+
+/// A node participating in the mesh
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WireGuardNodeConfig {
+    /// Node name, unique within the mesh
+    pub name: String,
+
+    /// Host address peers use to reach this node
+    pub host: String,
+
+    /// SSH username
+    pub ssh_user: String,
+
+    /// SSH key path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<PathBuf>,
+
+    /// UDP port WireGuard listens on
+    #[serde(default = "default_listen_port")]
+    pub listen_port: u16,
+
+    /// Fixed mesh address (allocated from the subnet if unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<IpAddr>,
+
+    /// Run node commands in this local network namespace instead of over SSH
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netns: Option<String>,
+
+    /// Override the mesh config directory for this node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_dir: Option<PathBuf>,
+}
+
+fn default_interface() -> String {
+    DEFAULT_INTERFACE.to_string()
+}
+
+fn default_config_dir() -> PathBuf {
+    PathBuf::from("/etc/wireguard")
+}
+
+fn default_listen_port() -> u16 {
+    DEFAULT_LISTEN_PORT
+}
+
+/// A provisioned mesh member with its address and keys
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshMember {
+    /// Node configuration
+    pub node: WireGuardNodeConfig,
+
+    /// Assigned mesh address
+    pub address: IpAddr,
+
+    /// Base64 private key
+    pub private_key: String,
+
+    /// Base64 public key
+    pub public_key: String,
+}
+
+/// A WireGuard mesh with addresses and keys assigned to every node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WireGuardMesh {
+    /// Mesh configuration
+    pub config: WireGuardMeshConfig,
+
+    /// Members in configuration order
+    pub members: Vec<MeshMember>,
+}
+
+impl WireGuardMesh {
+    /// Create a mesh, generating fresh keys for every node
+    pub fn new(config: WireGuardMeshConfig) -> Result<Self> {
+        Self::with_previous(config, None)
+    }
+
+    /// Create a mesh, reusing keys and addresses from a previous mesh where
+    /// the node still exists
+    pub fn with_previous(
+        config: WireGuardMeshConfig,
+        previous: Option<&WireGuardMesh>,
+    ) -> Result<Self> {
+        let mut allocator = IpAllocator::new(config.subnet)?;
+
+        // Pin fixed addresses first so allocation never hands them out
+        for node in &config.nodes {
+            if let Some(address) = node.address {
+                allocator.allocate_specific(&node.name, address)?;
+            }
+        }
+
+        // Keep previously assigned addresses stable
+        for node in config.nodes.iter().filter(|n| n.address.is_none()) {
+            if let Some(member) = previous.and_then(|p| p.member(&node.name))
+                && config.subnet.contains(&member.address)
+                && allocator.get_service_by_ip(&member.address).is_none()
+            {
+                allocator.allocate_specific(&node.name, member.address)?;
+            }
+        }
+
+        let mut members = Vec::with_capacity(config.nodes.len());
+        for node in &config.nodes {
+            if members
+                .iter()
+                .any(|m: &MeshMember| m.node.name == node.name)
+            {
+                return Err(Error::WireGuard(format!(
+                    "Duplicate node '{}' in network '{}'",
+                    node.name, config.name
+                )));
+            }
+
+            let address = allocator.allocate(&node.name)?;
+            let (private_key, public_key) = match previous.and_then(|p| p.member(&node.name)) {
+                Some(member) => (member.private_key.clone(), member.public_key.clone()),
+                None => generate_keypair(),
+            };
+
+            members.push(MeshMember {
+                node: node.clone(),
+                address,
+                private_key,
+                public_key,
+            });
+        }
+
+        Ok(Self { config, members })
+    }
+
+    /// Get a member by node name
+    pub fn member(&self, name: &str) -> Option<&MeshMember> {
+        self.members.iter().find(|m| m.node.name == name)
+    }
+
+    /// Get a member by public key
+    pub fn member_by_public_key(&self, public_key: &str) -> Option<&MeshMember> {
+        self.members.iter().find(|m| m.public_key == public_key)
+    }
+
+    /// Path of the rendered config on a node
+    pub fn config_path(&self, member: &MeshMember) -> PathBuf {
+        member
+            .node
+            .config_dir
+            .as_ref()
+            .unwrap_or(&self.config.config_dir)
+            .join(format!("{}.conf", self.config.interface))
+    }
+
+    /// Render the `wg-quick` configuration for a node
+    pub fn render_config(&self, name: &str) -> Result<String> {
+        let member = self
+            .member(name)
+            .ok_or_else(|| Error::WireGuard(format!("Unknown mesh node: {}", name)))?;
+
+        let mut out = String::new();
+        let _ = writeln!(out, "# Generated by graph-network-harness for {}", name);
+        let _ = writeln!(out, "[Interface]");
+        let _ = writeln!(
+            out,
+            "Address = {}/{}",
+            member.address,
+            self.config.subnet.prefix_len()
+        );
+        let _ = writeln!(out, "ListenPort = {}", member.node.listen_port);
+        let _ = writeln!(out, "PrivateKey = {}", member.private_key);
+
+        for peer in self.members.iter().filter(|m| m.node.name != name) {
+            let host_prefix = match peer.address {
+                IpAddr::V4(_) => 32,
+                IpAddr::V6(_) => 128,
+            };
+            let _ = writeln!(out);
+            let _ = writeln!(out, "# {}", peer.node.name);
+            let _ = writeln!(out, "[Peer]");
+            let _ = writeln!(out, "PublicKey = {}", peer.public_key);
+            let _ = writeln!(out, "AllowedIPs = {}/{}", peer.address, host_prefix);
+            let _ = writeln!(
+                out,
+                "Endpoint = {}",
+                format_endpoint(&peer.node.host, peer.node.listen_port)
+            );
+            let _ = writeln!(out, "PersistentKeepalive = 25");
+        }
+
+        Ok(out)
+    }
+
+    /// Network information for a node, for use by the resolver and topology
+    pub fn service_network(&self, name: &str) -> Option<ServiceNetwork> {
+        self.member(name).map(|member| ServiceNetwork {
+            service_name: member.node.name.clone(),
+            location: NetworkLocation::WireGuard {
+                endpoint: member.node.host.clone(),
+            },
+            host_ip: None,
+            lan_ip: None,
+            wireguard_ip: Some(member.address),
+            wireguard_public_key: Some(member.public_key.clone()),
+            interfaces: vec![self.config.interface.clone()],
+        })
+    }
+}
+
+/// Generate a new base64 encoded (private, public) keypair
+pub fn generate_keypair() -> (String, String) {
+    let pair = KeyPair::generate();
+    (pair.private.to_base64(), pair.public.to_base64())
+}
+
+/// Derive the base64 public key for a base64 private key
+pub fn public_key_for(private_key: &str) -> Result<String> {
+    let key = Key::from_base64(private_key)
+        .map_err(|_| Error::WireGuard("Invalid WireGuard private key".to_string()))?;
+    Ok(key.get_public().to_base64())
+}
+
+fn format_endpoint(host: &str, port: u16) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(v6)) => format!("[{}]:{}", v6, port),
+        _ => format!("{}:{}", host, port),
+    }
+}
+
+/// Handshake and transfer state of a single peer as seen from one node
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PeerStatus {
+    /// Peer public key
+    pub public_key: String,
+
+    /// Peer node name, if the key belongs to a known mesh member
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+
+    /// Current endpoint of the peer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Allowed IPs for the peer
+    pub allowed_ips: Vec<String>,
+
+    /// Time of the latest handshake, if any
+    pub latest_handshake: Option<DateTime<Utc>>,
+
+    /// Bytes received from the peer
+    pub rx_bytes: u64,
+
+    /// Bytes sent to the peer
+    pub tx_bytes: u64,
+}
+
+impl PeerStatus {
+    /// Whether the peer has completed a handshake recently
+    pub fn is_connected(&self, now: DateTime<Utc>) -> bool {
+        self.latest_handshake
+            .map(|t| (now - t).num_seconds() < HANDSHAKE_TIMEOUT_SECS)
+            .unwrap_or(false)
+    }
+}
+
+/// Status of a mesh node and its view of the other peers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeStatus {
+    /// Node name
+    pub node: String,
+
+    /// Mesh address
+    pub address: IpAddr,
+
+    /// Whether the interface is up on the node
+    pub interface_up: bool,
+
+    /// Peers as reported by the node
+    pub peers: Vec<PeerStatus>,
+
+    /// Error reaching the node, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Whether `ip -o link show` reports the link as administratively up
+///
+/// WireGuard links have no carrier state, so `state` reads `UNKNOWN` even
+/// when they work; the `UP` flag is what `wg-quick up` sets.
+pub fn parse_link_up(output: &str) -> bool {
+    output
+        .split_once('<')
+        .and_then(|(_, rest)| rest.split_once('>'))
+        .is_some_and(|(flags, _)| flags.split(',').any(|flag| flag == "UP"))
+}
+
+/// Parse the output of `wg show <interface> dump`
+///
+/// The first line describes the interface itself; every following line is a
+/// tab separated peer record.
+pub fn parse_wg_dump(output: &str) -> Result<Vec<PeerStatus>> {
+    let mut peers = Vec::new();
+
+    for line in output.lines().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 8 {
+            return Err(Error::WireGuard(format!(
+                "Unexpected `wg show dump` peer line: {}",
+                line
+            )));
+        }
+
+        let parse_u64 = |field: &str| {
+            field
+                .parse::<u64>()
+                .map_err(|_| Error::WireGuard(format!("Invalid number in wg dump: {}", field)))
+        };
+
+        let handshake = parse_u64(fields[4])?;
+        let latest_handshake = if handshake == 0 {
+            None
+        } else {
+            Utc.timestamp_opt(handshake as i64, 0).single()
+        };
+
+        peers.push(PeerStatus {
+            public_key: fields[0].to_string(),
+            node: None,
+            endpoint: (fields[2] != "(none)").then(|| fields[2].to_string()),
+            allowed_ips: fields[3]
+                .split(',')
+                .filter(|s| !s.is_empty() && *s != "(none)")
+                .map(|s| s.to_string())
+                .collect(),
+            latest_handshake,
+            rx_bytes: parse_u64(fields[5])?,
+            tx_bytes: parse_u64(fields[6])?,
+        });
+    }
+
+    Ok(peers)
+}
+
+/// Layer that runs commands inside a local network namespace
+#[derive(Debug, Clone)]
+pub struct NetnsLayer {
+    /// Namespace name as known to `ip netns`
+    pub namespace: String,
+}
+
+impl NetnsLayer {
+    /// Create a new network namespace layer
+    pub fn new(namespace: impl Into<String>) -> Self {
+        Self {
+            namespace: namespace.into(),
+        }
+    }
+}
+
+impl ExecutionLayer for NetnsLayer {
+    fn wrap_command(
+        &self,
+        command: Command,
+        _context: &ExecutionContext,
+    ) -> command_executor::Result<Command> {
+        let mut netns_cmd = Command::new("ip");
+        netns_cmd
+            .arg("netns")
+            .arg("exec")
+            .arg(&self.namespace)
+            .arg(command.get_program())
+            .args(command.get_args());
+
+        // `ip netns exec` inherits our environment and cwd
+        if command.get_env_clear() {
+            netns_cmd.env_clear();
+        }
+        netns_cmd.envs(command.get_envs());
+        if let Some(dir) = command.get_current_dir() {
+            netns_cmd.current_dir(dir);
+        }
+        netns_cmd.run_like(&command);
+
+        Ok(netns_cmd)
+    }
+
+    fn description(&self) -> String {
+        format!("Network namespace {}", self.namespace)
+    }
+}
+
+/// Pushes mesh configuration to nodes and controls their interfaces
+pub struct WireGuardProvisioner {
+    executor: Executor<LocalLauncher>,
+}
+
+impl WireGuardProvisioner {
+    /// Create a new provisioner
+    pub fn new() -> Self {
+        Self {
+            executor: Executor::new("wireguard-provisioner".to_string(), LocalLauncher),
+        }
+    }
+
+    /// Push config and bring the interface up on every node
+    pub async fn up(&self, mesh: &WireGuardMesh) -> Result<()> {
+        for member in &mesh.members {
+            self.push_config(mesh, &member.node.name).await?;
+            self.interface_up(mesh, &member.node.name).await?;
+        }
+        Ok(())
+    }
+
+    /// Bring the interface down on every node
+    pub async fn down(&self, mesh: &WireGuardMesh) -> Result<()> {
+        for member in &mesh.members {
+            self.interface_down(mesh, &member.node.name).await?;
+        }
+        Ok(())
+    }
+
+    /// Write the rendered config to a node
+    pub async fn push_config(&self, mesh: &WireGuardMesh, name: &str) -> Result<()> {
+        let member = mesh
+            .member(name)
+            .ok_or_else(|| Error::WireGuard(format!("Unknown mesh node: {}", name)))?;
+        let rendered = mesh.render_config(name)?;
+        let path = mesh.config_path(member);
+        let dir = path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        info!("Pushing WireGuard config to {} ({})", name, path.display());
+
+        // Paths go in as positional arguments, which every layer quotes, and
+        // the config as base64 so it survives any shell quoting layer
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(r#"umask 077 && mkdir -p "$1" && echo "$3" | base64 -d > "$2""#)
+            .arg("sh")
+            .arg(dir)
+            .arg(&path)
+            .arg(BASE64.encode(rendered));
+
+        self.run_checked(&member.node, cmd, "write WireGuard config")
+            .await
+            .map(|_| ())
+    }
+
+    /// Bring the interface up on a node, replacing any existing instance
+    pub async fn interface_up(&self, mesh: &WireGuardMesh, name: &str) -> Result<()> {
+        let member = mesh
+            .member(name)
+            .ok_or_else(|| Error::WireGuard(format!("Unknown mesh node: {}", name)))?;
+        let path = mesh.config_path(member);
+
+        info!("Bringing up {} on {}", mesh.config.interface, name);
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(r#"wg-quick down "$1" >/dev/null 2>&1; wg-quick up "$1""#)
+            .arg("sh")
+            .arg(&path);
+
+        self.run_checked(&member.node, cmd, "bring interface up")
+            .await
+            .map(|_| ())
+    }
+
+    /// Bring the interface down on a node
+    pub async fn interface_down(&self, mesh: &WireGuardMesh, name: &str) -> Result<()> {
+        let member = mesh
+            .member(name)
+            .ok_or_else(|| Error::WireGuard(format!("Unknown mesh node: {}", name)))?;
+
+        info!("Bringing down {} on {}", mesh.config.interface, name);
+
+        let mut cmd = Command::new("wg-quick");
+        cmd.arg("down").arg(mesh.config_path(member));
+
+        self.run_checked(&member.node, cmd, "bring interface down")
+            .await
+            .map(|_| ())
+    }
+
+    /// Query handshake state from every node
+    pub async fn status(&self, mesh: &WireGuardMesh) -> Vec<NodeStatus> {
+        let mut statuses = Vec::with_capacity(mesh.members.len());
+
+        for member in &mesh.members {
+            let link = self.link_up(mesh, &member.node.name).await;
+            let peers = self.node_peers(mesh, &member.node.name).await;
+            let error = link
+                .as_ref()
+                .err()
+                .or(peers.as_ref().err())
+                .map(|e| e.to_string());
+
+            statuses.push(NodeStatus {
+                node: member.node.name.clone(),
+                address: member.address,
+                interface_up: link.unwrap_or(false),
+                peers: peers.unwrap_or_default(),
+                error,
+            });
+        }
+
+        statuses
+    }
+
+    /// Whether the interface's link is up on a node
+    pub async fn link_up(&self, mesh: &WireGuardMesh, name: &str) -> Result<bool> {
+        let member = mesh
+            .member(name)
+            .ok_or_else(|| Error::WireGuard(format!("Unknown mesh node: {}", name)))?;
+
+        let mut cmd = Command::new("ip");
+        cmd.arg("-o")
+            .arg("link")
+            .arg("show")
+            .arg("dev")
+            .arg(&mesh.config.interface);
+
+        let output = self
+            .run_checked(&member.node, cmd, "read link state")
+            .await?;
+        Ok(parse_link_up(&output))
+    }
+
+    /// Query peer state from a single node
+    pub async fn node_peers(&self, mesh: &WireGuardMesh, name: &str) -> Result<Vec<PeerStatus>> {
+        let member = mesh
+            .member(name)
+            .ok_or_else(|| Error::WireGuard(format!("Unknown mesh node: {}", name)))?;
+
+        let mut cmd = Command::new("wg");
+        cmd.arg("show").arg(&mesh.config.interface).arg("dump");
+
+        let output = self
+            .run_checked(&member.node, cmd, "read interface state")
+            .await?;
+
+        let mut peers = parse_wg_dump(&output)?;
+        for peer in &mut peers {
+            peer.node = mesh
+                .member_by_public_key(&peer.public_key)
+                .map(|m| m.node.name.clone());
+        }
+        Ok(peers)
+    }
+
+    /// Wrap a command for the node's access method
+    fn wrap_for_node(&self, node: &WireGuardNodeConfig, command: Command) -> Result<Command> {
+        let context = ExecutionContext::new();
+
+        let wrapped = if let Some(namespace) = &node.netns {
+            NetnsLayer::new(namespace).wrap_command(command, &context)?
+        } else if is_local_host(&node.host) {
+            command
+        } else {
+            let mut layer = SshLayer::new(format!("{}@{}", node.ssh_user, node.host))
+                .with_option("-o")
+                .with_option("BatchMode=yes");
+            if let Some(key) = &node.ssh_key {
+                layer = layer.with_identity_file(key);
+            }
+            layer.wrap_command(command, &context)?
+        };
+
+        Ok(wrapped)
+    }
+
+    /// Run a command on a node and fail with its output on non-zero exit
+    async fn run_checked(
+        &self,
+        node: &WireGuardNodeConfig,
+        command: Command,
+        what: &str,
+    ) -> Result<String> {
+        let command = self.wrap_for_node(node, command)?;
+        debug!("Running on {}: {:?}", node.name, command);
+
+        let result = self.executor.execute(&Target::Command, command).await?;
+        if !result.success() {
+            return Err(Error::WireGuard(format!(
+                "Failed to {} on {} (exit code {:?}): {}",
+                what,
+                node.name,
+                result.code(),
+                result.output.trim()
+            )));
+        }
+
+        Ok(result.output)
+    }
+}
+
+impl Default for WireGuardProvisioner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_local_host(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, host: &str) -> WireGuardNodeConfig {
+        WireGuardNodeConfig {
+            name: name.to_string(),
+            host: host.to_string(),
+            ssh_user: "root".to_string(),
+            ssh_key: None,
+            listen_port: DEFAULT_LISTEN_PORT,
+            address: None,
+            netns: None,
+            config_dir: None,
+        }
+    }
+
+    fn mesh_config(nodes: Vec<WireGuardNodeConfig>) -> WireGuardMeshConfig {
+        WireGuardMeshConfig {
+            name: "mesh".to_string(),
+            interface: DEFAULT_INTERFACE.to_string(),
+            subnet: "10.42.0.0/24".parse().unwrap(),
+            config_dir: default_config_dir(),
+            nodes,
+        }
+    }
+
+    #[test]
+    fn test_keypair_generation() {
+        let (private_key, public_key) = generate_keypair();
+        assert_eq!(public_key_for(&private_key).unwrap(), public_key);
+        assert!(public_key_for("not-a-key").is_err());
+    }
+
+    #[test]
+    fn test_mesh_address_assignment() {
+        let mut fixed = node("fixed", "10.0.0.3");
+        fixed.address = Some("10.42.0.2".parse().unwrap());
+
+        let mesh = WireGuardMesh::new(mesh_config(vec![
+            node("a", "10.0.0.1"),
+            fixed,
+            node("b", "10.0.0.2"),
+        ]))
+        .unwrap();
+
+        // .1 is the gateway, .2 is pinned, so dynamic nodes start at .3
+        assert_eq!(
+            mesh.member("fixed").unwrap().address,
+            "10.42.0.2".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            mesh.member("a").unwrap().address,
+            "10.42.0.3".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            mesh.member("b").unwrap().address,
+            "10.42.0.4".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_mesh_reuses_previous_keys_and_addresses() {
+        let first =
+            WireGuardMesh::new(mesh_config(vec![node("a", "h1"), node("b", "h2")])).unwrap();
+
+        // Drop "a" and add "c"; "b" must keep its key and address
+        let second = WireGuardMesh::with_previous(
+            mesh_config(vec![node("c", "h3"), node("b", "h2")]),
+            Some(&first),
+        )
+        .unwrap();
+
+        let b1 = first.member("b").unwrap();
+        let b2 = second.member("b").unwrap();
+        assert_eq!(b1.address, b2.address);
+        assert_eq!(b1.public_key, b2.public_key);
+        assert_ne!(second.member("c").unwrap().address, b2.address);
+    }
+
+    #[test]
+    fn test_duplicate_node_rejected() {
+        let result = WireGuardMesh::new(mesh_config(vec![node("a", "h1"), node("a", "h2")]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_render_config() {
+        let mesh = WireGuardMesh::new(mesh_config(vec![
+            node("a", "192.168.1.10"),
+            node("b", "fd00::2"),
+        ]))
+        .unwrap();
+
+        let rendered = mesh.render_config("a").unwrap();
+        let a = mesh.member("a").unwrap();
+        let b = mesh.member("b").unwrap();
+
+        assert!(rendered.contains(&format!("Address = {}/24", a.address)));
+        assert!(rendered.contains(&format!("PrivateKey = {}", a.private_key)));
+        assert!(rendered.contains(&format!("PublicKey = {}", b.public_key)));
+        assert!(rendered.contains(&format!("AllowedIPs = {}/32", b.address)));
+        assert!(rendered.contains("Endpoint = [fd00::2]:51820"));
+        assert!(!rendered.contains(&a.public_key));
+
+        assert!(mesh.render_config("missing").is_err());
+    }
+
+    #[test]
+    fn test_parse_wg_dump() {
+        let dump = "privkey\tpubkey\t51820\toff\n\
+                    peerA\t(none)\t10.0.0.2:51820\t10.42.0.3/32\t1700000000\t1024\t2048\t25\n\
+                    peerB\t(none)\t(none)\t10.42.0.4/32,10.42.1.0/24\t0\t0\t0\toff\n";
+
+        let peers = parse_wg_dump(dump).unwrap();
+        assert_eq!(peers.len(), 2);
+
+        assert_eq!(peers[0].public_key, "peerA");
+        assert_eq!(peers[0].endpoint.as_deref(), Some("10.0.0.2:51820"));
+        assert_eq!(peers[0].rx_bytes, 1024);
+        assert_eq!(peers[0].tx_bytes, 2048);
+        assert_eq!(
+            peers[0].latest_handshake.unwrap().timestamp(),
+            1_700_000_000
+        );
+
+        assert!(peers[1].endpoint.is_none());
+        assert!(peers[1].latest_handshake.is_none());
+        assert_eq!(peers[1].allowed_ips.len(), 2);
+        assert!(!peers[1].is_connected(Utc::now()));
+
+        assert!(parse_wg_dump("iface\nbroken line").is_err());
+    }
+
+    #[test]
+    fn test_parse_link_up() {
+        // `ip -o` joins the lines of a record with a backslash
+        let up = r"5: wg0: <POINTOPOINT,NOARP,UP,LOWER_UP> mtu 1420 state UNKNOWN\    link/none";
+        assert!(parse_link_up(up));
+
+        let down = r"5: wg0: <POINTOPOINT,NOARP> mtu 1420 state DOWN\    link/none";
+        assert!(!parse_link_up(down));
+        assert!(!parse_link_up(""));
+    }
+
+    #[test]
+    fn test_netns_layer() {
+        let layer = NetnsLayer::new("wg-test-a");
+        let size = command_executor::PtySize::new(30, 100);
+        let timeout = std::time::Duration::from_secs(5);
+        let mut cmd = Command::new("wg");
+        cmd.arg("show")
+            .env("FOO", "bar")
+            .env_clear()
+            .pty(size)
+            .timeout(timeout);
+
+        let wrapped = layer.wrap_command(cmd, &ExecutionContext::new()).unwrap();
+        assert_eq!(wrapped.get_program(), "ip");
+        let args: Vec<_> = wrapped
+            .get_args()
+            .iter()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(args, vec!["netns", "exec", "wg-test-a", "wg", "show"]);
+        assert_eq!(wrapped.get_envs().len(), 1);
+        assert!(wrapped.get_env_clear());
+        assert_eq!(wrapped.get_pty(), Some(size));
+        assert_eq!(wrapped.get_timeout(), Some(timeout));
+    }
+}
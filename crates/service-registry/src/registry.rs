@@ -2,7 +2,9 @@
 
 use crate::{
     backend::RegistryBackend,
+    backend::memory::MemoryBackend,
     backend::sled::SledBackend,
+    config::RegistryConfig,
     error::{Error, Result},
     models::*,
 };
@@ -14,9 +16,12 @@ use std::sync::Arc;
 use tracing::{debug, info};
 
 /// Service registry with pluggable backend
+///
+/// Clones share the backend and the event subscribers.
+#[derive(Clone)]
 pub struct Registry {
     /// Storage backend
-    backend: Arc<Box<dyn RegistryBackend>>,
+    backend: Arc<dyn RegistryBackend>,
     /// Event subscribers (address -> event types)
     /// Note: We keep subscribers in memory for performance since they're transient
     subscribers: Arc<Mutex<HashMap<SocketAddr, EventSubscription>>>,
@@ -32,15 +37,10 @@ pub struct EventSubscription {
 use std::collections::HashSet;
 
 impl Registry {
-    /// Create a new registry with in-memory sled backend
+    /// Create a new registry with an in-memory backend
     pub async fn new() -> Self {
-        // Create in-memory backend
-        let backend = SledBackend::in_memory()
-            .await
-            .expect("Failed to create in-memory backend");
-
         Self {
-            backend: Arc::new(Box::new(backend)),
+            backend: Arc::new(MemoryBackend::new()),
             subscribers: Arc::new(Mutex::new(HashMap::new())),
         }
     }
@@ -54,7 +54,7 @@ impl Registry {
             .expect("Failed to create persistent backend");
 
         Self {
-            backend: Arc::new(Box::new(backend)),
+            backend: Arc::new(backend),
             subscribers: Arc::new(Mutex::new(HashMap::new())),
         }
     }
@@ -62,11 +62,17 @@ impl Registry {
     /// Create a registry with a custom backend
     pub fn with_backend(backend: Box<dyn RegistryBackend>) -> Self {
         Self {
-            backend: Arc::new(backend),
+            backend: Arc::from(backend),
             subscribers: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Create a registry using the storage backend selected in `config`
+    pub async fn from_config(config: &RegistryConfig) -> Result<Self> {
+        let backend = crate::backend::open(&config.storage).await?;
+        Ok(Self::with_backend(backend))
+    }
+
     /// Load registry from file (creates persistent sled backend)
     pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
@@ -77,11 +83,16 @@ impl Registry {
         backend.init().await?;
 
         Ok(Self {
-            backend: Arc::new(Box::new(backend)),
+            backend: Arc::new(backend),
             subscribers: Arc::new(Mutex::new(HashMap::new())),
         })
     }
 
+    /// Get a handle to the storage backend
+    pub fn backend(&self) -> Arc<dyn RegistryBackend> {
+        self.backend.clone()
+    }
+
     /// Register a new service
     pub async fn register(&self, entry: ServiceEntry) -> Result<Vec<(SocketAddr, WsMessage)>> {
         // Check if service already exists
@@ -94,6 +105,14 @@ impl Registry {
         // Store service
         let service_name = entry.name.clone();
         self.backend.put_service(&entry).await?;
+        self.record_history(
+            &service_name,
+            HistoryKind::Registered {
+                version: entry.version.clone(),
+            },
+            None,
+        )
+        .await?;
 
         // Generate service registered event
         let event_data = serde_json::json!({
@@ -142,6 +161,11 @@ impl Registry {
         Ok(())
     }
 
+    /// Number of addresses subscribed to events
+    pub async fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().await.len()
+    }
+
     /// Generate events for subscribers
     pub async fn emit_event(
         &self,
@@ -182,6 +206,16 @@ impl Registry {
         &self,
         name: &str,
         new_state: ServiceState,
+    ) -> Result<(ServiceState, Vec<(SocketAddr, WsMessage)>)> {
+        self.update_state_with_cause(name, new_state, None).await
+    }
+
+    /// Update service state, recording why it changed in the history log
+    pub async fn update_state_with_cause(
+        &self,
+        name: &str,
+        new_state: ServiceState,
+        cause: Option<String>,
     ) -> Result<(ServiceState, Vec<(SocketAddr, WsMessage)>)> {
         // Get current service
         let mut entry = self.get(name).await?;
@@ -200,6 +234,15 @@ impl Registry {
 
         // Store updated service
         self.backend.put_service(&entry).await?;
+        self.record_history(
+            name,
+            HistoryKind::StateChanged {
+                from: old_state,
+                to: new_state,
+            },
+            cause,
+        )
+        .await?;
 
         // Generate state change event
         let event_data = serde_json::json!({
@@ -228,6 +271,14 @@ impl Registry {
 
         // Store updated service
         self.backend.put_service(&entry).await?;
+        self.record_history(
+            name,
+            HistoryKind::EndpointsUpdated {
+                endpoints: endpoints.clone(),
+            },
+            None,
+        )
+        .await?;
 
         // Generate endpoint updated event
         let event_data = serde_json::json!({
@@ -268,6 +319,9 @@ impl Registry {
             .await?
             .ok_or_else(|| Error::ServiceNotFound(name.to_string()))?;
 
+        self.record_history(name, HistoryKind::Deregistered, None)
+            .await?;
+
         // Generate service deregistered event
         let event_data = serde_json::json!({
             "service": name,
@@ -281,6 +335,13 @@ impl Registry {
         Ok((entry, events))
     }
 
+    /// List all persisted IP leases, ordered by address
+    pub async fn ip_allocations(&self) -> Result<Vec<IpAllocation>> {
+        let mut allocations = self.backend.list_allocations().await?;
+        allocations.sort_by_key(|a| a.ip);
+        Ok(allocations)
+    }
+
     /// Add or update a service
     pub async fn add_or_update(&self, entry: ServiceEntry) -> Result<Vec<(SocketAddr, WsMessage)>> {
         info!("Adding/updating service: {} v{}", entry.name, entry.version);
@@ -294,11 +355,22 @@ impl Registry {
         self.backend.put_service(&entry).await?;
 
         // Generate appropriate event
-        let event_type = if is_new {
-            EventType::ServiceRegistered
+        let (event_type, kind) = if is_new {
+            (
+                EventType::ServiceRegistered,
+                HistoryKind::Registered {
+                    version: entry.version.clone(),
+                },
+            )
         } else {
-            EventType::ServiceUpdated
+            (
+                EventType::ServiceUpdated,
+                HistoryKind::Updated {
+                    version: entry.version.clone(),
+                },
+            )
         };
+        self.record_history(&service_name, kind, None).await?;
 
         let event_data = serde_json::json!({
             "service": service_name,
@@ -310,6 +382,58 @@ impl Registry {
         Ok(events)
     }
 
+    /// Record a health check result for a service
+    ///
+    /// Every result is kept on the service entry, but only results that turn
+    /// the service healthy or unhealthy go into the history.
+    pub async fn record_health(
+        &self,
+        name: &str,
+        status: HealthStatus,
+    ) -> Result<Vec<(SocketAddr, WsMessage)>> {
+        let mut entry = self.get(name).await?;
+        let changed = entry
+            .last_health_check
+            .as_ref()
+            .is_none_or(|last| last.healthy != status.healthy);
+        entry.last_health_check = Some(status.clone());
+        self.backend.put_service(&entry).await?;
+
+        let event_data = serde_json::json!({
+            "service": name,
+            "status": status,
+            "timestamp": chrono::Utc::now(),
+        });
+
+        if changed {
+            self.record_history(name, HistoryKind::HealthChecked { status }, None)
+                .await?;
+        }
+
+        let events = self
+            .emit_event(EventType::HealthCheckResult, event_data)
+            .await;
+
+        Ok(events)
+    }
+
+    /// Query the history log, oldest first
+    pub async fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEvent>> {
+        self.backend.query_history(query).await
+    }
+
+    /// Append an event to the history log
+    async fn record_history(
+        &self,
+        service: &str,
+        kind: HistoryKind,
+        cause: Option<String>,
+    ) -> Result<()> {
+        self.backend
+            .append_history(&HistoryEvent::new(service, kind, cause))
+            .await
+    }
+
     /// Persist registry to disk (no-op if using persistent backend)
     pub async fn persist(&self) -> Result<()> {
         // Backend handles its own persistence
@@ -512,6 +636,86 @@ mod tests {
         assert_eq!(events.len(), 0);
     }
 
+    #[smol_potat::test]
+    async fn test_history_recorded() {
+        let registry = Registry::new().await;
+
+        let service = ServiceEntry::new(
+            "test-service".to_string(),
+            "1.0.0".to_string(),
+            ExecutionInfo::ManagedProcess {
+                pid: None,
+                command: "test".to_string(),
+                args: vec![],
+            },
+            Location::Local,
+        )
+        .unwrap();
+        registry.register(service).await.unwrap();
+
+        let before_start = chrono::Utc::now();
+        registry
+            .update_state_with_cause(
+                "test-service",
+                ServiceState::Starting,
+                Some("operator".to_string()),
+            )
+            .await
+            .unwrap();
+        // Repeated results aren't history
+        for _ in 0..2 {
+            registry
+                .record_health(
+                    "test-service",
+                    HealthStatus {
+                        healthy: true,
+                        message: None,
+                        checked_at: chrono::Utc::now(),
+                        duration_ms: 3,
+                    },
+                )
+                .await
+                .unwrap();
+        }
+        registry.deregister("test-service").await.unwrap();
+
+        let history = registry
+            .history(&HistoryQuery::for_service("test-service"))
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 4);
+        assert!(matches!(history[0].kind, HistoryKind::Registered { .. }));
+        assert_eq!(
+            history[1].kind,
+            HistoryKind::StateChanged {
+                from: ServiceState::Registered,
+                to: ServiceState::Starting,
+            }
+        );
+        assert_eq!(history[1].cause.as_deref(), Some("operator"));
+        assert!(matches!(history[2].kind, HistoryKind::HealthChecked { .. }));
+        assert_eq!(history[3].kind, HistoryKind::Deregistered);
+
+        // Time windows and limits select the most recent matching events
+        let windowed = registry
+            .history(&HistoryQuery {
+                since: Some(before_start),
+                limit: Some(2),
+                ..HistoryQuery::for_service("test-service")
+            })
+            .await
+            .unwrap();
+        assert_eq!(windowed, history[2..].to_vec());
+
+        assert!(
+            registry
+                .history(&HistoryQuery::for_service("other"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     #[smol_potat::test]
     async fn test_persistence() {
         use tempfile::tempdir;
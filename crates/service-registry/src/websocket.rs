@@ -214,6 +214,7 @@ macro_rules! impl_connection_handler {
                     Action::Subscribe => self.handle_subscribe(params).await,
                     Action::Unsubscribe => self.handle_unsubscribe(params).await,
                     Action::DeployPackage => self.handle_deploy_package(params).await,
+                    Action::GetHistory => self.handle_get_history(params).await,
                 };
 
                 match response {
@@ -263,7 +264,11 @@ macro_rules! impl_connection_handler {
                     }
                 };
 
-                let (old_state, _events) = self.registry.update_state(&params.name, new_state).await?;
+                let cause = format!("{:?} requested by {}", params.action, self.addr);
+                let (old_state, _events) = self
+                    .registry
+                    .update_state_with_cause(&params.name, new_state, Some(cause))
+                    .await?;
 
                 Ok(serde_json::json!({
                     "service": params.name,
@@ -278,6 +283,18 @@ macro_rules! impl_connection_handler {
                 Ok(serde_json::to_value(&endpoints)?)
             }
 
+            /// Handle history query request
+            async fn handle_get_history(&self, params: serde_json::Value) -> Result<serde_json::Value> {
+                let query: HistoryQuery = if params.is_null() {
+                    HistoryQuery::default()
+                } else {
+                    serde_json::from_value(params)?
+                };
+
+                let events = self.registry.history(&query).await?;
+                Ok(serde_json::to_value(&events)?)
+            }
+
             /// Handle subscribe request
             async fn handle_subscribe(&mut self, params: serde_json::Value) -> Result<serde_json::Value> {
                 #[derive(Deserialize)]
@@ -2,7 +2,9 @@
 
 use anyhow::Result;
 use serde_json::Value;
-use service_registry::{EventType, ServiceAction, WsClient, WsClientHandle};
+use service_registry::{
+    EventType, HistoryEvent, HistoryQuery, ServiceAction, WsClient, WsClientHandle,
+};
 use std::net::SocketAddr;
 
 /// WebSocket test client wrapper
@@ -87,6 +89,11 @@ impl WebSocketTestClient {
         Ok(())
     }
 
+    /// Query the registry history log
+    pub async fn history(&self, query: &HistoryQuery) -> Result<Vec<HistoryEvent>> {
+        Ok(self.handle.history(query).await?)
+    }
+
     /// Close the connection
     pub async fn close(self) -> Result<()> {
         self.handle.close().await?;
@@ -157,6 +157,40 @@ async fn test_registry_subscription_management() {
     assert_eq!(events.len(), 0);
 }
 
+/// Test counting subscribed addresses
+#[smol_potat::test]
+#[cfg(feature = "integration-tests")]
+async fn test_registry_subscriber_count() {
+    let registry = Registry::new().await;
+    let first = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let second = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8081);
+    assert_eq!(registry.subscriber_count().await, 0);
+
+    registry
+        .subscribe(first, vec![EventType::ServiceRegistered])
+        .await
+        .expect("Failed to subscribe");
+    // More events for the same address don't add a subscriber
+    registry
+        .subscribe(first, vec![EventType::ServiceDeregistered])
+        .await
+        .expect("Failed to subscribe");
+    registry
+        .subscribe(second, vec![EventType::ServiceRegistered])
+        .await
+        .expect("Failed to subscribe");
+    assert_eq!(registry.subscriber_count().await, 2);
+
+    // Clones share their subscribers
+    assert_eq!(registry.clone().subscriber_count().await, 2);
+
+    registry
+        .remove_subscriber(first)
+        .await
+        .expect("Failed to remove subscriber");
+    assert_eq!(registry.subscriber_count().await, 1);
+}
+
 /// Test registry with complex event scenarios
 #[smol_potat::test]
 #[cfg(feature = "integration-tests")]
@@ -1,6 +1,6 @@
 //! WebSocket integration tests
 
-use service_registry::{EventType, Registry, ServiceState, WsServer};
+use service_registry::{EventType, HistoryKind, HistoryQuery, Registry, ServiceState, WsServer};
 use std::time::Duration;
 
 mod common;
@@ -140,6 +140,75 @@ async fn test_websocket_service_operations() {
     drop(server_task);
 }
 
+/// Test querying the history log via WebSocket
+#[cfg(feature = "integration-tests")]
+#[smol_potat::test]
+async fn test_websocket_history() {
+    let registry = Registry::new().await;
+    let service = create_echo_service().expect("Failed to create service");
+    registry
+        .register(service)
+        .await
+        .expect("Failed to register service");
+    registry
+        .update_state("echo-service", ServiceState::Starting)
+        .await
+        .expect("Failed to update state");
+
+    let server = WsServer::new("127.0.0.1:0", registry)
+        .await
+        .expect("Failed to create server");
+    let server_addr = server
+        .listener
+        .local_addr()
+        .expect("Failed to get server address");
+
+    let server_task = smol::spawn(async move {
+        while let Ok(handler) = server.accept().await {
+            smol::spawn(handler.handle()).detach();
+        }
+    });
+
+    smol::Timer::after(Duration::from_millis(100)).await;
+
+    let client = WebSocketTestClient::connect(server_addr)
+        .await
+        .expect("Failed to connect client");
+
+    let history = client
+        .history(&HistoryQuery::for_service("echo-service"))
+        .await
+        .expect("Failed to get history");
+    assert_eq!(history.len(), 2);
+    assert!(matches!(history[0].kind, HistoryKind::Registered { .. }));
+    assert_eq!(
+        history[1].kind,
+        HistoryKind::StateChanged {
+            from: ServiceState::Registered,
+            to: ServiceState::Starting,
+        }
+    );
+
+    // The limit keeps the most recent events
+    let latest = client
+        .history(&HistoryQuery {
+            limit: Some(1),
+            ..HistoryQuery::for_service("echo-service")
+        })
+        .await
+        .expect("Failed to get history");
+    assert_eq!(latest, history[1..].to_vec());
+
+    let other = client
+        .history(&HistoryQuery::for_service("other-service"))
+        .await
+        .expect("Failed to get history");
+    assert!(other.is_empty());
+
+    client.close().await.expect("Failed to close client");
+    drop(server_task);
+}
+
 /// Test event subscriptions
 #[cfg(feature = "integration-tests")]
 #[smol_potat::test]
@@ -0,0 +1,138 @@
+//! WireGuard mesh tests using network namespaces
+//!
+//! These tests build a two node mesh on the local machine: each node lives in
+//! its own network namespace, joined by a veth pair that stands in for the
+//! underlying LAN. They require root plus the `ip`, `wg` and `wg-quick` tools.
+//!
+//! ```bash
+//! sudo -E cargo test -p service-registry --features wireguard-tests --test wireguard_netns
+//! ```
+
+#![cfg(feature = "wireguard-tests")]
+
+use command_executor::{Command, Executor, Target, backends::LocalLauncher};
+use service_registry::network::wireguard::{
+    DEFAULT_INTERFACE, DEFAULT_LISTEN_PORT, WireGuardMesh, WireGuardMeshConfig,
+    WireGuardNodeConfig, WireGuardProvisioner,
+};
+use std::path::Path;
+use std::time::Duration;
+
+const NS_A: &str = "harness-wg-a";
+const NS_B: &str = "harness-wg-b";
+
+/// Run a local command, panicking on failure
+async fn sh(script: &str) {
+    let executor = Executor::new("wg-test".to_string(), LocalLauncher);
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(script);
+    let result = executor
+        .execute(&Target::Command, cmd)
+        .await
+        .expect("Failed to run setup command");
+    assert!(result.success(), "`{}` failed: {}", script, result.output);
+}
+
+/// Network namespaces joined by a veth pair, removed on drop
+struct NetnsPair;
+
+impl NetnsPair {
+    async fn create() -> Self {
+        // Clean up leftovers from an aborted run
+        Self::cleanup();
+
+        sh(&format!("ip netns add {NS_A} && ip netns add {NS_B}")).await;
+        sh(&format!(
+            "ip link add veth-wg-a netns {NS_A} type veth peer name veth-wg-b netns {NS_B}"
+        ))
+        .await;
+        sh(&format!(
+            "ip -n {NS_A} addr add 192.168.251.1/24 dev veth-wg-a && \
+             ip -n {NS_B} addr add 192.168.251.2/24 dev veth-wg-b && \
+             ip -n {NS_A} link set veth-wg-a up && ip -n {NS_B} link set veth-wg-b up && \
+             ip -n {NS_A} link set lo up && ip -n {NS_B} link set lo up"
+        ))
+        .await;
+
+        Self
+    }
+
+    fn cleanup() {
+        for ns in [NS_A, NS_B] {
+            let _ = std::process::Command::new("ip")
+                .args(["netns", "del", ns])
+                .status();
+        }
+    }
+}
+
+impl Drop for NetnsPair {
+    fn drop(&mut self) {
+        Self::cleanup();
+    }
+}
+
+fn node(name: &str, host: &str, netns: &str, config_dir: &Path) -> WireGuardNodeConfig {
+    WireGuardNodeConfig {
+        name: name.to_string(),
+        host: host.to_string(),
+        ssh_user: "root".to_string(),
+        ssh_key: None,
+        listen_port: DEFAULT_LISTEN_PORT,
+        address: None,
+        netns: Some(netns.to_string()),
+        config_dir: Some(config_dir.to_path_buf()),
+    }
+}
+
+#[smol_potat::test]
+async fn test_two_node_mesh_handshake() {
+    let _netns = NetnsPair::create().await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let mesh = WireGuardMesh::new(WireGuardMeshConfig {
+        name: "netns-mesh".to_string(),
+        interface: DEFAULT_INTERFACE.to_string(),
+        subnet: "10.99.0.0/24".parse().unwrap(),
+        config_dir: dir.path().to_path_buf(),
+        nodes: vec![
+            node("a", "192.168.251.1", NS_A, &dir.path().join("a")),
+            node("b", "192.168.251.2", NS_B, &dir.path().join("b")),
+        ],
+    })
+    .unwrap();
+
+    let provisioner = WireGuardProvisioner::new();
+    provisioner
+        .up(&mesh)
+        .await
+        .expect("Failed to bring mesh up");
+
+    // Traffic over the tunnel triggers the handshake
+    let b_addr = mesh.member("b").unwrap().address;
+    sh(&format!("ip netns exec {NS_A} ping -c 3 -W 2 {b_addr}")).await;
+
+    let mut connected = false;
+    for _ in 0..10 {
+        let statuses = provisioner.status(&mesh).await;
+        connected = statuses.iter().all(|s| {
+            s.interface_up && s.peers.len() == 1 && s.peers[0].is_connected(chrono::Utc::now())
+        });
+        if connected {
+            break;
+        }
+        smol::Timer::after(Duration::from_millis(500)).await;
+    }
+    assert!(connected, "Peers never completed a handshake");
+
+    // Peer records are attributed to mesh nodes
+    let peers = provisioner.node_peers(&mesh, "a").await.unwrap();
+    assert_eq!(peers[0].node.as_deref(), Some("b"));
+
+    provisioner
+        .down(&mesh)
+        .await
+        .expect("Failed to bring mesh down");
+    let statuses = provisioner.status(&mesh).await;
+    assert!(statuses.iter().all(|s| !s.interface_up));
+}
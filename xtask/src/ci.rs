@@ -150,6 +150,8 @@ async fn run_tests(extra_args: Vec<&str>, features_desc: Option<&str>) -> Result
             ProcessEventType::Started { pid } => {
                 eprintln!("Test process started (PID: {})", pid);
             }
+            // Not launched on a pseudo-terminal
+            ProcessEventType::Output => {}
             ProcessEventType::Exited { code, signal } => match (code, signal) {
                 (Some(0), _) if !test_failed => {
                     println!("\nAll tests passed");
@@ -0,0 +1,92 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use graph_test_daemon::daemon::GraphStackConfig;
+use graph_test_daemon::GraphTestDaemon;
+use harness_core::client::{Client, TestClient};
+use harness_core::codegen;
+use harness_core::protocol::DaemonSchema;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// Stack configuration the checked-in graph-test-daemon client is generated from
+const GRAPH_STACK_CONFIG: &str = "crates/graph-test-daemon/configs/graph-stack.yaml";
+
+/// Checked-in graph-test-daemon client
+const GRAPH_CLIENT: &str = "crates/graph-test-daemon/src/client.rs";
+
+#[derive(Args)]
+pub struct CodegenArgs {
+    /// Fetch the schema from a running daemon
+    #[arg(long, conflicts_with = "schema")]
+    endpoint: Option<SocketAddr>,
+
+    /// Read the schema from a JSON file
+    #[arg(long)]
+    schema: Option<PathBuf>,
+
+    /// Stack configuration for the graph-test-daemon schema
+    #[arg(long, default_value = GRAPH_STACK_CONFIG)]
+    config: PathBuf,
+
+    /// Name of the generated client struct
+    #[arg(long, default_value = "GraphTestClient")]
+    client: String,
+
+    /// Where to write the generated module
+    #[arg(short, long, default_value = GRAPH_CLIENT)]
+    out: PathBuf,
+
+    /// Fail if the output is out of date instead of writing it
+    #[arg(long)]
+    check: bool,
+}
+
+pub async fn run(args: CodegenArgs) -> Result<()> {
+    let root = workspace_root();
+
+    let schema = if let Some(endpoint) = args.endpoint {
+        println!("Fetching schema from daemon at {}", endpoint);
+        let client = TestClient::connect(endpoint).await?;
+        let schema = client.schema().await?;
+        client.disconnect().await?;
+        schema
+    } else if let Some(path) = &args.schema {
+        println!("Reading schema from {}", path.display());
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str::<DaemonSchema>(&json)?
+    } else {
+        let path = root.join(&args.config);
+        println!("Collecting graph-test-daemon schema for {}", path.display());
+        let yaml = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: GraphStackConfig = serde_yaml::from_str(&yaml)?;
+        GraphTestDaemon::schema(&config)?
+    };
+
+    let code = codegen::generate(&schema, &args.client);
+    let out = root.join(&args.out);
+    let current = std::fs::read_to_string(&out).unwrap_or_default();
+
+    if current == code {
+        println!("{} is up to date", args.out.display());
+        return Ok(());
+    }
+
+    if args.check {
+        bail!(
+            "{} is out of date. Run 'cargo xtask codegen' to regenerate it.",
+            args.out.display()
+        );
+    }
+
+    std::fs::write(&out, code).with_context(|| format!("Failed to write {}", out.display()))?;
+    println!("Wrote {}", args.out.display());
+    Ok(())
+}
+
+fn workspace_root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives in the workspace root")
+}
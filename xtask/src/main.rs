@@ -1,4 +1,5 @@
 mod ci;
+mod codegen;
 mod docker;
 mod test;
 
@@ -21,6 +22,8 @@ enum Command {
     Test(test::TestArgs),
     /// Docker operations
     Docker(docker::DockerArgs),
+    /// Generate typed daemon clients
+    Codegen(codegen::CodegenArgs),
 }
 
 fn main() -> Result<()> {
@@ -32,6 +35,7 @@ fn main() -> Result<()> {
             Command::Ci(args) => ci::run(args).await,
             Command::Test(args) => test::run(args).await,
             Command::Docker(args) => docker::run(args).await,
+            Command::Codegen(args) => codegen::run(args).await,
         }
     })
 }
@@ -97,6 +97,8 @@ pub async fn run(args: TestArgs) -> Result<()> {
             ProcessEventType::Started { pid } => {
                 eprintln!("Test process started (PID: {})", pid);
             }
+            // Not launched on a pseudo-terminal
+            ProcessEventType::Output => {}
             ProcessEventType::Exited { .. } => {
                 // Handled after loop
             }